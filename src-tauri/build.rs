@@ -1,3 +1,42 @@
+use std::path::Path;
+use std::process::Command;
+
+// git の出力（git が無い環境やリポジトリ外では空文字）
+fn git(args: &[&str]) -> String {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .unwrap_or_default()
+}
+
 fn main() {
+    // get_app_info で返すビルド元のコミットとそのコミット日時（UNIX 時刻）
+    // ビルドした時刻は埋め込まない（再ビルドの判定をコミットの変更のみにするため）
+    let git_hash = git(&["rev-parse", "--short", "HEAD"]);
+    let commit_timestamp = git(&["log", "-1", "--format=%ct"]);
+    println!("cargo:rustc-env=GHTTPPING_GIT_HASH={}", git_hash);
+    println!(
+        "cargo:rustc-env=GHTTPPING_COMMIT_TIMESTAMP={}",
+        commit_timestamp
+    );
+
+    // HEAD の切り替え（detached HEAD を含む）と、現在のブランチの ref の更新（packed-refs に移った場合を含む）で再実行
+    let mut watched = vec![git(&["rev-parse", "--git-path", "HEAD"])];
+    watched.push(git(&["rev-parse", "--git-path", "packed-refs"]));
+    let branch = git(&["symbolic-ref", "-q", "HEAD"]);
+    if !branch.is_empty() {
+        watched.push(git(&["rev-parse", "--git-path", &branch]));
+    }
+    // 存在しないパスを指定すると毎回再実行されるため、存在するもののみ
+    for path in watched
+        .iter()
+        .filter(|p| !p.is_empty() && Path::new(p).exists())
+    {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+
     tauri_build::build()
 }
@@ -0,0 +1,84 @@
+use std::collections::VecDeque;
+
+use crate::http_client;
+use crate::stats::PingSample;
+
+// 直近の応答時間のパーセンタイルにかける倍率（p99 × 3）
+const PERCENTILE: f64 = 0.99;
+const MULTIPLIER: f64 = 3.0;
+
+// パーセンタイルを求める直近のサンプル数と、計算を始めるまでに必要な数
+const WINDOW_SAMPLES: usize = 200;
+const MIN_SAMPLES: usize = 20;
+
+// 応答時間の分布からタイムアウトを決める（サンプルが少ない間は上限を使う）
+// 失敗が続く場合は回線が遅くなった可能性があるため、成功するまで失敗ごとに 2 倍にする
+pub struct AdaptiveTimeout {
+    min_secs: u64,
+    max_secs: u64,
+    samples: VecDeque<u64>,
+    failure_streak: u32,
+}
+
+impl AdaptiveTimeout {
+    pub fn new(min_secs: u64, max_secs: u64) -> Self {
+        AdaptiveTimeout {
+            min_secs,
+            max_secs,
+            samples: VecDeque::with_capacity(WINDOW_SAMPLES),
+            failure_streak: 0,
+        }
+    }
+
+    // 測定履歴の成功サンプルで初期化する（古い順）
+    pub fn seed(&mut self, samples: &[PingSample]) {
+        for sample in samples.iter().filter(|s| s.success) {
+            self.push(sample.response_time_ms);
+        }
+    }
+
+    pub fn observe(&mut self, success: bool, response_time_ms: Option<u64>) {
+        if success {
+            self.failure_streak = 0;
+            self.push(response_time_ms);
+        } else {
+            self.failure_streak = self.failure_streak.saturating_add(1);
+        }
+    }
+
+    fn push(&mut self, response_time_ms: Option<u64>) {
+        let Some(ms) = response_time_ms else {
+            return;
+        };
+        if self.samples.len() >= WINDOW_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(ms);
+    }
+
+    // 次の測定に使うタイムアウト（秒、min_secs〜max_secs の範囲）
+    pub fn timeout_secs(&self) -> u64 {
+        if self.samples.len() < MIN_SAMPLES {
+            return self.max_secs;
+        }
+        let mut latencies: Vec<u64> = self.samples.iter().copied().collect();
+        latencies.sort_unstable();
+        let count = latencies.len();
+        let p99 = latencies[((count as f64 * PERCENTILE).ceil() as usize).max(1) - 1];
+        let base = (p99 as f64 * MULTIPLIER / 1000.0).ceil() as u64;
+        let backoff = 1u64 << self.failure_streak.min(8);
+        base.saturating_mul(backoff)
+            .clamp(self.min_secs, self.max_secs)
+    }
+}
+
+pub fn validate_bounds(min_secs: u64, max_secs: u64) -> Result<(), String> {
+    http_client::validate_timeout_secs(min_secs)?;
+    if min_secs > max_secs {
+        return Err(format!(
+            "タイムアウトの下限（{} 秒）は上限（{} 秒）以下で指定してください",
+            min_secs, max_secs
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use serde::{Deserialize, Serialize};
+
+use crate::curl::{describe_exit_code, run_curl, CurlErrorKind, CurlFeature};
+
+// 1つの応答から確認する代替サービスの上限
+const MAX_PROBES_PER_RESPONSE: usize = 4;
+
+// 確認のタイムアウト（秒）
+const PROBE_TIMEOUT_SECS: u64 = 10;
+
+// Alt-Svc の1エントリ（RFC 7838）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AltService {
+    pub protocol: String,
+    // 省略時は元のホストと同じ
+    pub host: Option<String>,
+    pub port: u16,
+    pub max_age_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AltSvcProbeStatus {
+    Reachable,
+    Unreachable,
+    ClientUnsupported,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AltSvcProbe {
+    pub ip_version: u8,
+    pub service: AltService,
+    pub connect_to: String,
+    pub status: AltSvcProbeStatus,
+    pub status_code: Option<u16>,
+    pub http_version: Option<String>,
+    pub response_time_ms: Option<u64>,
+    pub error_message: Option<String>,
+    #[serde(default)]
+    pub unsupported_feature: Option<CurlFeature>,
+    #[serde(default)]
+    pub curl_error: Option<CurlErrorKind>,
+}
+
+// 引用符の外にある区切り文字で分割
+fn split_unquoted(value: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c == separator && !in_quotes => {
+                parts.push(&value[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&value[start..]);
+    parts
+}
+
+// `h3=":443"; ma=86400, h2="alt.example.com:443"` 形式のヘッダを解析（clear や不正なエントリは無視）
+pub fn parse_alt_svc(value: &str) -> Vec<AltService> {
+    split_unquoted(value, ',')
+        .into_iter()
+        .filter_map(|entry| {
+            let mut fields = split_unquoted(entry, ';').into_iter();
+            let (protocol, authority) = fields.next()?.trim().split_once('=')?;
+            let authority = authority.trim().trim_matches('"');
+            let (host, port) = authority.rsplit_once(':')?;
+            let max_age_secs = fields.find_map(|param| {
+                let (name, value) = param.trim().split_once('=')?;
+                (name.trim() == "ma")
+                    .then(|| value.trim().trim_matches('"').parse().ok())
+                    .flatten()
+            });
+            Some(AltService {
+                protocol: protocol.trim().replace("%3A", ":").replace("%3a", ":"),
+                host: (!host.is_empty()).then(|| host.trim_matches(['[', ']']).to_string()),
+                port: port.parse().ok()?,
+                max_age_secs,
+            })
+        })
+        .take(MAX_PROBES_PER_RESPONSE)
+        .collect()
+}
+
+// HTTP/3（QUIC）系のプロトコルか
+fn is_http3(protocol: &str) -> bool {
+    protocol == "h3" || protocol.starts_with("h3-")
+}
+
+// 広告された代替サービスへ実際に接続できるか確認
+// 接続先を差し替えても Host・SNI は元のホスト名のまま（Alt-Svc の仕様どおり）
+#[allow(clippy::too_many_arguments)]
+pub async fn probe_alt_service(
+    url: &str,
+    origin_host: &str,
+    origin_port: u16,
+    origin_ip: &str,
+    ip_version: u8,
+    service: AltService,
+    ignore_tls_errors: bool,
+    user_agent: &str,
+) -> AltSvcProbe {
+    // 代替ホストの指定がない場合は、元の測定と同じ IP アドレスの別ポートへ接続
+    let target = service
+        .host
+        .clone()
+        .unwrap_or_else(|| origin_ip.to_string());
+    let target = if target.contains(':') {
+        format!("[{}]", target)
+    } else {
+        target
+    };
+    let connect_to = format!("{}:{}", target, service.port);
+    let mut probe = AltSvcProbe {
+        ip_version,
+        service,
+        connect_to: connect_to.clone(),
+        status: AltSvcProbeStatus::Unreachable,
+        status_code: None,
+        http_version: None,
+        response_time_ms: None,
+        error_message: None,
+        unsupported_feature: None,
+        curl_error: None,
+    };
+
+    let (protocol_arg, required_feature) = match probe.service.protocol.as_str() {
+        p if is_http3(p) => ("--http3-only", Some(CurlFeature::Http3)),
+        "h2" => ("--http2", Some(CurlFeature::Http2)),
+        "http/1.1" => ("--http1.1", None),
+        p => {
+            probe.status = AltSvcProbeStatus::ClientUnsupported;
+            probe.error_message = Some(format!("未対応のプロトコルです: {}", p));
+            return probe;
+        }
+    };
+    // 古い curl や HTTP/3 を組み込んでいない curl では実行せずに未対応として報告
+    let capabilities = crate::curl::capabilities().await;
+    if let Some(feature) = required_feature
+        .into_iter()
+        .chain([CurlFeature::ConnectTo])
+        .find(|f| !capabilities.supports(*f))
+    {
+        probe.status = AltSvcProbeStatus::ClientUnsupported;
+        probe.unsupported_feature = Some(feature);
+        probe.error_message = Some(format!(
+            "インストールされている curl は {} の確認に対応していません",
+            probe.service.protocol
+        ));
+        return probe;
+    }
+    let mut args = vec![
+        protocol_arg.to_string(),
+        format!("--ipv{}", ip_version),
+        "--connect-to".to_string(),
+        format!("{}:{}:{}", origin_host, origin_port, connect_to),
+        "--silent".to_string(),
+        "--show-error".to_string(),
+        "--output".to_string(),
+        "nul".to_string(),
+        "--write-out".to_string(),
+        "%{http_code} %{time_total} %{http_version}".to_string(),
+        "--max-time".to_string(),
+        PROBE_TIMEOUT_SECS.to_string(),
+        "--user-agent".to_string(),
+        user_agent.to_string(),
+    ];
+    if ignore_tls_errors {
+        args.push("--insecure".to_string());
+    }
+    args.push(url.to_string());
+
+    let output = match run_curl(args).await {
+        Ok(output) => output,
+        Err(e) => {
+            probe.error_message = Some(e);
+            return probe;
+        }
+    };
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+
+    // 終了コード 2（オプション未対応）・4（機能が組み込まれていない）は curl 側が HTTP/3 等に非対応
+    if matches!(output.status.code(), Some(2) | Some(4)) {
+        probe.status = AltSvcProbeStatus::ClientUnsupported;
+        probe.error_message = Some(format!(
+            "インストールされている curl は {} に対応していません",
+            probe.service.protocol
+        ));
+        return probe;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let mut fields = stdout.split_whitespace();
+    let status_code = fields
+        .next()
+        .and_then(|v| v.parse::<u16>().ok())
+        .filter(|code| *code != 0);
+    probe.response_time_ms = fields
+        .next()
+        .and_then(|v| v.parse::<f64>().ok())
+        .map(|secs| (secs * 1000.0).round() as u64);
+    probe.http_version = fields.next().map(|v| v.to_string());
+
+    if output.status.success() && status_code.is_some() {
+        probe.status = AltSvcProbeStatus::Reachable;
+        probe.status_code = status_code;
+    } else {
+        if !output.status.success() {
+            probe.curl_error = Some(CurlErrorKind::from_exit_code(output.status.code()));
+        }
+        probe.error_message = Some(if stderr.is_empty() {
+            format!(
+                "接続できません: {}",
+                describe_exit_code(output.status.code())
+            )
+        } else {
+            stderr
+        });
+    }
+    probe
+}
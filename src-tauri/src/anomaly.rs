@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::history::load_history;
+use crate::stats::{ping_samples_by_target, samples_within, PingSample};
+
+// 異常とみなす z スコア（基準からの標準偏差の何倍遅いか）の既定値と範囲
+pub const DEFAULT_Z_THRESHOLD: f64 = 3.0;
+const MIN_Z_THRESHOLD: f64 = 1.0;
+const MAX_Z_THRESHOLD: f64 = 10.0;
+
+// 指数移動平均の重み（直近およそ 20 回分を基準とする）
+const EWMA_ALPHA: f64 = 0.1;
+
+// 基準が安定するまでは判定しない回数
+const WARMUP_SAMPLES: usize = 10;
+
+// 応答時間がほぼ一定の場合に、わずかな揺れを異常としないための下限（ミリ秒）
+const MIN_STDDEV_MS: f64 = 1.0;
+const MIN_DEVIATION_MS: f64 = 5.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyAnomaly {
+    pub ip_version: u8,
+    pub recorded_at: String,
+    pub response_time_ms: u64,
+    // 判定時点の基準（指数移動平均）と標準偏差
+    pub baseline_ms: f64,
+    pub stddev_ms: f64,
+    pub z_score: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FamilyAnomalyReport {
+    // 判定に使った成功サンプル数
+    pub samples: usize,
+    pub baseline_ms: Option<f64>,
+    pub stddev_ms: Option<f64>,
+    pub anomalies: Vec<LatencyAnomaly>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TargetAnomalyReport {
+    pub target: String,
+    pub ipv4: FamilyAnomalyReport,
+    pub ipv6: FamilyAnomalyReport,
+}
+
+// 応答時間の指数移動平均と分散を基準に、急に遅くなったサンプルを検出する
+pub struct AnomalyDetector {
+    ip_version: u8,
+    z_threshold: f64,
+    mean: Option<f64>,
+    variance: f64,
+    count: usize,
+}
+
+impl AnomalyDetector {
+    pub fn new(ip_version: u8, z_threshold: f64) -> Self {
+        AnomalyDetector {
+            ip_version,
+            z_threshold,
+            mean: None,
+            variance: 0.0,
+            count: 0,
+        }
+    }
+
+    // 成功したサンプルを追加し、異常な遅延であれば返す（失敗は損失率の集計で扱うため対象外）
+    pub fn observe(
+        &mut self,
+        recorded_at: &str,
+        response_time_ms: Option<u64>,
+    ) -> Option<LatencyAnomaly> {
+        let value = response_time_ms? as f64;
+        self.count += 1;
+        let Some(mean) = self.mean else {
+            self.mean = Some(value);
+            return None;
+        };
+
+        let stddev = self.variance.sqrt();
+        let deviation = value - mean;
+        let z_score = deviation / stddev.max(MIN_STDDEV_MS);
+        let anomaly = (self.count > WARMUP_SAMPLES
+            && deviation >= MIN_DEVIATION_MS
+            && z_score >= self.z_threshold)
+            .then(|| LatencyAnomaly {
+                ip_version: self.ip_version,
+                recorded_at: recorded_at.to_string(),
+                response_time_ms: value as u64,
+                baseline_ms: mean,
+                stddev_ms: stddev,
+                z_score,
+            });
+
+        // 遅い状態が続く場合は基準も徐々に追従する
+        let increment = EWMA_ALPHA * deviation;
+        self.mean = Some(mean + increment);
+        self.variance = (1.0 - EWMA_ALPHA) * (self.variance + deviation * increment);
+        anomaly
+    }
+
+    pub fn baseline_ms(&self) -> Option<f64> {
+        self.mean
+    }
+
+    pub fn stddev_ms(&self) -> Option<f64> {
+        self.mean.map(|_| self.variance.sqrt())
+    }
+}
+
+pub fn validate_z_threshold(z_threshold: f64) -> Result<(), String> {
+    if !(MIN_Z_THRESHOLD..=MAX_Z_THRESHOLD).contains(&z_threshold) {
+        return Err(format!(
+            "異常判定のしきい値（z スコア）は {} から {} の範囲で指定してください",
+            MIN_Z_THRESHOLD, MAX_Z_THRESHOLD
+        ));
+    }
+    Ok(())
+}
+
+fn family_report(samples: &[PingSample], ip_version: u8, z_threshold: f64) -> FamilyAnomalyReport {
+    let mut detector = AnomalyDetector::new(ip_version, z_threshold);
+    let anomalies = samples
+        .iter()
+        .filter(|s| s.success)
+        .filter_map(|s| detector.observe(&s.recorded_at.to_rfc3339(), s.response_time_ms))
+        .collect();
+    FamilyAnomalyReport {
+        samples: detector.count,
+        baseline_ms: detector.baseline_ms(),
+        stddev_ms: detector.stddev_ms(),
+        anomalies,
+    }
+}
+
+// 測定履歴の応答時間を対象ごとに古い順にたどり、普段より統計的に遅いサンプルを返す
+#[tauri::command]
+pub async fn get_latency_anomalies(
+    app: AppHandle,
+    target: Option<String>,
+    window_minutes: Option<u64>,
+    z_threshold: Option<f64>,
+) -> Result<Vec<TargetAnomalyReport>, String> {
+    let z_threshold = z_threshold.unwrap_or(DEFAULT_Z_THRESHOLD);
+    validate_z_threshold(z_threshold)?;
+    if window_minutes.is_some_and(|w| w == 0 || w > 60 * 24 * 365) {
+        return Err("集計期間は 1 分〜1 年の範囲で指定してください".to_string());
+    }
+
+    let entries = load_history(&app)?;
+    let within = |samples: Vec<PingSample>| match window_minutes {
+        Some(window_minutes) => samples_within(&samples, window_minutes),
+        None => samples,
+    };
+    Ok(ping_samples_by_target(&entries)
+        .into_iter()
+        .filter(|(t, _, _)| target.as_ref().is_none_or(|target| t == target))
+        .map(|(target, ipv4, ipv6)| TargetAnomalyReport {
+            target,
+            ipv4: family_report(&within(ipv4), 4, z_threshold),
+            ipv6: family_report(&within(ipv6), 6, z_threshold),
+        })
+        .collect())
+}
@@ -0,0 +1,147 @@
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::process::run_command;
+use crate::{curl, dry_run};
+
+// build.rs で埋め込むビルド元のコミットとそのコミット日時（ビルドした日時ではない）
+const GIT_HASH: &str = env!("GHTTPPING_GIT_HASH");
+const COMMIT_TIMESTAMP: &str = env!("GHTTPPING_COMMIT_TIMESTAMP");
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppInfo {
+    pub app_name: String,
+    pub app_version: String,
+    pub tauri_version: String,
+    pub webview_version: Option<String>,
+    pub git_hash: Option<String>,
+    pub commit_date: Option<String>,
+    pub build_profile: String,
+    pub os_name: Option<String>,
+    pub os_version: Option<String>,
+    pub curl_path: Option<String>,
+    pub curl_version: Option<String>,
+    pub curl_features: Vec<String>,
+    pub features: Vec<String>,
+    pub error_messages: Vec<String>,
+}
+
+// PowerShell 出力解析用の内部構造体
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct OsInfoOutput {
+    caption: Option<String>,
+    version: Option<String>,
+}
+
+#[tauri::command]
+pub async fn get_app_info(app: AppHandle) -> Result<AppInfo, String> {
+    let package_info = app.package_info();
+    let mut info = AppInfo {
+        app_name: package_info.name.clone(),
+        app_version: package_info.version.to_string(),
+        tauri_version: tauri::VERSION.to_string(),
+        webview_version: tauri::webview_version().ok(),
+        git_hash: Some(GIT_HASH.to_string()).filter(|h| !h.is_empty()),
+        commit_date: commit_date(),
+        build_profile: if cfg!(debug_assertions) {
+            "debug"
+        } else {
+            "release"
+        }
+        .to_string(),
+        os_name: None,
+        os_version: None,
+        curl_path: None,
+        curl_version: None,
+        curl_features: vec![],
+        features: enabled_features(),
+        error_messages: vec![],
+    };
+
+    let (os_result, curl_path_result, curl_capabilities) =
+        tokio::join!(fetch_os_info(), find_curl_path(), curl::capabilities());
+
+    match os_result {
+        Ok(os) => {
+            info.os_name = os.caption;
+            info.os_version = os.version;
+        }
+        Err(e) => info
+            .error_messages
+            .push(format!("OS 情報の取得に失敗: {}", e)),
+    }
+    match curl_path_result {
+        Ok(path) => info.curl_path = Some(path),
+        Err(e) => info
+            .error_messages
+            .push(format!("curl.exe の場所の取得に失敗: {}", e)),
+    }
+    // 各機能と同じ確認済みの結果を使う（ドライランで未確認の場合は curl.exe を起動しないため空のまま）
+    info.curl_version = curl_capabilities.version.clone();
+    info.curl_features = curl_capabilities.features.clone();
+    if info.curl_version.is_none() && !dry_run::is_enabled() {
+        info.error_messages
+            .push("curl のバージョン取得に失敗: curl --version を実行できません".to_string());
+    }
+
+    Ok(info)
+}
+
+// ビルド元のコミットの日時（ローカル時刻の RFC3339）
+fn commit_date() -> Option<String> {
+    let timestamp: i64 = COMMIT_TIMESTAMP.parse().ok().filter(|t| *t > 0)?;
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.to_rfc3339())
+}
+
+// 有効な Cargo feature の一覧
+fn enabled_features() -> Vec<String> {
+    let mut features = Vec::new();
+    if cfg!(feature = "custom-protocol") {
+        features.push("custom-protocol".to_string());
+    }
+    features
+}
+
+// Windows の製品名とバージョンを取得
+async fn fetch_os_info() -> Result<OsInfoOutput, String> {
+    let args = [
+        "-NoProfile".to_string(),
+        "-WindowStyle".to_string(),
+        "Hidden".to_string(),
+        "-Command".to_string(),
+        "Get-CimInstance Win32_OperatingSystem | Select-Object Caption, Version | ConvertTo-Json -Compress"
+            .to_string(),
+    ];
+    let output = run_command("powershell", &args)
+        .await
+        .map_err(|e| format!("PowerShellコマンド実行失敗: {}", e))?;
+    if !output.status.success() {
+        return Err(crate::decode_command_output(&output.stderr)
+            .trim()
+            .to_string());
+    }
+
+    let stdout = crate::decode_command_output(&output.stdout);
+    serde_json::from_str(stdout.trim()).map_err(|e| format!("OS 情報の解析失敗: {}", e))
+}
+
+// PATH 上で実際に使われる curl.exe のパス
+async fn find_curl_path() -> Result<String, String> {
+    let output = run_command("where.exe", &["curl.exe".to_string()])
+        .await
+        .map_err(|e| format!("where.exe 実行失敗: {}", e))?;
+
+    crate::decode_command_output(&output.stdout)
+        .lines()
+        .map(|l| l.trim().to_string())
+        .find(|l| !l.is_empty())
+        .ok_or_else(|| {
+            "curl.exe が見つかりません。Windows 標準の curl.exe が PATH に含まれているか確認してください"
+                .to_string()
+        })
+}
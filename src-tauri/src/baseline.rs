@@ -0,0 +1,539 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::env_diff::{diff_environment, EnvironmentDiff};
+use crate::operations::{run_operation, OperationKind};
+use crate::{EnvironmentCheckResult, HttpPingDualResult, HttpPingResult};
+
+// 正常に使えていたときの環境と測定結果（アプリデータディレクトリ配下に保存）
+const BASELINE_FILE_NAME: &str = "baseline.json";
+
+// 測定先を指定しない場合に使う同梱プリセットの地域と件数
+const DEFAULT_TARGET_REGION: &str = "global";
+const DEFAULT_TARGET_COUNT: usize = 3;
+const MAX_TARGETS: usize = 10;
+
+// 応答時間の悪化とみなす倍率と増加幅（短い応答時間のわずかな揺れは無視する）
+const LATENCY_RATIO_THRESHOLD: f64 = 2.0;
+const LATENCY_MIN_INCREASE_MS: u64 = 50;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvironmentBaseline {
+    pub captured_at: String,
+    pub environment: EnvironmentCheckResult,
+    pub pings: Vec<HttpPingDualResult>,
+    // 正常とはいえない状態で取得した場合の注意（インターネットに接続できないなど）
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviationSeverity {
+    // 改善した、または影響が小さいと考えられる変化
+    Info,
+    Warning,
+    // ベースライン時点ではできていたことができなくなった
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviationCategory {
+    Adapter,
+    DnsServer,
+    GlobalIp,
+    Connectivity,
+    NameResolution,
+    Reachability,
+    Status,
+    Latency,
+    Protocol,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BaselineDeviation {
+    pub category: DeviationCategory,
+    pub severity: DeviationSeverity,
+    // 測定先の URL（環境全体の変化の場合は None）
+    pub target: Option<String>,
+    pub ip_version: Option<u8>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BaselineComparison {
+    pub baseline_captured_at: String,
+    pub compared_at: String,
+    pub environment: EnvironmentCheckResult,
+    pub pings: Vec<HttpPingDualResult>,
+    pub environment_diff: EnvironmentDiff,
+    // 重要度の高い順の変化の一覧（画面にそのまま表示できる説明付き）
+    pub deviations: Vec<BaselineDeviation>,
+    pub has_deviations: bool,
+}
+
+fn baseline_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("アプリデータディレクトリの取得に失敗: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("設定ディレクトリの作成に失敗: {}", e))?;
+    Ok(dir.join(BASELINE_FILE_NAME))
+}
+
+fn read_baseline(app: &AppHandle) -> Result<Option<EnvironmentBaseline>, String> {
+    let path = baseline_file_path(app)?;
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Ok(None);
+    };
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("ベースラインのファイルを読み込めません: {}", e))
+}
+
+// 環境チェックと測定先への測定を行い、正常な状態の基準として保存する（既存のベースラインは置き換える）
+// urls を省略した場合は同梱プリセットの先頭の数件を測定する
+#[tauri::command]
+pub async fn capture_baseline(
+    app: AppHandle,
+    urls: Option<Vec<String>>,
+) -> Result<EnvironmentBaseline, String> {
+    let urls = match urls {
+        Some(urls) => urls,
+        None => crate::targets::get_builtin_targets(
+            app.clone(),
+            Some(DEFAULT_TARGET_REGION.to_string()),
+        )
+        .await?
+        .into_iter()
+        .take(DEFAULT_TARGET_COUNT)
+        .map(|t| t.url)
+        .collect(),
+    };
+    if urls.is_empty() || urls.len() > MAX_TARGETS {
+        return Err(format!(
+            "ベースラインの測定先は 1 から {} 件の範囲で指定してください",
+            MAX_TARGETS
+        ));
+    }
+    for url in &urls {
+        crate::validate_url(&crate::template::expand_template(url)?)?;
+    }
+
+    let task_app = app.clone();
+    let baseline = run_operation(&app, OperationKind::Diagnostic, "baseline", async move {
+        let environment = crate::execute_environment_check(task_app.clone()).await?;
+        let mut pings = Vec::new();
+        for url in urls {
+            pings.push(
+                crate::ping_with_options(task_app.clone(), url, crate::default_ping_options())
+                    .await?,
+            );
+        }
+        Ok((environment, pings))
+    })
+    .await
+    .map(|(environment, pings)| {
+        let warnings = capture_warnings(&environment, &pings);
+        EnvironmentBaseline {
+            captured_at: crate::now_rfc3339(),
+            environment,
+            pings,
+            warnings,
+        }
+    })?;
+
+    let body = serde_json::to_string_pretty(&baseline)
+        .map_err(|e| format!("ベースラインの変換に失敗: {}", e))?;
+    fs::write(baseline_file_path(&app)?, body)
+        .map_err(|e| format!("ベースラインのファイルの書き込みに失敗: {}", e))?;
+    Ok(baseline)
+}
+
+// 保存されているベースライン（未取得の場合は None、初回の案内の判定にも使う）
+#[tauri::command]
+pub async fn get_baseline(app: AppHandle) -> Result<Option<EnvironmentBaseline>, String> {
+    read_baseline(&app)
+}
+
+#[tauri::command]
+pub async fn clear_baseline(app: AppHandle) -> Result<(), String> {
+    let path = baseline_file_path(&app)?;
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("ベースラインのファイルの削除に失敗: {}", e))?;
+    }
+    Ok(())
+}
+
+// ベースラインと同じ測定をもう一度行い、ベースラインから変わった点をすべて返す
+#[tauri::command]
+pub async fn compare_to_baseline(app: AppHandle) -> Result<BaselineComparison, String> {
+    let baseline = read_baseline(&app)?.ok_or_else(|| {
+        "ベースラインがありません。正常に使えているときに取得してください".to_string()
+    })?;
+    let urls: Vec<String> = baseline
+        .pings
+        .iter()
+        .map(|p| p.url_template.clone().unwrap_or_else(|| p.url.clone()))
+        .collect();
+
+    let task_app = app.clone();
+    let (environment, pings) =
+        run_operation(&app, OperationKind::Diagnostic, "baseline", async move {
+            let environment = crate::execute_environment_check(task_app.clone()).await?;
+            let mut pings = Vec::new();
+            for url in urls {
+                // 測定できなかった URL もベースラインとの比較に含める
+                pings.push(
+                    crate::ping_with_options(task_app.clone(), url, crate::default_ping_options())
+                        .await,
+                );
+            }
+            Ok((environment, pings))
+        })
+        .await?;
+
+    let environment_diff = diff_environment(&baseline.environment, &environment);
+    let mut deviations = environment_deviations(&environment_diff);
+    for (before, after) in baseline.pings.iter().zip(&pings) {
+        match after {
+            Ok(after) => deviations.extend(ping_deviations(before, after)),
+            Err(e) => deviations.push(BaselineDeviation {
+                category: DeviationCategory::Reachability,
+                severity: DeviationSeverity::Critical,
+                target: Some(before.url.clone()),
+                ip_version: None,
+                before: None,
+                after: Some(e.clone()),
+                message: format!("{} を測定できませんでした: {}", before.url, e),
+            }),
+        }
+    }
+    deviations.sort_by_key(|d| std::cmp::Reverse(d.severity as u8));
+
+    Ok(BaselineComparison {
+        baseline_captured_at: baseline.captured_at,
+        compared_at: crate::now_rfc3339(),
+        environment,
+        pings: pings.into_iter().filter_map(Result::ok).collect(),
+        environment_diff,
+        has_deviations: !deviations.is_empty(),
+        deviations,
+    })
+}
+
+fn capture_warnings(
+    environment: &EnvironmentCheckResult,
+    pings: &[HttpPingDualResult],
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if !environment.internet_available {
+        warnings.push(
+            "インターネットに接続できない状態で取得しました。正常に使えているときに取得し直してください"
+                .to_string(),
+        );
+    }
+    for ping in pings {
+        if !ping.ipv4.success && !ping.ipv6.success {
+            warnings.push(format!("{} に接続できない状態で取得しました", ping.url));
+        }
+    }
+    warnings
+}
+
+fn deviation(
+    category: DeviationCategory,
+    severity: DeviationSeverity,
+    before: Option<String>,
+    after: Option<String>,
+    message: String,
+) -> BaselineDeviation {
+    BaselineDeviation {
+        category,
+        severity,
+        target: None,
+        ip_version: None,
+        before,
+        after,
+        message,
+    }
+}
+
+fn environment_deviations(diff: &EnvironmentDiff) -> Vec<BaselineDeviation> {
+    let mut deviations = Vec::new();
+    for flag in &diff.flag_changes {
+        let label = match flag.name.as_str() {
+            "ipv4_connectivity" => "IPv4 での接続",
+            "ipv6_connectivity" => "IPv6 での接続",
+            "dns_resolution" => "名前解決",
+            "internet_available" => "インターネットへの接続",
+            other => other,
+        };
+        let (severity, message) = if flag.after {
+            (
+                DeviationSeverity::Info,
+                format!("{}ができるようになりました", label),
+            )
+        } else {
+            (
+                DeviationSeverity::Critical,
+                format!("{}ができなくなりました", label),
+            )
+        };
+        deviations.push(deviation(
+            DeviationCategory::Connectivity,
+            severity,
+            Some(flag.before.to_string()),
+            Some(flag.after.to_string()),
+            message,
+        ));
+    }
+    for name in &diff.adapters_removed {
+        deviations.push(deviation(
+            DeviationCategory::Adapter,
+            DeviationSeverity::Warning,
+            Some(name.clone()),
+            None,
+            format!("ネットワークアダプタ「{}」が見つからなくなりました", name),
+        ));
+    }
+    for name in &diff.adapters_added {
+        deviations.push(deviation(
+            DeviationCategory::Adapter,
+            DeviationSeverity::Info,
+            None,
+            Some(name.clone()),
+            format!("ネットワークアダプタ「{}」が追加されました", name),
+        ));
+    }
+    for change in &diff.adapter_address_changes {
+        deviations.push(deviation(
+            DeviationCategory::Adapter,
+            DeviationSeverity::Info,
+            Some(change.removed_addresses.join(", ")),
+            Some(change.added_addresses.join(", ")),
+            format!(
+                "ネットワークアダプタ「{}」の IP アドレスが変わりました",
+                change.name
+            ),
+        ));
+    }
+    for change in &diff.dns_server_changes {
+        let servers = |ipv4: &[String], ipv6: &[String]| {
+            let servers: Vec<&str> = ipv4.iter().chain(ipv6).map(String::as_str).collect();
+            (!servers.is_empty()).then(|| servers.join(", "))
+        };
+        deviations.push(deviation(
+            DeviationCategory::DnsServer,
+            DeviationSeverity::Warning,
+            servers(&change.before_ipv4, &change.before_ipv6),
+            servers(&change.after_ipv4, &change.after_ipv6),
+            format!(
+                "「{}」で使う DNS サーバが変わりました",
+                change.interface_alias
+            ),
+        ));
+    }
+    for (ip_version, change) in [
+        (4, &diff.ipv4_global_ip_change),
+        (6, &diff.ipv6_global_ip_change),
+    ] {
+        let Some(change) = change else {
+            continue;
+        };
+        let (severity, message) = match (&change.before, &change.after) {
+            (Some(_), None) => (
+                DeviationSeverity::Warning,
+                format!(
+                    "IPv{} のグローバル IP アドレスを取得できなくなりました",
+                    ip_version
+                ),
+            ),
+            (None, Some(_)) => (
+                DeviationSeverity::Info,
+                format!(
+                    "IPv{} のグローバル IP アドレスを取得できるようになりました",
+                    ip_version
+                ),
+            ),
+            // 回線や経路が変わった可能性がある（動的に割り当てられる回線では通常の変化）
+            _ => (
+                DeviationSeverity::Info,
+                format!("IPv{} のグローバル IP アドレスが変わりました", ip_version),
+            ),
+        };
+        let mut deviation = deviation(
+            DeviationCategory::GlobalIp,
+            severity,
+            change.before.clone(),
+            change.after.clone(),
+            message,
+        );
+        deviation.ip_version = Some(ip_version);
+        deviations.push(deviation);
+    }
+    deviations
+}
+
+// 測定先ごと・ファミリごとの変化（ベースライン時点の結果と比べる）
+fn ping_deviations(
+    before: &HttpPingDualResult,
+    after: &HttpPingDualResult,
+) -> Vec<BaselineDeviation> {
+    let mut deviations = Vec::new();
+    let target = before
+        .url_template
+        .clone()
+        .unwrap_or_else(|| before.url.clone());
+    let families = [
+        (
+            4,
+            &before.dns_resolution.ipv4_addresses,
+            &after.dns_resolution.ipv4_addresses,
+            &before.ipv4,
+            &after.ipv4,
+        ),
+        (
+            6,
+            &before.dns_resolution.ipv6_addresses,
+            &after.dns_resolution.ipv6_addresses,
+            &before.ipv6,
+            &after.ipv6,
+        ),
+    ];
+    for (ip_version, before_addresses, after_addresses, before_result, after_result) in families {
+        let mut push =
+            |category, severity, before: Option<String>, after: Option<String>, message| {
+                deviations.push(BaselineDeviation {
+                    category,
+                    severity,
+                    target: Some(target.clone()),
+                    ip_version: Some(ip_version),
+                    before,
+                    after,
+                    message,
+                });
+            };
+
+        if !before_addresses.is_empty() && after_addresses.is_empty() {
+            push(
+                DeviationCategory::NameResolution,
+                DeviationSeverity::Critical,
+                Some(before_addresses.join(", ")),
+                None,
+                format!(
+                    "{} の IPv{} アドレスを名前解決できなくなりました",
+                    target, ip_version
+                ),
+            );
+            continue;
+        }
+        if before_addresses.is_empty() && !after_addresses.is_empty() {
+            push(
+                DeviationCategory::NameResolution,
+                DeviationSeverity::Info,
+                None,
+                Some(after_addresses.join(", ")),
+                format!(
+                    "{} の IPv{} アドレスを名前解決できるようになりました",
+                    target, ip_version
+                ),
+            );
+        }
+
+        match (before_result.success, after_result.success) {
+            (true, false) => {
+                push(
+                    DeviationCategory::Reachability,
+                    DeviationSeverity::Critical,
+                    before_result.status_code.map(|s| s.to_string()),
+                    after_result.error_message.clone(),
+                    format!(
+                        "IPv{} で {} に接続できなくなりました{}",
+                        ip_version,
+                        target,
+                        after_result
+                            .error_message
+                            .as_ref()
+                            .map(|e| format!("（{}）", e))
+                            .unwrap_or_default()
+                    ),
+                );
+                continue;
+            }
+            (false, true) => push(
+                DeviationCategory::Reachability,
+                DeviationSeverity::Info,
+                before_result.error_message.clone(),
+                after_result.status_code.map(|s| s.to_string()),
+                format!(
+                    "IPv{} で {} に接続できるようになりました",
+                    ip_version, target
+                ),
+            ),
+            (false, false) => continue,
+            (true, true) => {}
+        }
+
+        if before_result.status_code != after_result.status_code {
+            push(
+                DeviationCategory::Status,
+                DeviationSeverity::Warning,
+                before_result.status_code.map(|s| s.to_string()),
+                after_result.status_code.map(|s| s.to_string()),
+                format!(
+                    "IPv{} で {} の HTTP ステータスが変わりました",
+                    ip_version, target
+                ),
+            );
+        }
+        if let Some((before_ms, after_ms)) = latency_regression(before_result, after_result) {
+            push(
+                DeviationCategory::Latency,
+                DeviationSeverity::Warning,
+                Some(format!("{} ms", before_ms)),
+                Some(format!("{} ms", after_ms)),
+                format!(
+                    "IPv{} で {} の応答時間が {} ms から {} ms に増えました",
+                    ip_version, target, before_ms, after_ms
+                ),
+            );
+        }
+        for (label, before_value, after_value) in [
+            (
+                "HTTP のバージョン",
+                before_result.http_version.map(|v| v.as_str().to_string()),
+                after_result.http_version.map(|v| v.as_str().to_string()),
+            ),
+            (
+                "TLS のバージョン",
+                before_result.tls_version.clone(),
+                after_result.tls_version.clone(),
+            ),
+        ] {
+            if before_value != after_value {
+                push(
+                    DeviationCategory::Protocol,
+                    DeviationSeverity::Info,
+                    before_value,
+                    after_value,
+                    format!("IPv{} で {} の{}が変わりました", ip_version, target, label),
+                );
+            }
+        }
+    }
+    deviations
+}
+
+fn latency_regression(before: &HttpPingResult, after: &HttpPingResult) -> Option<(u64, u64)> {
+    let before_ms = before.response_time_ms?;
+    let after_ms = after.response_time_ms?;
+    let regressed = after_ms as f64 > before_ms as f64 * LATENCY_RATIO_THRESHOLD
+        && after_ms >= before_ms + LATENCY_MIN_INCREASE_MS;
+    regressed.then_some((before_ms, after_ms))
+}
@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::AppHandle;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::result_stream::ResultStream;
+use crate::{http_client, HttpPingDualResult};
+
+// 一度に測定できる URL の上限と、同時に測定する数の既定値と上限
+pub const MAX_URLS: usize = 500;
+const DEFAULT_CONCURRENCY: usize = 8;
+const MAX_CONCURRENCY: usize = 64;
+
+// stream_results を指定した場合に URL ごとの測定が終わるたびに通知するイベント名（測定の完了順）
+pub const BATCH_RESULT_EVENT: &str = "batch-ping-result";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchPingFailure {
+    pub url: String,
+    pub error_message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HttpPingBatchResult {
+    pub started_at: String,
+    pub finished_at: String,
+    // 測定できた URL の結果（指定した順）
+    pub results: Vec<HttpPingDualResult>,
+    // URL が不正・測定中の内部エラーなどで測定できなかったもの
+    pub failures: Vec<BatchPingFailure>,
+    // 失敗した URL が abort_after_failures に達して残りを測定しなかった場合は true
+    #[serde(default)]
+    pub aborted: bool,
+    #[serde(default)]
+    pub skipped_urls: Vec<String>,
+}
+
+// イベントで通知する 1 件の結果（測定できなかった場合は failure）
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum BatchPingOutcome<'a> {
+    Result(&'a HttpPingDualResult),
+    Failure(&'a BatchPingFailure),
+}
+
+// 複数の URL を同時に concurrency 件ずつ測定（各 URL の結果は ping_http_dual と同様に履歴にも記録する）
+// ヘッダの値と本文の {{counter}} などの変数は URL と同様に測定ごとに展開する
+// stream_results と abort_after_failures は run_benchmark と同じ（打ち切った場合は測定中のものも中止する）
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn ping_http_batch(
+    app: AppHandle,
+    urls: Vec<String>,
+    concurrency: Option<usize>,
+    ignore_tls_errors: Option<bool>,
+    timeout_secs: Option<u64>,
+    method: Option<String>,
+    headers: Option<HashMap<String, String>>,
+    body: Option<String>,
+    stream_results: Option<bool>,
+    abort_after_failures: Option<u32>,
+) -> Result<HttpPingBatchResult, String> {
+    if urls.is_empty() || urls.len() > MAX_URLS {
+        return Err(format!(
+            "URL は 1 から {} 件の範囲で指定してください",
+            MAX_URLS
+        ));
+    }
+    let concurrency = concurrency.unwrap_or(DEFAULT_CONCURRENCY);
+    if !(1..=MAX_CONCURRENCY).contains(&concurrency) {
+        return Err(format!(
+            "同時に測定する数は 1 から {} の範囲で指定してください",
+            MAX_CONCURRENCY
+        ));
+    }
+    let timeout_secs = timeout_secs.unwrap_or(http_client::DEFAULT_TIMEOUT_SECS);
+    http_client::validate_timeout_secs(timeout_secs)?;
+    let method = match method {
+        Some(method) => http_client::HttpMethod::parse(&method)?,
+        None => http_client::HttpMethod::Get,
+    };
+    let mut request = http_client::RequestOptions {
+        method,
+        headers: http_client::parse_custom_headers(headers.unwrap_or_default())?,
+        ..Default::default()
+    };
+    if let Some(body) = body {
+        request.set_body(body, None)?;
+    }
+    let ignore_tls_errors = ignore_tls_errors.unwrap_or(false);
+    ResultStream::validate_abort_after_failures(abort_after_failures)?;
+
+    let task_app = app.clone();
+    run_operation(&app, OperationKind::Ping, "batch", async move {
+        let started_at = crate::now_rfc3339();
+        let semaphore = Arc::new(Semaphore::new(concurrency));
+        let mut tasks = JoinSet::new();
+        // 測定中に panic したタスクの URL を失敗として記録できるよう、タスクと URL の位置を対応付ける
+        let mut task_indexes = HashMap::new();
+        let total = urls.len();
+        for (index, url) in urls.iter().cloned().enumerate() {
+            let app = task_app.clone();
+            let semaphore = semaphore.clone();
+            let request = request.clone();
+            let handle = tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let mut options = crate::default_ping_options();
+                options.ignore_tls_errors = ignore_tls_errors;
+                options.timeout_secs = timeout_secs;
+                options.request = request;
+                // 変数の展開は測定ごとに 1 回のみ（展開後の URL の誤りはその URL の失敗として扱い、他の URL の測定は続ける）
+                let result = crate::ping_with_options(app, url.clone(), options).await;
+                (
+                    index,
+                    result.map_err(|error_message| BatchPingFailure { url, error_message }),
+                )
+            });
+            task_indexes.insert(handle.id(), index);
+        }
+
+        // 両ファミリとも失敗した URL を失敗として数え、abort_after_failures に達したら測定中のものも含めて打ち切る
+        let mut stream = ResultStream::new(
+            task_app,
+            BATCH_RESULT_EVENT,
+            total,
+            stream_results.unwrap_or(false),
+            abort_after_failures,
+        );
+        let mut outcomes = Vec::with_capacity(total);
+        while let Some(joined) = tasks.join_next().await {
+            let joined = match joined {
+                Ok(joined) => Some(joined),
+                // 打ち切りで中止したタスクは skipped_urls に含める
+                Err(e) if e.is_cancelled() => None,
+                Err(e) => {
+                    let index = task_indexes[&e.id()];
+                    let failure = BatchPingFailure {
+                        url: urls[index].clone(),
+                        error_message: format!("測定中に内部エラーが発生しました: {}", e),
+                    };
+                    Some((index, Err(failure)))
+                }
+            };
+            if let Some((index, outcome)) = joined {
+                let (event, failed) = match &outcome {
+                    Ok(result) => (
+                        BatchPingOutcome::Result(result),
+                        !result.ipv4.success && !result.ipv6.success,
+                    ),
+                    Err(failure) => (BatchPingOutcome::Failure(failure), true),
+                };
+                stream.push(index, &event, failed);
+                outcomes.push((index, outcome));
+                if stream.should_abort() {
+                    tasks.abort_all();
+                }
+            }
+            report_progress((total - tasks.len()) as f64 * 100.0 / total as f64);
+        }
+        outcomes.sort_by_key(|(index, _)| *index);
+
+        let mut skipped = vec![true; total];
+        let mut results = Vec::new();
+        let mut failures = Vec::new();
+        for (index, outcome) in outcomes {
+            skipped[index] = false;
+            match outcome {
+                Ok(result) => results.push(result),
+                Err(failure) => failures.push(failure),
+            }
+        }
+        let skipped_urls: Vec<String> = urls
+            .into_iter()
+            .zip(skipped)
+            .filter_map(|(url, skipped)| skipped.then_some(url))
+            .collect();
+        Ok(HttpPingBatchResult {
+            started_at,
+            finished_at: crate::now_rfc3339(),
+            results,
+            failures,
+            aborted: stream.should_abort(),
+            skipped_urls,
+        })
+    })
+    .await
+}
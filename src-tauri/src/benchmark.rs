@@ -0,0 +1,290 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use url::Url;
+
+use crate::http_client;
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::result_stream::ResultStream;
+use crate::stats::{compute_loss_stats, latency_and_jitter, PingSample};
+use crate::HttpPingResult;
+
+// 1ターゲットあたりの測定回数と対象数の上限
+const DEFAULT_COUNT: u32 = 5;
+const MAX_COUNT: u32 = 50;
+const MAX_TARGETS: usize = 20;
+
+// ターゲットごとの比較結果を測定の完了順に通知するイベント名
+pub const BENCHMARK_ROW_EVENT: &str = "benchmark-row";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkFamilyStats {
+    pub attempts: usize,
+    pub successes: usize,
+    pub success_rate: f64,
+    pub min_ms: Option<u64>,
+    pub avg_ms: Option<f64>,
+    pub median_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+    pub jitter_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkRow {
+    pub url: String,
+    pub protocol: String,
+    pub ipv4_address: Option<String>,
+    pub ipv6_address: Option<String>,
+    pub ipv4: BenchmarkFamilyStats,
+    pub ipv6: BenchmarkFamilyStats,
+    pub fastest_family: Option<u8>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub count: u32,
+    pub rows: Vec<BenchmarkRow>,
+    pub fastest_url: Option<String>,
+    // 失敗したターゲットが abort_after_failures に達して残りを測定しなかった場合は true
+    #[serde(default)]
+    pub aborted: bool,
+    #[serde(default)]
+    pub skipped_urls: Vec<String>,
+}
+
+// 複数ターゲットを同じ回数ずつ測定し、比較表を返す
+#[tauri::command]
+pub async fn run_benchmark(
+    app: AppHandle,
+    urls: Vec<String>,
+    count: Option<u32>,
+    ignore_tls_errors: Option<bool>,
+    stream_results: Option<bool>,
+    abort_after_failures: Option<u32>,
+) -> Result<BenchmarkReport, String> {
+    let count = count.unwrap_or(DEFAULT_COUNT);
+    if !(1..=MAX_COUNT).contains(&count) {
+        return Err(format!(
+            "測定回数は 1 から {} の範囲で指定してください",
+            MAX_COUNT
+        ));
+    }
+    if urls.is_empty() || urls.len() > MAX_TARGETS {
+        return Err(format!(
+            "比較するURLは 1 から {} 個の範囲で指定してください",
+            MAX_TARGETS
+        ));
+    }
+    for url in &urls {
+        crate::validate_url(url)?;
+    }
+    ResultStream::validate_abort_after_failures(abort_after_failures)?;
+
+    let target = format!("{} targets", urls.len());
+    run_operation(
+        &app,
+        OperationKind::Ping,
+        &target,
+        execute_benchmark(
+            app.clone(),
+            urls,
+            count,
+            ignore_tls_errors.unwrap_or(false),
+            stream_results.unwrap_or(false),
+            abort_after_failures,
+        ),
+    )
+    .await
+}
+
+async fn execute_benchmark(
+    app: AppHandle,
+    urls: Vec<String>,
+    count: u32,
+    ignore_tls_errors: bool,
+    stream_results: bool,
+    abort_after_failures: Option<u32>,
+) -> Result<BenchmarkReport, String> {
+    if ignore_tls_errors {
+        crate::log_security_warning("TLS証明書検証が無効化されています");
+    }
+
+    // ターゲットごとに順番に測定（同時に行うと互いの遅延に影響するため）
+    let total = urls.len();
+    let total_attempts = (total as u32 * count) as f64;
+    let mut rows = Vec::new();
+    let mut stream = ResultStream::new(
+        app,
+        BENCHMARK_ROW_EVENT,
+        total,
+        stream_results,
+        abort_after_failures,
+    );
+    let mut skipped_urls = Vec::new();
+    for (index, url) in urls.into_iter().enumerate() {
+        if stream.should_abort() {
+            skipped_urls.push(url);
+            continue;
+        }
+        let done = (index as u32 * count) as f64;
+        let row = benchmark_target(url, count, ignore_tls_errors, done, total_attempts).await;
+        stream.push(index, &row, is_failed(&row));
+        rows.push(row);
+    }
+
+    let fastest_url = rows
+        .iter()
+        .filter_map(|row| best_avg_ms(row).map(|avg| (row, avg)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(row, _)| row.url.clone());
+
+    Ok(BenchmarkReport {
+        count,
+        rows,
+        fastest_url,
+        aborted: !skipped_urls.is_empty(),
+        skipped_urls,
+    })
+}
+
+// どちらのファミリでも1回も成功しなかったターゲット
+fn is_failed(row: &BenchmarkRow) -> bool {
+    row.ipv4.successes == 0 && row.ipv6.successes == 0
+}
+
+async fn benchmark_target(
+    url: String,
+    count: u32,
+    ignore_tls_errors: bool,
+    done: f64,
+    total_attempts: f64,
+) -> BenchmarkRow {
+    let mut row = BenchmarkRow {
+        protocol: url.split("://").next().unwrap_or("").to_string(),
+        url,
+        ipv4_address: None,
+        ipv6_address: None,
+        ipv4: family_stats(&[]),
+        ipv6: family_stats(&[]),
+        fastest_family: None,
+        error_message: None,
+    };
+
+    let parsed_url = match Url::parse(&row.url) {
+        Ok(u) => u,
+        Err(e) => {
+            row.error_message = Some(format!("無効なURL: {}", e));
+            return row;
+        }
+    };
+    let Some(host) = parsed_url.host_str().map(|h| h.to_string()) else {
+        row.error_message = Some("URLからホスト名を抽出できません".to_string());
+        return row;
+    };
+    if let Err(e) = crate::validate_hostname(&host) {
+        row.error_message = Some(e);
+        return row;
+    }
+
+    // 全試行で同じアドレスに接続するよう、名前解決は最初の1回のみ
+    let dns_result = crate::resolve_dns(&host).await;
+    row.ipv4_address = dns_result.ipv4_addresses.first().cloned();
+    row.ipv6_address = dns_result.ipv6_addresses.first().cloned();
+    if row.ipv4_address.is_none() && row.ipv6_address.is_none() {
+        row.error_message = Some(
+            dns_result
+                .failure
+                .map(|f| f.message)
+                .unwrap_or_else(|| format!("{} を名前解決できません", host)),
+        );
+        return row;
+    }
+
+    let mut ipv4_samples = Vec::new();
+    let mut ipv6_samples = Vec::new();
+    for attempt in 0..count {
+        report_progress((done + attempt as f64) * 100.0 / total_attempts);
+        let request = http_client::RequestOptions::default();
+        let (ipv4, ipv6) = tokio::join!(
+            crate::connect_to_ip_with_host(
+                row.url.clone(),
+                &request,
+                &dns_result.ipv4_addresses,
+                &host,
+                ignore_tls_errors,
+                parsed_url.port(),
+                false,
+                None,
+                http_client::DEFAULT_TIMEOUT_SECS,
+            ),
+            crate::connect_to_ip_with_host(
+                row.url.clone(),
+                &request,
+                &dns_result.ipv6_addresses,
+                &host,
+                ignore_tls_errors,
+                parsed_url.port(),
+                false,
+                None,
+                http_client::DEFAULT_TIMEOUT_SECS,
+            ),
+        );
+        ipv4_samples.extend(to_sample(&ipv4));
+        ipv6_samples.extend(to_sample(&ipv6));
+    }
+
+    row.ipv4 = family_stats(&ipv4_samples);
+    row.ipv6 = family_stats(&ipv6_samples);
+    row.fastest_family = match (row.ipv4.avg_ms, row.ipv6.avg_ms) {
+        (Some(v4), Some(v6)) => Some(if v6 <= v4 { 6 } else { 4 }),
+        (Some(_), None) => Some(4),
+        (None, Some(_)) => Some(6),
+        (None, None) => None,
+    };
+    row
+}
+
+// アドレスがなく接続を試行していない結果は集計から除外
+fn to_sample(result: &HttpPingResult) -> Option<PingSample> {
+    result.ip_address.as_ref()?;
+    Some(PingSample {
+        recorded_at: Local::now(),
+        success: result.success,
+        response_time_ms: result.response_time_ms,
+    })
+}
+
+fn family_stats(samples: &[PingSample]) -> BenchmarkFamilyStats {
+    let loss = compute_loss_stats(samples);
+    let mut latencies: Vec<u64> = samples
+        .iter()
+        .filter(|s| s.success)
+        .filter_map(|s| s.response_time_ms)
+        .collect();
+    latencies.sort_unstable();
+    let latency_and_jitter = latency_and_jitter(samples);
+
+    BenchmarkFamilyStats {
+        attempts: loss.total,
+        successes: loss.total - loss.failures,
+        success_rate: if loss.total > 0 {
+            100.0 - loss.loss_percent
+        } else {
+            0.0
+        },
+        min_ms: latencies.first().copied(),
+        avg_ms: latency_and_jitter.map(|(avg, _)| avg),
+        median_ms: latencies.get(latencies.len() / 2).copied(),
+        max_ms: latencies.last().copied(),
+        jitter_ms: latency_and_jitter.map(|(_, jitter)| jitter),
+    }
+}
+
+// 速い方のファミリの平均応答時間
+fn best_avg_ms(row: &BenchmarkRow) -> Option<f64> {
+    match (row.ipv4.avg_ms, row.ipv6.avg_ms) {
+        (Some(v4), Some(v6)) => Some(v4.min(v6)),
+        (v4, v6) => v4.or(v6),
+    }
+}
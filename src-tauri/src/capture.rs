@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager};
+
+use crate::process::run_command;
+
+// キャプチャファイルの保存先（アプリデータディレクトリ配下）
+const CAPTURE_DIR_NAME: &str = "captures";
+
+// pktmon のフィルタ名（他のツールが追加したフィルタと区別するため）
+const FILTER_NAME: &str = "ghttpping";
+
+// pktmon のセッションはシステムで1つのため、同時に1件のみ実行
+static CAPTURE_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PacketCapture {
+    pub tool: String,
+    pub filters: Vec<String>,
+    pub etl_path: Option<String>,
+    pub pcapng_path: Option<String>,
+    pub started_at: Option<String>,
+    pub stopped_at: Option<String>,
+    pub error_message: Option<String>,
+}
+
+// 実行中のキャプチャ（停止せずに破棄された場合も pktmon を停止する）
+pub struct CaptureSession {
+    etl_path: PathBuf,
+    filters: Vec<String>,
+    started_at: String,
+    stopped: bool,
+}
+
+impl Drop for CaptureSession {
+    fn drop(&mut self) {
+        if self.stopped {
+            return;
+        }
+        // 測定が中止された場合などはバックグラウンドで停止
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn(async {
+                stop_pktmon().await;
+                CAPTURE_RUNNING.store(false, Ordering::SeqCst);
+            });
+        } else {
+            CAPTURE_RUNNING.store(false, Ordering::SeqCst);
+        }
+    }
+}
+
+async fn pktmon(args: &[&str]) -> Result<String, String> {
+    let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    let output = run_command("pktmon", &args).await.map_err(|e| {
+        format!(
+            "pktmon を実行できません（Windows 10 バージョン 2004 以降が必要です）: {}",
+            e
+        )
+    })?;
+    let stdout = crate::decode_command_output(&output.stdout);
+    if output.status.success() {
+        return Ok(stdout);
+    }
+    let stderr = crate::decode_command_output(&output.stderr);
+    let message = if stderr.trim().is_empty() {
+        stdout.trim().to_string()
+    } else {
+        stderr.trim().to_string()
+    };
+    Err(format!("pktmon {} に失敗: {}", args.join(" "), message))
+}
+
+// pktmon filter remove はすべてのフィルタを削除するため、このアプリのフィルタのみの場合に限り削除する
+async fn stop_pktmon() {
+    if let Err(e) = pktmon(&["stop"]).await {
+        eprintln!("Failed to stop pktmon: {}", e);
+    }
+    match pktmon(&["filter", "list"]).await {
+        Ok(list) if filter_names(&list).iter().all(|name| name == FILTER_NAME) => {
+            if let Err(e) = pktmon(&["filter", "remove"]).await {
+                eprintln!("Failed to remove pktmon filters: {}", e);
+            }
+        }
+        Ok(_) => eprintln!("Kept pktmon filters because filters from other tools were added"),
+        Err(e) => eprintln!("Failed to list pktmon filters: {}", e),
+    }
+}
+
+// pktmon filter list の各行（"1 ghttpping  10.0.0.1  443" など）の名前
+// 名前のないフィルタは 2 列目が名前以外になるため、このアプリのもの以外として扱われる
+fn filter_names(list: &str) -> Vec<String> {
+    list.lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            columns.next()?.parse::<u32>().ok()?;
+            Some(columns.next().unwrap_or_default().to_string())
+        })
+        .collect()
+}
+
+// セッションとフィルタはシステムで共有されるため、他のツールが使用中の場合は停止・削除しないよう開始しない
+// （最初の pktmon 操作のため、失敗した場合は権限不足の可能性を案内）
+async fn ensure_pktmon_unused() -> Result<(), String> {
+    let list = pktmon(&["filter", "list"])
+        .await
+        .map_err(|e| format!("{}（パケットキャプチャには管理者権限が必要です）", e))?;
+    let foreign = filter_names(&list)
+        .iter()
+        .filter(|name| *name != FILTER_NAME)
+        .count();
+    if foreign > 0 {
+        return Err(format!(
+            "pktmon に他のツールのフィルタが {} 件設定されているため、パケットキャプチャを開始できません",
+            foreign
+        ));
+    }
+    // 実行中でない場合も終了コードが 0 以外になることがあるため、出力の内容で判定する
+    let status = pktmon(&["status"]).await.unwrap_or_else(|e| e);
+    if !status.to_lowercase().contains("not running") {
+        return Err(
+            "pktmon のキャプチャが実行中のため、パケットキャプチャを開始できません".to_string(),
+        );
+    }
+    Ok(())
+}
+
+fn capture_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("アプリデータディレクトリの取得に失敗: {}", e))?
+        .join(CAPTURE_DIR_NAME);
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("キャプチャ保存ディレクトリの作成に失敗: {}", e))?;
+    let file_name = format!(
+        "ping-{}.etl",
+        chrono::Local::now().format("%Y%m%d-%H%M%S%3f")
+    );
+    Ok(dir.join(file_name))
+}
+
+// 接続先の IP アドレス・ポートに絞ってキャプチャを開始
+pub async fn start_capture(
+    app: &AppHandle,
+    ip_addresses: &[&str],
+    port: u16,
+) -> Result<CaptureSession, String> {
+    if ip_addresses.is_empty() {
+        return Err("キャプチャ対象の IP アドレスがありません".to_string());
+    }
+    if CAPTURE_RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("別のパケットキャプチャが実行中です".to_string());
+    }
+
+    let etl_path = match ensure_pktmon_unused()
+        .await
+        .and_then(|()| capture_file_path(app))
+    {
+        Ok(path) => path,
+        Err(e) => {
+            CAPTURE_RUNNING.store(false, Ordering::SeqCst);
+            return Err(e);
+        }
+    };
+    // ここから先で失敗した場合は Drop で pktmon の停止とフィルタ削除を行う
+    let mut session = CaptureSession {
+        etl_path,
+        filters: ip_addresses
+            .iter()
+            .map(|ip| format!("{} port {}", ip, port))
+            .collect(),
+        started_at: crate::now_rfc3339(),
+        stopped: false,
+    };
+
+    // 以前の測定のフィルタが残っているとキャプチャ対象が変わるため先に削除（他のツールのフィルタがないことは確認済み）
+    pktmon(&["filter", "remove"]).await?;
+    let port = port.to_string();
+    for ip in ip_addresses {
+        pktmon(&["filter", "add", FILTER_NAME, "-i", ip, "-p", &port]).await?;
+    }
+    let etl_path = session.etl_path.to_string_lossy().to_string();
+    pktmon(&[
+        "start",
+        "--capture",
+        "--pkt-size",
+        "0",
+        "--file-name",
+        &etl_path,
+    ])
+    .await?;
+    session.started_at = crate::now_rfc3339();
+    Ok(session)
+}
+
+impl CaptureSession {
+    // キャプチャを停止し、Wireshark で開ける pcapng に変換
+    pub async fn stop(mut self) -> PacketCapture {
+        stop_pktmon().await;
+        self.stopped = true;
+        CAPTURE_RUNNING.store(false, Ordering::SeqCst);
+        let stopped_at = crate::now_rfc3339();
+
+        let etl_path = self.etl_path.to_string_lossy().to_string();
+        let pcapng_path = self
+            .etl_path
+            .with_extension("pcapng")
+            .to_string_lossy()
+            .to_string();
+        let (pcapng_path, error_message) =
+            match pktmon(&["etl2pcap", &etl_path, "--out", &pcapng_path]).await {
+                Ok(_) => (Some(pcapng_path), None),
+                Err(e) => (None, Some(format!("pcapng への変換に失敗: {}", e))),
+            };
+
+        PacketCapture {
+            tool: "pktmon".to_string(),
+            filters: self.filters.clone(),
+            etl_path: Some(etl_path),
+            pcapng_path,
+            started_at: Some(self.started_at.clone()),
+            stopped_at: Some(stopped_at),
+            error_message,
+        }
+    }
+}
+
+// キャプチャを開始できなかった場合の結果
+pub fn failed_capture(ip_addresses: &[&str], port: u16, error: String) -> PacketCapture {
+    PacketCapture {
+        tool: "pktmon".to_string(),
+        filters: ip_addresses
+            .iter()
+            .map(|ip| format!("{} port {}", ip, port))
+            .collect(),
+        etl_path: None,
+        pcapng_path: None,
+        started_at: None,
+        stopped_at: None,
+        error_message: Some(error),
+    }
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::process::run_command_with_stdin;
+use crate::redact::redact_value;
+use crate::{HttpPingDualResult, HttpPingResult};
+
+// 長すぎて貼り付けに向かない項目
+const EXCLUDED_KEYS: [&str; 2] = ["verbose_log", "verbose_info"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CopyFormat {
+    Markdown,
+    PlainText,
+    OneLine,
+}
+
+#[tauri::command]
+pub async fn copy_result(
+    data: Value,
+    format: CopyFormat,
+    redact: Option<bool>,
+) -> Result<String, String> {
+    let mut data = data;
+    if redact.unwrap_or(false) {
+        redact_value(&mut data);
+    }
+    let text = format_result(&data, format);
+
+    // clip.exe は BOM 付き UTF-16LE を受け付ける
+    let mut input = vec![0xff, 0xfe];
+    input.extend(text.encode_utf16().flat_map(|u| u.to_le_bytes()));
+    let output = run_command_with_stdin("clip.exe", &[], &input)
+        .await
+        .map_err(|e| format!("クリップボードへのコピーに失敗: {}", e))?;
+    if !output.status.success() {
+        return Err(
+            "クリップボードへのコピーに失敗しました。他のアプリがクリップボードを使用していないか確認してください"
+                .to_string(),
+        );
+    }
+
+    Ok(text)
+}
+
+// 結果を指定形式の文字列に変換（HTTP Ping の結果は専用の表形式）
+pub fn format_result(data: &Value, format: CopyFormat) -> String {
+    if let Ok(ping) = serde_json::from_value::<HttpPingDualResult>(data.clone()) {
+        return format_ping(&ping, format);
+    }
+
+    let rows = flatten(data);
+    match format {
+        CopyFormat::Markdown => {
+            let mut lines = vec!["| 項目 | 値 |".to_string(), "| --- | --- |".to_string()];
+            lines.extend(
+                rows.iter()
+                    .map(|(k, v)| format!("| {} | {} |", escape_markdown(k), escape_markdown(v))),
+            );
+            lines.join("\n")
+        }
+        CopyFormat::PlainText => rows
+            .iter()
+            .map(|(k, v)| format!("{}: {}", k, v))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        CopyFormat::OneLine => rows
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, v.replace('\n', " ")))
+            .collect::<Vec<_>>()
+            .join("; "),
+    }
+}
+
+fn format_ping(ping: &HttpPingDualResult, format: CopyFormat) -> String {
+    // プロキシが名前を解決した場合はファミリごとの結果がないため、その 1 件のみ
+    let families = match &ping.via_proxy {
+        Some(via_proxy) => vec![("プロキシ経由", via_proxy)],
+        None => vec![("IPv4", &ping.ipv4), ("IPv6", &ping.ipv6)],
+    };
+    match format {
+        CopyFormat::Markdown => {
+            let mut lines = vec![
+                format!("**{}**", escape_markdown(&ping.url)),
+                String::new(),
+                "| IP | アドレス | ステータス | 応答時間 | 結果 |".to_string(),
+                "| --- | --- | --- | --- | --- |".to_string(),
+            ];
+            for (family, result) in families {
+                lines.push(format!(
+                    "| {} | {} | {} | {} | {} |",
+                    family,
+                    escape_markdown(result.ip_address.as_deref().unwrap_or("-")),
+                    status_text(result),
+                    time_text(result),
+                    escape_markdown(&outcome_text(result)),
+                ));
+            }
+            lines.join("\n")
+        }
+        CopyFormat::PlainText => {
+            let mut lines = vec![ping.url.clone()];
+            for (family, result) in families {
+                lines.push(format!(
+                    "{}: {} ステータス {} 応答時間 {} {}",
+                    family,
+                    result.ip_address.as_deref().unwrap_or("-"),
+                    status_text(result),
+                    time_text(result),
+                    outcome_text(result),
+                ));
+            }
+            lines.join("\n")
+        }
+        CopyFormat::OneLine => {
+            let parts: Vec<String> = families
+                .iter()
+                .map(|(family, result)| {
+                    if result.success {
+                        format!("{} {} ({})", family, status_text(result), time_text(result))
+                    } else {
+                        format!("{} {}", family, outcome_text(result).replace('\n', " "))
+                    }
+                })
+                .collect();
+            format!("{} {}", ping.url, parts.join(" / "))
+        }
+    }
+}
+
+fn status_text(result: &HttpPingResult) -> String {
+    result
+        .status_code
+        .map(|c| c.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn time_text(result: &HttpPingResult) -> String {
+    result
+        .response_time_ms
+        .map(|ms| format!("{}ms", ms))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn outcome_text(result: &HttpPingResult) -> String {
+    if result.success {
+        "成功".to_string()
+    } else {
+        format!(
+            "失敗: {}",
+            result.error_message.as_deref().unwrap_or("不明なエラー")
+        )
+    }
+}
+
+// JSON を「パス: 値」の組に平坦化
+fn flatten(data: &Value) -> Vec<(String, String)> {
+    let mut rows = Vec::new();
+    flatten_into(data, String::new(), &mut rows);
+    rows
+}
+
+fn flatten_into(value: &Value, path: String, rows: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, item) in map {
+                if EXCLUDED_KEYS.contains(&key.as_str()) {
+                    continue;
+                }
+                let child = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{}.{}", path, key)
+                };
+                flatten_into(item, child, rows);
+            }
+        }
+        Value::Array(items) if items.is_empty() => rows.push((path, "-".to_string())),
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                flatten_into(item, format!("{}[{}]", path, i), rows);
+            }
+        }
+        Value::Null => rows.push((path, "-".to_string())),
+        Value::String(s) => rows.push((path, s.clone())),
+        other => rows.push((path, other.to_string())),
+    }
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', "<br>")
+}
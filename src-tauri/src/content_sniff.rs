@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+
+// 種類の判定に使う応答本文の先頭のバイト数
+pub const SNIFF_BYTES: usize = 1024;
+
+// 応答の Content-Type と、本文の先頭から判定した実際の種類
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseContent {
+    // Content-Type のメディアタイプ（小文字、パラメータを除く）
+    pub declared_type: Option<String>,
+    pub charset: Option<String>,
+    // 本文の先頭から判定した種類（本文がない、または圧縮されている場合は None）
+    pub sniffed_type: Option<String>,
+    // 宣言と実際の内容が食い違う場合の説明（中継機器のエラーページなどの検出用）
+    pub mismatch: Option<String>,
+}
+
+// 応答ヘッダ（名前は小文字）と本文の先頭から内容の種類を調べる（どちらもない場合は None）
+pub fn inspect(headers: &[(String, String)], prefix: &[u8]) -> Option<ResponseContent> {
+    let content_type = headers
+        .iter()
+        .find(|(name, _)| name == "content-type")
+        .map(|(_, value)| value.as_str());
+    if content_type.is_none() && prefix.is_empty() {
+        return None;
+    }
+    let (declared_type, charset) = content_type.map(parse_content_type).unwrap_or_default();
+
+    // 圧縮された本文は展開しないと判定できない
+    let encoded = headers.iter().any(|(name, value)| {
+        name == "content-encoding" && !value.trim().eq_ignore_ascii_case("identity")
+    });
+    let sniffed_type = (!encoded && !prefix.is_empty()).then(|| sniff(prefix).to_string());
+
+    let mismatch = type_mismatch(declared_type.as_deref(), sniffed_type.as_deref())
+        .or_else(|| charset_mismatch(charset.as_deref(), sniffed_type.as_deref(), prefix));
+    Some(ResponseContent {
+        declared_type,
+        charset,
+        sniffed_type,
+        mismatch,
+    })
+}
+
+// "text/html; charset=UTF-8" をメディアタイプと文字コードに分ける
+fn parse_content_type(value: &str) -> (Option<String>, Option<String>) {
+    let mut parts = value.split(';');
+    let media_type = parts
+        .next()
+        .map(|t| t.trim().to_ascii_lowercase())
+        .filter(|t| !t.is_empty());
+    let charset = parts.find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches('"').to_ascii_lowercase())
+            .filter(|v| !v.is_empty())
+    });
+    (media_type, charset)
+}
+
+// 先頭のバイト列から種類を判定（WHATWG の MIME Sniffing の主なパターンと JSON）
+fn sniff(prefix: &[u8]) -> &'static str {
+    const SIGNATURES: [(&[u8], &str); 10] = [
+        (b"%PDF-", "application/pdf"),
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"\x1f\x8b\x08", "application/gzip"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x00asm", "application/wasm"),
+        (b"\x1aE\xdf\xa3", "video/webm"),
+        (b"OggS\x00", "application/ogg"),
+    ];
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(sig, _)| prefix.starts_with(sig)) {
+        return mime;
+    }
+    if prefix.starts_with(b"RIFF") && prefix.get(8..12) == Some(b"WEBP") {
+        return "image/webp";
+    }
+
+    // 先頭の BOM と空白を除いてテキストの種類を判定
+    let text = prefix.strip_prefix(b"\xef\xbb\xbf").unwrap_or(prefix);
+    let text = text
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .map(|i| &text[i..])
+        .unwrap_or_default();
+    const HTML_TAGS: [&[u8]; 12] = [
+        b"<!doctype html",
+        b"<html",
+        b"<head",
+        b"<body",
+        b"<script",
+        b"<title",
+        b"<iframe",
+        b"<table",
+        b"<div",
+        b"<style",
+        b"<h1",
+        b"<!--",
+    ];
+    let lower: Vec<u8> = text.iter().take(16).map(u8::to_ascii_lowercase).collect();
+    if HTML_TAGS.iter().any(|tag| lower.starts_with(tag)) {
+        return "text/html";
+    }
+    if lower.starts_with(b"<?xml") {
+        return "text/xml";
+    }
+    if matches!(text.first(), Some(b'{') | Some(b'[')) {
+        return "application/json";
+    }
+    if is_text(prefix) {
+        "text/plain"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+// 制御文字（改行・タブなどを除く）を含まなければテキストとみなす
+fn is_text(prefix: &[u8]) -> bool {
+    !prefix
+        .iter()
+        .any(|&b| b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0c | 0x1b))
+}
+
+fn is_json_type(media_type: &str) -> bool {
+    media_type == "application/json" || media_type.ends_with("+json")
+}
+
+fn is_xml_type(media_type: &str) -> bool {
+    media_type.ends_with("/xml") || media_type.ends_with("+xml")
+}
+
+fn is_textual_type(media_type: &str) -> bool {
+    media_type.starts_with("text/")
+        || is_json_type(media_type)
+        || is_xml_type(media_type)
+        || media_type.ends_with("javascript")
+}
+
+// 判定した種類が宣言された Content-Type と両立しない場合の説明
+fn type_mismatch(declared: Option<&str>, sniffed: Option<&str>) -> Option<String> {
+    let sniffed = sniffed?;
+    // 宣言がない場合や汎用のバイナリ、単なるテキストは判定しない
+    let declared = declared.filter(|d| *d != "application/octet-stream")?;
+    let compatible = match sniffed {
+        "text/plain" => is_textual_type(declared),
+        "text/html" => declared == "text/html" || declared == "application/xhtml+xml",
+        "text/xml" => is_xml_type(declared) || declared == "text/html",
+        "application/json" => {
+            is_json_type(declared) || declared == "text/plain" || declared.ends_with("javascript")
+        }
+        "application/octet-stream" => !is_textual_type(declared),
+        _ => declared == sniffed || declared.split('/').next() == sniffed.split('/').next(),
+    };
+    if compatible {
+        return None;
+    }
+    Some(if sniffed == "text/html" {
+        format!(
+            "Content-Type は {} ですが応答本文は HTML です（プロキシやWAFなどのエラーページの可能性があります）",
+            declared
+        )
+    } else {
+        format!(
+            "Content-Type は {} ですが応答本文は {} のようです",
+            declared, sniffed
+        )
+    })
+}
+
+// UTF-8 と宣言されたテキストに UTF-8 として不正なバイトが含まれる場合の説明
+fn charset_mismatch(charset: Option<&str>, sniffed: Option<&str>, prefix: &[u8]) -> Option<String> {
+    let charset = charset?;
+    if !matches!(charset, "utf-8" | "utf8")
+        || matches!(sniffed, None | Some("application/octet-stream"))
+    {
+        return None;
+    }
+    match std::str::from_utf8(prefix) {
+        // 末尾で文字の途中まで受信した場合は不正とみなさない
+        Err(e) if e.error_len().is_some() => Some(format!(
+            "文字コードは {} と宣言されていますが、応答本文に UTF-8 として不正なバイトがあります",
+            charset
+        )),
+        _ => None,
+    }
+}
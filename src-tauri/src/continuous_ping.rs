@@ -0,0 +1,554 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::task::AbortHandle;
+use url::Url;
+
+use crate::adaptive_timeout::{self, AdaptiveTimeout};
+use crate::anomaly::{self, AnomalyDetector, LatencyAnomaly};
+use crate::history::load_history;
+use crate::maintenance::{self, MonitorKind};
+use crate::monitor_group::{self, MonitorDependency};
+use crate::operations::{run_operation, OperationKind, OPERATION_CANCELLED_MESSAGE};
+use crate::stats::ping_samples_by_target;
+use crate::{http_client, template, webhook, HttpPingResult};
+
+// 1回分の結果をフロントエンドへ通知するイベント名
+pub const CONTINUOUS_PING_RESULT_EVENT: &str = "continuous-ping-result";
+
+// 普段より統計的に遅い応答を検出したときに通知するイベント名
+pub const LATENCY_ANOMALY_EVENT: &str = "latency-anomaly";
+
+// 送信間隔（ミリ秒）
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+const MIN_INTERVAL_MS: u64 = 200;
+const MAX_INTERVAL_MS: u64 = 3_600_000;
+
+// 問題の有無が変わらない間は、Webhook をこの回数ごとにのみ送信する
+const WEBHOOK_SAMPLE_INTERVAL: u64 = 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContinuousPingEvent {
+    pub sequence: u64,
+    pub sent_at: String,
+    pub url: String,
+    // メンテナンス時間帯中の結果には時間帯の名前を付ける
+    pub maintenance_window: Option<String>,
+    pub ipv4: HttpPingResult,
+    pub ipv6: HttpPingResult,
+    // この回の応答時間がそれまでの基準から外れたファミリ
+    #[serde(default)]
+    pub anomalies: Vec<LatencyAnomaly>,
+    // この回に使ったタイムアウト（秒、自動調整する場合はファミリごとに異なる）
+    #[serde(default)]
+    pub ipv4_timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub ipv6_timeout_secs: Option<u64>,
+    // 依存先のグループが同じ時期に停止していたため通知を抑止した場合のグループ名（原因は上流）
+    #[serde(default)]
+    pub suppressed_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyAnomalyEvent {
+    pub url: String,
+    pub sequence: u64,
+    pub anomaly: LatencyAnomaly,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContinuousPingStatus {
+    pub running: bool,
+    pub paused: bool,
+    pub url: Option<String>,
+    pub interval_ms: Option<u64>,
+    // タイムアウトを応答時間の分布から自動調整しているか
+    #[serde(default)]
+    pub adaptive_timeout: bool,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    pub started_at: Option<String>,
+    pub sent: u64,
+    pub last_sent_at: Option<String>,
+}
+
+struct RunningPing {
+    url: String,
+    interval_ms: u64,
+    adaptive_timeout: bool,
+    dependency: MonitorDependency,
+    started_at: String,
+    abort_handle: AbortHandle,
+}
+
+#[derive(Default)]
+struct ContinuousPingState {
+    running: Option<RunningPing>,
+    // 一時停止中は送信を飛ばす（送信間隔と連番は再開後もそのまま）
+    paused: bool,
+    sent: u64,
+    last_sent_at: Option<String>,
+}
+
+// 連続測定の実行状態（Tauri の State として管理）
+#[derive(Default)]
+pub struct ContinuousPing {
+    state: Mutex<ContinuousPingState>,
+}
+
+impl ContinuousPing {
+    fn status(&self) -> Result<ContinuousPingStatus, String> {
+        let state = self
+            .state
+            .lock()
+            .map_err(|_| "連続測定の状態のロック取得に失敗".to_string())?;
+        Ok(ContinuousPingStatus {
+            running: state.running.is_some(),
+            paused: state.running.is_some() && state.paused,
+            url: state.running.as_ref().map(|r| r.url.clone()),
+            interval_ms: state.running.as_ref().map(|r| r.interval_ms),
+            adaptive_timeout: state.running.as_ref().is_some_and(|r| r.adaptive_timeout),
+            group: state
+                .running
+                .as_ref()
+                .and_then(|r| r.dependency.group.clone()),
+            depends_on: state
+                .running
+                .as_ref()
+                .map(|r| r.dependency.depends_on.clone())
+                .unwrap_or_default(),
+            started_at: state.running.as_ref().map(|r| r.started_at.clone()),
+            sent: state.sent,
+            last_sent_at: state.last_sent_at.clone(),
+        })
+    }
+
+    fn set_paused(&self, paused: bool) -> Result<(), String> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| "連続測定の状態のロック取得に失敗".to_string())?;
+        if state.running.is_none() {
+            return Err("連続測定は実行されていません".to_string());
+        }
+        state.paused = paused;
+        Ok(())
+    }
+}
+
+// 一定間隔で測定を続け、結果をイベントで通知（実行中の場合は新しい条件で再開）
+// adaptive_timeout を指定すると、timeout_secs を上限・min_timeout_secs を下限として
+// 測定履歴と直近の応答時間の p99 × 3 をタイムアウトにする
+// headers の値の {{counter}} などの変数は URL と同様に毎回展開する
+// group と depends_on を指定すると、依存先のグループの定期実行が停止している間は通知を抑止する
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn start_continuous_ping(
+    app: AppHandle,
+    continuous_ping: State<'_, ContinuousPing>,
+    url: String,
+    interval_ms: Option<u64>,
+    ignore_tls_errors: Option<bool>,
+    timeout_secs: Option<u64>,
+    anomaly_z_threshold: Option<f64>,
+    adaptive_timeout: Option<bool>,
+    min_timeout_secs: Option<u64>,
+    headers: Option<HashMap<String, String>>,
+    group: Option<String>,
+    depends_on: Option<Vec<String>>,
+) -> Result<ContinuousPingStatus, String> {
+    let interval_ms = interval_ms.unwrap_or(DEFAULT_INTERVAL_MS);
+    if !(MIN_INTERVAL_MS..=MAX_INTERVAL_MS).contains(&interval_ms) {
+        return Err(format!(
+            "送信間隔は {} から {} ミリ秒の範囲で指定してください",
+            MIN_INTERVAL_MS, MAX_INTERVAL_MS
+        ));
+    }
+    let timeout_secs = timeout_secs.unwrap_or(http_client::DEFAULT_TIMEOUT_SECS);
+    http_client::validate_timeout_secs(timeout_secs)?;
+    let anomaly_z_threshold = anomaly_z_threshold.unwrap_or(anomaly::DEFAULT_Z_THRESHOLD);
+    anomaly::validate_z_threshold(anomaly_z_threshold)?;
+    let min_timeout_secs = if adaptive_timeout.unwrap_or(false) {
+        let min_timeout_secs = min_timeout_secs.unwrap_or(http_client::MIN_TIMEOUT_SECS);
+        adaptive_timeout::validate_bounds(min_timeout_secs, timeout_secs)?;
+        Some(min_timeout_secs)
+    } else {
+        None
+    };
+
+    // 開始前に URL とヘッダを検証し、誤りがあればイベントではなくエラーとして返す
+    let request = http_client::RequestOptions {
+        headers: http_client::parse_custom_headers(headers.unwrap_or_default())?,
+        ..Default::default()
+    };
+    let (expanded, _) = template::expand_request(&url, &request)?;
+    crate::validate_url(&expanded)?;
+    let host = Url::parse(&expanded)
+        .map_err(|e| format!("無効なURL: {}", e))?
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+
+    let ignore_tls_errors = ignore_tls_errors.unwrap_or(false);
+    if ignore_tls_errors {
+        crate::log_security_warning("TLS証明書検証が無効化されています");
+    }
+    let dependency = monitor_group::register(
+        &app,
+        MonitorKind::ContinuousPing,
+        group,
+        depends_on.unwrap_or_default(),
+    )?;
+
+    {
+        let mut state = continuous_ping
+            .state
+            .lock()
+            .map_err(|_| "連続測定の状態のロック取得に失敗".to_string())?;
+        if let Some(running) = state.running.take() {
+            running.abort_handle.abort();
+        }
+        let handle = tokio::spawn(ping_loop(
+            app.clone(),
+            url.clone(),
+            request,
+            interval_ms,
+            ignore_tls_errors,
+            timeout_secs,
+            min_timeout_secs,
+            anomaly_z_threshold,
+        ));
+        state.running = Some(RunningPing {
+            url,
+            interval_ms,
+            adaptive_timeout: min_timeout_secs.is_some(),
+            dependency,
+            started_at: crate::now_rfc3339(),
+            abort_handle: handle.abort_handle(),
+        });
+        state.paused = false;
+        state.sent = 0;
+        state.last_sent_at = None;
+    }
+
+    continuous_ping.status()
+}
+
+#[tauri::command]
+pub async fn stop_continuous_ping(
+    app: AppHandle,
+    continuous_ping: State<'_, ContinuousPing>,
+) -> Result<ContinuousPingStatus, String> {
+    monitor_group::unregister(&app, MonitorKind::ContinuousPing);
+    {
+        let mut state = continuous_ping
+            .state
+            .lock()
+            .map_err(|_| "連続測定の状態のロック取得に失敗".to_string())?;
+        if let Some(running) = state.running.take() {
+            running.abort_handle.abort();
+        }
+    }
+    continuous_ping.status()
+}
+
+#[tauri::command]
+pub async fn get_continuous_ping_status(
+    continuous_ping: State<'_, ContinuousPing>,
+) -> Result<ContinuousPingStatus, String> {
+    continuous_ping.status()
+}
+
+#[tauri::command]
+pub async fn pause_continuous_ping(
+    continuous_ping: State<'_, ContinuousPing>,
+) -> Result<ContinuousPingStatus, String> {
+    continuous_ping.set_paused(true)?;
+    continuous_ping.status()
+}
+
+#[tauri::command]
+pub async fn resume_continuous_ping(
+    continuous_ping: State<'_, ContinuousPing>,
+) -> Result<ContinuousPingStatus, String> {
+    continuous_ping.set_paused(false)?;
+    continuous_ping.status()
+}
+
+// 開始時刻を基準にした間隔で送信（応答が間隔より遅れた回は飛ばし、送信時刻がずれないようにする）
+#[allow(clippy::too_many_arguments)]
+async fn ping_loop(
+    app: AppHandle,
+    url: String,
+    request: http_client::RequestOptions,
+    interval_ms: u64,
+    ignore_tls_errors: bool,
+    timeout_secs: u64,
+    min_timeout_secs: Option<u64>,
+    anomaly_z_threshold: f64,
+) {
+    let mut interval = tokio::time::interval(Duration::from_millis(interval_ms));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    let mut sequence = 0;
+    let mut previous_problem = false;
+    // 応答時間の基準は測定の開始からファミリごとに学習する
+    let mut detectors = [
+        AnomalyDetector::new(4, anomaly_z_threshold),
+        AnomalyDetector::new(6, anomaly_z_threshold),
+    ];
+    // 自動調整する場合は同じ URL の測定履歴から分布を引き継ぐ
+    let mut timeouts = min_timeout_secs.map(|min_timeout_secs| {
+        let mut timeouts = [
+            AdaptiveTimeout::new(min_timeout_secs, timeout_secs),
+            AdaptiveTimeout::new(min_timeout_secs, timeout_secs),
+        ];
+        let history = load_history(&app).unwrap_or_default();
+        if let Some((_, ipv4, ipv6)) = ping_samples_by_target(&history)
+            .into_iter()
+            .find(|(target, _, _)| target == &url)
+        {
+            timeouts[0].seed(&ipv4);
+            timeouts[1].seed(&ipv6);
+        }
+        timeouts
+    });
+
+    loop {
+        interval.tick().await;
+        let paused = app
+            .state::<ContinuousPing>()
+            .state
+            .lock()
+            .is_ok_and(|state| state.paused);
+        if paused {
+            continue;
+        }
+        sequence += 1;
+        let sent_at = crate::now_rfc3339();
+        let family_timeouts = match &timeouts {
+            Some([ipv4, ipv6]) => [ipv4.timeout_secs(), ipv6.timeout_secs()],
+            None => [timeout_secs, timeout_secs],
+        };
+
+        // {{timestamp}} などの変数は毎回展開し、長時間の測定中の DNS の変化も反映するため毎回名前解決
+        let mut event = match template::expand_request(&url, &request) {
+            Ok((expanded, request)) => {
+                // stop_all で中止できるよう、各回を操作として登録する
+                let tick_sent_at = sent_at.clone();
+                let tick = run_operation(&app, OperationKind::Ping, &url, async move {
+                    Ok(ping_once(
+                        &expanded,
+                        &request,
+                        sequence,
+                        tick_sent_at,
+                        ignore_tls_errors,
+                        family_timeouts,
+                    )
+                    .await)
+                })
+                .await;
+                match tick {
+                    Ok(event) => event,
+                    // 中止された回は送信しなかったものとして扱い、連続測定は次の回から続ける
+                    Err(e) if e == OPERATION_CANCELLED_MESSAGE => {
+                        sequence -= 1;
+                        continue;
+                    }
+                    Err(e) => failed_event(&url, sequence, sent_at, e),
+                }
+            }
+            Err(e) => failed_event(&url, sequence, sent_at, e),
+        };
+        event.maintenance_window = maintenance::active_window(&app, MonitorKind::ContinuousPing);
+        event.ipv4_timeout_secs = Some(family_timeouts[0]);
+        event.ipv6_timeout_secs = Some(family_timeouts[1]);
+        if let Some(timeouts) = timeouts.as_mut() {
+            // アドレスがなく接続していないファミリは分布に含めない
+            for (timeout, result) in timeouts.iter_mut().zip([&event.ipv4, &event.ipv6]) {
+                if result.ip_address.is_some() {
+                    timeout.observe(result.success, result.response_time_ms);
+                }
+            }
+        }
+        for (detector, result) in detectors.iter_mut().zip([&event.ipv4, &event.ipv6]) {
+            if !result.success {
+                continue;
+            }
+            if let Some(anomaly) = detector.observe(&event.sent_at, result.response_time_ms) {
+                event.anomalies.push(anomaly);
+            }
+        }
+
+        // 次の回までに結果がない場合も含め、2 回分の間隔の間は依存元から参照できるようにする
+        event.suppressed_by = monitor_group::evaluate(
+            &app,
+            MonitorKind::ContinuousPing,
+            has_failure(&event),
+            Duration::from_millis(interval_ms * 2),
+        );
+
+        let continuous_ping = app.state::<ContinuousPing>();
+        if let Ok(mut state) = continuous_ping.state.lock() {
+            state.sent = sequence;
+            state.last_sent_at = Some(event.sent_at.clone());
+        }
+
+        if let Err(e) = app.emit(CONTINUOUS_PING_RESULT_EVENT, &event) {
+            eprintln!("Failed to emit {}: {}", CONTINUOUS_PING_RESULT_EVENT, e);
+        }
+        let problem = event.maintenance_window.is_none()
+            && (has_failure(&event) || !event.anomalies.is_empty());
+        // 依存先の停止により抑止した回は問題として扱わず、抑止が解けた時点で改めて送信する
+        let alert = problem && event.suppressed_by.is_none();
+        // 毎回送信すると短い間隔では受信側に負荷がかかるため、問題の発生・解消時と一定回数ごとにのみ送信する
+        if alert != previous_problem || sequence % WEBHOOK_SAMPLE_INTERVAL == 0 {
+            webhook::notify(
+                &app,
+                MonitorKind::ContinuousPing,
+                &event,
+                problem,
+                event.suppressed_by.as_deref(),
+            );
+        }
+        previous_problem = alert;
+        // メンテナンス時間帯中と抑止した回は結果に含めるのみで通知しない
+        if event.maintenance_window.is_some() || event.suppressed_by.is_some() {
+            continue;
+        }
+        for anomaly in &event.anomalies {
+            let anomaly_event = LatencyAnomalyEvent {
+                url: event.url.clone(),
+                sequence,
+                anomaly: anomaly.clone(),
+            };
+            if let Err(e) = app.emit(LATENCY_ANOMALY_EVENT, &anomaly_event) {
+                eprintln!("Failed to emit {}: {}", LATENCY_ANOMALY_EVENT, e);
+            }
+        }
+    }
+}
+
+// アドレスがなく接続していないファミリ（IPv4 のみの対象の IPv6 など）は失敗に含めない
+// どちらのファミリにもアドレスがない場合は名前解決の失敗として扱う
+fn has_failure(event: &ContinuousPingEvent) -> bool {
+    let attempted: Vec<&HttpPingResult> = [&event.ipv4, &event.ipv6]
+        .into_iter()
+        .filter(|result| result.ip_address.is_some())
+        .collect();
+    attempted.is_empty() || attempted.iter().any(|result| !result.success)
+}
+
+async fn ping_once(
+    url: &str,
+    request: &http_client::RequestOptions,
+    sequence: u64,
+    sent_at: String,
+    ignore_tls_errors: bool,
+    timeouts_secs: [u64; 2],
+) -> ContinuousPingEvent {
+    let parsed_url = match Url::parse(url) {
+        Ok(u) => u,
+        Err(e) => return failed_event(url, sequence, sent_at, format!("無効なURL: {}", e)),
+    };
+    let Some(host) = parsed_url.host_str() else {
+        return failed_event(
+            url,
+            sequence,
+            sent_at,
+            "URLからホスト名を抽出できません".to_string(),
+        );
+    };
+
+    let dns_result = crate::resolve_dns(host).await;
+    let (ipv4, ipv6) = tokio::join!(
+        crate::connect_to_ip_with_host(
+            url.to_string(),
+            request,
+            &dns_result.ipv4_addresses,
+            host,
+            ignore_tls_errors,
+            parsed_url.port(),
+            false,
+            None,
+            timeouts_secs[0],
+        ),
+        crate::connect_to_ip_with_host(
+            url.to_string(),
+            request,
+            &dns_result.ipv6_addresses,
+            host,
+            ignore_tls_errors,
+            parsed_url.port(),
+            false,
+            None,
+            timeouts_secs[1],
+        ),
+    );
+
+    ContinuousPingEvent {
+        sequence,
+        sent_at,
+        url: url.to_string(),
+        maintenance_window: None,
+        anomalies: Vec::new(),
+        ipv4_timeout_secs: None,
+        ipv6_timeout_secs: None,
+        suppressed_by: None,
+        ipv4,
+        ipv6,
+    }
+}
+
+// 接続前に失敗した回も欠番にせず、両ファミリの失敗として通知
+fn failed_event(url: &str, sequence: u64, sent_at: String, error: String) -> ContinuousPingEvent {
+    let failed = HttpPingResult {
+        url: url.to_string(),
+        ip_address: None,
+        status_code: None,
+        response_time_ms: None,
+        success: false,
+        error_message: Some(error),
+        verbose_log: None,
+        verbose_info: None,
+        started_at: None,
+        finished_at: None,
+        timings: None,
+        alt_svc: None,
+        http_version: None,
+        tls_version: None,
+        cipher_suite: None,
+        downloaded_bytes: None,
+        download_bytes_per_sec: None,
+        download_truncated: false,
+        content: None,
+        response_headers: Vec::new(),
+        response_body_prefix: Vec::new(),
+        captured_headers: None,
+        header_assertions: Vec::new(),
+        body_assertions: Vec::new(),
+        attempts: Vec::new(),
+        redirects: Vec::new(),
+        curl_command: None,
+        second_request: None,
+        certificate: None,
+        warnings: Vec::new(),
+        proxy_auth: None,
+    };
+    ContinuousPingEvent {
+        sequence,
+        sent_at,
+        url: url.to_string(),
+        maintenance_window: None,
+        anomalies: Vec::new(),
+        ipv4_timeout_secs: None,
+        ipv6_timeout_secs: None,
+        suppressed_by: None,
+        ipv4: failed.clone(),
+        ipv6: failed,
+    }
+}
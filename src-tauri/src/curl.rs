@@ -0,0 +1,419 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::process::Output;
+use tokio::sync::OnceCell;
+use url::Url;
+
+use crate::http_client::{
+    HttpMethod, HttpVersion, RequestOptions, TlsVersion, SENSITIVE_HEADERS, USER_AGENT_VALUE,
+};
+use crate::process::run_command;
+use crate::proxy::ProxyAuthMethod;
+use crate::rate_limit::acquire_for_curl_args;
+use crate::redirect::MAX_REDIRECTS;
+
+// 機能ごとに必要な curl の機能（バージョンまたは --version の Features）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurlFeature {
+    Http2,
+    Http3,
+    ResolveIpv6Brackets,
+    ConnectTo,
+    TlsEarlyData,
+}
+
+const ALL_FEATURES: [CurlFeature; 5] = [
+    CurlFeature::Http2,
+    CurlFeature::Http3,
+    CurlFeature::ResolveIpv6Brackets,
+    CurlFeature::ConnectTo,
+    CurlFeature::TlsEarlyData,
+];
+
+impl CurlFeature {
+    // 対応した curl のバージョンと、--version の Features に必要な項目
+    fn requirement(self) -> ((u32, u32, u32), Option<&'static str>) {
+        match self {
+            CurlFeature::Http2 => ((7, 33, 0), Some("HTTP2")),
+            CurlFeature::Http3 => ((7, 88, 0), Some("HTTP3")),
+            CurlFeature::ResolveIpv6Brackets => ((7, 57, 0), None),
+            CurlFeature::ConnectTo => ((7, 49, 0), None),
+            CurlFeature::TlsEarlyData => ((8, 11, 0), None),
+        }
+    }
+}
+
+// curl の終了コードを原因ごとに分類したもの（結果の表示や再試行の判断に使う）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CurlErrorKind {
+    UnsupportedOption,
+    InvalidUrl,
+    ProxyResolveFailed,
+    HostResolveFailed,
+    ConnectFailed,
+    HttpError,
+    Timeout,
+    TooManyRedirects,
+    TransferFailed,
+    ProtocolError,
+    TlsHandshakeFailed,
+    CertificateInvalid,
+    Other,
+}
+
+impl CurlErrorKind {
+    // 終了コードの一覧は https://curl.se/libcurl/c/libcurl-errors.html
+    pub fn from_exit_code(code: Option<i32>) -> Self {
+        match code {
+            Some(1 | 2 | 4 | 48) => CurlErrorKind::UnsupportedOption,
+            Some(3) => CurlErrorKind::InvalidUrl,
+            Some(5) => CurlErrorKind::ProxyResolveFailed,
+            Some(6) => CurlErrorKind::HostResolveFailed,
+            Some(7 | 97) => CurlErrorKind::ConnectFailed,
+            Some(22) => CurlErrorKind::HttpError,
+            Some(28) => CurlErrorKind::Timeout,
+            Some(47) => CurlErrorKind::TooManyRedirects,
+            Some(18 | 52 | 55 | 56) => CurlErrorKind::TransferFailed,
+            Some(8 | 16 | 92 | 95) => CurlErrorKind::ProtocolError,
+            Some(35 | 53 | 54 | 58 | 59 | 64 | 66 | 80) => CurlErrorKind::TlsHandshakeFailed,
+            Some(51 | 60 | 77 | 82 | 83 | 90 | 91) => CurlErrorKind::CertificateInvalid,
+            _ => CurlErrorKind::Other,
+        }
+    }
+
+    // TLS/証明書関連のエラーか
+    pub fn is_tls(self) -> bool {
+        matches!(
+            self,
+            CurlErrorKind::TlsHandshakeFailed | CurlErrorKind::CertificateInvalid
+        )
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            CurlErrorKind::UnsupportedOption => {
+                "インストールされている curl が必要な機能に対応していません"
+            }
+            CurlErrorKind::InvalidUrl => "URL またはオプションの形式が正しくありません",
+            CurlErrorKind::ProxyResolveFailed => "プロキシサーバの名前を解決できません",
+            CurlErrorKind::HostResolveFailed => {
+                "接続先の名前を解決できません。ホスト名と DNS の設定を確認してください"
+            }
+            CurlErrorKind::ConnectFailed => {
+                "接続先に接続できません（接続拒否、経路なし、またはファイアウォールによる遮断）"
+            }
+            CurlErrorKind::HttpError => "サーバがエラーのステータスコードを返しました",
+            CurlErrorKind::Timeout => "時間内に応答がなく、タイムアウトしました",
+            CurlErrorKind::TooManyRedirects => "リダイレクトの回数が上限を超えました",
+            CurlErrorKind::TransferFailed => "通信の途中で接続が切断されました",
+            CurlErrorKind::ProtocolError => "HTTP の通信でプロトコルエラーが発生しました",
+            CurlErrorKind::TlsHandshakeFailed => {
+                "TLS ハンドシェイクに失敗しました（TLS バージョンや暗号方式の不一致、通信の妨害など）"
+            }
+            CurlErrorKind::CertificateInvalid => {
+                "サーバ証明書を検証できません（期限切れ、名前の不一致、信頼されていない発行元など）"
+            }
+            CurlErrorKind::Other => "curl の実行に失敗しました",
+        }
+    }
+}
+
+// 終了コードを利用者向けの説明に変換（元のコードも調査用に併記）
+pub fn describe_exit_code(code: Option<i32>) -> String {
+    format!(
+        "{} (curl 終了コード: {})",
+        CurlErrorKind::from_exit_code(code).description(),
+        code.unwrap_or(-1)
+    )
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurlCapabilities {
+    pub version: Option<String>,
+    pub features: Vec<String>,
+    pub unsupported_features: Vec<CurlFeature>,
+}
+
+impl CurlCapabilities {
+    pub fn supports(&self, feature: CurlFeature) -> bool {
+        !self.unsupported_features.contains(&feature)
+    }
+}
+
+// curl.exe はアプリ実行中に変わらないため、初回のみ確認
+static CAPABILITIES: OnceCell<CurlCapabilities> = OnceCell::const_new();
+
+// ドライラン中で未確認の場合に仮定する対応機能（すべて対応）
+static ASSUMED_CAPABILITIES: CurlCapabilities = CurlCapabilities {
+    version: None,
+    features: Vec::new(),
+    unsupported_features: Vec::new(),
+};
+
+// "curl 8.4.0 (Windows) libcurl/8.4.0 ..." からバージョン番号を取得
+fn parse_version(first_line: &str) -> Option<(u32, u32, u32)> {
+    let version = first_line.split_whitespace().nth(1)?;
+    let mut numbers = version.split('.').map(|part| {
+        part.chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>()
+            .parse::<u32>()
+            .ok()
+    });
+    Some((
+        numbers.next()??,
+        numbers.next()??,
+        numbers.next().flatten().unwrap_or(0),
+    ))
+}
+
+async fn detect_capabilities() -> CurlCapabilities {
+    let output = run_command("curl.exe", &["--version".to_string()]).await;
+    let stdout = match output {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).to_string()
+        }
+        Ok(output) => {
+            eprintln!("curl --version failed: {:?}", output.status.code());
+            String::new()
+        }
+        Err(e) => {
+            eprintln!("Failed to run curl --version: {}", e);
+            String::new()
+        }
+    };
+
+    let first_line = stdout.lines().next().unwrap_or("").trim().to_string();
+    let version_number = parse_version(&first_line);
+    let features: Vec<String> = stdout
+        .lines()
+        .find_map(|l| l.strip_prefix("Features:"))
+        .map(|l| l.split_whitespace().map(|f| f.to_string()).collect())
+        .unwrap_or_default();
+
+    // バージョンが分からない場合はすべて未対応として扱う
+    let unsupported_features = ALL_FEATURES
+        .into_iter()
+        .filter(|feature| {
+            let (min_version, required_flag) = feature.requirement();
+            let version_ok = version_number.is_some_and(|v| v >= min_version);
+            let flag_ok = required_flag.is_none_or(|flag| features.iter().any(|f| f == flag));
+            !(version_ok && flag_ok)
+        })
+        .collect();
+
+    CurlCapabilities {
+        version: (!first_line.is_empty()).then_some(first_line),
+        features,
+        unsupported_features,
+    }
+}
+
+// インストールされている curl.exe の対応機能
+pub async fn capabilities() -> &'static CurlCapabilities {
+    // ドライランでは確認のために curl.exe を起動しない（確認済みならその結果を使う）
+    if crate::dry_run::is_enabled() && !CAPABILITIES.initialized() {
+        return &ASSUMED_CAPABILITIES;
+    }
+    CAPABILITIES.get_or_init(detect_capabilities).await
+}
+
+#[tauri::command]
+pub async fn get_curl_capabilities() -> Result<CurlCapabilities, String> {
+    Ok(capabilities().await.clone())
+}
+
+// curl.exe の既定の User-Agent（curl/x.y.z）は WAF などで拒否されることがあるため、指定がなければアプリの値を送る
+// --next で区切った転送では指定が引き継がれないため、転送ごとに指定する
+pub fn ensure_user_agent(args: &mut Vec<String>) {
+    if args.iter().any(|a| a == "--user-agent") {
+        return;
+    }
+    let user_agent = ["--user-agent".to_string(), USER_AGENT_VALUE.to_string()];
+    let mut with_user_agent = user_agent.to_vec();
+    for arg in args.drain(..) {
+        let next = arg == "--next";
+        with_user_agent.push(arg);
+        if next {
+            with_user_agent.extend(user_agent.clone());
+        }
+    }
+    *args = with_user_agent;
+}
+
+// curl.exe を実行し、出力をそのまま返す（宛先ホストごとの送信数の上限を守る）
+pub async fn run_curl(mut args: Vec<String>) -> Result<Output, String> {
+    ensure_user_agent(&mut args);
+    acquire_for_curl_args(&args).await;
+    run_command("curl.exe", &args)
+        .await
+        .map_err(|e| format!("curl 実行失敗: {}", e))
+}
+
+// 測定と同じリクエストを別の環境で再現するための curl のコマンドライン
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CurlCommand {
+    pub args: Vec<String>,
+    // PowerShell に貼り付けて実行できるコマンドライン（Windows PowerShell では curl が Invoke-WebRequest の別名のため curl.exe とする）
+    pub command_line: String,
+    // WSL や macOS などの sh 系シェル向けのコマンドライン
+    #[serde(default)]
+    pub posix_command_line: String,
+}
+
+// 測定したアドレスに --resolve で接続先を固定したコマンドを組み立てる（ip_address が None の場合はプロキシに名前の解決を任せる）
+// 認証情報を履歴に残さないよう、認証系のヘッダの値と本文は伏せる（本文は @body を置き換えて使う）
+pub fn ping_command(
+    url: &Url,
+    request: &RequestOptions,
+    ip_address: Option<&str>,
+    ignore_tls_errors: bool,
+    timeout_secs: u64,
+) -> CurlCommand {
+    let mut args: Vec<String> = ip_address
+        .map(|ip| if ip.contains(':') { "--ipv6" } else { "--ipv4" }.to_string())
+        .into_iter()
+        .collect();
+    let mut url = url.clone();
+    let mut headers = request.headers.clone();
+    let port = url.port_or_known_default().unwrap_or(443);
+    // SNI を指定した場合は URL をその名前にし、Host は元の URL のものを送る
+    if let Some(sni) = request.sni.as_deref() {
+        if url.host_str() != Some(sni) {
+            if !headers.iter().any(|(name, _)| name == "host") {
+                let authority = match url.port() {
+                    Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+                    None => url.host_str().unwrap_or_default().to_string(),
+                };
+                headers.push(("host".to_string(), authority));
+            }
+            let _ = url.set_host(Some(sni));
+        }
+    }
+    // IP アドレスの URL は名前解決しないため固定不要
+    let pinned_host = url.host_str().filter(|h| h.parse::<IpAddr>().is_err());
+    if let (Some(host), Some(ip_address)) = (pinned_host, ip_address) {
+        if !host.starts_with('[') {
+            let address = if ip_address.contains(':') {
+                format!("[{}]", ip_address)
+            } else {
+                ip_address.to_string()
+            };
+            args.push("--resolve".to_string());
+            args.push(format!("{}:{}:{}", host, port, address));
+        }
+    }
+
+    match request.method {
+        HttpMethod::Get => {}
+        HttpMethod::Head => args.push("--head".to_string()),
+        method => {
+            args.push("--request".to_string());
+            args.push(method.as_str().to_string());
+        }
+    }
+    match request.http_version {
+        Some(HttpVersion::Http1_1) => args.push("--http1.1".to_string()),
+        // HTTP では測定と同じく事前合意（h2c prior knowledge）で接続する
+        Some(HttpVersion::Http2) if url.scheme() == "http" => {
+            args.push("--http2-prior-knowledge".to_string())
+        }
+        Some(HttpVersion::Http2) => args.push("--http2".to_string()),
+        None => {}
+    }
+    match request.tls_version {
+        Some(TlsVersion::Tls1_2) => {
+            args.extend(["--tlsv1.2", "--tls-max", "1.2"].map(String::from));
+        }
+        Some(TlsVersion::Tls1_3) => args.push("--tlsv1.3".to_string()),
+        None => {}
+    }
+    if ignore_tls_errors {
+        args.push("--insecure".to_string());
+    }
+    if let Some(proxy) = &request.proxy {
+        args.push("--proxy".to_string());
+        args.push(proxy.display());
+        // パスワードは含めず、curl に入力を求めさせる
+        if let Some(username) = &proxy.username {
+            args.push("--proxy-user".to_string());
+            args.push(username.clone());
+            match proxy.auth {
+                ProxyAuthMethod::Ntlm => args.push("--proxy-ntlm".to_string()),
+                ProxyAuthMethod::Negotiate => args.push("--proxy-negotiate".to_string()),
+                _ => {}
+            }
+        }
+    }
+    args.push("--user-agent".to_string());
+    args.push(request.effective_user_agent().to_string());
+    for (name, value) in headers.iter().filter(|(name, _)| name != "user-agent") {
+        let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+            "[REDACTED]"
+        } else {
+            value.as_str()
+        };
+        args.push("--header".to_string());
+        args.push(format!("{}: {}", name, value));
+    }
+    if request.body.is_some() {
+        args.push("--data-binary".to_string());
+        args.push("@body".to_string());
+    }
+    if request.follow_redirects {
+        args.push("--location".to_string());
+        args.push("--max-redirs".to_string());
+        args.push(MAX_REDIRECTS.to_string());
+    }
+    args.push("--max-time".to_string());
+    args.push(timeout_secs.to_string());
+    // IPv6 アドレスの URL の角括弧を curl の URL の展開とみなさないようにする
+    if url.as_str().contains('[') {
+        args.push("--globoff".to_string());
+    }
+    args.push(url.to_string());
+    // 同じ URL を続けて指定すると curl は同じ接続で 2 回目を送信する
+    if request.reuse_connection {
+        args.push(url.to_string());
+    }
+
+    CurlCommand {
+        command_line: join_command("curl.exe", &args, powershell_quote),
+        posix_command_line: join_command("curl", &args, shell_quote),
+        args,
+    }
+}
+
+fn join_command(program: &str, args: &[String], quote: fn(&str) -> String) -> String {
+    std::iter::once(program.to_string())
+        .chain(args.iter().map(|a| quote(a)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+// PowerShell では @ で始まる引数は展開、カンマは配列になるため、これらを含む引数も単一引用符で囲む
+fn powershell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=%+".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        crate::powershell::quote(arg)
+    }
+}
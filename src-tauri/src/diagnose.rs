@@ -0,0 +1,545 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use url::Url;
+
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::tls::TlsProbeResult;
+use crate::traceroute::TracerouteResult;
+use crate::{http_client, DnsResolution, HttpPingResult};
+
+// 1回の接続のタイムアウト（秒）
+const DIAGNOSE_TIMEOUT_SECS: u64 = 10;
+
+// 証明書の期限切れが近いとみなす日数
+const CERTIFICATE_EXPIRY_WARNING_DAYS: i64 = 14;
+
+// IPv6 が IPv4 より遅いとみなす差（ミリ秒）と比率
+const FAMILY_SLOWDOWN_MS: u64 = 200;
+const FAMILY_SLOWDOWN_RATIO: u64 = 2;
+
+// 各段階が遅いとみなす時間（ミリ秒）
+const SLOW_CONNECT_MS: f64 = 300.0;
+const SLOW_TLS_HANDSHAKE_MS: f64 = 500.0;
+const SLOW_FIRST_BYTE_MS: f64 = 1000.0;
+
+// CDN やキャッシュを経由していることを示す応答ヘッダ
+const CDN_HEADERS: [&str; 6] = [
+    "cf-ray",
+    "x-amz-cf-id",
+    "x-akamai-transformed",
+    "x-served-by",
+    "x-cache",
+    "via",
+];
+
+// 所見の重要度（Critical から順に並べる）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingSeverity {
+    Critical,
+    Warning,
+    Info,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosisArea {
+    Dns,
+    Connectivity,
+    Tls,
+    Http,
+    Route,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosisFinding {
+    pub severity: FindingSeverity,
+    pub area: DiagnosisArea,
+    pub message: String,
+    // 原因の見当と次に確認すること
+    pub explanation: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UrlDiagnosis {
+    pub url: String,
+    pub host: String,
+    pub dns_resolution: DnsResolution,
+    pub ipv4: HttpPingResult,
+    pub ipv6: HttpPingResult,
+    // 成功した接続の応答ヘッダ（Set-Cookie の値は伏せる）
+    pub response_headers: Vec<(String, String)>,
+    pub tls: Option<TlsProbeResult>,
+    pub tls_error: Option<String>,
+    pub traceroute: Option<TracerouteResult>,
+    pub traceroute_error: Option<String>,
+    // 重要度の高い順
+    pub findings: Vec<DiagnosisFinding>,
+}
+
+// URL について DNS・IPv4/IPv6 での接続・TLS・応答ヘッダ・経路をまとめて調べ、問題点を重要度順に示す
+#[tauri::command]
+pub async fn diagnose(
+    app: AppHandle,
+    url: String,
+    ignore_tls_errors: Option<bool>,
+) -> Result<UrlDiagnosis, String> {
+    let target = url.clone();
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        &target,
+        execute_diagnose(url, ignore_tls_errors.unwrap_or(false)),
+    )
+    .await
+}
+
+async fn execute_diagnose(url: String, ignore_tls_errors: bool) -> Result<UrlDiagnosis, String> {
+    crate::validate_url(&url)?;
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+    if ignore_tls_errors {
+        crate::log_security_warning("TLS証明書検証が無効化されています");
+    }
+    let is_https = parsed_url.scheme() == "https";
+
+    report_progress(0.0);
+    let mut dns_resolution = crate::resolve_dns(&host).await;
+    dns_resolution.details = crate::lookup_dns_details(&host, &dns_resolution).await;
+    report_progress(15.0);
+
+    let request = http_client::RequestOptions::default();
+    let (ipv4, ipv6) = tokio::join!(
+        crate::connect_to_ip_with_host(
+            url.clone(),
+            &request,
+            &dns_resolution.ipv4_addresses,
+            &host,
+            ignore_tls_errors,
+            parsed_url.port(),
+            false,
+            None,
+            DIAGNOSE_TIMEOUT_SECS,
+        ),
+        crate::connect_to_ip_with_host(
+            url.clone(),
+            &request,
+            &dns_resolution.ipv6_addresses,
+            &host,
+            ignore_tls_errors,
+            parsed_url.port(),
+            false,
+            None,
+            DIAGNOSE_TIMEOUT_SECS,
+        ),
+    );
+    report_progress(50.0);
+
+    // TLS は応答のあったアドレス、経路は失敗したファミリのアドレスを優先して調べる
+    let tls_address = [&ipv4, &ipv6]
+        .into_iter()
+        .find(|r| r.status_code.is_some())
+        .or(Some(&ipv4).filter(|r| r.ip_address.is_some()))
+        .or(Some(&ipv6).filter(|r| r.ip_address.is_some()))
+        .and_then(|r| r.ip_address.clone());
+    let route_address = [&ipv4, &ipv6]
+        .into_iter()
+        .find(|r| !r.success && r.ip_address.is_some())
+        .and_then(|r| r.ip_address.clone())
+        .or_else(|| tls_address.clone());
+    let port = parsed_url.port_or_known_default().unwrap_or(443);
+
+    let (tls, traceroute) = tokio::join!(
+        async {
+            match (&tls_address, is_https) {
+                (Some(ip), true) => Some(crate::tls::probe_tls(&host, Some(ip), port).await),
+                _ => None,
+            }
+        },
+        async {
+            match &route_address {
+                Some(ip) => Some(crate::traceroute::traceroute(ip).await),
+                None => None,
+            }
+        },
+    );
+    report_progress(95.0);
+
+    let (tls, tls_error) = split_result(tls);
+    let (traceroute, traceroute_error) = split_result(traceroute);
+    let response_headers = [&ipv4, &ipv6]
+        .into_iter()
+        .find(|r| r.status_code.is_some())
+        .map(|r| redact_headers(&r.response_headers))
+        .unwrap_or_default();
+
+    let mut findings = Vec::new();
+    dns_findings(&mut findings, &dns_resolution);
+    connectivity_findings(&mut findings, &dns_resolution, &ipv4, &ipv6);
+    http_findings(&mut findings, &ipv4, &ipv6, &response_headers);
+    tls_findings(&mut findings, tls.as_ref(), tls_error.as_deref());
+    route_findings(&mut findings, &ipv4, &ipv6, traceroute.as_ref());
+    findings.sort_by_key(|f| f.severity);
+    report_progress(100.0);
+
+    Ok(UrlDiagnosis {
+        url,
+        host,
+        dns_resolution,
+        ipv4,
+        ipv6,
+        response_headers,
+        tls,
+        tls_error,
+        traceroute,
+        traceroute_error,
+        findings,
+    })
+}
+
+fn split_result<T>(result: Option<Result<T, String>>) -> (Option<T>, Option<String>) {
+    match result {
+        Some(Ok(value)) => (Some(value), None),
+        Some(Err(e)) => (None, Some(e)),
+        None => (None, None),
+    }
+}
+
+fn redact_headers(headers: &[(String, String)]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            if name.eq_ignore_ascii_case("set-cookie") {
+                (name.clone(), "[REDACTED]".to_string())
+            } else {
+                (name.clone(), value.clone())
+            }
+        })
+        .collect()
+}
+
+fn push(
+    findings: &mut Vec<DiagnosisFinding>,
+    severity: FindingSeverity,
+    area: DiagnosisArea,
+    message: String,
+    explanation: &str,
+) {
+    findings.push(DiagnosisFinding {
+        severity,
+        area,
+        message,
+        explanation: explanation.to_string(),
+    });
+}
+
+fn dns_findings(findings: &mut Vec<DiagnosisFinding>, dns_resolution: &DnsResolution) {
+    if let Some(failure) = &dns_resolution.failure {
+        push(
+            findings,
+            FindingSeverity::Critical,
+            DiagnosisArea::Dns,
+            format!("名前解決に失敗しました: {}", failure.message),
+            "ホスト名の綴り、DNS サーバの設定、社内 DNS やフィルタリングでの遮断を確認してください。",
+        );
+        return;
+    }
+    if dns_resolution.ipv6_addresses.is_empty() {
+        push(
+            findings,
+            FindingSeverity::Info,
+            DiagnosisArea::Dns,
+            "AAAA レコードがありません".to_string(),
+            "このホストは IPv6 に対応していないため、IPv4 でのみ接続されます。",
+        );
+    }
+    if let Some(details) = &dns_resolution.details {
+        if !details.cname_chain.is_empty() {
+            push(
+                findings,
+                FindingSeverity::Info,
+                DiagnosisArea::Dns,
+                format!("CNAME で別名が付けられています: {}", details.cname_chain.join(" → ")),
+                "CDN やロードバランサを経由している可能性があり、接続先は地域や時間によって変わることがあります。",
+            );
+        }
+    }
+}
+
+fn connectivity_findings(
+    findings: &mut Vec<DiagnosisFinding>,
+    dns_resolution: &DnsResolution,
+    ipv4: &HttpPingResult,
+    ipv6: &HttpPingResult,
+) {
+    if dns_resolution.failure.is_some() {
+        return;
+    }
+    let ipv4_connected = ipv4.status_code.is_some();
+    let ipv6_connected = ipv6.status_code.is_some();
+    let ipv4_attempted = ipv4.ip_address.is_some();
+    let ipv6_attempted = ipv6.ip_address.is_some();
+
+    if !ipv4_connected && !ipv6_connected {
+        push(
+            findings,
+            FindingSeverity::Critical,
+            DiagnosisArea::Connectivity,
+            "IPv4/IPv6 のどちらでも接続できません".to_string(),
+            "サーバの停止、ファイアウォールやプロキシでの遮断、ネットワーク自体の不通が考えられます。経路の確認結果で途切れた位置を確認してください。",
+        );
+    } else if ipv4_attempted && !ipv4_connected {
+        push(
+            findings,
+            FindingSeverity::Warning,
+            DiagnosisArea::Connectivity,
+            format!(
+                "IPv4 でのみ接続できません: {}",
+                ipv4.error_message.as_deref().unwrap_or("不明なエラー")
+            ),
+            "IPv4 の経路やファイアウォールに問題がある可能性があります。IPv6 に対応していないクライアントからは接続できません。",
+        );
+    } else if ipv6_attempted && !ipv6_connected {
+        push(
+            findings,
+            FindingSeverity::Warning,
+            DiagnosisArea::Connectivity,
+            format!(
+                "IPv6 でのみ接続できません: {}",
+                ipv6.error_message.as_deref().unwrap_or("不明なエラー")
+            ),
+            "IPv6 の経路が壊れていると、ブラウザが IPv4 に切り替えるまで待たされ、表示が遅くなることがあります。",
+        );
+    }
+
+    if let (Some(v4_ms), Some(v6_ms)) = (ipv4.response_time_ms, ipv6.response_time_ms) {
+        if v6_ms > v4_ms + FAMILY_SLOWDOWN_MS && v6_ms > v4_ms * FAMILY_SLOWDOWN_RATIO {
+            push(
+                findings,
+                FindingSeverity::Warning,
+                DiagnosisArea::Connectivity,
+                format!("IPv6 の応答が IPv4 より遅いです（IPv4: {} ms, IPv6: {} ms）", v4_ms, v6_ms),
+                "IPv6 が優先されるため、ブラウザでの表示も遅くなります。IPv6 のトンネルや遠回りの経路が考えられます。",
+            );
+        } else if v4_ms > v6_ms + FAMILY_SLOWDOWN_MS && v4_ms > v6_ms * FAMILY_SLOWDOWN_RATIO {
+            push(
+                findings,
+                FindingSeverity::Info,
+                DiagnosisArea::Connectivity,
+                format!(
+                    "IPv4 の応答が IPv6 より遅いです（IPv4: {} ms, IPv6: {} ms）",
+                    v4_ms, v6_ms
+                ),
+                "IPv4 の NAT（PPPoE など）が混雑している可能性があります。",
+            );
+        }
+    }
+}
+
+fn http_findings(
+    findings: &mut Vec<DiagnosisFinding>,
+    ipv4: &HttpPingResult,
+    ipv6: &HttpPingResult,
+    response_headers: &[(String, String)],
+) {
+    let Some(result) = [ipv4, ipv6].into_iter().find(|r| r.status_code.is_some()) else {
+        return;
+    };
+    let status_code = result.status_code.unwrap_or_default();
+    match status_code {
+        500..=599 => push(
+            findings,
+            FindingSeverity::Critical,
+            DiagnosisArea::Http,
+            format!("サーバエラーが返されました（HTTP {}）", status_code),
+            "接続はできていますが、サーバ側またはその手前のプロキシ・ロードバランサで問題が起きています。",
+        ),
+        400..=499 => push(
+            findings,
+            FindingSeverity::Warning,
+            DiagnosisArea::Http,
+            format!("クライアントエラーが返されました（HTTP {}）", status_code),
+            "URL のパスや認証、アクセス制限（地域や IP アドレスによる遮断）を確認してください。",
+        ),
+        300..=399 => {
+            let location = response_headers
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case("location"))
+                .map(|(_, value)| value.as_str())
+                .unwrap_or("不明");
+            push(
+                findings,
+                FindingSeverity::Info,
+                DiagnosisArea::Http,
+                format!("リダイレクトされます（HTTP {} → {}）", status_code, location),
+                "最終的な転送先の URL も診断すると、転送先での問題を確認できます。",
+            );
+        }
+        _ => {}
+    }
+
+    let cdn_headers: Vec<&str> = response_headers
+        .iter()
+        .filter(|(name, _)| CDN_HEADERS.iter().any(|h| name.eq_ignore_ascii_case(h)))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    if !cdn_headers.is_empty() {
+        push(
+            findings,
+            FindingSeverity::Info,
+            DiagnosisArea::Http,
+            format!(
+                "CDN またはキャッシュ経由の応答です（{}）",
+                cdn_headers.join(", ")
+            ),
+            "応答はオリジンサーバではなく CDN やキャッシュから返されている可能性があります。",
+        );
+    }
+
+    let Some(timings) = &result.timings else {
+        return;
+    };
+    if timings.connect_ms.is_some_and(|ms| ms > SLOW_CONNECT_MS) {
+        push(
+            findings,
+            FindingSeverity::Warning,
+            DiagnosisArea::Connectivity,
+            format!(
+                "TCP 接続に時間がかかっています（{:.0} ms）",
+                timings.connect_ms.unwrap_or_default()
+            ),
+            "サーバまでの距離が遠いか、経路上で遅延やパケットロスが起きている可能性があります。",
+        );
+    }
+    if timings
+        .tls_handshake_ms
+        .is_some_and(|ms| ms > SLOW_TLS_HANDSHAKE_MS)
+    {
+        push(
+            findings,
+            FindingSeverity::Warning,
+            DiagnosisArea::Tls,
+            format!(
+                "TLS ハンドシェイクに時間がかかっています（{:.0} ms）",
+                timings.tls_handshake_ms.unwrap_or_default()
+            ),
+            "TLS を検査するプロキシやセキュリティ製品の経由、サーバの負荷が考えられます。",
+        );
+    }
+    if timings
+        .first_byte_ms
+        .is_some_and(|ms| ms > SLOW_FIRST_BYTE_MS)
+    {
+        push(
+            findings,
+            FindingSeverity::Info,
+            DiagnosisArea::Http,
+            format!(
+                "最初の応答までに時間がかかっています（{:.0} ms）",
+                timings.first_byte_ms.unwrap_or_default()
+            ),
+            "ネットワークではなく、サーバ側の処理時間が長い可能性があります。",
+        );
+    }
+}
+
+fn tls_findings(
+    findings: &mut Vec<DiagnosisFinding>,
+    tls: Option<&TlsProbeResult>,
+    tls_error: Option<&str>,
+) {
+    if let Some(e) = tls_error {
+        push(
+            findings,
+            FindingSeverity::Warning,
+            DiagnosisArea::Tls,
+            format!("証明書を確認できませんでした: {}", e),
+            "TLS ハンドシェイクに失敗したか、途中で接続が切断されました。",
+        );
+    }
+    let Some(tls) = tls else {
+        return;
+    };
+    if tls.policy_errors != "None" {
+        push(
+            findings,
+            FindingSeverity::Critical,
+            DiagnosisArea::Tls,
+            format!("証明書の検証に失敗しました: {}", tls.policy_errors),
+            "証明書の期限切れ、ホスト名の不一致、中間証明書の不足、または通信を検査するプロキシによる置き換えが考えられます。",
+        );
+    }
+    let Some(not_after) = tls
+        .certificates
+        .first()
+        .and_then(|c| DateTime::parse_from_rfc3339(&c.not_after).ok())
+    else {
+        return;
+    };
+    let remaining = not_after.with_timezone(&Utc) - Utc::now();
+    if remaining.num_seconds() <= 0 {
+        push(
+            findings,
+            FindingSeverity::Critical,
+            DiagnosisArea::Tls,
+            format!(
+                "サーバ証明書の有効期限が切れています（{}）",
+                not_after.to_rfc3339()
+            ),
+            "ブラウザでは警告が表示されます。サーバ管理者に証明書の更新を依頼してください。",
+        );
+    } else if remaining.num_days() < CERTIFICATE_EXPIRY_WARNING_DAYS {
+        push(
+            findings,
+            FindingSeverity::Warning,
+            DiagnosisArea::Tls,
+            format!(
+                "サーバ証明書の有効期限が近づいています（残り {} 日）",
+                remaining.num_days()
+            ),
+            "自動更新が機能しているか、サーバ管理者に確認してください。",
+        );
+    }
+}
+
+fn route_findings(
+    findings: &mut Vec<DiagnosisFinding>,
+    ipv4: &HttpPingResult,
+    ipv6: &HttpPingResult,
+    traceroute: Option<&TracerouteResult>,
+) {
+    let Some(traceroute) = traceroute else {
+        return;
+    };
+    // 接続に失敗したアドレスへの経路が途中で途切れている場合のみ、途切れた位置を示す
+    let failed = [ipv4, ipv6]
+        .into_iter()
+        .any(|r| !r.success && r.ip_address.as_deref() == Some(traceroute.target.as_str()));
+    if !failed || traceroute.reached {
+        return;
+    }
+    let message = match traceroute.last_responding_hop() {
+        Some(hop) => format!(
+            "{} への経路は {} ホップ目（{}）の先で途切れています",
+            traceroute.target,
+            hop.hop,
+            hop.address.as_deref().unwrap_or_default()
+        ),
+        None => format!(
+            "{} への経路は最初のホップから応答がありません",
+            traceroute.target
+        ),
+    };
+    push(
+        findings,
+        FindingSeverity::Warning,
+        DiagnosisArea::Route,
+        message,
+        "途切れた位置の先のルータやファイアウォールで通信が遮断されている可能性があります。ICMP に応答しないだけのルータもあるため、参考情報として扱ってください。",
+    );
+}
@@ -0,0 +1,627 @@
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+
+use crate::dry_run::{self, ActionKind, DRY_RUN_MESSAGE};
+use crate::http_client::{self, RequestOptions};
+
+// DoH (DNS over HTTPS) のタイムアウト
+const DOH_TIMEOUT_SECS: u64 = 5;
+
+// DoH の応答本文を保持する上限
+const DOH_MAX_RESPONSE_BYTES: usize = 64 * 1024;
+
+// 名前の圧縮ポインタや CNAME をたどる回数の上限
+const MAX_NAME_POINTERS: usize = 16;
+
+// DNS レスポンスコード
+pub const RCODE_SERVFAIL: u16 = 2;
+pub const RCODE_NXDOMAIN: u16 = 3;
+pub const RCODE_REFUSED: u16 = 5;
+
+// DNS レコードタイプ
+pub const RECORD_TYPE_A: u16 = 1;
+pub const RECORD_TYPE_CNAME: u16 = 5;
+pub const RECORD_TYPE_PTR: u16 = 12;
+pub const RECORD_TYPE_TXT: u16 = 16;
+pub const RECORD_TYPE_AAAA: u16 = 28;
+pub const RECORD_TYPE_HTTPS: u16 = 65;
+
+// HTTPS レコードの SvcParam のキー（ECHConfigList）
+const SVC_PARAM_ECH: u16 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DohProvider {
+    Cloudflare,
+    Google,
+}
+
+impl DohProvider {
+    // JSON API のエンドポイント
+    fn endpoint(&self) -> &'static str {
+        match self {
+            DohProvider::Cloudflare => "https://cloudflare-dns.com/dns-query",
+            DohProvider::Google => "https://dns.google/resolve",
+        }
+    }
+
+    // エンドポイントのホスト名
+    fn host(&self) -> &'static str {
+        match self {
+            DohProvider::Cloudflare => "cloudflare-dns.com",
+            DohProvider::Google => "dns.google",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsRecord {
+    pub name: String,
+    pub record_type: u16,
+    pub ttl: u32,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DohAnswer {
+    pub status: u16,
+    pub records: Vec<DnsRecord>,
+}
+
+impl DohAnswer {
+    // A / AAAA レコードのアドレスのみを取り出す
+    pub fn addresses(&self) -> Vec<String> {
+        self.records
+            .iter()
+            .filter(|r| r.record_type == RECORD_TYPE_A || r.record_type == RECORD_TYPE_AAAA)
+            .map(|r| r.data.clone())
+            .collect()
+    }
+}
+
+// DoH JSON レスポンス解析用の内部構造体
+#[derive(Deserialize)]
+struct DohJsonResponse {
+    #[serde(rename = "Status")]
+    status: u16,
+    #[serde(rename = "Answer", default)]
+    answer: Vec<DohJsonRecord>,
+}
+
+#[derive(Deserialize)]
+struct DohJsonRecord {
+    name: String,
+    #[serde(rename = "type")]
+    record_type: u16,
+    #[serde(rename = "TTL", default)]
+    ttl: u32,
+    data: String,
+}
+
+// DoH で名前解決（record_type は "A" / "AAAA" / "CNAME" / "TXT" など）
+pub async fn doh_query(
+    provider: DohProvider,
+    name: &str,
+    record_type: &str,
+) -> Result<DohAnswer, String> {
+    let url = format!(
+        "{}?name={}&type={}",
+        provider.endpoint(),
+        name,
+        record_type
+    );
+
+    let host = provider.host();
+    let dns_resolution = crate::resolve_dns(host).await;
+    let ip_address = dns_resolution
+        .ipv4_addresses
+        .first()
+        .or(dns_resolution.ipv6_addresses.first())
+        .ok_or_else(|| format!("DoH サーバの名前を解決できません: {}", host))?;
+
+    let options = RequestOptions {
+        headers: vec![("accept".to_string(), "application/dns-json".to_string())],
+        ..RequestOptions::default()
+    };
+    let outcome = http_client::send_request(&http_client::HttpRequest {
+        url: &url,
+        options: &options,
+        ip_address: Some(ip_address),
+        host,
+        port: None,
+        ignore_tls_errors: false,
+        source_address: None,
+        verbose: false,
+        max_body_bytes: DOH_MAX_RESPONSE_BYTES,
+        timeout_secs: DOH_TIMEOUT_SECS,
+    })
+    .await;
+
+    if let Some(e) = outcome.error_message {
+        return Err(format!("DoH 問い合わせに失敗: {}", e));
+    }
+    match outcome.status_code {
+        Some(200) => {}
+        status_code => {
+            return Err(format!(
+                "DoH 問い合わせに失敗: HTTPステータス: {}",
+                status_code.unwrap_or_default()
+            ))
+        }
+    }
+
+    let body: DohJsonResponse = serde_json::from_slice(&outcome.body.unwrap_or_default())
+        .map_err(|e| format!("DoH 応答の解析失敗: {}", e))?;
+
+    Ok(DohAnswer {
+        status: body.status,
+        records: body
+            .answer
+            .into_iter()
+            .map(|r| DnsRecord {
+                name: r.name.trim_end_matches('.').to_string(),
+                record_type: r.record_type,
+                ttl: r.ttl,
+                data: r.data,
+            })
+            .collect(),
+    })
+}
+
+// OS のリゾルバで名前解決し、アドレス一覧を返す
+pub async fn system_lookup(host: &str) -> Result<Vec<String>, String> {
+    if dry_run::intercept(ActionKind::Dns, host, Some("OS のリゾルバ".to_string())) {
+        return Err(DRY_RUN_MESSAGE.to_string());
+    }
+    let addrs = tokio::net::lookup_host(format!("{}:80", host))
+        .await
+        .map_err(|e| format!("名前解決失敗: {}", e))?;
+
+    let mut result: Vec<String> = Vec::new();
+    for addr in addrs {
+        let ip = addr.ip().to_string();
+        if !result.contains(&ip) {
+            result.push(ip);
+        }
+    }
+    Ok(result)
+}
+
+// DNS サーバへ直接問い合わせた結果
+#[derive(Debug, Clone)]
+pub struct DirectQueryResponse {
+    pub rcode: u16,
+    pub answer_count: u16,
+    pub addresses: Vec<String>,
+    pub records: Vec<DnsRecord>,
+    pub elapsed_ms: f64,
+}
+
+#[derive(Debug, Clone)]
+pub enum DirectQueryError {
+    Timeout,
+    Failed(String),
+}
+
+// "192.0.2.1" や "fe80::1%12" 形式の DNS サーバアドレスを解析（ポートは 53）
+pub fn parse_dns_server(server: &str) -> Option<SocketAddr> {
+    let (address, scope) = match server.trim().split_once('%') {
+        Some((address, scope)) => (address, scope.parse::<u32>().ok()),
+        None => (server.trim(), None),
+    };
+    match address.parse::<IpAddr>().ok()? {
+        IpAddr::V6(v6) => Some(SocketAddr::V6(SocketAddrV6::new(
+            v6,
+            53,
+            0,
+            scope.unwrap_or(0),
+        ))),
+        ip => Some(SocketAddr::new(ip, 53)),
+    }
+}
+
+// 指定した DNS サーバへ UDP で1回問い合わせる（OS のキャッシュを介さない）
+pub async fn direct_query(
+    server: SocketAddr,
+    name: &str,
+    record_type: u16,
+    timeout: Duration,
+) -> Result<DirectQueryResponse, DirectQueryError> {
+    let id = (SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0)
+        & 0xffff) as u16;
+    let packet = build_query(id, name, record_type).map_err(DirectQueryError::Failed)?;
+    let detail = format!("{} (type {}) を UDP で問い合わせる", name, record_type);
+    if dry_run::intercept(ActionKind::Dns, server.to_string(), Some(detail)) {
+        return Err(DirectQueryError::Failed(DRY_RUN_MESSAGE.to_string()));
+    }
+
+    let bind_addr = if server.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(bind_addr)
+        .await
+        .map_err(|e| DirectQueryError::Failed(format!("ソケット作成失敗: {}", e)))?;
+    let start = Instant::now();
+    socket
+        .send_to(&packet, server)
+        .await
+        .map_err(|e| DirectQueryError::Failed(format!("送信失敗: {}", e)))?;
+
+    let receive = async {
+        let mut buf = [0u8; 1500];
+        loop {
+            let (len, from) = socket
+                .recv_from(&mut buf)
+                .await
+                .map_err(|e| DirectQueryError::Failed(format!("受信失敗: {}", e)))?;
+            // 別の応答や偽装応答は無視
+            if from.ip() != server.ip() || len < 12 || u16::from_be_bytes([buf[0], buf[1]]) != id {
+                continue;
+            }
+            let records = parse_answer_records(&buf[..len]);
+            return Ok(DirectQueryResponse {
+                rcode: u16::from(buf[3] & 0x0f),
+                answer_count: u16::from_be_bytes([buf[6], buf[7]]),
+                addresses: records
+                    .iter()
+                    .filter(|r| r.record_type != RECORD_TYPE_CNAME)
+                    .map(|r| r.data.clone())
+                    .collect(),
+                records,
+                elapsed_ms: start.elapsed().as_secs_f64() * 1000.0,
+            });
+        }
+    };
+
+    tokio::time::timeout(timeout, receive)
+        .await
+        .map_err(|_| DirectQueryError::Timeout)?
+}
+
+// 逆引き用にアドレスを逆順に並べたラベル（IPv4 はオクテット、IPv6 はニブル単位、末尾のゾーンは含まない）
+pub fn reversed_labels(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(v4) => v4
+            .octets()
+            .iter()
+            .rev()
+            .map(|o| o.to_string())
+            .collect::<Vec<_>>()
+            .join("."),
+        IpAddr::V6(v6) => v6
+            .octets()
+            .iter()
+            .rev()
+            .flat_map(|o| [o & 0x0f, o >> 4])
+            .map(|n| format!("{:x}", n))
+            .collect::<Vec<_>>()
+            .join("."),
+    }
+}
+
+// PTR レコードを問い合わせる名前（in-addr.arpa / ip6.arpa）
+pub fn ptr_name(ip: IpAddr) -> String {
+    let zone = if ip.is_ipv4() { "in-addr.arpa" } else { "ip6.arpa" };
+    format!("{}.{}", reversed_labels(ip), zone)
+}
+
+// 再帰問い合わせ用の DNS クエリパケットを組み立てる
+fn build_query(id: u16, name: &str, record_type: u16) -> Result<Vec<u8>, String> {
+    let mut packet = Vec::with_capacity(512);
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&[0x01, 0x00]); // RD (再帰要求)
+    packet.extend_from_slice(&[0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+
+    for label in name.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 || !label.is_ascii() {
+            return Err(format!("問い合わせできないホスト名です: {}", name));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&record_type.to_be_bytes());
+    packet.extend_from_slice(&[0x00, 0x01]); // IN
+    Ok(packet)
+}
+
+// 名前フィールドを読み飛ばし、次の位置を返す（圧縮ポインタに対応）
+fn skip_name(packet: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *packet.get(pos)?;
+        if len & 0xc0 == 0xc0 {
+            return Some(pos + 2);
+        }
+        if len == 0 {
+            return Some(pos + 1);
+        }
+        pos += len as usize + 1;
+    }
+}
+
+// 名前フィールドを読み取る（圧縮ポインタをたどり、ループする応答は打ち切る）
+fn read_name(packet: &[u8], mut pos: usize) -> Option<String> {
+    let mut labels: Vec<String> = Vec::new();
+    for _ in 0..MAX_NAME_POINTERS {
+        loop {
+            let len = *packet.get(pos)?;
+            if len & 0xc0 == 0xc0 {
+                pos = usize::from(u16::from_be_bytes([len & 0x3f, *packet.get(pos + 1)?]));
+                break;
+            }
+            if len == 0 {
+                return Some(labels.join("."));
+            }
+            let label = packet.get(pos + 1..pos + 1 + len as usize)?;
+            labels.push(String::from_utf8_lossy(label).to_string());
+            pos += len as usize + 1;
+        }
+    }
+    None
+}
+
+// TXT レコードの文字列（長さ付きの文字列が続く形式）を連結する
+fn read_txt(data: &[u8]) -> Option<String> {
+    let mut text = String::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let len = data[pos] as usize;
+        text.push_str(&String::from_utf8_lossy(data.get(pos + 1..pos + 1 + len)?));
+        pos += len + 1;
+    }
+    Some(text)
+}
+
+// HTTPS レコード（RFC 9460）の SvcParam のうち ech の値（ECHConfigList）を Base64 で取り出す
+fn read_https_ech(data: &[u8]) -> Option<String> {
+    // 優先度の後の TargetName は圧縮されない
+    let mut pos = skip_name(data, 2)?;
+    while let Some(header) = data.get(pos..pos + 4) {
+        let key = u16::from_be_bytes([header[0], header[1]]);
+        let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+        let value = data.get(pos + 4..pos + 4 + len)?;
+        if key == SVC_PARAM_ECH {
+            return Some(BASE64.encode(value));
+        }
+        pos += 4 + len;
+    }
+    None
+}
+
+// 応答の回答セクションから A / AAAA / CNAME / PTR / TXT のレコードを応答順に取り出す
+// HTTPS レコードは ECH の確認用に ech の値を含むもののみ取り出す
+fn parse_answer_records(packet: &[u8]) -> Vec<DnsRecord> {
+    let mut records = Vec::new();
+    if packet.len() < 12 {
+        return records;
+    }
+    let question_count = u16::from_be_bytes([packet[4], packet[5]]);
+    let answer_count = u16::from_be_bytes([packet[6], packet[7]]);
+
+    let mut pos = 12;
+    for _ in 0..question_count {
+        let Some(next) = skip_name(packet, pos) else {
+            return records;
+        };
+        pos = next + 4;
+    }
+    for _ in 0..answer_count {
+        let Some(next) = skip_name(packet, pos) else {
+            break;
+        };
+        let Some(header) = packet.get(next..next + 10) else {
+            break;
+        };
+        let record_type = u16::from_be_bytes([header[0], header[1]]);
+        let ttl = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        let data_len = u16::from_be_bytes([header[8], header[9]]) as usize;
+        let Some(data) = packet.get(next + 10..next + 10 + data_len) else {
+            break;
+        };
+        let data = match (record_type, data_len) {
+            (RECORD_TYPE_A, 4) => {
+                Some(Ipv4Addr::new(data[0], data[1], data[2], data[3]).to_string())
+            }
+            (RECORD_TYPE_AAAA, 16) => {
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(data);
+                Some(Ipv6Addr::from(octets).to_string())
+            }
+            (RECORD_TYPE_CNAME | RECORD_TYPE_PTR, _) => read_name(packet, next + 10),
+            (RECORD_TYPE_TXT, _) => read_txt(data),
+            (RECORD_TYPE_HTTPS, _) => read_https_ech(data),
+            _ => None,
+        };
+        if let (Some(data), Some(name)) = (data, read_name(packet, pos)) {
+            records.push(DnsRecord {
+                name,
+                record_type,
+                ttl,
+                data,
+            });
+        }
+        pos = next + 10 + data_len;
+    }
+    records
+}
+
+// DNS サーバへ HTTPS レコードを直接問い合わせ、ECH の設定（ECHConfigList）を取り出す（公開していない場合は None）
+pub async fn query_ech_config(
+    server: SocketAddr,
+    host: &str,
+    timeout: Duration,
+) -> Result<Option<Vec<u8>>, String> {
+    let response = match direct_query(server, host, RECORD_TYPE_HTTPS, timeout).await {
+        Ok(response) => response,
+        Err(DirectQueryError::Timeout) => {
+            return Err(format!("DNS サーバ {} から応答がありません", server.ip()))
+        }
+        Err(DirectQueryError::Failed(e)) => return Err(e),
+    };
+    Ok(response
+        .records
+        .iter()
+        .filter(|r| r.record_type == RECORD_TYPE_HTTPS)
+        .find_map(|r| BASE64.decode(&r.data).ok()))
+}
+
+// 名前解決の詳細（CNAME の連鎖と各レコードの TTL）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsAnswerDetails {
+    pub server: String,
+    // 別名をたどった順の正規名（別名でない場合は空）
+    pub cname_chain: Vec<String>,
+    pub records: Vec<DnsRecord>,
+}
+
+// A と AAAA を DNS サーバへ直接問い合わせ、CNAME の連鎖と TTL を取り出す（OS のリゾルバは返さないため）
+pub async fn query_answer_details(
+    server: SocketAddr,
+    host: &str,
+    timeout: Duration,
+) -> Result<DnsAnswerDetails, String> {
+    let (a, aaaa) = tokio::join!(
+        direct_query(server, host, RECORD_TYPE_A, timeout),
+        direct_query(server, host, RECORD_TYPE_AAAA, timeout),
+    );
+    let responses: Vec<DirectQueryResponse> = [a, aaaa].into_iter().flatten().collect();
+    if responses.is_empty() {
+        return Err(format!("DNS サーバ {} から応答がありません", server.ip()));
+    }
+
+    // CNAME は A と AAAA の両方の応答に含まれるため重複を除く
+    let mut records: Vec<DnsRecord> = Vec::new();
+    for record in responses.into_iter().flat_map(|r| r.records) {
+        if !records.iter().any(|r| {
+            r.record_type == record.record_type && r.name == record.name && r.data == record.data
+        }) {
+            records.push(record);
+        }
+    }
+
+    let mut cname_chain: Vec<String> = Vec::new();
+    let mut current = host.trim_end_matches('.').to_ascii_lowercase();
+    while let Some(record) = records
+        .iter()
+        .find(|r| r.record_type == RECORD_TYPE_CNAME && r.name.eq_ignore_ascii_case(&current))
+    {
+        let target = record.data.to_ascii_lowercase();
+        if cname_chain.contains(&target) || cname_chain.len() >= MAX_NAME_POINTERS {
+            break;
+        }
+        cname_chain.push(target.clone());
+        current = target;
+    }
+
+    Ok(DnsAnswerDetails {
+        server: server.ip().to_string(),
+        cname_chain,
+        records,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // X25519・HKDF-SHA256・AES-128-GCM の ECHConfig を1つ含む ECHConfigList（公開鍵は仮の値）
+    fn ech_config_list(public_name: &str) -> Vec<u8> {
+        let mut contents = vec![0x01, 0x00, 0x20, 0x00, 0x20];
+        contents.extend_from_slice(&[0x07; 32]);
+        contents.extend_from_slice(&[0x00, 0x04, 0x00, 0x01, 0x00, 0x01, 0x00]);
+        contents.push(public_name.len() as u8);
+        contents.extend_from_slice(public_name.as_bytes());
+        contents.extend_from_slice(&[0x00, 0x00]);
+        let mut config = vec![0xfe, 0x0d];
+        config.extend_from_slice(&(contents.len() as u16).to_be_bytes());
+        config.extend_from_slice(&contents);
+        let mut list = (config.len() as u16).to_be_bytes().to_vec();
+        list.extend_from_slice(&config);
+        list
+    }
+
+    fn svc_param(key: u16, value: &[u8]) -> Vec<u8> {
+        let mut param = key.to_be_bytes().to_vec();
+        param.extend_from_slice(&(value.len() as u16).to_be_bytes());
+        param.extend_from_slice(value);
+        param
+    }
+
+    // CDN が公開している HTTPS レコードと同じ構成
+    // 1 . alpn="http/1.1,h2" ipv4hint=192.0.2.1,192.0.2.2 ech=... ipv6hint=2001:db8::1
+    fn https_rdata(ech: Option<&[u8]>) -> Vec<u8> {
+        let mut rdata = vec![0x00, 0x01, 0x00];
+        rdata.extend(svc_param(1, b"\x08http/1.1\x02h2"));
+        rdata.extend(svc_param(4, &[192, 0, 2, 1, 192, 0, 2, 2]));
+        if let Some(ech) = ech {
+            rdata.extend(svc_param(SVC_PARAM_ECH, ech));
+        }
+        rdata.extend(svc_param(
+            6,
+            &"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets(),
+        ));
+        rdata
+    }
+
+    #[test]
+    fn reads_ech_from_https_record() {
+        let list = ech_config_list("cloudflare-ech.com");
+        assert_eq!(
+            read_https_ech(&https_rdata(Some(&list))),
+            Some(BASE64.encode(&list))
+        );
+    }
+
+    #[test]
+    fn https_record_without_ech() {
+        assert_eq!(read_https_ech(&https_rdata(None)), None);
+        // 別名形式（優先度 0）のレコードには SvcParams がない
+        let alias = b"\x00\x00\x07example\x03net\x00";
+        assert_eq!(read_https_ech(alias), None);
+        assert_eq!(read_https_ech(&[]), None);
+    }
+
+    #[test]
+    fn truncated_https_record() {
+        let list = ech_config_list("cloudflare-ech.com");
+        let rdata = https_rdata(Some(&list));
+        // ech の値の途中で切れている
+        let ech_end = rdata.len() - 20;
+        assert_eq!(read_https_ech(&rdata[..ech_end - 1]), None);
+        // TargetName の途中で切れている
+        assert_eq!(read_https_ech(b"\x00\x01\x07exam"), None);
+    }
+
+    #[test]
+    fn answer_records_keep_https_with_ech() {
+        let list = ech_config_list("cloudflare-ech.com");
+        let mut packet = vec![
+            0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+        ];
+        packet.extend_from_slice(b"\x07example\x03com\x00\x00\x41\x00\x01");
+        for rdata in [https_rdata(Some(&list)), https_rdata(None)] {
+            packet.extend_from_slice(&[0xc0, 0x0c, 0x00, 0x41, 0x00, 0x01, 0x00, 0x00, 0x01, 0x2c]);
+            packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            packet.extend_from_slice(&rdata);
+        }
+
+        let records = parse_answer_records(&packet);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].name, "example.com");
+        assert_eq!(records[0].record_type, RECORD_TYPE_HTTPS);
+        assert_eq!(records[0].ttl, 300);
+        assert_eq!(BASE64.decode(&records[0].data).unwrap(), list);
+
+        // 回答の途中で切れている場合は読めたところまで
+        assert_eq!(parse_answer_records(&packet[..packet.len() - 1]).len(), 1);
+    }
+}
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use crate::dns::{
+    direct_query, parse_dns_server, DirectQueryError, RCODE_NXDOMAIN, RCODE_REFUSED,
+    RCODE_SERVFAIL, RECORD_TYPE_A, RECORD_TYPE_AAAA,
+};
+
+// 分類のために問い合わせる DNS サーバの上限と待ち時間
+const MAX_SERVERS_TO_QUERY: usize = 3;
+const QUERY_TIMEOUT_SECS: u64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsFailureKind {
+    Nxdomain,
+    Servfail,
+    Refused,
+    Timeout,
+    NoRecords,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsFailure {
+    pub kind: DnsFailureKind,
+    pub server: Option<String>,
+    pub message: String,
+}
+
+// 名前解決に失敗した原因を、システムの DNS サーバへ直接問い合わせて分類
+pub async fn classify_dns_failure(host: &str, os_error: Option<String>) -> DnsFailure {
+    let servers = system_dns_servers().await;
+    if servers.is_empty() {
+        return DnsFailure {
+            kind: DnsFailureKind::Other,
+            server: None,
+            message: format!(
+                "DNS サーバが設定されていません{}。ネットワーク設定を確認してください",
+                os_error.map(|e| format!("（{}）", e)).unwrap_or_default()
+            ),
+        };
+    }
+
+    let timeout = Duration::from_secs(QUERY_TIMEOUT_SECS);
+    let mut last_error: Option<String> = None;
+    for server in servers.into_iter().take(MAX_SERVERS_TO_QUERY) {
+        let response = match direct_query(server, host, RECORD_TYPE_A, timeout).await {
+            Ok(response) => response,
+            Err(DirectQueryError::Timeout) => continue,
+            Err(DirectQueryError::Failed(e)) => {
+                last_error = Some(e);
+                continue;
+            }
+        };
+        let server_ip = server.ip();
+
+        let (kind, message) = match response.rcode {
+            RCODE_NXDOMAIN => (
+                DnsFailureKind::Nxdomain,
+                format!("{} は存在しないドメインです (NXDOMAIN)。URL のつづりを確認してください", host),
+            ),
+            RCODE_SERVFAIL => (
+                DnsFailureKind::Servfail,
+                format!(
+                    "DNS サーバ {} が名前解決に失敗しました (SERVFAIL)。時間をおいて再試行するか、別の DNS サーバを試してください",
+                    server_ip
+                ),
+            ),
+            RCODE_REFUSED => (
+                DnsFailureKind::Refused,
+                format!(
+                    "DNS サーバ {} に問い合わせを拒否されました (REFUSED)。DNS サーバの設定を確認してください",
+                    server_ip
+                ),
+            ),
+            0 if response.answer_count == 0 => {
+                // A がなければ AAAA も確認
+                let has_aaaa = direct_query(server, host, RECORD_TYPE_AAAA, timeout)
+                    .await
+                    .is_ok_and(|r| r.rcode == 0 && r.answer_count > 0);
+                if has_aaaa {
+                    (DnsFailureKind::Other, resolver_mismatch_message(server_ip))
+                } else {
+                    (
+                        DnsFailureKind::NoRecords,
+                        format!("{} には A / AAAA レコードがありません", host),
+                    )
+                }
+            }
+            0 => (DnsFailureKind::Other, resolver_mismatch_message(server_ip)),
+            rcode => (
+                DnsFailureKind::Other,
+                format!("DNS サーバ {} がエラーを返しました (RCODE {})", server_ip, rcode),
+            ),
+        };
+        return DnsFailure {
+            kind,
+            server: Some(server_ip.to_string()),
+            message,
+        };
+    }
+
+    match last_error {
+        Some(e) => DnsFailure {
+            kind: DnsFailureKind::Other,
+            server: None,
+            message: format!("DNS サーバへの問い合わせに失敗: {}", e),
+        },
+        None => DnsFailure {
+            kind: DnsFailureKind::Timeout,
+            server: None,
+            message: "DNS サーバから応答がありません。ネットワーク接続と DNS サーバの設定を確認してください"
+                .to_string(),
+        },
+    }
+}
+
+// DNS サーバは名前を解決できるのに OS のリゾルバだけが失敗している場合
+fn resolver_mismatch_message(server: IpAddr) -> String {
+    format!(
+        "DNS サーバ {} は応答していますが、OS の名前解決に失敗しました。hosts ファイルやセキュリティソフトの設定を確認してください",
+        server
+    )
+}
+
+// システムに設定された DNS サーバ（重複を除き、IPv4 を優先）
+pub async fn system_dns_servers() -> Vec<SocketAddr> {
+    let Ok(Ok((infos, _))) =
+        tokio::time::timeout(Duration::from_secs(5), crate::get_dns_servers_async()).await
+    else {
+        return vec![];
+    };
+
+    let mut servers: Vec<SocketAddr> = Vec::new();
+    let ipv4 = infos.iter().flat_map(|i| i.ipv4_dns_servers.iter());
+    let ipv6 = infos.iter().flat_map(|i| i.ipv6_dns_servers.iter());
+    for server in ipv4.chain(ipv6).filter_map(|s| parse_dns_server(s)) {
+        if !servers.contains(&server) {
+            servers.push(server);
+        }
+    }
+    servers
+}
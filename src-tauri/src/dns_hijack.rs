@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+
+use crate::dns::{doh_query, system_lookup, DohProvider, RCODE_NXDOMAIN};
+use crate::operations::{run_operation, OperationKind};
+
+// 比較用のカナリアドメイン（どのリゾルバから引いても同じ固定アドレスを返すもの）
+const CANARY_DOMAINS: [&str; 4] = [
+    "one.one.one.one",
+    "dns.google",
+    "dns.quad9.net",
+    "resolver1.opendns.com",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CanaryVerdict {
+    Consistent,
+    Mismatch,
+    PrivateAddress,
+    SystemFailed,
+    DohFailed,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CanaryComparison {
+    pub domain: String,
+    pub system_addresses: Vec<String>,
+    pub cloudflare_addresses: Vec<String>,
+    pub google_addresses: Vec<String>,
+    pub verdict: CanaryVerdict,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DnsHijackCheckResult {
+    pub canaries: Vec<CanaryComparison>,
+    pub nxdomain_probe_domain: String,
+    pub nxdomain_rewritten: bool,
+    pub nxdomain_rewrite_addresses: Vec<String>,
+    pub hijack_suspected: bool,
+    pub findings: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn check_dns_hijacking(app: AppHandle) -> Result<DnsHijackCheckResult, String> {
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        "dns_hijacking",
+        execute_dns_hijacking_check(),
+    )
+    .await
+}
+
+async fn execute_dns_hijacking_check() -> Result<DnsHijackCheckResult, String> {
+    let mut canaries = Vec::new();
+    let mut findings = Vec::new();
+
+    for domain in CANARY_DOMAINS {
+        let comparison = compare_canary(domain).await;
+        match comparison.verdict {
+            CanaryVerdict::Mismatch => findings.push(format!(
+                "{} の応答が DoH と一致しません（システム: {}）",
+                domain,
+                comparison.system_addresses.join(", ")
+            )),
+            CanaryVerdict::PrivateAddress => findings.push(format!(
+                "{} がプライベートアドレスに解決されています（システム: {}）",
+                domain,
+                comparison.system_addresses.join(", ")
+            )),
+            _ => {}
+        }
+        canaries.push(comparison);
+    }
+
+    // 存在しないドメインを引き、NXDOMAIN が書き換えられていないか確認
+    let nxdomain_probe_domain = nxdomain_probe_domain();
+    let (system_result, doh_result) = tokio::join!(
+        system_lookup(&nxdomain_probe_domain),
+        doh_query(DohProvider::Cloudflare, &nxdomain_probe_domain, "A"),
+    );
+    let nxdomain_rewrite_addresses = system_result.unwrap_or_default();
+    let upstream_nxdomain = matches!(&doh_result, Ok(answer) if answer.status == RCODE_NXDOMAIN);
+    let nxdomain_rewritten = upstream_nxdomain && !nxdomain_rewrite_addresses.is_empty();
+    if nxdomain_rewritten {
+        findings.push(format!(
+            "存在しないドメインがアドレスに書き換えられています（{}）。ISP やルータによる検索ページへの誘導の可能性があります",
+            nxdomain_rewrite_addresses.join(", ")
+        ));
+    }
+
+    let hijack_suspected = nxdomain_rewritten
+        || canaries.iter().any(|c| {
+            matches!(
+                c.verdict,
+                CanaryVerdict::Mismatch | CanaryVerdict::PrivateAddress
+            )
+        });
+
+    Ok(DnsHijackCheckResult {
+        canaries,
+        nxdomain_probe_domain,
+        nxdomain_rewritten,
+        nxdomain_rewrite_addresses,
+        hijack_suspected,
+        findings,
+    })
+}
+
+// カナリアドメインをシステムリゾルバと DoH で引き比べる
+async fn compare_canary(domain: &str) -> CanaryComparison {
+    let (system_result, cloudflare_result, google_result) = tokio::join!(
+        system_lookup(domain),
+        doh_query(DohProvider::Cloudflare, domain, "A"),
+        doh_query(DohProvider::Google, domain, "A"),
+    );
+
+    let system_addresses: Vec<String> = system_result
+        .as_ref()
+        .map(|addrs| {
+            addrs
+                .iter()
+                .filter(|a| !a.contains(':'))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    let cloudflare_addresses = cloudflare_result
+        .as_ref()
+        .map(|a| a.addresses())
+        .unwrap_or_default();
+    let google_addresses = google_result
+        .as_ref()
+        .map(|a| a.addresses())
+        .unwrap_or_default();
+
+    let verdict = if system_result.is_err() || system_addresses.is_empty() {
+        CanaryVerdict::SystemFailed
+    } else if system_addresses.iter().any(|a| is_non_global_address(a)) {
+        CanaryVerdict::PrivateAddress
+    } else if cloudflare_addresses.is_empty() && google_addresses.is_empty() {
+        CanaryVerdict::DohFailed
+    } else if system_addresses
+        .iter()
+        .any(|a| cloudflare_addresses.contains(a) || google_addresses.contains(a))
+    {
+        CanaryVerdict::Consistent
+    } else {
+        CanaryVerdict::Mismatch
+    };
+
+    CanaryComparison {
+        domain: domain.to_string(),
+        system_addresses,
+        cloudflare_addresses,
+        google_addresses,
+        verdict,
+    }
+}
+
+// 毎回異なる存在しないドメイン名を生成
+fn nxdomain_probe_domain() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("ghttpping-nx-{:x}.com", nanos)
+}
+
+// グローバルアドレス以外（プライベート・ループバック等）かどうか
+fn is_non_global_address(address: &str) -> bool {
+    match address.parse::<IpAddr>() {
+        Ok(IpAddr::V4(v4)) => !crate::is_global_ipv4(&v4),
+        Ok(IpAddr::V6(v6)) => !crate::is_global_ipv6(&v6),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::task::JoinSet;
+
+use crate::dns::{direct_query, parse_dns_server, DirectQueryError, RECORD_TYPE_A};
+use crate::DnsServerInfo;
+
+// 応答時間の測定に使う問い合わせ（どの DNS サーバでも解決できる名前）
+const PROBE_NAME: &str = "www.example.com";
+
+// サーバごとの測定回数と1回あたりの待ち時間
+const PROBE_COUNT: usize = 3;
+const PROBE_TIMEOUT_MS: u64 = 1500;
+
+// これより遅い DNS サーバは slow とする
+const SLOW_THRESHOLD_MS: f64 = 100.0;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DnsServerLatency {
+    pub server: String,
+    pub interface_aliases: Vec<String>,
+    pub latency_ms: Option<f64>,
+    pub rcode: Option<u16>,
+    pub rank: Option<usize>,
+    pub slow: bool,
+    pub unreachable: bool,
+    pub error_message: Option<String>,
+}
+
+// 発見した DNS サーバの応答時間を測定し、速い順に並べる
+pub async fn rank_dns_servers(dns_servers: &[DnsServerInfo]) -> Vec<DnsServerLatency> {
+    // 同じサーバが複数のインターフェースに設定されている場合はまとめる
+    let mut servers: Vec<(String, SocketAddr, Vec<String>)> = Vec::new();
+    for info in dns_servers {
+        for server in info.ipv4_dns_servers.iter().chain(&info.ipv6_dns_servers) {
+            let Some(addr) = parse_dns_server(server) else {
+                continue;
+            };
+            match servers.iter_mut().find(|(_, a, _)| *a == addr) {
+                Some((_, _, aliases)) => {
+                    if !aliases.contains(&info.interface_alias) {
+                        aliases.push(info.interface_alias.clone());
+                    }
+                }
+                None => servers.push((
+                    server.trim().to_string(),
+                    addr,
+                    vec![info.interface_alias.clone()],
+                )),
+            }
+        }
+    }
+
+    let mut tasks = JoinSet::new();
+    for (server, addr, interface_aliases) in servers {
+        tasks.spawn(async move {
+            let (latency_ms, rcode, error_message) = measure_server(addr).await;
+            DnsServerLatency {
+                server,
+                interface_aliases,
+                latency_ms,
+                rcode,
+                rank: None,
+                slow: latency_ms.is_some_and(|ms| ms > SLOW_THRESHOLD_MS),
+                unreachable: latency_ms.is_none(),
+                error_message,
+            }
+        });
+    }
+    let mut results = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(result) = joined {
+            results.push(result);
+        }
+    }
+
+    // 応答したサーバを速い順に、応答しなかったサーバを末尾に
+    results.sort_by(|a, b| match (a.latency_ms, b.latency_ms) {
+        (Some(a), Some(b)) => a.total_cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.server.cmp(&b.server),
+    });
+    for (i, result) in results.iter_mut().enumerate() {
+        if result.latency_ms.is_some() {
+            result.rank = Some(i + 1);
+        }
+    }
+    results
+}
+
+// 複数回問い合わせて応答時間の中央値を返す
+async fn measure_server(addr: SocketAddr) -> (Option<f64>, Option<u16>, Option<String>) {
+    let timeout = Duration::from_millis(PROBE_TIMEOUT_MS);
+    let mut samples = Vec::new();
+    let mut rcode = None;
+    let mut error_message = None;
+
+    for _ in 0..PROBE_COUNT {
+        match direct_query(addr, PROBE_NAME, RECORD_TYPE_A, timeout).await {
+            Ok(response) => {
+                samples.push(response.elapsed_ms);
+                rcode = Some(response.rcode);
+            }
+            Err(DirectQueryError::Timeout) => {
+                error_message = Some(format!("{} ミリ秒以内に応答がありません", PROBE_TIMEOUT_MS));
+            }
+            Err(DirectQueryError::Failed(e)) => error_message = Some(e),
+        }
+    }
+
+    if samples.is_empty() {
+        return (None, None, error_message);
+    }
+    samples.sort_by(|a, b| a.total_cmp(b));
+    (Some(samples[samples.len() / 2]), rcode, None)
+}
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::AppHandle;
+
+use crate::dns::{direct_query, system_lookup, RECORD_TYPE_A, RECORD_TYPE_AAAA};
+use crate::dns_failure::system_dns_servers;
+use crate::operations::{report_progress, run_operation, OperationKind};
+
+// 問い合わせ回数と間隔
+const DEFAULT_SAMPLES: u32 = 5;
+const MAX_SAMPLES: u32 = 20;
+const SAMPLE_INTERVAL_MS: u64 = 500;
+const QUERY_TIMEOUT_SECS: u64 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoundRobinFamilyResult {
+    pub record_type: String,
+    pub samples: Vec<Vec<String>>,
+    pub failed_samples: u32,
+    pub all_addresses: Vec<String>,
+    pub order_rotates: bool,
+    pub answer_shrinks: bool,
+    pub answer_set_changes: bool,
+    pub first_address_changes: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoundRobinCheckResult {
+    pub host: String,
+    pub dns_server: Option<String>,
+    pub ipv4: RoundRobinFamilyResult,
+    pub ipv6: RoundRobinFamilyResult,
+    pub system_samples: Vec<Vec<String>>,
+    pub findings: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn check_dns_round_robin(
+    app: AppHandle,
+    host: String,
+    samples: Option<u32>,
+) -> Result<RoundRobinCheckResult, String> {
+    let samples = samples.unwrap_or(DEFAULT_SAMPLES);
+    if !(2..=MAX_SAMPLES).contains(&samples) {
+        return Err(format!(
+            "問い合わせ回数は 2 から {} の範囲で指定してください",
+            MAX_SAMPLES
+        ));
+    }
+    let host = crate::normalize_hostname(&host)?;
+
+    let target = host.clone();
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        &target,
+        execute_round_robin_check(host, samples),
+    )
+    .await
+}
+
+async fn execute_round_robin_check(
+    host: String,
+    samples: u32,
+) -> Result<RoundRobinCheckResult, String> {
+    // OS のキャッシュを介さずに毎回の応答を見るため、システムの DNS サーバへ直接問い合わせる
+    let server = system_dns_servers().await.into_iter().next();
+    let timeout = Duration::from_secs(QUERY_TIMEOUT_SECS);
+
+    let mut ipv4_samples = Vec::new();
+    let mut ipv6_samples = Vec::new();
+    let mut ipv4_failed = 0;
+    let mut ipv6_failed = 0;
+    let mut system_samples = Vec::new();
+
+    for i in 0..samples {
+        report_progress(f64::from(i) * 100.0 / f64::from(samples));
+        if i > 0 {
+            tokio::time::sleep(Duration::from_millis(SAMPLE_INTERVAL_MS)).await;
+        }
+
+        if let Some(server) = server {
+            let (a, aaaa) = tokio::join!(
+                direct_query(server, &host, RECORD_TYPE_A, timeout),
+                direct_query(server, &host, RECORD_TYPE_AAAA, timeout),
+            );
+            match a {
+                Ok(response) => ipv4_samples.push(response.addresses),
+                Err(_) => ipv4_failed += 1,
+            }
+            match aaaa {
+                Ok(response) => ipv6_samples.push(response.addresses),
+                Err(_) => ipv6_failed += 1,
+            }
+        }
+
+        // アプリの接続で実際に使われる OS リゾルバの結果も記録
+        system_samples.push(system_lookup(&host).await.unwrap_or_default());
+    }
+
+    if server.is_none() {
+        // DNS サーバが取得できない場合は OS リゾルバの結果のみで判定
+        for addresses in &system_samples {
+            let (v4, v6): (Vec<String>, Vec<String>) =
+                addresses.iter().cloned().partition(|a| !a.contains(':'));
+            ipv4_samples.push(v4);
+            ipv6_samples.push(v6);
+        }
+    }
+
+    let ipv4 = analyze_samples("A", ipv4_samples, ipv4_failed);
+    let ipv6 = analyze_samples("AAAA", ipv6_samples, ipv6_failed);
+    let findings = build_findings(&ipv4, &ipv6, &system_samples, server.is_some());
+
+    Ok(RoundRobinCheckResult {
+        host,
+        dns_server: server.map(|s| s.ip().to_string()),
+        ipv4,
+        ipv6,
+        system_samples,
+        findings,
+    })
+}
+
+// 複数回の応答を比較して、順序の入れ替わり・件数の減少・内容の変化を判定
+fn analyze_samples(
+    record_type: &str,
+    samples: Vec<Vec<String>>,
+    failed_samples: u32,
+) -> RoundRobinFamilyResult {
+    let mut all_addresses: Vec<String> = Vec::new();
+    for address in samples.iter().flatten() {
+        if !all_addresses.contains(address) {
+            all_addresses.push(address.clone());
+        }
+    }
+
+    let non_empty: Vec<&Vec<String>> = samples.iter().filter(|s| !s.is_empty()).collect();
+    let sorted = |s: &Vec<String>| {
+        let mut s = s.clone();
+        s.sort();
+        s
+    };
+    let answer_set_changes = non_empty.windows(2).any(|w| sorted(w[0]) != sorted(w[1]));
+    let order_rotates = non_empty
+        .windows(2)
+        .any(|w| sorted(w[0]) == sorted(w[1]) && w[0] != w[1]);
+    let answer_shrinks = non_empty.iter().any(|s| s.len() < all_addresses.len());
+    let first_address_changes = non_empty.windows(2).any(|w| w[0][0] != w[1][0]);
+
+    RoundRobinFamilyResult {
+        record_type: record_type.to_string(),
+        samples,
+        failed_samples,
+        all_addresses,
+        order_rotates,
+        answer_shrinks,
+        answer_set_changes,
+        first_address_changes,
+    }
+}
+
+fn build_findings(
+    ipv4: &RoundRobinFamilyResult,
+    ipv6: &RoundRobinFamilyResult,
+    system_samples: &[Vec<String>],
+    direct: bool,
+) -> Vec<String> {
+    let mut findings = Vec::new();
+
+    for (family, result) in [("IPv4", ipv4), ("IPv6", ipv6)] {
+        if result.first_address_changes {
+            findings.push(format!(
+                "{} の応答の先頭アドレスが問い合わせごとに変わります。このアプリは先頭のアドレスにのみ接続するため、測定ごとに接続先サーバが異なり結果がばらつく原因になります",
+                family
+            ));
+        } else if result.order_rotates {
+            findings.push(format!(
+                "{} の応答はラウンドロビンで順序が入れ替わっています",
+                family
+            ));
+        }
+        if result.answer_set_changes || result.answer_shrinks {
+            findings.push(format!(
+                "{} の応答に含まれるアドレスが問い合わせごとに変わります（全体で {} 件）。DNS ベースの負荷分散や GeoDNS が使われている可能性があります",
+                family,
+                result.all_addresses.len()
+            ));
+        }
+        if result.failed_samples > 0 {
+            findings.push(format!(
+                "{} ({}) の問い合わせが {} 回失敗しました。DNS サーバの応答が不安定な可能性があります",
+                family, result.record_type, result.failed_samples
+            ));
+        }
+    }
+
+    // OS のキャッシュにより直接問い合わせの変化がアプリに見えていない場合
+    let system_first_changes = system_samples
+        .iter()
+        .filter(|s| !s.is_empty())
+        .map(|s| &s[0])
+        .collect::<Vec<_>>()
+        .windows(2)
+        .any(|w| w[0] != w[1]);
+    if direct && (ipv4.first_address_changes || ipv6.first_address_changes) && !system_first_changes
+    {
+        findings.push(
+            "OS のリゾルバはキャッシュにより同じ順序を返しているため、キャッシュの有効期限（TTL）が切れたタイミングで接続先が変わります"
+                .to_string(),
+        );
+    }
+
+    if findings.is_empty() {
+        findings.push("応答のアドレスと順序は一定でした".to_string());
+    }
+    findings
+}
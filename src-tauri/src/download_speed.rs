@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use url::Url;
+
+use crate::history::{record_history, HistoryKind};
+use crate::http_client::{self, RequestOptions};
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::DnsResolution;
+
+// 受信するサイズの既定値と範囲（短時間で比較できる大きさに制限する）
+const DEFAULT_SAMPLE_BYTES: u64 = 1024 * 1024;
+const MIN_SAMPLE_BYTES: u64 = 1024 * 1024;
+const MAX_SAMPLE_BYTES: u64 = 10 * 1024 * 1024;
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DownloadSample {
+    pub ip_version: u8,
+    pub ip_address: Option<String>,
+    pub status_code: Option<u16>,
+    pub downloaded_bytes: Option<u64>,
+    // 接続開始から指定したサイズ（またはそれより小さい応答本文の最後）を受信するまで
+    pub total_ms: Option<f64>,
+    // 接続開始から応答ヘッダ受信まで
+    pub time_to_first_byte_ms: Option<f64>,
+    pub transfer_ms: Option<f64>,
+    pub bits_per_second: Option<f64>,
+    pub success: bool,
+    pub error_message: Option<String>,
+    // 応答本文が指定したサイズより小さい場合など、結果の比較に注意が必要な事項
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DownloadSpeedResult {
+    pub url: String,
+    pub sample_bytes: u64,
+    pub started_at: String,
+    pub dns_resolution: DnsResolution,
+    pub ipv4: DownloadSample,
+    pub ipv6: DownloadSample,
+    // 両方成功した場合の速い方（4 または 6）と、遅い方に対する速度の比
+    pub faster_ip_version: Option<u8>,
+    pub speed_ratio: Option<f64>,
+}
+
+// 対象の URL から指定したサイズを IPv4 と IPv6 で順に受信し、受信速度と所要時間を比較する
+#[tauri::command]
+pub async fn measure_download_speed(
+    app: AppHandle,
+    url: String,
+    sample_bytes: Option<u64>,
+    ignore_tls_errors: Option<bool>,
+    timeout_secs: Option<u64>,
+) -> Result<DownloadSpeedResult, String> {
+    let sample_bytes = sample_bytes.unwrap_or(DEFAULT_SAMPLE_BYTES);
+    if !(MIN_SAMPLE_BYTES..=MAX_SAMPLE_BYTES).contains(&sample_bytes) {
+        return Err(format!(
+            "受信するサイズは {}〜{} バイトの範囲で指定してください",
+            MIN_SAMPLE_BYTES, MAX_SAMPLE_BYTES
+        ));
+    }
+    let timeout_secs = timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS);
+    http_client::validate_timeout_secs(timeout_secs)?;
+    let ignore_tls_errors = ignore_tls_errors.unwrap_or(false);
+    if ignore_tls_errors {
+        crate::log_security_warning("TLS証明書検証が無効化されています");
+    }
+    crate::validate_url(&url)?;
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+
+    let target = host.clone();
+    let task_app = app.clone();
+    run_operation(&app, OperationKind::SpeedTest, &target, async move {
+        let started_at = crate::now_rfc3339();
+        let dns_resolution = crate::resolve_dns(&host).await;
+        let request = RequestOptions {
+            max_download_bytes: Some(sample_bytes),
+            ..Default::default()
+        };
+        let sample = |ip_version: u8, addresses: &[String]| {
+            measure_family(
+                &url,
+                &request,
+                &host,
+                parsed_url.port(),
+                ip_version,
+                addresses.first().cloned(),
+                sample_bytes,
+                ignore_tls_errors,
+                timeout_secs,
+            )
+        };
+        // 帯域を奪い合わないよう IPv4 → IPv6 の順に受信
+        let ipv4 = sample(4, &dns_resolution.ipv4_addresses).await;
+        report_progress(50.0);
+        let ipv6 = sample(6, &dns_resolution.ipv6_addresses).await;
+
+        let (faster_ip_version, speed_ratio) = match (ipv4.bits_per_second, ipv6.bits_per_second) {
+            (Some(v4), Some(v6)) if ipv4.success && ipv6.success && v4 > 0.0 && v6 > 0.0 => {
+                if v4 >= v6 {
+                    (Some(4), Some(v4 / v6))
+                } else {
+                    (Some(6), Some(v6 / v4))
+                }
+            }
+            _ => (None, None),
+        };
+        let result = DownloadSpeedResult {
+            url: url.clone(),
+            sample_bytes,
+            started_at,
+            dns_resolution,
+            ipv4,
+            ipv6,
+            faster_ip_version,
+            speed_ratio,
+        };
+        if let Err(e) = record_history(&task_app, HistoryKind::SpeedTest, &url, &result) {
+            eprintln!("Failed to record download speed history: {}", e);
+        }
+        Ok(result)
+    })
+    .await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn measure_family(
+    url: &str,
+    request: &RequestOptions,
+    host: &str,
+    port: Option<u16>,
+    ip_version: u8,
+    ip_address: Option<String>,
+    sample_bytes: u64,
+    ignore_tls_errors: bool,
+    timeout_secs: u64,
+) -> DownloadSample {
+    let mut sample = DownloadSample {
+        ip_version,
+        ip_address: ip_address.clone(),
+        status_code: None,
+        downloaded_bytes: None,
+        total_ms: None,
+        time_to_first_byte_ms: None,
+        transfer_ms: None,
+        bits_per_second: None,
+        success: false,
+        error_message: None,
+        warnings: Vec::new(),
+    };
+    let Some(ip_address) = ip_address else {
+        sample.error_message = Some(format!("IPv{}アドレスが見つかりません", ip_version));
+        return sample;
+    };
+
+    let outcome = http_client::send_request(&http_client::HttpRequest {
+        url,
+        options: request,
+        ip_address: Some(&ip_address),
+        host,
+        port,
+        ignore_tls_errors,
+        source_address: None,
+        verbose: false,
+        max_body_bytes: 0,
+        timeout_secs,
+    })
+    .await;
+    let timings = &outcome.timings;
+    sample.status_code = outcome.status_code;
+    sample.downloaded_bytes = outcome.body_bytes;
+    sample.total_ms = Some(timings.total_ms);
+    sample.time_to_first_byte_ms = timings.first_byte_ms.map(|first_byte| {
+        first_byte + timings.connect_ms.unwrap_or(0.0) + timings.tls_handshake_ms.unwrap_or(0.0)
+    });
+    sample.transfer_ms = timings.transfer_ms;
+    sample.bits_per_second = outcome.download_speed().map(|speed| speed * 8.0);
+
+    let (success, error_message) =
+        crate::evaluate_status(outcome.status_code, outcome.error_message);
+    sample.success = success;
+    sample.error_message = error_message;
+    if success && outcome.body_bytes.unwrap_or(0) < sample_bytes {
+        sample.warnings.push(format!(
+            "応答本文（{} バイト）が指定したサイズより小さいため、受信速度の精度が低い可能性があります",
+            outcome.body_bytes.unwrap_or(0)
+        ));
+    }
+    sample
+}
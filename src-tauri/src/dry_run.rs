@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use tokio::net::TcpStream;
+
+// ドライラン中に外部への操作を行わなかった場合のエラーメッセージ
+pub const DRY_RUN_MESSAGE: &str = "ドライランのため実行していません";
+
+// 名前解決の代わりに返す文書用のアドレス（RFC 5737 / RFC 3849、以降の操作の引数を確認できるようにする）
+pub const PLACEHOLDER_IPV4: &str = "192.0.2.1";
+pub const PLACEHOLDER_IPV6: &str = "2001:db8::1";
+
+// 記録しておく操作の上限（連続測定などで増え続けないよう古いものから捨てる）
+const MAX_ACTIONS: usize = 1000;
+
+// 有効にするとすべてのコマンドが外部コマンドの実行や通信を行わず、行う予定の操作を記録する
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static ACTIONS: LazyLock<Mutex<VecDeque<PlannedAction>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    // 外部コマンド（curl.exe、PowerShell など）の実行
+    Process,
+    // 内蔵の HTTP クライアントによるリクエスト
+    Http,
+    // 証明書の確認のための TLS ハンドシェイク
+    Tls,
+    // 名前解決、DNS サーバへの問い合わせ
+    Dns,
+    Tcp,
+    // ポートでの待ち受け
+    Listen,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlannedAction {
+    pub recorded_at: String,
+    pub kind: ActionKind,
+    // 外部コマンドの場合のプログラム名と引数（加工せずそのまま）
+    pub program: Option<String>,
+    pub args: Vec<String>,
+    // 接続先（URL、アドレスとポート、名前など）
+    pub target: Option<String>,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DryRunStatus {
+    pub enabled: bool,
+    pub recorded_actions: usize,
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::SeqCst)
+}
+
+fn record(action: PlannedAction) {
+    if let Ok(mut actions) = ACTIONS.lock() {
+        if actions.len() >= MAX_ACTIONS {
+            actions.pop_front();
+        }
+        actions.push_back(action);
+    }
+}
+
+// ドライラン中であれば操作を記録して true を返す（呼び出し元は実行せずに DRY_RUN_MESSAGE のエラーとする）
+pub fn intercept(kind: ActionKind, target: impl Into<String>, detail: Option<String>) -> bool {
+    if !is_enabled() {
+        return false;
+    }
+    record(PlannedAction {
+        recorded_at: crate::now_rfc3339(),
+        kind,
+        program: None,
+        args: Vec::new(),
+        target: Some(target.into()),
+        detail,
+    });
+    true
+}
+
+// 外部コマンドの実行について intercept と同様に判定する
+pub fn intercept_process(program: &str, args: &[String], detail: Option<String>) -> bool {
+    if !is_enabled() {
+        return false;
+    }
+    record(PlannedAction {
+        recorded_at: crate::now_rfc3339(),
+        kind: ActionKind::Process,
+        program: Some(program.to_string()),
+        args: args.to_vec(),
+        target: None,
+        detail,
+    });
+    true
+}
+
+// TCP 接続（ドライラン中は記録のみで接続しない）
+pub async fn connect_tcp(address: SocketAddr) -> std::io::Result<TcpStream> {
+    if intercept(ActionKind::Tcp, address.to_string(), None) {
+        return Err(std::io::Error::other(DRY_RUN_MESSAGE));
+    }
+    TcpStream::connect(address).await
+}
+
+fn status() -> DryRunStatus {
+    DryRunStatus {
+        enabled: is_enabled(),
+        recorded_actions: ACTIONS.lock().map(|a| a.len()).unwrap_or(0),
+    }
+}
+
+// ドライランを切り替える（有効にする際はそれまでの記録を消去する）
+#[tauri::command]
+pub async fn set_dry_run(enabled: bool) -> Result<DryRunStatus, String> {
+    if enabled {
+        if let Ok(mut actions) = ACTIONS.lock() {
+            actions.clear();
+        }
+    }
+    ENABLED.store(enabled, Ordering::SeqCst);
+    Ok(status())
+}
+
+#[tauri::command]
+pub async fn get_dry_run_status() -> Result<DryRunStatus, String> {
+    Ok(status())
+}
+
+// ドライラン中に記録した操作を古い順に返す（clear を指定すると返した分を消去する）
+#[tauri::command]
+pub async fn get_dry_run_actions(clear: Option<bool>) -> Result<Vec<PlannedAction>, String> {
+    let mut actions = ACTIONS
+        .lock()
+        .map_err(|_| "ドライランの記録を読み込めません".to_string())?;
+    Ok(if clear.unwrap_or(false) {
+        actions.drain(..).collect()
+    } else {
+        actions.iter().cloned().collect()
+    })
+}
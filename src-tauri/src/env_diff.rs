@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{DnsServerInfo, EnvironmentCheckResult, GlobalIPInfo, NetworkAdapter};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdapterAddressChange {
+    pub name: String,
+    pub added_addresses: Vec<String>,
+    pub removed_addresses: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DnsServerChange {
+    pub interface_alias: String,
+    pub before_ipv4: Vec<String>,
+    pub after_ipv4: Vec<String>,
+    pub before_ipv6: Vec<String>,
+    pub after_ipv6: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GlobalIpChange {
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FlagChange {
+    pub name: String,
+    pub before: bool,
+    pub after: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvironmentDiff {
+    pub adapters_added: Vec<String>,
+    pub adapters_removed: Vec<String>,
+    pub adapter_address_changes: Vec<AdapterAddressChange>,
+    pub dns_server_changes: Vec<DnsServerChange>,
+    pub ipv4_global_ip_change: Option<GlobalIpChange>,
+    pub ipv6_global_ip_change: Option<GlobalIpChange>,
+    pub flag_changes: Vec<FlagChange>,
+    pub has_changes: bool,
+}
+
+#[tauri::command]
+pub async fn diff_environment_results(
+    before: EnvironmentCheckResult,
+    after: EnvironmentCheckResult,
+) -> Result<EnvironmentDiff, String> {
+    Ok(diff_environment(&before, &after))
+}
+
+// 2つの環境確認結果の差分を計算
+pub fn diff_environment(
+    before: &EnvironmentCheckResult,
+    after: &EnvironmentCheckResult,
+) -> EnvironmentDiff {
+    let (adapters_added, adapters_removed, adapter_address_changes) =
+        diff_adapters(&before.adapters, &after.adapters);
+    let dns_server_changes = diff_dns_servers(&before.dns_servers, &after.dns_servers);
+    let ipv4_global_ip_change = diff_global_ip(&before.ipv4_global_ip, &after.ipv4_global_ip);
+    let ipv6_global_ip_change = diff_global_ip(&before.ipv6_global_ip, &after.ipv6_global_ip);
+
+    let flag_changes: Vec<FlagChange> = [
+        (
+            "ipv4_connectivity",
+            before.ipv4_connectivity,
+            after.ipv4_connectivity,
+        ),
+        (
+            "ipv6_connectivity",
+            before.ipv6_connectivity,
+            after.ipv6_connectivity,
+        ),
+        (
+            "dns_resolution",
+            before.dns_resolution,
+            after.dns_resolution,
+        ),
+        (
+            "internet_available",
+            before.internet_available,
+            after.internet_available,
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, b, a)| b != a)
+    .map(|(name, before, after)| FlagChange {
+        name: name.to_string(),
+        before,
+        after,
+    })
+    .collect();
+
+    let has_changes = !adapters_added.is_empty()
+        || !adapters_removed.is_empty()
+        || !adapter_address_changes.is_empty()
+        || !dns_server_changes.is_empty()
+        || ipv4_global_ip_change.is_some()
+        || ipv6_global_ip_change.is_some()
+        || !flag_changes.is_empty();
+
+    EnvironmentDiff {
+        adapters_added,
+        adapters_removed,
+        adapter_address_changes,
+        dns_server_changes,
+        ipv4_global_ip_change,
+        ipv6_global_ip_change,
+        flag_changes,
+        has_changes,
+    }
+}
+
+// a にあって b にない要素（順序は a のまま）
+fn missing_from(a: &[String], b: &[String]) -> Vec<String> {
+    a.iter().filter(|x| !b.contains(x)).cloned().collect()
+}
+
+// アダプタの追加・削除と、同名アダプタのアドレス変化
+fn diff_adapters(
+    before: &[NetworkAdapter],
+    after: &[NetworkAdapter],
+) -> (Vec<String>, Vec<String>, Vec<AdapterAddressChange>) {
+    let before_names: Vec<String> = before.iter().map(|a| a.name.clone()).collect();
+    let after_names: Vec<String> = after.iter().map(|a| a.name.clone()).collect();
+
+    let changes = after
+        .iter()
+        .filter_map(|a| {
+            let b = before.iter().find(|b| b.name == a.name)?;
+            let added_addresses = missing_from(&a.ip_addresses, &b.ip_addresses);
+            let removed_addresses = missing_from(&b.ip_addresses, &a.ip_addresses);
+            if added_addresses.is_empty() && removed_addresses.is_empty() {
+                return None;
+            }
+            Some(AdapterAddressChange {
+                name: a.name.clone(),
+                added_addresses,
+                removed_addresses,
+            })
+        })
+        .collect();
+
+    (
+        missing_from(&after_names, &before_names),
+        missing_from(&before_names, &after_names),
+        changes,
+    )
+}
+
+// インターフェースごとの DNS サーバの変化（片方にしかないものも含む）
+fn diff_dns_servers(before: &[DnsServerInfo], after: &[DnsServerInfo]) -> Vec<DnsServerChange> {
+    let mut aliases: Vec<&str> = before.iter().map(|d| d.interface_alias.as_str()).collect();
+    for d in after {
+        if !aliases.contains(&d.interface_alias.as_str()) {
+            aliases.push(&d.interface_alias);
+        }
+    }
+
+    aliases
+        .into_iter()
+        .filter_map(|alias| {
+            let b = before.iter().find(|d| d.interface_alias == alias);
+            let a = after.iter().find(|d| d.interface_alias == alias);
+            let servers = |d: Option<&DnsServerInfo>| {
+                d.map(|d| (d.ipv4_dns_servers.clone(), d.ipv6_dns_servers.clone()))
+                    .unwrap_or_default()
+            };
+            let (before_ipv4, before_ipv6) = servers(b);
+            let (after_ipv4, after_ipv6) = servers(a);
+            if before_ipv4 == after_ipv4 && before_ipv6 == after_ipv6 {
+                return None;
+            }
+            Some(DnsServerChange {
+                interface_alias: alias.to_string(),
+                before_ipv4,
+                after_ipv4,
+                before_ipv6,
+                after_ipv6,
+            })
+        })
+        .collect()
+}
+
+// グローバル IP の変化（取得可否の変化も含む）
+fn diff_global_ip(
+    before: &Option<GlobalIPInfo>,
+    after: &Option<GlobalIPInfo>,
+) -> Option<GlobalIpChange> {
+    let before = before.as_ref().map(|i| i.client_host.clone());
+    let after = after.as_ref().map(|i| i.client_host.clone());
+    if before == after {
+        return None;
+    }
+    Some(GlobalIpChange { before, after })
+}
@@ -0,0 +1,383 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::task::AbortHandle;
+
+use crate::env_diff::diff_environment;
+use crate::maintenance::{self, MonitorKind};
+use crate::monitor_group::{self, MonitorDependency};
+use crate::operations::{run_operation, OperationKind, OPERATION_CANCELLED_MESSAGE};
+use crate::webhook;
+use crate::EnvironmentCheckResult;
+
+// 変化を検出したときにフロントエンドへ通知するイベント名
+pub const ENVIRONMENT_ALERT_EVENT: &str = "environment-alert";
+
+// 定期確認の間隔（秒）
+const DEFAULT_INTERVAL_SECS: u64 = 300;
+const MIN_INTERVAL_SECS: u64 = 60;
+const MAX_INTERVAL_SECS: u64 = 86400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvironmentAlertKind {
+    GlobalIpChanged,
+    Ipv4Lost,
+    Ipv6Lost,
+    DnsServersChanged,
+    WentOffline,
+    CheckFailed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentAlert {
+    pub kind: EnvironmentAlertKind,
+    pub message: String,
+    pub detected_at: String,
+    // メンテナンス時間帯中に検出した変化は通知せず、時間帯の名前を付けて記録のみ行う
+    pub maintenance_window: Option<String>,
+    // 依存先のグループが同じ時期に停止していたため通知しなかった場合のグループ名（原因は上流）
+    #[serde(default)]
+    pub suppressed_by: Option<String>,
+}
+
+// Webhook で送信する1回分の確認結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentCheckSummary {
+    pub checked_at: String,
+    pub alerts: Vec<EnvironmentAlert>,
+    pub maintenance_window: Option<String>,
+    #[serde(default)]
+    pub suppressed_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentMonitorStatus {
+    pub running: bool,
+    pub paused: bool,
+    pub interval_secs: Option<u64>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    pub last_checked_at: Option<String>,
+    pub last_alerts: Vec<EnvironmentAlert>,
+}
+
+struct RunningMonitor {
+    interval_secs: u64,
+    dependency: MonitorDependency,
+    abort_handle: AbortHandle,
+}
+
+#[derive(Default)]
+struct MonitorState {
+    running: Option<RunningMonitor>,
+    // 一時停止中は確認を飛ばす（再開後は停止前の結果と比較する）
+    paused: bool,
+    last_checked_at: Option<String>,
+    last_alerts: Vec<EnvironmentAlert>,
+}
+
+// 環境確認の定期実行状態（Tauri の State として管理）
+#[derive(Default)]
+pub struct EnvironmentMonitor {
+    state: Mutex<MonitorState>,
+}
+
+impl EnvironmentMonitor {
+    fn status(&self) -> Result<EnvironmentMonitorStatus, String> {
+        let state = self
+            .state
+            .lock()
+            .map_err(|_| "定期確認の状態のロック取得に失敗".to_string())?;
+        Ok(EnvironmentMonitorStatus {
+            running: state.running.is_some(),
+            paused: state.running.is_some() && state.paused,
+            interval_secs: state.running.as_ref().map(|m| m.interval_secs),
+            group: state
+                .running
+                .as_ref()
+                .and_then(|m| m.dependency.group.clone()),
+            depends_on: state
+                .running
+                .as_ref()
+                .map(|m| m.dependency.depends_on.clone())
+                .unwrap_or_default(),
+            last_checked_at: state.last_checked_at.clone(),
+            last_alerts: state.last_alerts.clone(),
+        })
+    }
+
+    fn set_paused(&self, paused: bool) -> Result<(), String> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| "定期確認の状態のロック取得に失敗".to_string())?;
+        if state.running.is_none() {
+            return Err("定期確認は実行されていません".to_string());
+        }
+        state.paused = paused;
+        Ok(())
+    }
+}
+
+// 環境確認の定期実行を開始（実行中の場合は間隔を変えて再開）
+// group と depends_on を指定すると、依存先のグループの定期実行が停止している間は通知を抑止する
+#[tauri::command]
+pub async fn start_environment_monitor(
+    app: AppHandle,
+    monitor: State<'_, EnvironmentMonitor>,
+    interval_secs: Option<u64>,
+    group: Option<String>,
+    depends_on: Option<Vec<String>>,
+) -> Result<EnvironmentMonitorStatus, String> {
+    let interval_secs = interval_secs.unwrap_or(DEFAULT_INTERVAL_SECS);
+    if !(MIN_INTERVAL_SECS..=MAX_INTERVAL_SECS).contains(&interval_secs) {
+        return Err(format!(
+            "確認間隔は {} 秒から {} 秒の範囲で指定してください",
+            MIN_INTERVAL_SECS, MAX_INTERVAL_SECS
+        ));
+    }
+    let dependency = monitor_group::register(
+        &app,
+        MonitorKind::Environment,
+        group,
+        depends_on.unwrap_or_default(),
+    )?;
+
+    {
+        let mut state = monitor
+            .state
+            .lock()
+            .map_err(|_| "定期確認の状態のロック取得に失敗".to_string())?;
+        if let Some(running) = state.running.take() {
+            running.abort_handle.abort();
+        }
+        let handle = tokio::spawn(monitor_loop(app.clone(), interval_secs));
+        state.running = Some(RunningMonitor {
+            interval_secs,
+            dependency,
+            abort_handle: handle.abort_handle(),
+        });
+        state.paused = false;
+    }
+
+    monitor.status()
+}
+
+#[tauri::command]
+pub async fn stop_environment_monitor(
+    app: AppHandle,
+    monitor: State<'_, EnvironmentMonitor>,
+) -> Result<EnvironmentMonitorStatus, String> {
+    monitor_group::unregister(&app, MonitorKind::Environment);
+    {
+        let mut state = monitor
+            .state
+            .lock()
+            .map_err(|_| "定期確認の状態のロック取得に失敗".to_string())?;
+        if let Some(running) = state.running.take() {
+            running.abort_handle.abort();
+        }
+    }
+    monitor.status()
+}
+
+#[tauri::command]
+pub async fn get_environment_monitor_status(
+    monitor: State<'_, EnvironmentMonitor>,
+) -> Result<EnvironmentMonitorStatus, String> {
+    monitor.status()
+}
+
+#[tauri::command]
+pub async fn pause_environment_monitor(
+    monitor: State<'_, EnvironmentMonitor>,
+) -> Result<EnvironmentMonitorStatus, String> {
+    monitor.set_paused(true)?;
+    monitor.status()
+}
+
+#[tauri::command]
+pub async fn resume_environment_monitor(
+    monitor: State<'_, EnvironmentMonitor>,
+) -> Result<EnvironmentMonitorStatus, String> {
+    monitor.set_paused(false)?;
+    monitor.status()
+}
+
+// 一定間隔で環境確認を行い、前回からの重要な変化を通知する
+async fn monitor_loop(app: AppHandle, interval_secs: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut previous: Option<EnvironmentCheckResult> = None;
+    let mut previous_failed = false;
+
+    loop {
+        interval.tick().await;
+        let paused = app
+            .state::<EnvironmentMonitor>()
+            .state
+            .lock()
+            .is_ok_and(|state| state.paused);
+        if paused {
+            continue;
+        }
+        let detected_at = chrono::Local::now().to_rfc3339();
+
+        // stop_all で中止できるよう、各回を操作として登録する（中止された回は結果を通知しない）
+        let check = run_operation(
+            &app,
+            OperationKind::Diagnostic,
+            "environment monitor",
+            crate::execute_environment_check(app.clone()),
+        )
+        .await;
+        // オフライン・両ファミリの接続喪失・確認の失敗を、依存元から見た停止として扱う
+        let down = check.as_ref().map_or(true, |current| {
+            current.offline || (!current.ipv4_connectivity && !current.ipv6_connectivity)
+        });
+        let mut alerts = match check {
+            Ok(current) => {
+                let alerts = previous
+                    .as_ref()
+                    .map(|before| find_alerts(before, &current, &detected_at))
+                    .unwrap_or_default();
+                previous = Some(current);
+                previous_failed = false;
+                alerts
+            }
+            Err(e) if e == OPERATION_CANCELLED_MESSAGE => continue,
+            // 失敗が続く場合は最初の1回だけ通知する
+            Err(e) if !previous_failed => {
+                previous_failed = true;
+                vec![EnvironmentAlert {
+                    kind: EnvironmentAlertKind::CheckFailed,
+                    message: format!("定期的な環境確認に失敗しました: {}", e),
+                    detected_at: detected_at.clone(),
+                    maintenance_window: None,
+                    suppressed_by: None,
+                }]
+            }
+            Err(_) => vec![],
+        };
+        let maintenance_window = maintenance::active_window(&app, MonitorKind::Environment);
+        let suppressed_by = monitor_group::evaluate(
+            &app,
+            MonitorKind::Environment,
+            down,
+            Duration::from_secs(interval_secs * 2),
+        );
+        for alert in &mut alerts {
+            alert.maintenance_window = maintenance_window.clone();
+            alert.suppressed_by = suppressed_by.clone();
+        }
+
+        let monitor = app.state::<EnvironmentMonitor>();
+        if let Ok(mut state) = monitor.state.lock() {
+            state.last_checked_at = Some(detected_at.clone());
+            if !alerts.is_empty() {
+                state.last_alerts = alerts.clone();
+            }
+        }
+
+        let summary = EnvironmentCheckSummary {
+            checked_at: detected_at,
+            alerts: alerts.clone(),
+            maintenance_window: maintenance_window.clone(),
+            suppressed_by: suppressed_by.clone(),
+        };
+        let problem = maintenance_window.is_none() && !alerts.is_empty();
+        webhook::notify(
+            &app,
+            MonitorKind::Environment,
+            &summary,
+            problem,
+            suppressed_by.as_deref(),
+        );
+
+        if maintenance_window.is_some() || suppressed_by.is_some() {
+            continue;
+        }
+        for alert in &alerts {
+            if let Err(e) = app.emit(ENVIRONMENT_ALERT_EVENT, alert) {
+                eprintln!("Failed to emit {}: {}", ENVIRONMENT_ALERT_EVENT, e);
+            }
+        }
+    }
+}
+
+// 前回と今回の結果から通知すべき変化を抽出
+fn find_alerts(
+    before: &EnvironmentCheckResult,
+    after: &EnvironmentCheckResult,
+    detected_at: &str,
+) -> Vec<EnvironmentAlert> {
+    let mut alerts = Vec::new();
+    let mut push = |kind: EnvironmentAlertKind, message: String| {
+        alerts.push(EnvironmentAlert {
+            kind,
+            message,
+            detected_at: detected_at.to_string(),
+            maintenance_window: None,
+            suppressed_by: None,
+        });
+    };
+
+    if !before.offline && after.offline {
+        push(
+            EnvironmentAlertKind::WentOffline,
+            "オフラインになりました。LANケーブルやWi-Fiの接続を確認してください".to_string(),
+        );
+        return alerts;
+    }
+
+    let diff = diff_environment(before, after);
+    for (family, change) in [
+        ("IPv4", &diff.ipv4_global_ip_change),
+        ("IPv6", &diff.ipv6_global_ip_change),
+    ] {
+        // 取得失敗による変化は接続喪失として別に通知する
+        if let Some(change) = change {
+            if let (Some(b), Some(a)) = (&change.before, &change.after) {
+                push(
+                    EnvironmentAlertKind::GlobalIpChanged,
+                    format!(
+                        "{} グローバル IP が {} から {} に変わりました",
+                        family, b, a
+                    ),
+                );
+            }
+        }
+    }
+
+    if before.ipv4_connectivity && !after.ipv4_connectivity {
+        push(
+            EnvironmentAlertKind::Ipv4Lost,
+            "IPv4 で外部に接続できなくなりました".to_string(),
+        );
+    }
+    if before.ipv6_connectivity && !after.ipv6_connectivity {
+        push(
+            EnvironmentAlertKind::Ipv6Lost,
+            "IPv6 で外部に接続できなくなりました。ルーターや VPN の設定変更がないか確認してください"
+                .to_string(),
+        );
+    }
+
+    if !diff.dns_server_changes.is_empty() {
+        let aliases: Vec<&str> = diff
+            .dns_server_changes
+            .iter()
+            .map(|c| c.interface_alias.as_str())
+            .collect();
+        push(
+            EnvironmentAlertKind::DnsServersChanged,
+            format!("DNS サーバの設定が変わりました: {}", aliases.join(", ")),
+        );
+    }
+
+    alerts
+}
@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use tauri::AppHandle;
+
+use crate::operations::{run_operation, OperationKind};
+use crate::process::run_command;
+
+// 動的ポートの範囲と使用中のポートを取得するスクリプト
+// netsh int ipv4 show dynamicport の出力は OS の表示言語で変わるため、同じ情報を返すコマンドレットを使用
+const EPHEMERAL_PORT_SCRIPT: &str = r#"$names = @{}
+Get-Process -ErrorAction SilentlyContinue | ForEach-Object { $names[[int]$_.Id] = $_.ProcessName }
+$tcp = Get-NetTCPSetting -ErrorAction Stop | Where-Object { $_.DynamicPortRangeNumberOfPorts } | Select-Object -First 1
+$udp = Get-NetUDPSetting -ErrorAction Stop
+[pscustomobject]@{
+    tcp_start_port = [int]$tcp.DynamicPortRangeStartPort
+    tcp_number_of_ports = [int]$tcp.DynamicPortRangeNumberOfPorts
+    udp_start_port = [int]$udp.DynamicPortRangeStartPort
+    udp_number_of_ports = [int]$udp.DynamicPortRangeNumberOfPorts
+    tcp = @(Get-NetTCPConnection -ErrorAction SilentlyContinue | ForEach-Object {
+        [pscustomobject]@{
+            local_port = [int]$_.LocalPort
+            state = [string]$_.State
+            process = [string]$names[[int]$_.OwningProcess]
+        }
+    })
+    udp = @(Get-NetUDPEndpoint -ErrorAction SilentlyContinue | ForEach-Object {
+        [pscustomobject]@{
+            local_port = [int]$_.LocalPort
+            state = ''
+            process = [string]$names[[int]$_.OwningProcess]
+        }
+    })
+} | ConvertTo-Json -Compress -Depth 3"#;
+
+// Windows Vista 以降の既定の範囲（49152〜65535）
+const DEFAULT_NUMBER_OF_PORTS: u32 = 16384;
+
+// 使用率がこの値以上なら枯渇の恐れがあるとみなす（%）
+const WARNING_USAGE_PERCENT: f64 = 80.0;
+
+// 使用数の多いプロセスとして返す件数
+const MAX_TOP_PROCESSES: usize = 5;
+
+#[derive(Debug, Deserialize)]
+struct RawSocket {
+    local_port: u32,
+    state: String,
+    process: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawEphemeralPorts {
+    tcp_start_port: u32,
+    tcp_number_of_ports: u32,
+    udp_start_port: u32,
+    udp_number_of_ports: u32,
+    tcp: Vec<RawSocket>,
+    udp: Vec<RawSocket>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortStateCount {
+    pub state: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProcessPortUsage {
+    pub process: String,
+    pub ports: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EphemeralPortUsage {
+    pub protocol: String,
+    pub start_port: u32,
+    pub end_port: u32,
+    pub number_of_ports: u32,
+    pub ports_in_use: usize,
+    pub usage_percent: f64,
+    pub state_counts: Vec<PortStateCount>,
+    pub top_processes: Vec<ProcessPortUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EphemeralPortDiagnostics {
+    pub tcp: EphemeralPortUsage,
+    pub udp: EphemeralPortUsage,
+    pub findings: Vec<String>,
+}
+
+// 動的ポートの範囲と使用状況を確認（枯渇すると接続が散発的に失敗する）
+#[tauri::command]
+pub async fn check_ephemeral_ports(app: AppHandle) -> Result<EphemeralPortDiagnostics, String> {
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        "ephemeral ports",
+        execute_ephemeral_port_check(),
+    )
+    .await
+}
+
+async fn execute_ephemeral_port_check() -> Result<EphemeralPortDiagnostics, String> {
+    let args = [
+        "-NoProfile".to_string(),
+        "-WindowStyle".to_string(),
+        "Hidden".to_string(),
+        "-Command".to_string(),
+        EPHEMERAL_PORT_SCRIPT.to_string(),
+    ];
+    let output = run_command("powershell", &args)
+        .await
+        .map_err(|e| format!("PowerShellコマンド実行失敗: {}", e))?;
+    if !output.status.success() {
+        return Err("動的ポートの情報を取得できません".to_string());
+    }
+
+    let stdout = crate::decode_command_output(&output.stdout);
+    let raw: RawEphemeralPorts = serde_json::from_str(stdout.trim())
+        .map_err(|e| format!("動的ポートの情報の解析失敗: {}", e))?;
+
+    let tcp = summarize_usage("tcp", raw.tcp_start_port, raw.tcp_number_of_ports, &raw.tcp);
+    let udp = summarize_usage("udp", raw.udp_start_port, raw.udp_number_of_ports, &raw.udp);
+    let findings = find_issues(&tcp, &udp);
+
+    Ok(EphemeralPortDiagnostics { tcp, udp, findings })
+}
+
+// 範囲内のローカルポートを数える（同じポートを複数の接続で共有する場合は1つとして数える）
+fn summarize_usage(
+    protocol: &str,
+    start_port: u32,
+    number_of_ports: u32,
+    sockets: &[RawSocket],
+) -> EphemeralPortUsage {
+    let end_port = (start_port + number_of_ports).saturating_sub(1);
+    let in_range: Vec<&RawSocket> = sockets
+        .iter()
+        .filter(|s| number_of_ports > 0 && (start_port..=end_port).contains(&s.local_port))
+        .collect();
+    let ports_in_use = in_range
+        .iter()
+        .map(|s| s.local_port)
+        .collect::<HashSet<_>>()
+        .len();
+
+    let mut state_counts: HashMap<&str, usize> = HashMap::new();
+    let mut process_ports: HashMap<&str, HashSet<u32>> = HashMap::new();
+    for socket in &in_range {
+        if !socket.state.is_empty() {
+            *state_counts.entry(socket.state.as_str()).or_default() += 1;
+        }
+        let process = if socket.process.is_empty() {
+            "(不明)"
+        } else {
+            socket.process.as_str()
+        };
+        process_ports
+            .entry(process)
+            .or_default()
+            .insert(socket.local_port);
+    }
+
+    let mut state_counts: Vec<PortStateCount> = state_counts
+        .into_iter()
+        .map(|(state, count)| PortStateCount {
+            state: state.to_string(),
+            count,
+        })
+        .collect();
+    state_counts.sort_by(|a, b| b.count.cmp(&a.count).then(a.state.cmp(&b.state)));
+
+    let mut top_processes: Vec<ProcessPortUsage> = process_ports
+        .into_iter()
+        .map(|(process, ports)| ProcessPortUsage {
+            process: process.to_string(),
+            ports: ports.len(),
+        })
+        .collect();
+    top_processes.sort_by(|a, b| b.ports.cmp(&a.ports).then(a.process.cmp(&b.process)));
+    top_processes.truncate(MAX_TOP_PROCESSES);
+
+    EphemeralPortUsage {
+        protocol: protocol.to_string(),
+        start_port,
+        end_port,
+        number_of_ports,
+        ports_in_use,
+        usage_percent: if number_of_ports > 0 {
+            ports_in_use as f64 * 100.0 / number_of_ports as f64
+        } else {
+            0.0
+        },
+        state_counts,
+        top_processes,
+    }
+}
+
+fn find_issues(tcp: &EphemeralPortUsage, udp: &EphemeralPortUsage) -> Vec<String> {
+    let mut findings = Vec::new();
+    for usage in [tcp, udp] {
+        let protocol = usage.protocol.to_uppercase();
+        if usage.usage_percent >= WARNING_USAGE_PERCENT {
+            let top = usage
+                .top_processes
+                .first()
+                .map(|p| {
+                    format!(
+                        "（最も多く使用しているプロセス: {} {} 個）",
+                        p.process, p.ports
+                    )
+                })
+                .unwrap_or_default();
+            findings.push(format!(
+                "{} の動的ポートの {:.0}% が使用中です。枯渇すると新しい接続が散発的に失敗します{}",
+                protocol, usage.usage_percent, top
+            ));
+        }
+        if usage.number_of_ports < DEFAULT_NUMBER_OF_PORTS {
+            findings.push(format!(
+                "{} の動的ポートの範囲が {}〜{}（{} 個）に狭められています。Windows の既定は 49152〜65535 です",
+                protocol, usage.start_port, usage.end_port, usage.number_of_ports
+            ));
+        }
+    }
+
+    // TIME_WAIT が大半を占める場合は短い接続を大量に繰り返しているアプリがある
+    let time_wait = tcp
+        .state_counts
+        .iter()
+        .find(|s| s.state == "TimeWait")
+        .map_or(0, |s| s.count);
+    if tcp.ports_in_use > 0 && time_wait * 2 > tcp.ports_in_use {
+        findings.push(format!(
+            "TCP の動的ポートの多くが TIME_WAIT 状態です（{} 個）。短い接続を繰り返すアプリケーションがないか確認してください",
+            time_wait
+        ));
+    }
+    findings
+}
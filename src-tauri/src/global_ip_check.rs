@@ -0,0 +1,206 @@
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+
+use crate::dns::{direct_query, DirectQueryError, RECORD_TYPE_A, RECORD_TYPE_TXT};
+use crate::ip_echo::IpEchoEndpoint;
+use crate::operations::{report_progress, run_operation, OperationKind};
+
+// HTTP で取得する場合のタイムアウト（秒）
+const HTTP_TIMEOUT_SECS: u64 = 5;
+
+// DNS で取得する場合の1回の問い合わせのタイムアウト
+const DNS_TIMEOUT: Duration = Duration::from_secs(3);
+
+// 問い合わせ元のアドレスを返す権威 DNS サーバ（提供元、サーバ、問い合わせる名前、レコードタイプ）
+// リゾルバを介すとリゾルバのアドレスが返るため、権威サーバへ直接問い合わせる
+const DNS_METHODS: [(&str, &str, &str, u16); 3] = [
+    (
+        "Google",
+        "216.239.32.10",
+        "o-o.myaddr.l.google.com",
+        RECORD_TYPE_TXT,
+    ),
+    (
+        "OpenDNS",
+        "208.67.222.222",
+        "myip.opendns.com",
+        RECORD_TYPE_A,
+    ),
+    ("Akamai", "193.108.88.1", "whoami.akamai.net", RECORD_TYPE_A),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GlobalIpMethod {
+    Http,
+    Dns,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalIpObservation {
+    pub method: GlobalIpMethod,
+    // HTTP の場合は IP エコーサービスの URL、DNS の場合は "提供元: 名前 @ サーバ"
+    pub source: String,
+    pub address: Option<String>,
+    pub elapsed_ms: f64,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GlobalIpConsistencyResult {
+    pub observations: Vec<GlobalIpObservation>,
+    // 取得できたアドレス（重複なし）
+    pub addresses: Vec<String>,
+    // 2つ以上の方法で取得でき、すべて一致した場合は true（比較できない場合は None）
+    pub consistent: Option<bool>,
+    pub findings: Vec<String>,
+}
+
+// グローバル IPv4 アドレスを HTTP（IP エコーサービス）と DNS（権威サーバの問い合わせ元を返す名前）の両方で取得して比較
+// HTTP だけ異なる場合はプロキシの経由、DNS の問い合わせ先で異なる場合は DNS の横取りが疑われる
+#[tauri::command]
+pub async fn check_global_ip_consistency(
+    app: AppHandle,
+) -> Result<GlobalIpConsistencyResult, String> {
+    let echo_endpoints = crate::ip_echo::load_ip_echo_endpoints(&app);
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        "global_ip_consistency",
+        execute_consistency_check(echo_endpoints),
+    )
+    .await
+}
+
+async fn execute_consistency_check(
+    echo_endpoints: Vec<IpEchoEndpoint>,
+) -> Result<GlobalIpConsistencyResult, String> {
+    let http = async {
+        let started = Instant::now();
+        let fetched = crate::fetch_global_ip(&echo_endpoints, 4, HTTP_TIMEOUT_SECS).await;
+        GlobalIpObservation {
+            method: GlobalIpMethod::Http,
+            source: echo_endpoints
+                .iter()
+                .filter(|e| e.ip_version == 4)
+                .map(|e| e.url.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+            elapsed_ms: started.elapsed().as_secs_f64() * 1000.0,
+            address: fetched.as_ref().ok().map(|info| info.client_host.clone()),
+            error_message: fetched.err(),
+        }
+    };
+    let (http, google, opendns, akamai) = tokio::join!(
+        http,
+        observe_dns(DNS_METHODS[0]),
+        observe_dns(DNS_METHODS[1]),
+        observe_dns(DNS_METHODS[2]),
+    );
+    report_progress(90.0);
+
+    let observations = vec![http, google, opendns, akamai];
+    let addresses = distinct_addresses(observations.iter());
+    let findings = find_discrepancies(&observations);
+    let observed = observations.iter().filter(|o| o.address.is_some()).count();
+    Ok(GlobalIpConsistencyResult {
+        consistent: (observed >= 2).then_some(addresses.len() == 1),
+        observations,
+        addresses,
+        findings,
+    })
+}
+
+async fn observe_dns(
+    (provider, server, name, record_type): (&str, &str, &str, u16),
+) -> GlobalIpObservation {
+    let mut observation = GlobalIpObservation {
+        method: GlobalIpMethod::Dns,
+        source: format!("{}: {} @ {}", provider, name, server),
+        address: None,
+        elapsed_ms: 0.0,
+        error_message: None,
+    };
+    let Ok(ip) = server.parse::<IpAddr>() else {
+        observation.error_message = Some(format!("無効なDNSサーバ: {}", server));
+        return observation;
+    };
+    let started = Instant::now();
+    let response = direct_query(SocketAddr::new(ip, 53), name, record_type, DNS_TIMEOUT).await;
+    observation.elapsed_ms = started.elapsed().as_secs_f64() * 1000.0;
+    match response {
+        Ok(response) => {
+            // TXT の場合も最初の IPv4 アドレスの形式の値を使う
+            observation.address = response
+                .records
+                .iter()
+                .filter(|r| r.record_type == record_type)
+                .find_map(|r| match r.data.trim().parse::<IpAddr>() {
+                    Ok(IpAddr::V4(v4)) => Some(v4.to_string()),
+                    _ => None,
+                });
+            if observation.address.is_none() {
+                observation.error_message = Some(format!(
+                    "アドレスを含む応答がありません（rcode: {}）",
+                    response.rcode
+                ));
+            }
+        }
+        Err(DirectQueryError::Timeout) => {
+            observation.error_message = Some("DNS 問い合わせがタイムアウトしました".to_string())
+        }
+        Err(DirectQueryError::Failed(e)) => observation.error_message = Some(e),
+    }
+    observation
+}
+
+fn distinct_addresses<'a>(
+    observations: impl Iterator<Item = &'a GlobalIpObservation>,
+) -> Vec<String> {
+    let mut addresses: Vec<String> = Vec::new();
+    for address in observations.filter_map(|o| o.address.as_ref()) {
+        if !addresses.contains(address) {
+            addresses.push(address.clone());
+        }
+    }
+    addresses
+}
+
+fn find_discrepancies(observations: &[GlobalIpObservation]) -> Vec<String> {
+    let mut findings = Vec::new();
+    let http_address = observations
+        .iter()
+        .find(|o| o.method == GlobalIpMethod::Http)
+        .and_then(|o| o.address.as_ref());
+    let dns_addresses = distinct_addresses(
+        observations
+            .iter()
+            .filter(|o| o.method == GlobalIpMethod::Dns),
+    );
+
+    if dns_addresses.len() > 1 {
+        findings.push(format!(
+            "DNS の問い合わせ先によってアドレスが異なります（{}）。DNS の問い合わせが横取りされている可能性があります",
+            dns_addresses.join(", ")
+        ));
+    }
+    if let Some(http_address) = http_address {
+        if !dns_addresses.is_empty() && !dns_addresses.contains(http_address) {
+            findings.push(format!(
+                "HTTP で取得したアドレス（{}）と DNS で取得したアドレス（{}）が異なります。HTTP がプロキシを経由している可能性があります",
+                http_address,
+                dns_addresses.join(", ")
+            ));
+        }
+    }
+    // DNS の問い合わせがすべて失敗した場合は、外部の DNS サーバへの通信が遮断されている可能性がある
+    if dns_addresses.is_empty() && http_address.is_some() {
+        findings.push(
+            "DNS でアドレスを取得できません。外部の DNS サーバへの通信（UDP 53 番）が遮断されている可能性があります"
+                .to_string(),
+        );
+    }
+    findings
+}
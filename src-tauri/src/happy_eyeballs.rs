@@ -0,0 +1,326 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::time::Instant;
+use url::Url;
+
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::DnsResolution;
+
+// 比較する IPv4 へのフォールバック開始までの待ち時間（ミリ秒、RFC 8305 の推奨値は 250）
+const DEFAULT_FALLBACK_DELAYS_MS: [u64; 5] = [0, 50, 150, 250, 1000];
+const MAX_FALLBACK_DELAYS: usize = 10;
+const MAX_FALLBACK_DELAY_MS: u64 = 5000;
+
+// 1回の接続試行のタイムアウト（ミリ秒）
+const DEFAULT_CONNECT_TIMEOUT_MS: u64 = 3000;
+const MIN_CONNECT_TIMEOUT_MS: u64 = 100;
+const MAX_CONNECT_TIMEOUT_MS: u64 = 30_000;
+
+// 待ち時間ごとの試行回数
+const DEFAULT_ROUNDS: u32 = 3;
+const MAX_ROUNDS: u32 = 10;
+
+// 試行の間隔（前の接続の後始末が次の測定に影響しないようにする）
+const ROUND_INTERVAL_MS: u64 = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaceAttempt {
+    pub ip_address: Option<String>,
+    // 競争の開始からこのファミリの接続を始めるまでの時間（開始しなかった場合は None）
+    pub started_ms: Option<f64>,
+    pub connect_ms: Option<f64>,
+    pub error_message: Option<String>,
+    // もう一方のファミリが先に接続したため打ち切った
+    pub cancelled: bool,
+}
+
+impl RaceAttempt {
+    fn new(address: Option<SocketAddr>) -> Self {
+        RaceAttempt {
+            ip_address: address.map(|a| a.ip().to_string()),
+            started_ms: None,
+            connect_ms: None,
+            error_message: None,
+            cancelled: false,
+        }
+    }
+
+    // 接続できた場合は true
+    fn finish(&mut self, result: Result<f64, String>) -> bool {
+        match result {
+            Ok(connect_ms) => {
+                self.connect_ms = Some(connect_ms);
+                true
+            }
+            Err(e) => {
+                self.error_message = Some(e);
+                false
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaceTrial {
+    pub winner_ip_version: Option<u8>,
+    // 競争の開始から最初に接続が確立するまでの時間
+    pub time_to_connect_ms: Option<f64>,
+    pub ipv6: RaceAttempt,
+    pub ipv4: RaceAttempt,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FallbackDelayResult {
+    pub fallback_delay_ms: u64,
+    pub ipv6_wins: u32,
+    pub ipv4_wins: u32,
+    pub failures: u32,
+    pub avg_time_to_connect_ms: Option<f64>,
+    pub trials: Vec<RaceTrial>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HappyEyeballsResult {
+    pub url: String,
+    pub port: u16,
+    pub connect_timeout_ms: u64,
+    pub rounds: u32,
+    pub dns_resolution: DnsResolution,
+    pub results: Vec<FallbackDelayResult>,
+}
+
+type AttemptFuture = Pin<Box<dyn Future<Output = Result<f64, String>> + Send>>;
+
+// Happy Eyeballs（RFC 8305）と同様に IPv6 を先に試し、待ち時間の後に IPv4 でも接続を始めて先に確立した方を採用
+// 待ち時間を変えて繰り返し、どちらのファミリで接続するかと接続までの時間がどう変わるかを返す（OS やアプリの設定の調整用）
+#[tauri::command]
+pub async fn race_happy_eyeballs(
+    app: AppHandle,
+    url: String,
+    fallback_delays_ms: Option<Vec<u64>>,
+    connect_timeout_ms: Option<u64>,
+    rounds: Option<u32>,
+) -> Result<HappyEyeballsResult, String> {
+    let mut fallback_delays_ms =
+        fallback_delays_ms.unwrap_or_else(|| DEFAULT_FALLBACK_DELAYS_MS.to_vec());
+    fallback_delays_ms.sort_unstable();
+    fallback_delays_ms.dedup();
+    if fallback_delays_ms.is_empty()
+        || fallback_delays_ms.len() > MAX_FALLBACK_DELAYS
+        || fallback_delays_ms
+            .last()
+            .is_some_and(|&ms| ms > MAX_FALLBACK_DELAY_MS)
+    {
+        return Err(format!(
+            "フォールバックの待ち時間は 0〜{} ミリ秒の範囲で {} 個以内で指定してください",
+            MAX_FALLBACK_DELAY_MS, MAX_FALLBACK_DELAYS
+        ));
+    }
+    let connect_timeout_ms = connect_timeout_ms.unwrap_or(DEFAULT_CONNECT_TIMEOUT_MS);
+    if !(MIN_CONNECT_TIMEOUT_MS..=MAX_CONNECT_TIMEOUT_MS).contains(&connect_timeout_ms) {
+        return Err(format!(
+            "接続のタイムアウトは {} から {} ミリ秒の範囲で指定してください",
+            MIN_CONNECT_TIMEOUT_MS, MAX_CONNECT_TIMEOUT_MS
+        ));
+    }
+    let rounds = rounds.unwrap_or(DEFAULT_ROUNDS);
+    if !(1..=MAX_ROUNDS).contains(&rounds) {
+        return Err(format!(
+            "試行回数は 1 から {} の範囲で指定してください",
+            MAX_ROUNDS
+        ));
+    }
+    crate::validate_url(&url)?;
+
+    let target = url.clone();
+    run_operation(
+        &app,
+        OperationKind::Ping,
+        &target,
+        execute_race(url, fallback_delays_ms, connect_timeout_ms, rounds),
+    )
+    .await
+}
+
+async fn execute_race(
+    url: String,
+    fallback_delays_ms: Vec<u64>,
+    connect_timeout_ms: u64,
+    rounds: u32,
+) -> Result<HappyEyeballsResult, String> {
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+    let port = parsed_url.port_or_known_default().unwrap_or(443);
+
+    // 全試行で同じアドレスに接続するよう、名前解決は最初の1回のみ
+    let dns_resolution = crate::resolve_dns(&host).await;
+    let socket_address = |addresses: &[String]| {
+        addresses
+            .first()
+            .and_then(|a| a.parse::<IpAddr>().ok())
+            .map(|ip| SocketAddr::new(ip, port))
+    };
+    let ipv6 = socket_address(&dns_resolution.ipv6_addresses);
+    let ipv4 = socket_address(&dns_resolution.ipv4_addresses);
+    if ipv6.is_none() && ipv4.is_none() {
+        return Err(format!("{} のアドレスを解決できません", host));
+    }
+
+    let timeout = Duration::from_millis(connect_timeout_ms);
+    let total = fallback_delays_ms.len() * rounds as usize;
+    let mut results = Vec::new();
+    for (index, &fallback_delay_ms) in fallback_delays_ms.iter().enumerate() {
+        let mut trials = Vec::new();
+        for round in 0..rounds as usize {
+            if index > 0 || round > 0 {
+                tokio::time::sleep(Duration::from_millis(ROUND_INTERVAL_MS)).await;
+            }
+            report_progress((index * rounds as usize + round) as f64 * 100.0 / total as f64);
+            trials.push(
+                race(
+                    ipv6,
+                    ipv4,
+                    Duration::from_millis(fallback_delay_ms),
+                    timeout,
+                )
+                .await,
+            );
+        }
+        results.push(summarize_trials(fallback_delay_ms, trials));
+    }
+
+    Ok(HappyEyeballsResult {
+        url,
+        port,
+        connect_timeout_ms,
+        rounds,
+        dns_resolution,
+        results,
+    })
+}
+
+// IPv6 の接続を始め、待ち時間が過ぎるか IPv6 が失敗した時点で IPv4 の接続も始める
+async fn race(
+    ipv6: Option<SocketAddr>,
+    ipv4: Option<SocketAddr>,
+    fallback_delay: Duration,
+    timeout: Duration,
+) -> RaceTrial {
+    let started = Instant::now();
+    let mut trial = RaceTrial {
+        winner_ip_version: None,
+        time_to_connect_ms: None,
+        ipv6: RaceAttempt::new(ipv6),
+        ipv4: RaceAttempt::new(ipv4),
+    };
+
+    let mut ipv6_attempt: Option<AttemptFuture> = ipv6.map(|addr| {
+        trial.ipv6.started_ms = Some(0.0);
+        connect_attempt(addr, timeout)
+    });
+    let mut ipv4_attempt: Option<AttemptFuture> = None;
+    // IPv6 のアドレスがない場合は待たずに IPv4 で接続する
+    let mut fallback_pending = ipv4.is_some();
+    let fallback = tokio::time::sleep(if ipv6.is_some() {
+        fallback_delay
+    } else {
+        Duration::ZERO
+    });
+    tokio::pin!(fallback);
+
+    loop {
+        if ipv6_attempt.is_none() && ipv4_attempt.is_none() && !fallback_pending {
+            break;
+        }
+        tokio::select! {
+            result = async { ipv6_attempt.as_mut().unwrap().await }, if ipv6_attempt.is_some() => {
+                ipv6_attempt = None;
+                if trial.ipv6.finish(result) {
+                    trial.winner_ip_version = Some(6);
+                    break;
+                }
+                // IPv6 が失敗した場合は待ち時間の経過を待たずに IPv4 へ切り替える
+                if fallback_pending {
+                    fallback_pending = false;
+                    ipv4_attempt = start_attempt(&mut trial.ipv4, ipv4, started, timeout);
+                }
+            }
+            _ = &mut fallback, if fallback_pending => {
+                fallback_pending = false;
+                ipv4_attempt = start_attempt(&mut trial.ipv4, ipv4, started, timeout);
+            }
+            result = async { ipv4_attempt.as_mut().unwrap().await }, if ipv4_attempt.is_some() => {
+                ipv4_attempt = None;
+                if trial.ipv4.finish(result) {
+                    trial.winner_ip_version = Some(4);
+                    break;
+                }
+            }
+        }
+    }
+
+    if ipv6_attempt.is_some() {
+        trial.ipv6.cancelled = true;
+    }
+    if ipv4_attempt.is_some() {
+        trial.ipv4.cancelled = true;
+    }
+    trial.time_to_connect_ms = trial
+        .winner_ip_version
+        .is_some()
+        .then(|| started.elapsed().as_secs_f64() * 1000.0);
+    trial
+}
+
+fn start_attempt(
+    attempt: &mut RaceAttempt,
+    address: Option<SocketAddr>,
+    started: Instant,
+    timeout: Duration,
+) -> Option<AttemptFuture> {
+    let address = address?;
+    attempt.started_ms = Some(started.elapsed().as_secs_f64() * 1000.0);
+    Some(connect_attempt(address, timeout))
+}
+
+// TCP 接続にかかった時間（ミリ秒）
+fn connect_attempt(address: SocketAddr, timeout: Duration) -> AttemptFuture {
+    Box::pin(async move {
+        let started = Instant::now();
+        match tokio::time::timeout(timeout, crate::dry_run::connect_tcp(address)).await {
+            Ok(Ok(_)) => Ok(started.elapsed().as_secs_f64() * 1000.0),
+            Ok(Err(e)) => Err(format!("接続エラー: {}", e)),
+            Err(_) => Err("接続がタイムアウトしました".to_string()),
+        }
+    })
+}
+
+fn summarize_trials(fallback_delay_ms: u64, trials: Vec<RaceTrial>) -> FallbackDelayResult {
+    let count_wins = |version: u8| {
+        trials
+            .iter()
+            .filter(|t| t.winner_ip_version == Some(version))
+            .count() as u32
+    };
+    let times: Vec<f64> = trials.iter().filter_map(|t| t.time_to_connect_ms).collect();
+    FallbackDelayResult {
+        fallback_delay_ms,
+        ipv6_wins: count_wins(6),
+        ipv4_wins: count_wins(4),
+        failures: trials
+            .iter()
+            .filter(|t| t.winner_ip_version.is_none())
+            .count() as u32,
+        avg_time_to_connect_ms: (!times.is_empty())
+            .then(|| times.iter().sum::<f64>() / times.len() as f64),
+        trials,
+    }
+}
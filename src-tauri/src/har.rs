@@ -0,0 +1,350 @@
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::PathBuf;
+use tauri::AppHandle;
+use tauri_plugin_dialog::DialogExt;
+use url::Url;
+
+use crate::http_client::{HttpMethod, HttpTimings};
+use crate::{HttpPingDualResult, HttpPingResult};
+
+// 一度に書き出せる測定結果の上限
+const MAX_RESULTS: usize = 1000;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HarExport {
+    pub path: String,
+    pub size_bytes: u64,
+    pub page_count: usize,
+    pub entry_count: usize,
+}
+
+// 測定結果を HTTP Archive（HAR 1.2）として、ダイアログで選択した保存先に書き出す
+// 測定結果ごとに 1 ページとし、IPv4/IPv6 の各リクエストとリダイレクト先へのリクエストをエントリにする
+#[tauri::command]
+pub async fn export_har(
+    app: AppHandle,
+    results: Vec<HttpPingDualResult>,
+) -> Result<HarExport, String> {
+    if results.is_empty() || results.len() > MAX_RESULTS {
+        return Err(format!(
+            "書き出す測定結果は 1 から {} 件の範囲で指定してください",
+            MAX_RESULTS
+        ));
+    }
+    // 任意のパスに書き込めないよう、保存先は利用者がダイアログで選択したものに限る
+    let path = choose_save_path(&app).await?;
+
+    let mut pages = Vec::new();
+    let mut entries = Vec::new();
+    for (index, result) in results.iter().enumerate() {
+        let page_id = format!("page_{}", index + 1);
+        let family_results: Vec<(&str, &HttpPingResult)> = if result.address_results.is_empty() {
+            // プロキシが名前を解決した場合は ipv4/ipv6 は未測定のため、その結果を含める
+            [("ipv4", &result.ipv4), ("ipv6", &result.ipv6)]
+                .into_iter()
+                .chain(result.via_proxy.iter().map(|r| ("proxy", r)))
+                .collect()
+        } else {
+            result
+                .address_results
+                .iter()
+                .map(|r| {
+                    let family = match &r.ip_address {
+                        Some(ip) if ip.contains(':') => "ipv6",
+                        _ => "ipv4",
+                    };
+                    (family, r)
+                })
+                .collect()
+        };
+        let mut page_started_at: Option<&String> = None;
+        for (family, ping) in family_results {
+            let Some(started_at) = &ping.started_at else {
+                continue;
+            };
+            if page_started_at.is_none_or(|s| started_at < s) {
+                page_started_at = Some(started_at);
+            }
+            push_entries(&mut entries, &page_id, result.method, family, ping);
+        }
+        let Some(started_at) = page_started_at else {
+            continue;
+        };
+        pages.push(json!({
+            "startedDateTime": started_at,
+            "id": page_id,
+            "title": format!("{} {}", result.method.as_str(), result.url),
+            "pageTimings": {
+                "onContentLoad": -1,
+                "onLoad": -1,
+            },
+        }));
+    }
+    if entries.is_empty() {
+        return Err("書き出せるリクエストがありません（測定していない結果のみです）".to_string());
+    }
+
+    let har = json!({
+        "log": {
+            "version": "1.2",
+            "creator": {
+                "name": app.package_info().name,
+                "version": app.package_info().version.to_string(),
+            },
+            "pages": pages,
+            "entries": entries,
+        },
+    });
+    let body = serde_json::to_vec_pretty(&har).map_err(|e| format!("HAR の変換に失敗: {}", e))?;
+    fs::write(&path, &body).map_err(|e| format!("HAR ファイルの書き込みに失敗: {}", e))?;
+
+    Ok(HarExport {
+        path: path.to_string_lossy().to_string(),
+        size_bytes: body.len() as u64,
+        page_count: pages.len(),
+        entry_count: entries.len(),
+    })
+}
+
+async fn choose_save_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let file_name = format!("ghttpping-{}.har", Local::now().format("%Y%m%d-%H%M%S"));
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("HAR ファイルの保存先")
+        .add_filter("HAR", &["har"])
+        .set_file_name(file_name)
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    rx.await
+        .ok()
+        .flatten()
+        .ok_or_else(|| "保存先が選択されませんでした".to_string())?
+        .into_path()
+        .map_err(|e| format!("保存先のパスを取得できません: {}", e))
+}
+
+// 最初のリクエストと、リダイレクト先へのリクエストをエントリにする
+// 応答ヘッダ（詳細ログがない場合）と本文の情報は最終的な応答のものしかないため、最後のエントリに含める
+fn push_entries(
+    entries: &mut Vec<Value>,
+    page_id: &str,
+    method: HttpMethod,
+    family: &str,
+    ping: &HttpPingResult,
+) {
+    let Some(started_at) = ping.started_at.as_deref() else {
+        return;
+    };
+    let verbose = ping.verbose_info.as_ref();
+    let has_redirects = !ping.redirects.is_empty();
+    let elapsed_ms = ping
+        .timings
+        .as_ref()
+        .map(|t| t.total_ms)
+        .or(ping.response_time_ms.map(|ms| ms as f64))
+        .unwrap_or(0.0);
+
+    // リダイレクトした場合、最初の応答のステータスは詳細ログにのみ残る
+    let (status, reason) = if has_redirects {
+        verbose
+            .and_then(|v| v.status_line.as_deref())
+            .map(parse_status_line)
+            .unwrap_or((0, String::new()))
+    } else {
+        let status = ping.status_code.unwrap_or(0);
+        (status, status_text(status))
+    };
+    let mut response_headers = verbose
+        .map(|v| headers(&v.response_headers))
+        .unwrap_or_default();
+    if response_headers.is_empty() && !has_redirects {
+        response_headers = captured_headers(ping);
+    }
+    let first_error = if has_redirects {
+        None
+    } else {
+        ping.error_message.as_deref()
+    };
+    entries.push(json!({
+        "pageref": page_id,
+        "startedDateTime": started_at,
+        "time": elapsed_ms,
+        "request": {
+            "method": method.as_str(),
+            "url": ping.url,
+            "httpVersion": http_version(ping),
+            "cookies": [],
+            "headers": verbose.map(|v| headers(&v.request_headers)).unwrap_or_default(),
+            "queryString": query_string(&ping.url),
+            "headersSize": -1,
+            "bodySize": -1,
+        },
+        "response": {
+            "status": status,
+            "statusText": reason,
+            "httpVersion": http_version(ping),
+            "cookies": [],
+            "headers": response_headers,
+            "content": if has_redirects { empty_content() } else { content(ping) },
+            "redirectURL": ping.redirects.first().map(|h| h.url.as_str()).unwrap_or(""),
+            "headersSize": -1,
+            "bodySize": if has_redirects { -1 } else { body_size(ping) },
+            "_error": first_error,
+        },
+        "cache": {},
+        "timings": timings(ping.timings.as_ref(), elapsed_ms),
+        "serverIPAddress": ping.ip_address,
+        "_ipFamily": family,
+        "_tlsVersion": ping.tls_version,
+        "_cipherSuite": ping.cipher_suite,
+    }));
+
+    // 各リダイレクト先の開始時刻は、それまでのリクエストの所要時間から求める
+    let mut hop_started_at = DateTime::parse_from_rfc3339(started_at).ok();
+    let mut previous_ms = elapsed_ms;
+    for (index, hop) in ping.redirects.iter().enumerate() {
+        hop_started_at =
+            hop_started_at.map(|t| t + Duration::microseconds((previous_ms * 1000.0) as i64));
+        let is_last = index + 1 == ping.redirects.len();
+        let hop_ms = hop.response_time_ms.unwrap_or(0) as f64;
+        previous_ms = hop_ms;
+        let status = hop.status_code.unwrap_or(0);
+        entries.push(json!({
+            "pageref": page_id,
+            "startedDateTime": hop_started_at
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_else(|| started_at.to_string()),
+            "time": hop_ms,
+            "request": {
+                "method": method.as_str(),
+                "url": hop.url,
+                "httpVersion": http_version(ping),
+                "cookies": [],
+                "headers": [],
+                "queryString": query_string(&hop.url),
+                "headersSize": -1,
+                "bodySize": -1,
+            },
+            "response": {
+                "status": status,
+                "statusText": status_text(status),
+                "httpVersion": http_version(ping),
+                "cookies": [],
+                "headers": if is_last { captured_headers(ping) } else { Vec::new() },
+                "content": if is_last { content(ping) } else { empty_content() },
+                "redirectURL": ping.redirects.get(index + 1).map(|h| h.url.as_str()).unwrap_or(""),
+                "headersSize": -1,
+                "bodySize": if is_last { body_size(ping) } else { -1 },
+                "_error": hop.error_message,
+            },
+            "cache": {},
+            "timings": timings(None, hop_ms),
+            "serverIPAddress": hop.ip_address,
+            "_ipFamily": family,
+        }));
+    }
+}
+
+// HAR の connect は TLS のハンドシェイクを含み、wait はリクエスト送信から応答ヘッダ受信まで
+// 内訳がない場合は全体を wait とする
+fn timings(timings: Option<&HttpTimings>, elapsed_ms: f64) -> Value {
+    let Some(t) = timings else {
+        return json!({
+            "blocked": -1,
+            "dns": -1,
+            "connect": -1,
+            "ssl": -1,
+            "send": 0,
+            "wait": elapsed_ms,
+            "receive": 0,
+        });
+    };
+    let connect = t
+        .connect_ms
+        .map(|ms| ms + t.tls_handshake_ms.unwrap_or(0.0));
+    json!({
+        "blocked": -1,
+        "dns": t.dns_lookup_ms.unwrap_or(-1.0),
+        "connect": connect.unwrap_or(-1.0),
+        "ssl": t.tls_handshake_ms.unwrap_or(-1.0),
+        "send": 0,
+        "wait": t.first_byte_ms.unwrap_or(0.0),
+        "receive": t.transfer_ms.unwrap_or(0.0),
+    })
+}
+
+fn http_version(ping: &HttpPingResult) -> &'static str {
+    ping.http_version.map(|v| v.as_str()).unwrap_or("")
+}
+
+fn headers(headers: &[(String, String)]) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn captured_headers(ping: &HttpPingResult) -> Vec<Value> {
+    ping.captured_headers
+        .iter()
+        .flatten()
+        .map(|(name, value)| json!({ "name": name, "value": value }))
+        .collect()
+}
+
+fn query_string(url: &str) -> Vec<Value> {
+    Url::parse(url)
+        .map(|u| {
+            u.query_pairs()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn content(ping: &HttpPingResult) -> Value {
+    let mime_type = ping
+        .content
+        .as_ref()
+        .and_then(|c| {
+            let declared = c.declared_type.as_ref()?;
+            Some(match &c.charset {
+                Some(charset) => format!("{}; charset={}", declared, charset),
+                None => declared.clone(),
+            })
+        })
+        .unwrap_or_else(|| "x-unknown".to_string());
+    json!({
+        "size": ping.downloaded_bytes.unwrap_or(0),
+        "mimeType": mime_type,
+    })
+}
+
+fn empty_content() -> Value {
+    json!({ "size": 0, "mimeType": "x-unknown" })
+}
+
+fn body_size(ping: &HttpPingResult) -> i64 {
+    ping.downloaded_bytes.map(|b| b as i64).unwrap_or(-1)
+}
+
+fn status_text(status: u16) -> String {
+    hyper::StatusCode::from_u16(status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("")
+        .to_string()
+}
+
+// "HTTP/1.1 301 Moved Permanently" からステータスコードと理由を取り出す
+fn parse_status_line(line: &str) -> (u16, String) {
+    let mut parts = line.splitn(3, ' ');
+    let _version = parts.next();
+    let status = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+    (status, parts.next().unwrap_or("").to_string())
+}
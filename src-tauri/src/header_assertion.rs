@@ -0,0 +1,142 @@
+use serde::{Deserialize, Serialize};
+
+use crate::http_client::is_header_name;
+
+const MAX_ASSERTIONS: usize = 50;
+const MAX_ASSERTION_LEN: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HeaderOperator {
+    Equals,
+    NotEquals,
+    Contains,
+    NotContains,
+}
+
+// 記号の演算子と、前後に空白が必要な単語の演算子
+const OPERATORS: [(&str, HeaderOperator); 4] = [
+    ("==", HeaderOperator::Equals),
+    ("!=", HeaderOperator::NotEquals),
+    ("!contains", HeaderOperator::NotContains),
+    ("contains", HeaderOperator::Contains),
+];
+
+#[derive(Debug, Clone)]
+pub struct HeaderAssertion {
+    source: String,
+    name: String,
+    // None の場合はヘッダが存在するかのみ確認
+    comparison: Option<(HeaderOperator, String)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderAssertionResult {
+    pub assertion: String,
+    pub passed: bool,
+    pub actual: Option<String>,
+    pub message: Option<String>,
+}
+
+// `server == cloudflare` / `x-cache contains HIT` / `cf-ray`（存在確認）形式のアサーションを解析
+fn parse_header_assertion(source: &str) -> Result<HeaderAssertion, String> {
+    let trimmed = source.trim();
+    if trimmed.is_empty() || trimmed.len() > MAX_ASSERTION_LEN {
+        return Err(format!(
+            "ヘッダのアサーションは 1 から {} 文字で指定してください",
+            MAX_ASSERTION_LEN
+        ));
+    }
+
+    // 最初に現れる演算子でヘッダ名と期待値に分ける（単語の演算子は前に空白がある場合のみ）
+    let operator = (0..trimmed.len())
+        .filter(|i| trimmed.is_char_boundary(*i))
+        .find_map(|i| {
+            OPERATORS
+                .iter()
+                .filter(|(op, _)| {
+                    !op.ends_with("contains") || trimmed[..i].ends_with(char::is_whitespace)
+                })
+                .find(|(op, _)| trimmed[i..].starts_with(op))
+                .map(|(op, operator)| (i, op.len(), *operator))
+        });
+
+    let (name, comparison) = match operator {
+        Some((position, len, operator)) => {
+            let value = trimmed[position + len..].trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+            (
+                trimmed[..position].trim(),
+                Some((operator, value.to_string())),
+            )
+        }
+        None => (trimmed, None),
+    };
+    if !is_header_name(name) {
+        return Err(format!("ヘッダ名が不正です: {}", name));
+    }
+    Ok(HeaderAssertion {
+        source: trimmed.to_string(),
+        name: name.to_ascii_lowercase(),
+        comparison,
+    })
+}
+
+// 測定前に構文エラーを返す
+pub fn parse_header_assertions(sources: &[String]) -> Result<Vec<HeaderAssertion>, String> {
+    if sources.len() > MAX_ASSERTIONS {
+        return Err(format!(
+            "ヘッダのアサーションは {} 個まで指定できます",
+            MAX_ASSERTIONS
+        ));
+    }
+    sources.iter().map(|s| parse_header_assertion(s)).collect()
+}
+
+// 同名のヘッダが複数ある場合はカンマ区切りで連結して比較（値の大文字・小文字は区別しない）
+pub fn evaluate_header_assertions(
+    assertions: &[HeaderAssertion],
+    headers: &[(String, String)],
+) -> Vec<HeaderAssertionResult> {
+    assertions
+        .iter()
+        .map(|assertion| {
+            let values: Vec<&str> = headers
+                .iter()
+                .filter(|(name, _)| name.eq_ignore_ascii_case(&assertion.name))
+                .map(|(_, value)| value.as_str())
+                .collect();
+            let actual = (!values.is_empty()).then(|| values.join(", "));
+
+            let passed = match (&actual, &assertion.comparison) {
+                (None, Some((HeaderOperator::NotEquals | HeaderOperator::NotContains, _))) => true,
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(actual), Some((operator, expected))) => {
+                    let actual = actual.to_lowercase();
+                    let expected = expected.to_lowercase();
+                    match operator {
+                        HeaderOperator::Equals => actual == expected,
+                        HeaderOperator::NotEquals => actual != expected,
+                        HeaderOperator::Contains => actual.contains(&expected),
+                        HeaderOperator::NotContains => !actual.contains(&expected),
+                    }
+                }
+            };
+            let message = match (&actual, passed) {
+                (_, true) => None,
+                (None, false) => Some("応答にヘッダがありません".to_string()),
+                (Some(actual), false) => Some(format!("実際の値は {} です", actual)),
+            };
+
+            HeaderAssertionResult {
+                assertion: assertion.source.clone(),
+                passed,
+                actual,
+                message,
+            }
+        })
+        .collect()
+}
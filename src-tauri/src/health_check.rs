@@ -0,0 +1,378 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+use url::Url;
+
+use crate::http_client::{self, HttpTimings};
+use crate::operations::{run_operation, OperationKind};
+use crate::DnsResolution;
+
+// ヘルスチェックの応答本文の上限（JSON として解析するため保持する）
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+const MAX_ASSERTIONS: usize = 50;
+const MAX_ASSERTION_LEN: usize = 256;
+
+// 比較演算子（2文字の演算子を先に照合する）
+const OPERATORS: [&str; 6] = ["==", "!=", ">=", "<=", ">", "<"];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssertionResult {
+    pub assertion: String,
+    pub passed: bool,
+    pub actual: Option<Value>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthFamilyResult {
+    pub ip_address: Option<String>,
+    pub status_code: Option<u16>,
+    pub response_time_ms: Option<u64>,
+    pub timings: Option<HttpTimings>,
+    pub healthy: bool,
+    pub error_message: Option<String>,
+    pub assertions: Vec<AssertionResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthCheckResult {
+    pub url: String,
+    pub dns_resolution: DnsResolution,
+    pub ipv4: HealthFamilyResult,
+    pub ipv6: HealthFamilyResult,
+    pub healthy: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone)]
+struct Assertion {
+    source: String,
+    path: Vec<PathSegment>,
+    // None の場合はパスが存在するかのみ確認
+    comparison: Option<(String, Value)>,
+}
+
+// `$.checks[0].status` 形式のパスを分解（先頭の `$` / `$.` は省略可）
+fn parse_path(path: &str) -> Result<Vec<PathSegment>, String> {
+    let path = path.trim();
+    let path = path
+        .strip_prefix("$.")
+        .or_else(|| path.strip_prefix('$'))
+        .unwrap_or(path);
+    let mut segments = Vec::new();
+    let mut key = String::new();
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                if key.is_empty() && !matches!(segments.last(), Some(PathSegment::Index(_))) {
+                    return Err(format!("パスの指定が不正です: {}", path));
+                }
+                if !key.is_empty() {
+                    segments.push(PathSegment::Key(std::mem::take(&mut key)));
+                }
+            }
+            '[' => {
+                if !key.is_empty() {
+                    segments.push(PathSegment::Key(std::mem::take(&mut key)));
+                }
+                let mut index = String::new();
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    index.push(c);
+                }
+                let index = index
+                    .trim()
+                    .parse::<usize>()
+                    .map_err(|_| format!("配列の添字が不正です: [{}]", index))?;
+                segments.push(PathSegment::Index(index));
+            }
+            c if c.is_whitespace() => {
+                return Err(format!("パスに空白は使用できません: {}", path));
+            }
+            c => key.push(c),
+        }
+    }
+    if !key.is_empty() {
+        segments.push(PathSegment::Key(key));
+    }
+    Ok(segments)
+}
+
+// `status == "ok"` / `uptime > 0` / `checks.db`（存在確認）形式のアサーションを解析
+fn parse_assertion(source: &str) -> Result<Assertion, String> {
+    let trimmed = source.trim();
+    if trimmed.is_empty() || trimmed.len() > MAX_ASSERTION_LEN {
+        return Err(format!(
+            "アサーションは 1 から {} 文字で指定してください",
+            MAX_ASSERTION_LEN
+        ));
+    }
+
+    // 最初に現れる演算子で左辺（パス）と右辺（JSON 値）に分ける
+    let operator = (0..trimmed.len())
+        .filter(|i| trimmed.is_char_boundary(*i))
+        .find_map(|i| {
+            OPERATORS
+                .iter()
+                .find(|op| trimmed[i..].starts_with(**op))
+                .map(|op| (i, *op))
+        });
+
+    let Some((position, operator)) = operator else {
+        return Ok(Assertion {
+            source: trimmed.to_string(),
+            path: parse_path(trimmed)?,
+            comparison: None,
+        });
+    };
+    let path = parse_path(&trimmed[..position])?;
+    let literal = trimmed[position + operator.len()..].trim();
+    let expected: Value = serde_json::from_str(literal).map_err(|_| {
+        format!(
+            "比較する値は JSON で指定してください（文字列は \"ok\" のように引用符で囲みます）: {}",
+            literal
+        )
+    })?;
+    if matches!(operator, ">" | ">=" | "<" | "<=")
+        && !(expected.is_number() || expected.is_string())
+    {
+        return Err(format!(
+            "大小比較は数値または文字列のみ指定できます: {}",
+            trimmed
+        ));
+    }
+    Ok(Assertion {
+        source: trimmed.to_string(),
+        path,
+        comparison: Some((operator.to_string(), expected)),
+    })
+}
+
+fn lookup<'a>(value: &'a Value, path: &[PathSegment]) -> Option<&'a Value> {
+    path.iter()
+        .try_fold(value, |current, segment| match segment {
+            PathSegment::Key(key) => current.get(key),
+            PathSegment::Index(index) => current.get(index),
+        })
+}
+
+// 数値は型（整数・小数）によらず値で比較
+fn values_equal(actual: &Value, expected: &Value) -> bool {
+    match (actual.as_f64(), expected.as_f64()) {
+        (Some(a), Some(e)) if actual.is_number() && expected.is_number() => a == e,
+        _ => actual == expected,
+    }
+}
+
+fn evaluate(assertion: &Assertion, body: &Value) -> AssertionResult {
+    let actual = lookup(body, &assertion.path).cloned();
+    let (passed, message) = match (&actual, &assertion.comparison) {
+        (None, _) => (false, Some("指定したパスが応答に存在しません".to_string())),
+        (Some(_), None) => (true, None),
+        (Some(actual), Some((operator, expected))) => {
+            let ordering = match (actual, expected) {
+                (Value::Number(a), Value::Number(e)) => a
+                    .as_f64()
+                    .zip(e.as_f64())
+                    .and_then(|(a, e)| a.partial_cmp(&e)),
+                (Value::String(a), Value::String(e)) => Some(a.cmp(e)),
+                _ => None,
+            };
+            let passed = match operator.as_str() {
+                "==" => values_equal(actual, expected),
+                "!=" => !values_equal(actual, expected),
+                ">" => ordering.is_some_and(|o| o.is_gt()),
+                ">=" => ordering.is_some_and(|o| o.is_ge()),
+                "<" => ordering.is_some_and(|o| o.is_lt()),
+                "<=" => ordering.is_some_and(|o| o.is_le()),
+                _ => false,
+            };
+            let message = if passed {
+                None
+            } else if ordering.is_none() && operator != "==" && operator != "!=" {
+                Some(format!("{} と {} は大小比較できません", actual, expected))
+            } else {
+                Some(format!("実際の値は {} です", actual))
+            };
+            (passed, message)
+        }
+    };
+    AssertionResult {
+        assertion: assertion.source.clone(),
+        passed,
+        actual,
+        message,
+    }
+}
+
+// REST のヘルスチェック用エンドポイントに接続し、JSON 応答をアサーションで検証
+#[tauri::command]
+pub async fn check_health_endpoint(
+    app: AppHandle,
+    url: String,
+    assertions: Option<Vec<String>>,
+    ignore_tls_errors: Option<bool>,
+) -> Result<HealthCheckResult, String> {
+    crate::validate_url(&url)?;
+    let assertions = assertions.unwrap_or_default();
+    if assertions.len() > MAX_ASSERTIONS {
+        return Err(format!(
+            "アサーションは {} 個まで指定できます",
+            MAX_ASSERTIONS
+        ));
+    }
+    // 測定前に構文エラーを返す
+    let assertions = assertions
+        .iter()
+        .map(|a| parse_assertion(a))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let target = url.clone();
+    run_operation(
+        &app,
+        OperationKind::Ping,
+        &target,
+        execute_health_check(url, assertions, ignore_tls_errors.unwrap_or(false)),
+    )
+    .await
+}
+
+async fn execute_health_check(
+    url: String,
+    assertions: Vec<Assertion>,
+    ignore_tls_errors: bool,
+) -> Result<HealthCheckResult, String> {
+    if ignore_tls_errors {
+        crate::log_security_warning("TLS証明書検証が無効化されています");
+    }
+
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+
+    let dns_resolution = crate::resolve_dns(&host).await;
+    let (ipv4, ipv6) = tokio::join!(
+        check_family(
+            &url,
+            dns_resolution.ipv4_addresses.first(),
+            &host,
+            parsed_url.port(),
+            ignore_tls_errors,
+            &assertions,
+            4,
+        ),
+        check_family(
+            &url,
+            dns_resolution.ipv6_addresses.first(),
+            &host,
+            parsed_url.port(),
+            ignore_tls_errors,
+            &assertions,
+            6,
+        ),
+    );
+
+    // 接続できたファミリがすべて正常な場合のみ正常と判定
+    let attempted: Vec<&HealthFamilyResult> = [&ipv4, &ipv6]
+        .into_iter()
+        .filter(|r| r.ip_address.is_some())
+        .collect();
+    let healthy = !attempted.is_empty() && attempted.iter().all(|r| r.healthy);
+
+    Ok(HealthCheckResult {
+        url,
+        dns_resolution,
+        ipv4,
+        ipv6,
+        healthy,
+    })
+}
+
+async fn check_family(
+    url: &str,
+    ip_address: Option<&String>,
+    host: &str,
+    port: Option<u16>,
+    ignore_tls_errors: bool,
+    assertions: &[Assertion],
+    ip_version: u8,
+) -> HealthFamilyResult {
+    let mut result = HealthFamilyResult {
+        ip_address: ip_address.cloned(),
+        status_code: None,
+        response_time_ms: None,
+        timings: None,
+        healthy: false,
+        error_message: None,
+        assertions: Vec::new(),
+    };
+    let Some(ip_address) = ip_address else {
+        result.error_message = Some(format!("IPv{}アドレスが見つかりません", ip_version));
+        return result;
+    };
+
+    let outcome = http_client::send_request(&http_client::HttpRequest {
+        url,
+        options: &http_client::RequestOptions::default(),
+        ip_address: Some(ip_address),
+        host,
+        port,
+        ignore_tls_errors,
+        source_address: None,
+        verbose: false,
+        max_body_bytes: MAX_BODY_BYTES,
+        timeout_secs: http_client::DEFAULT_TIMEOUT_SECS,
+    })
+    .await;
+    result.status_code = outcome.status_code;
+    result.response_time_ms = Some(outcome.timings.total_ms.round() as u64);
+    result.timings = Some(outcome.timings);
+    if let Some(e) = outcome.error_message {
+        result.error_message = Some(format!("接続エラー: {}", e));
+        return result;
+    }
+
+    // 異常時も JSON で詳細を返すエンドポイントがあるため、ステータスによらず本文を検証
+    let status_ok = outcome
+        .status_code
+        .is_some_and(|code| (200..300).contains(&code));
+    let body = outcome.body.unwrap_or_default();
+    let json: Value = match serde_json::from_slice(&body) {
+        Ok(json) => json,
+        Err(e) => {
+            result.error_message = Some(if status_ok {
+                format!("応答本文を JSON として解析できません: {}", e)
+            } else {
+                format!(
+                    "HTTPステータス: {}",
+                    outcome.status_code.unwrap_or_default()
+                )
+            });
+            return result;
+        }
+    };
+    result.assertions = assertions.iter().map(|a| evaluate(a, &json)).collect();
+
+    let failed = result.assertions.iter().filter(|a| !a.passed).count();
+    result.healthy = status_ok && failed == 0;
+    if !status_ok {
+        result.error_message = outcome
+            .status_code
+            .map(|code| format!("HTTPステータス: {}", code));
+    } else if failed > 0 {
+        result.error_message = Some(format!("{} 件のアサーションが失敗しました", failed));
+    }
+    result
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+// 履歴ファイル名（アプリデータディレクトリ配下に JSON Lines 形式で保存）
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+// 履歴の最大保持件数（超過分は古いものから削除）
+const MAX_HISTORY_ENTRIES: usize = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryKind {
+    Ping,
+    Iperf3,
+    SpeedTest,
+    Environment,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub recorded_at: String,
+    pub kind: HistoryKind,
+    pub target: String,
+    pub data: serde_json::Value,
+}
+
+// 履歴ストア（Tauri の State として管理）
+#[derive(Default)]
+pub struct HistoryStore {
+    entries: Mutex<Option<Vec<HistoryEntry>>>,
+}
+
+// 履歴ファイルのパスを取得（ディレクトリがなければ作成）
+fn history_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("アプリデータディレクトリの取得に失敗: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("履歴ディレクトリの作成に失敗: {}", e))?;
+    Ok(dir.join(HISTORY_FILE_NAME))
+}
+
+// 履歴ファイルを読み込む（壊れた行は読み飛ばす）
+fn read_history_file(path: &PathBuf) -> Vec<HistoryEntry> {
+    match fs::read_to_string(path) {
+        Ok(content) => content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+// 履歴ファイルを全件書き直す
+fn write_history_file(path: &PathBuf, entries: &[HistoryEntry]) -> Result<(), String> {
+    let mut body = String::new();
+    for entry in entries {
+        let line = serde_json::to_string(entry).map_err(|e| format!("履歴の変換に失敗: {}", e))?;
+        body.push_str(&line);
+        body.push('\n');
+    }
+    fs::write(path, body).map_err(|e| format!("履歴ファイルの書き込みに失敗: {}", e))
+}
+
+// 測定結果を履歴に追加
+pub fn record_history<T: Serialize>(
+    app: &AppHandle,
+    kind: HistoryKind,
+    target: &str,
+    data: &T,
+) -> Result<(), String> {
+    let path = history_file_path(app)?;
+    let entry = HistoryEntry {
+        recorded_at: chrono::Local::now().to_rfc3339(),
+        kind,
+        target: target.to_string(),
+        data: serde_json::to_value(data).map_err(|e| format!("履歴の変換に失敗: {}", e))?,
+    };
+
+    let store = app.state::<HistoryStore>();
+    let mut guard = store
+        .entries
+        .lock()
+        .map_err(|_| "履歴ストアのロック取得に失敗".to_string())?;
+    let entries = guard.get_or_insert_with(|| read_history_file(&path));
+    entries.push(entry.clone());
+
+    if entries.len() > MAX_HISTORY_ENTRIES {
+        // 上限を超えたら古い履歴を削除してファイルを書き直す
+        let excess = entries.len() - MAX_HISTORY_ENTRIES;
+        entries.drain(..excess);
+        return write_history_file(&path, entries);
+    }
+
+    let line = serde_json::to_string(&entry).map_err(|e| format!("履歴の変換に失敗: {}", e))?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("履歴ファイルのオープンに失敗: {}", e))?;
+    writeln!(file, "{}", line).map_err(|e| format!("履歴ファイルの書き込みに失敗: {}", e))
+}
+
+// 保存済みの履歴を全件取得（古い順）
+pub fn load_history(app: &AppHandle) -> Result<Vec<HistoryEntry>, String> {
+    let path = history_file_path(app)?;
+    let store = app.state::<HistoryStore>();
+    let mut guard = store
+        .entries
+        .lock()
+        .map_err(|_| "履歴ストアのロック取得に失敗".to_string())?;
+    Ok(guard.get_or_insert_with(|| read_history_file(&path)).clone())
+}
+
+#[tauri::command]
+pub async fn get_history(
+    app: AppHandle,
+    kind: Option<HistoryKind>,
+    target: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<HistoryEntry>, String> {
+    let mut entries: Vec<HistoryEntry> = load_history(&app)?
+        .into_iter()
+        .filter(|e| kind.is_none_or(|k| e.kind == k))
+        .filter(|e| target.as_ref().is_none_or(|t| &e.target == t))
+        .collect();
+
+    // 新しいものから limit 件に絞り込む
+    if let Some(limit) = limit {
+        if entries.len() > limit {
+            entries.drain(..entries.len() - limit);
+        }
+    }
+
+    Ok(entries)
+}
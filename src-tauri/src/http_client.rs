@@ -0,0 +1,1631 @@
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::body::Incoming;
+use hyper::client::conn::{http1, http2};
+use hyper::header::ALT_SVC;
+use hyper::{Method, Request, Response};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+#[cfg(feature = "ech")]
+use rustls::client::{EchConfig, EchMode};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpSocket, TcpStream};
+use tokio::time::Instant;
+use tokio_rustls::TlsConnector;
+use url::Url;
+
+use crate::content_sniff::{self, ResponseContent, SNIFF_BYTES};
+use crate::dry_run::{self, ActionKind, DRY_RUN_MESSAGE};
+use crate::proxy::{self, ProxyAuthMethod, ProxyConfig, ProxyKind, TunnelTarget};
+
+// リクエスト全体のタイムアウトの既定値と範囲（秒）
+pub const DEFAULT_TIMEOUT_SECS: u64 = 10;
+pub const MIN_TIMEOUT_SECS: u64 = 1;
+pub const MAX_TIMEOUT_SECS: u64 = 120;
+
+// 既定の User-Agent（送信元のアプリとバージョンを識別できる値）
+pub const USER_AGENT_VALUE: &str = concat!("ghttpping-tauri/", env!("CARGO_PKG_VERSION"));
+
+// 追加できるリクエストヘッダの数と値の長さの上限
+const MAX_CUSTOM_HEADERS: usize = 32;
+const MAX_HEADER_VALUE_LEN: usize = 4096;
+
+// メッセージの区切りや接続の管理に関わるため、利用者には指定させないヘッダ
+const RESERVED_HEADERS: [&str; 8] = [
+    "connection",
+    "content-length",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+// 送信できる本文の上限（バイト）と、Content-Type を省略した場合の既定値
+const MAX_REQUEST_BODY_BYTES: usize = 64 * 1024;
+const DEFAULT_CONTENT_TYPE: &str = "application/json";
+
+// 詳細ログに値を残さないヘッダ
+pub const SENSITIVE_HEADERS: [&str; 3] = ["authorization", "proxy-authorization", "cookie"];
+
+// 各段階の所要時間
+// first_byte_ms はリクエスト送信から応答ヘッダ受信まで、transfer_ms は応答本文の受信にかかった時間
+// total_ms は接続開始から本文受信完了まで（名前解決は含まない）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpTimings {
+    #[serde(default)]
+    pub dns_lookup_ms: Option<f64>,
+    pub connect_ms: Option<f64>,
+    pub tls_handshake_ms: Option<f64>,
+    pub first_byte_ms: Option<f64>,
+    #[serde(default)]
+    pub transfer_ms: Option<f64>,
+    pub total_ms: f64,
+}
+
+// 送信できる HTTP メソッド（本文を指定しない POST/PUT は空の本文で送信する）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Head,
+    Post,
+    Put,
+    Options,
+}
+
+impl HttpMethod {
+    // 大文字・小文字を区別せずに解析
+    pub fn parse(method: &str) -> Result<Self, String> {
+        match method.trim().to_ascii_uppercase().as_str() {
+            "GET" => Ok(HttpMethod::Get),
+            "HEAD" => Ok(HttpMethod::Head),
+            "POST" => Ok(HttpMethod::Post),
+            "PUT" => Ok(HttpMethod::Put),
+            "OPTIONS" => Ok(HttpMethod::Options),
+            _ => Err(format!(
+                "未対応の HTTP メソッドです: {}（GET, HEAD, POST, PUT, OPTIONS のいずれかを指定してください）",
+                method
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Head => "HEAD",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Options => "OPTIONS",
+        }
+    }
+
+    fn to_hyper(self) -> Method {
+        match self {
+            HttpMethod::Get => Method::GET,
+            HttpMethod::Head => Method::HEAD,
+            HttpMethod::Post => Method::POST,
+            HttpMethod::Put => Method::PUT,
+            HttpMethod::Options => Method::OPTIONS,
+        }
+    }
+}
+
+// 使用する HTTP のバージョン（ALPN で選択されたもの）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HttpVersion {
+    #[serde(rename = "HTTP/1.1")]
+    Http1_1,
+    #[serde(rename = "HTTP/2")]
+    Http2,
+}
+
+impl HttpVersion {
+    // "1.1" / "http/1.1" / "2" / "h2" などを受け付ける
+    pub fn parse(version: &str) -> Result<Self, String> {
+        match version.trim().to_ascii_lowercase().as_str() {
+            "1.1" | "http/1.1" | "http1.1" => Ok(HttpVersion::Http1_1),
+            "2" | "h2" | "http/2" | "http2" => Ok(HttpVersion::Http2),
+            _ => Err(format!(
+                "未対応の HTTP バージョンです: {}（HTTP/1.1 または HTTP/2 を指定してください）",
+                version
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            HttpVersion::Http1_1 => "HTTP/1.1",
+            HttpVersion::Http2 => "HTTP/2",
+        }
+    }
+}
+
+// 使用する TLS のバージョン（経路上の機器によるダウングレードの確認用に固定できる）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TlsVersion {
+    #[serde(rename = "TLSv1.2")]
+    Tls1_2,
+    #[serde(rename = "TLSv1.3")]
+    Tls1_3,
+}
+
+impl TlsVersion {
+    // "1.2" / "tls1.2" / "TLSv1.3" などを受け付ける
+    pub fn parse(version: &str) -> Result<Self, String> {
+        let normalized = version.trim().to_ascii_lowercase();
+        match normalized
+            .trim_start_matches("tls")
+            .trim_start_matches('v')
+            .trim()
+        {
+            "1.2" => Ok(TlsVersion::Tls1_2),
+            "1.3" => Ok(TlsVersion::Tls1_3),
+            _ => Err(format!(
+                "未対応の TLS バージョンです: {}（TLS 1.2 または TLS 1.3 を指定してください）",
+                version
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TlsVersion::Tls1_2 => "TLSv1.2",
+            TlsVersion::Tls1_3 => "TLSv1.3",
+        }
+    }
+
+    fn protocol_version(self) -> &'static rustls::SupportedProtocolVersion {
+        match self {
+            TlsVersion::Tls1_2 => &rustls::version::TLS12,
+            TlsVersion::Tls1_3 => &rustls::version::TLS13,
+        }
+    }
+}
+
+// ネゴシエーションされた TLS のバージョンの表示名
+fn protocol_version_name(version: rustls::ProtocolVersion) -> String {
+    match version {
+        rustls::ProtocolVersion::TLSv1_2 => TlsVersion::Tls1_2.as_str().to_string(),
+        rustls::ProtocolVersion::TLSv1_3 => TlsVersion::Tls1_3.as_str().to_string(),
+        other => format!("{:?}", other),
+    }
+}
+
+// 利用者が指定するリクエストの内容（既定は GET・追加ヘッダなし・本文なし・リダイレクトはたどらない）
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    pub method: HttpMethod,
+    // 名前は小文字（Host・User-Agent・Accept は既定値を置き換える）
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Bytes>,
+    pub follow_redirects: bool,
+    // None の場合は HTTPS では ALPN で HTTP/2 と HTTP/1.1 を提示し、HTTP では HTTP/1.1 を使う
+    pub http_version: Option<HttpVersion>,
+    // 応答本文の受信を打ち切るバイト数（None の場合は最後まで受信する）
+    pub max_download_bytes: Option<u64>,
+    // None の場合は TLS 1.2 と 1.3 の両方を提示する
+    pub tls_version: Option<TlsVersion>,
+    // TLS の SNI に使う名前（None の場合は URL のホスト名、Host ヘッダとは独立）
+    pub sni: Option<String>,
+    // 経由するプロキシ（接続先の IP アドレスを指定しない場合は名前の解決をプロキシに任せる）
+    pub proxy: Option<ProxyConfig>,
+    // 送信する User-Agent（None の場合は USER_AGENT_VALUE）
+    pub user_agent: Option<String>,
+    // 応答本文の先頭を保持するバイト数（本文のアサーション用、超えた分は受信するが保持しない）
+    pub body_prefix_bytes: usize,
+    // 応答の受信後に同じ接続で同じリクエストをもう一度送信する（接続の確立にかかる時間の内訳の確認用）
+    pub reuse_connection: bool,
+}
+
+impl RequestOptions {
+    // 実際に送信する User-Agent（ヘッダでの指定を優先）
+    pub fn effective_user_agent(&self) -> &str {
+        self.headers
+            .iter()
+            .find(|(name, _)| name == "user-agent")
+            .map(|(_, value)| value.as_str())
+            .or(self.user_agent.as_deref())
+            .unwrap_or(USER_AGENT_VALUE)
+    }
+
+    // 送信する本文を検証して設定し、Content-Type をヘッダに加える（本文を送れるのは POST/PUT のみ）
+    pub fn set_body(&mut self, body: String, content_type: Option<String>) -> Result<(), String> {
+        if !matches!(self.method, HttpMethod::Post | HttpMethod::Put) {
+            return Err(format!(
+                "{} では本文を送信できません（POST または PUT を指定してください）",
+                self.method.as_str()
+            ));
+        }
+        if body.len() > MAX_REQUEST_BODY_BYTES {
+            return Err(format!(
+                "本文は {} バイト以内で指定してください",
+                MAX_REQUEST_BODY_BYTES
+            ));
+        }
+        if self.headers.iter().any(|(name, _)| name == "content-type") {
+            if content_type.is_some() {
+                return Err(
+                    "Content-Type はヘッダと content_type の一方のみで指定してください".to_string(),
+                );
+            }
+        } else {
+            let content_type = content_type
+                .map(|c| c.trim().to_string())
+                .unwrap_or_else(|| DEFAULT_CONTENT_TYPE.to_string());
+            if content_type.is_empty()
+                || hyper::header::HeaderValue::from_str(&content_type).is_err()
+            {
+                return Err(format!("Content-Type が不正です: {}", content_type));
+            }
+            self.headers
+                .push(("content-type".to_string(), content_type));
+            self.headers.sort();
+        }
+        self.body = Some(Bytes::from(body));
+        Ok(())
+    }
+}
+
+// ヘッダ名に使える文字（RFC 9110 の token）
+pub fn is_header_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c))
+}
+
+// 追加するリクエストヘッダを検証（改行を含む値によるヘッダインジェクションを防ぐ）
+pub fn parse_custom_headers(
+    headers: HashMap<String, String>,
+) -> Result<Vec<(String, String)>, String> {
+    if headers.len() > MAX_CUSTOM_HEADERS {
+        return Err(format!(
+            "追加するヘッダは {} 個まで指定できます",
+            MAX_CUSTOM_HEADERS
+        ));
+    }
+    let mut parsed = Vec::new();
+    for (name, value) in headers {
+        let name = name.trim().to_ascii_lowercase();
+        if !is_header_name(&name) {
+            return Err(format!("ヘッダ名が不正です: {}", name));
+        }
+        if RESERVED_HEADERS.contains(&name.as_str()) {
+            return Err(format!("{} ヘッダは指定できません", name));
+        }
+        // プロキシ経由でない場合や CONNECT のトンネル内では接続先のサーバに認証情報を送ってしまい、
+        // 転送を依頼する場合はプロキシの認証のヘッダと重複するため、認証情報はプロキシの設定でのみ受け付ける
+        if name == "proxy-authorization" {
+            return Err(
+                "proxy-authorization ヘッダは指定できません（プロキシの認証情報は proxy_username と proxy_password で指定してください）"
+                    .to_string(),
+            );
+        }
+        if parsed.iter().any(|(n, _): &(String, String)| n == &name) {
+            return Err(format!("{} ヘッダが重複しています", name));
+        }
+        let value = value.trim().to_string();
+        if value.len() > MAX_HEADER_VALUE_LEN {
+            return Err(format!(
+                "{} ヘッダの値は {} バイト以内で指定してください",
+                name, MAX_HEADER_VALUE_LEN
+            ));
+        }
+        if value.chars().any(|c| c == '\r' || c == '\n' || c == '\0')
+            || hyper::header::HeaderValue::from_str(&value).is_err()
+        {
+            return Err(format!(
+                "{} ヘッダの値に使用できない文字が含まれています",
+                name
+            ));
+        }
+        parsed.push((name, value));
+    }
+    parsed.sort();
+    Ok(parsed)
+}
+
+// ヘッダをヘッダ名ごとの値にまとめる（同じ名前のヘッダは ", " で連結、Cookie や認証情報の値は残さない）
+pub fn header_map(headers: &[(String, String)]) -> BTreeMap<String, String> {
+    let mut map: BTreeMap<String, String> = BTreeMap::new();
+    for (name, value) in headers {
+        let name = name.to_ascii_lowercase();
+        if name == "set-cookie" || SENSITIVE_HEADERS.contains(&name.as_str()) {
+            map.insert(name, "[REDACTED]".to_string());
+            continue;
+        }
+        map.entry(name)
+            .and_modify(|existing| {
+                existing.push_str(", ");
+                existing.push_str(value);
+            })
+            .or_insert_with(|| value.clone());
+    }
+    map
+}
+
+// 接続先を固定した1回分のリクエスト
+pub struct HttpRequest<'a> {
+    pub url: &'a str,
+    pub options: &'a RequestOptions,
+    // None の場合は名前のままプロキシに接続を依頼する（名前を解決するプロキシを指定した場合のみ）
+    pub ip_address: Option<&'a str>,
+    pub host: &'a str,
+    pub port: Option<u16>,
+    pub ignore_tls_errors: bool,
+    pub source_address: Option<&'a str>,
+    pub verbose: bool,
+    // 応答本文を保持する上限（0 の場合は読み捨てる）
+    pub max_body_bytes: usize,
+    pub timeout_secs: u64,
+}
+
+// リクエスト全体の締め切り
+#[derive(Clone, Copy)]
+struct Deadline {
+    at: Instant,
+    timeout: Duration,
+}
+
+pub struct HttpOutcome {
+    pub status_code: Option<u16>,
+    pub error_message: Option<String>,
+    pub timings: HttpTimings,
+    pub verbose_log: Option<String>,
+    pub body: Option<Vec<u8>>,
+    // 代替サービス（HTTP/3 など）の広告
+    pub alt_svc: Option<String>,
+    // 応答ヘッダ（名前は小文字）
+    pub headers: Vec<(String, String)>,
+    pub http_version: Option<HttpVersion>,
+    // 受信した応答本文のバイト数と、max_download_bytes で打ち切ったかどうか
+    pub body_bytes: Option<u64>,
+    pub body_truncated: bool,
+    // HTTPS の場合のサーバ証明書と TLS のバージョン・暗号スイート（詳細ログの指定に関係なく取得）
+    pub certificate: Option<PeerCertificate>,
+    pub tls_version: Option<String>,
+    pub cipher_suite: Option<String>,
+    pub verbose_info: Option<VerboseInfo>,
+    // Content-Type と本文の先頭から判定した実際の種類
+    pub content: Option<ResponseContent>,
+    // 応答本文の先頭（種類の判定用と、body_prefix_bytes で指定した長さの長い方）
+    pub body_prefix: Vec<u8>,
+    // reuse_connection を指定した場合の 2 回目のリクエストの結果
+    pub second_request: Option<SecondRequest>,
+    // プロキシで認証した方式（詳細ログの指定に関係なく取得）
+    pub proxy_auth: Option<ProxyAuthMethod>,
+}
+
+impl HttpOutcome {
+    // 応答本文の受信速度（バイト/秒、受信時間が測れないほど短い場合は None）
+    pub fn download_speed(&self) -> Option<f64> {
+        let bytes = self.body_bytes?;
+        let transfer_ms = self.timings.transfer_ms?;
+        (transfer_ms > 0.0).then(|| bytes as f64 * 1000.0 / transfer_ms)
+    }
+}
+
+// 接続先のサーバ証明書（日時は RFC 3339 の UTC）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: String,
+    pub not_after: String,
+}
+
+impl PeerCertificate {
+    // 有効期限までの残り時間（期限切れの場合は負の値）
+    pub fn expires_in(&self) -> Option<chrono::TimeDelta> {
+        let not_after = chrono::DateTime::parse_from_rfc3339(&self.not_after).ok()?;
+        Some(not_after.to_utc() - chrono::Utc::now())
+    }
+}
+
+// 詳細ログと同じ内容を構造化したもの（画面表示や機械可読なエクスポート用、ヘッダの値の伏せ方もログと同じ）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VerboseInfo {
+    pub connected_ip: Option<String>,
+    pub connected_port: Option<u16>,
+    pub tls_version: Option<String>,
+    pub cipher_suite: Option<String>,
+    pub alpn: Option<String>,
+    pub certificate: Option<PeerCertificate>,
+    pub request_line: Option<String>,
+    pub request_headers: Vec<(String, String)>,
+    pub status_line: Option<String>,
+    pub response_headers: Vec<(String, String)>,
+    // プロキシで認証した方式
+    #[serde(default)]
+    pub proxy_auth: Option<ProxyAuthMethod>,
+}
+
+// 詳細ログの行と、その内容を構造化したもの
+#[derive(Default)]
+struct VerboseLog {
+    lines: Vec<String>,
+    info: VerboseInfo,
+}
+
+impl VerboseLog {
+    fn push(&mut self, line: String) {
+        self.lines.push(line);
+    }
+}
+
+fn sniff_prefix(prefix: &[u8]) -> &[u8] {
+    &prefix[..prefix.len().min(SNIFF_BYTES)]
+}
+
+// 応答を受信できた場合の内容
+struct HttpResponseData {
+    status_code: u16,
+    http_version: HttpVersion,
+    alt_svc: Option<String>,
+    headers: Vec<(String, String)>,
+    body: Option<Vec<u8>>,
+    // 種類の判定用の本文の先頭（本文を保持しない場合も受信する）
+    body_prefix: Vec<u8>,
+    body_bytes: u64,
+    body_truncated: bool,
+    second_request: Option<SecondRequest>,
+}
+
+// 同じ接続で送信した 2 回目のリクエスト（接続と TLS ハンドシェイクを含まない）
+// saved_ms は 1 回目の total_ms との差で、接続の確立にかかった時間の目安
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecondRequest {
+    pub status_code: Option<u16>,
+    pub first_byte_ms: Option<f64>,
+    pub transfer_ms: Option<f64>,
+    pub total_ms: f64,
+    pub saved_ms: Option<f64>,
+    pub error_message: Option<String>,
+}
+
+// OS の証明書ストアのルート証明書（社内 CA なども curl.exe と同様に信頼される）
+static NATIVE_ROOTS: LazyLock<Result<Arc<RootCertStore>, String>> = LazyLock::new(|| {
+    let native = rustls_native_certs::load_native_certs();
+    for e in &native.errors {
+        eprintln!("Failed to load a native certificate: {}", e);
+    }
+    let mut roots = RootCertStore::empty();
+    let (added, ignored) = roots.add_parsable_certificates(native.certs);
+    if added == 0 {
+        return Err("OSの証明書ストアから証明書を読み込めません".to_string());
+    }
+    if ignored > 0 {
+        eprintln!("Ignored {} unparsable native certificates", ignored);
+    }
+    Ok(Arc::new(roots))
+});
+
+// OS の証明書ストアを信頼する TLS 設定
+static VERIFIED_TLS_CONFIG: LazyLock<Result<Arc<ClientConfig>, String>> = LazyLock::new(|| {
+    let config = ClientConfig::builder_with_provider(crypto_provider())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS設定の作成に失敗: {}", e))?
+        .with_root_certificates(NATIVE_ROOTS.clone()?)
+        .with_no_client_auth();
+    Ok(Arc::new(config))
+});
+
+// 証明書の検証を行わない TLS 設定（署名の検証のみ行う）
+static INSECURE_TLS_CONFIG: LazyLock<Result<Arc<ClientConfig>, String>> = LazyLock::new(|| {
+    let provider = crypto_provider();
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS設定の作成に失敗: {}", e))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(provider)))
+        .with_no_client_auth();
+    Ok(Arc::new(config))
+});
+
+// バージョンの指定がなければ共通の設定を使い、固定する場合はその都度作成する
+fn tls_config(
+    ignore_tls_errors: bool,
+    tls_version: Option<TlsVersion>,
+) -> Result<Arc<ClientConfig>, String> {
+    let Some(tls_version) = tls_version else {
+        return if ignore_tls_errors {
+            INSECURE_TLS_CONFIG.clone()
+        } else {
+            VERIFIED_TLS_CONFIG.clone()
+        };
+    };
+    let provider = crypto_provider();
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&[tls_version.protocol_version()])
+        .map_err(|e| format!("TLS設定の作成に失敗: {}", e))?;
+    let config = if ignore_tls_errors {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(provider)))
+            .with_no_client_auth()
+    } else {
+        builder
+            .with_root_certificates(NATIVE_ROOTS.clone()?)
+            .with_no_client_auth()
+    };
+    Ok(Arc::new(config))
+}
+
+// ECH（Encrypted Client Hello）で SNI を暗号化する TLS 1.3 の設定（ECHConfigList は接続先の HTTPS レコードの値）
+// SNI による遮断の確認用のため、他の SNI での試行と同じく証明書は検証しない
+// HPKE は aws-lc-rs のみが提供するため ech フィーチャでビルドした場合のみ使える（他の TLS の接続は ring のまま）
+#[cfg(feature = "ech")]
+pub fn ech_tls_config(ech_config_list: Vec<u8>) -> Result<Arc<ClientConfig>, String> {
+    let ech_config = EchConfig::new(
+        ech_config_list.into(),
+        rustls::crypto::aws_lc_rs::hpke::ALL_SUPPORTED_SUITES,
+    )
+    .map_err(|e| format!("ECH の設定を使用できません: {}", e))?;
+    let provider = crypto_provider();
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_ech(EchMode::Enable(ech_config))
+        .map_err(|e| format!("TLS設定の作成に失敗: {}", e))?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(NoCertificateVerification(provider)))
+        .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+pub fn crypto_provider() -> Arc<CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+// ALPN で提示するプロトコル（バージョンの指定がなければ HTTP/2 を優先）
+fn with_alpn(config: &ClientConfig, http_version: Option<HttpVersion>) -> Arc<ClientConfig> {
+    let mut config = config.clone();
+    config.alpn_protocols = match http_version {
+        Some(HttpVersion::Http1_1) => vec![b"http/1.1".to_vec()],
+        Some(HttpVersion::Http2) => vec![b"h2".to_vec()],
+        None => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
+    };
+    Arc::new(config)
+}
+
+#[derive(Debug)]
+struct NoCertificateVerification(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoCertificateVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+// 証明書は検証せず、サーバが証明書とあわせて送信した OCSP 応答（OCSP ステープリング）を記録する
+#[derive(Debug)]
+struct StapledOcspRecorder {
+    inner: NoCertificateVerification,
+    ocsp_response: Mutex<Option<Vec<u8>>>,
+}
+
+impl ServerCertVerifier for StapledOcspRecorder {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if !ocsp_response.is_empty() {
+            if let Ok(mut recorded) = self.ocsp_response.lock() {
+                *recorded = Some(ocsp_response.to_vec());
+            }
+        }
+        self.inner
+            .verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+fn elapsed_ms(since: Instant) -> f64 {
+    since.elapsed().as_secs_f64() * 1000.0
+}
+
+// 締め切りまでに終わらなければタイムアウトとして扱う
+async fn before_deadline<T>(
+    deadline: Deadline,
+    stage: &str,
+    future: impl Future<Output = Result<T, String>>,
+) -> Result<T, String> {
+    tokio::time::timeout_at(deadline.at, future)
+        .await
+        .unwrap_or_else(|_| {
+            Err(format!(
+                "{}中にタイムアウトしました（{}秒）",
+                stage,
+                deadline.timeout.as_secs()
+            ))
+        })
+}
+
+// User-Agent の値を検証する（ヘッダの値と同じ制限）
+pub fn normalize_user_agent(user_agent: &str) -> Result<String, String> {
+    let user_agent = user_agent.trim();
+    if user_agent.is_empty() {
+        return Err("User-Agent が空です".to_string());
+    }
+    if user_agent.len() > MAX_HEADER_VALUE_LEN {
+        return Err(format!(
+            "User-Agent は {} バイト以内で指定してください",
+            MAX_HEADER_VALUE_LEN
+        ));
+    }
+    if user_agent.chars().any(|c| c.is_control())
+        || hyper::header::HeaderValue::from_str(user_agent).is_err()
+    {
+        return Err("User-Agent に使用できない文字が含まれています".to_string());
+    }
+    Ok(user_agent.to_string())
+}
+
+// 範囲外のタイムアウト指定を拒否
+pub fn validate_timeout_secs(timeout_secs: u64) -> Result<(), String> {
+    if !(MIN_TIMEOUT_SECS..=MAX_TIMEOUT_SECS).contains(&timeout_secs) {
+        return Err(format!(
+            "タイムアウトは {} から {} 秒の範囲で指定してください",
+            MIN_TIMEOUT_SECS, MAX_TIMEOUT_SECS
+        ));
+    }
+    Ok(())
+}
+
+// 指定した IP アドレスに接続してリクエストを送信し、応答本文の受信完了までを測定
+pub async fn send_request(request: &HttpRequest<'_>) -> HttpOutcome {
+    // 送信枠の待ち時間は測定に含めない
+    crate::rate_limit::acquire(request.host).await;
+    let start = Instant::now();
+    let mut timings = HttpTimings::default();
+    let mut log = VerboseLog::default();
+
+    let mut result = execute(request, start, &mut timings, &mut log).await;
+    // 同じ接続での 2 回目のリクエストの時間は 1 回目の測定に含めない
+    let second_total_ms = result
+        .as_ref()
+        .ok()
+        .and_then(|r| r.second_request.as_ref())
+        .map(|s| s.total_ms)
+        .unwrap_or_default();
+    timings.total_ms = elapsed_ms(start) - second_total_ms;
+    if let Some(second) = result.as_mut().ok().and_then(|r| r.second_request.as_mut()) {
+        if second.error_message.is_none() {
+            second.saved_ms = Some(timings.total_ms - second.total_ms);
+        }
+    }
+
+    let (mut response, error_message) = match result {
+        Ok(response) => (Some(response), None),
+        Err(e) => {
+            log.push(format!("* {}", e));
+            (None, Some(e))
+        }
+    };
+    HttpOutcome {
+        status_code: response.as_ref().map(|r| r.status_code),
+        error_message,
+        timings,
+        verbose_log: if request.verbose && !log.lines.is_empty() {
+            Some(log.lines.join("\n"))
+        } else {
+            None
+        },
+        certificate: log.info.certificate.clone(),
+        tls_version: log.info.tls_version.clone(),
+        cipher_suite: log.info.cipher_suite.clone(),
+        proxy_auth: log.info.proxy_auth,
+        verbose_info: request.verbose.then_some(log.info),
+        content: response
+            .as_ref()
+            .and_then(|r| content_sniff::inspect(&r.headers, sniff_prefix(&r.body_prefix))),
+        body_prefix: response
+            .as_mut()
+            .map(|r| std::mem::take(&mut r.body_prefix))
+            .unwrap_or_default(),
+        alt_svc: response.as_ref().and_then(|r| r.alt_svc.clone()),
+        http_version: response.as_ref().map(|r| r.http_version),
+        headers: response
+            .as_mut()
+            .map(|r| std::mem::take(&mut r.headers))
+            .unwrap_or_default(),
+        body_bytes: response.as_ref().map(|r| r.body_bytes),
+        body_truncated: response.as_ref().is_some_and(|r| r.body_truncated),
+        second_request: response.as_mut().and_then(|r| r.second_request.take()),
+        body: response.and_then(|r| r.body),
+    }
+}
+
+async fn execute(
+    request: &HttpRequest<'_>,
+    start: Instant,
+    timings: &mut HttpTimings,
+    log: &mut VerboseLog,
+) -> Result<HttpResponseData, String> {
+    let timeout = Duration::from_secs(request.timeout_secs);
+    let deadline = Deadline {
+        at: start + timeout,
+        timeout,
+    };
+    let url = Url::parse(request.url).map_err(|e| format!("無効なURL: {}", e))?;
+    let is_https = match url.scheme() {
+        "https" => true,
+        "http" => false,
+        scheme => return Err(format!("未対応のスキームです: {}", scheme)),
+    };
+    let port = request
+        .port
+        .or(url.port())
+        .unwrap_or(if is_https { 443 } else { 80 });
+    let target = match request.ip_address {
+        Some(ip_address) => {
+            let ip: IpAddr = ip_address
+                .parse()
+                .map_err(|_| format!("無効なIPアドレス: {}", ip_address))?;
+            TunnelTarget::Address(SocketAddr::new(ip, port))
+        }
+        None => TunnelTarget::Name(request.host.to_string(), port),
+    };
+    let version = request.options.http_version.unwrap_or(HttpVersion::Http1_1);
+    let forward_uri =
+        forward_proxy(request, &url, version).map(|_| absolute_uri(&url, request.host, port));
+
+    let detail = match &request.options.proxy {
+        Some(proxy) => format!(
+            "{} を {} へプロキシ {} 経由で送信",
+            request.options.method.as_str(),
+            target,
+            proxy.display()
+        ),
+        None => format!("{} を {} へ送信", request.options.method.as_str(), target),
+    };
+    if dry_run::intercept(ActionKind::Http, request.url, Some(detail)) {
+        return Err(DRY_RUN_MESSAGE.to_string());
+    }
+
+    // TCP 接続（プロキシを指定した場合はトンネルを開くまでを接続時間とする）
+    let connect_started = Instant::now();
+    let stream: Box<dyn Connection> = match (&request.options.proxy, &target) {
+        (Some(proxy), _) => {
+            let route = match &forward_uri {
+                Some(uri) => ProxyRoute::Forward(uri),
+                None => ProxyRoute::Tunnel(&target),
+            };
+            before_deadline(
+                deadline,
+                "プロキシ経由の接続",
+                connect_via_proxy(proxy, route, request, port, log),
+            )
+            .await?
+        }
+        (None, TunnelTarget::Address(address)) => {
+            log.push(format!("* Trying {}...", address));
+            let stream = before_deadline(
+                deadline,
+                "TCP接続",
+                connect_tcp(*address, request.source_address),
+            )
+            .await?;
+            Box::new(stream)
+        }
+        (None, TunnelTarget::Name(..)) => {
+            return Err("プロキシを経由しない場合は接続先の IP アドレスが必要です".to_string());
+        }
+    };
+    timings.connect_ms = Some(elapsed_ms(connect_started));
+    // プロキシが名前を解決した場合、接続先のアドレスはわからない
+    if let TunnelTarget::Address(address) = &target {
+        log.info.connected_ip = Some(address.ip().to_string());
+    }
+    log.info.connected_port = Some(port);
+    log.push(format!(
+        "* Connected to {} ({:.1} ms)",
+        target,
+        timings.connect_ms.unwrap_or_default()
+    ));
+
+    // HTTP で HTTP/2 を指定した場合は事前合意（h2c prior knowledge）で接続
+    if !is_https {
+        return exchange(stream, &url, request, port, version, deadline, timings, log).await;
+    }
+
+    // TLS ハンドシェイク（SNI には指定がなければ URL のホスト名を使用し、証明書もその名前で検証）
+    let config = tls_config(request.ignore_tls_errors, request.options.tls_version)?;
+    let config = with_alpn(&config, request.options.http_version);
+    let sni = request.options.sni.as_deref().unwrap_or(request.host);
+    let server_name = ServerName::try_from(sni.trim_matches(['[', ']']).to_string())
+        .map_err(|_| format!("TLSのサーバ名として使用できません: {}", sni))?;
+    if request.options.sni.is_some() {
+        log.push(format!("* Using SNI {}", sni));
+    }
+    let tls_started = Instant::now();
+    let tls_stream = before_deadline(deadline, "TLSハンドシェイク", async {
+        TlsConnector::from(config)
+            .connect(server_name, stream)
+            .await
+            .map_err(describe_tls_error)
+    })
+    .await?;
+    timings.tls_handshake_ms = Some(elapsed_ms(tls_started));
+    let (_, connection) = tls_stream.get_ref();
+    log.info.tls_version = connection.protocol_version().map(protocol_version_name);
+    log.info.cipher_suite = connection
+        .negotiated_cipher_suite()
+        .map(|s| format!("{:?}", s.suite()));
+    log.push(format!(
+        "* TLS handshake completed: {} / {} ({:.1} ms)",
+        log.info.tls_version.clone().unwrap_or_default(),
+        log.info.cipher_suite.clone().unwrap_or_default(),
+        timings.tls_handshake_ms.unwrap_or_default()
+    ));
+    log.info.certificate = connection
+        .peer_certificates()
+        .and_then(|certificates| certificates.first())
+        .and_then(peer_certificate);
+    if let Some(certificate) = &log.info.certificate {
+        let lines = [
+            "* Server certificate:".to_string(),
+            format!("*  subject: {}", certificate.subject),
+            format!("*  start date: {}", certificate.not_before),
+            format!("*  expire date: {}", certificate.not_after),
+            format!("*  issuer: {}", certificate.issuer),
+        ];
+        log.lines.extend(lines);
+    }
+
+    // ALPN に対応しないサーバは HTTP/1.1 とみなす
+    let version = match connection.alpn_protocol() {
+        Some(b"h2") => HttpVersion::Http2,
+        _ => HttpVersion::Http1_1,
+    };
+    log.info.alpn = connection
+        .alpn_protocol()
+        .map(|p| String::from_utf8_lossy(p).to_string());
+    log.push(format!(
+        "* ALPN: {}",
+        log.info.alpn.as_deref().unwrap_or("(none)")
+    ));
+    if request.options.http_version == Some(HttpVersion::Http2) && version != HttpVersion::Http2 {
+        return Err(
+            "サーバが HTTP/2 に対応していません（ALPN で h2 が選択されませんでした）".to_string(),
+        );
+    }
+
+    exchange(
+        tls_stream, &url, request, port, version, deadline, timings, log,
+    )
+    .await
+}
+
+// 直接の TCP 接続とプロキシ経由のトンネルを同じように扱うためのストリーム
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+// TCP 接続（送信元アドレス指定時はアダプタ別比較のため bind してから接続）
+async fn connect_tcp(
+    target: SocketAddr,
+    source_address: Option<&str>,
+) -> Result<TcpStream, String> {
+    let ip = target.ip();
+    let socket = if ip.is_ipv6() {
+        TcpSocket::new_v6()
+    } else {
+        TcpSocket::new_v4()
+    }
+    .map_err(|e| format!("ソケットの作成に失敗: {}", e))?;
+    if let Some(source_address) = source_address {
+        let source: IpAddr = source_address
+            .parse()
+            .map_err(|_| format!("無効な送信元アドレス: {}", source_address))?;
+        if source.is_ipv6() != ip.is_ipv6() {
+            return Err(format!(
+                "送信元アドレス {} と接続先 {} のIPバージョンが一致しません",
+                source, ip
+            ));
+        }
+        socket
+            .bind(SocketAddr::new(source, 0))
+            .map_err(|e| format!("送信元アドレス {} を使用できません: {}", source, e))?;
+    }
+    let stream = socket
+        .connect(target)
+        .await
+        .map_err(|e| format!("{} に接続できません: {}", target, e))?;
+    let _ = stream.set_nodelay(true);
+    Ok(stream)
+}
+
+// プロキシへの依頼方法
+enum ProxyRoute<'a> {
+    // CONNECT または SOCKS5 で接続先へのトンネルを開く
+    Tunnel(&'a TunnelTarget),
+    // 絶対形式の URI でリクエストの転送を依頼する
+    Forward(&'a str),
+}
+
+// プロキシに接続し、トンネルを開くか転送を依頼するための認証を済ませる
+// 送信元アドレスを指定した場合は、そのアドレスと同じファミリのプロキシのアドレスに接続する
+async fn connect_via_proxy(
+    proxy: &ProxyConfig,
+    route: ProxyRoute<'_>,
+    request: &HttpRequest<'_>,
+    port: u16,
+    log: &mut VerboseLog,
+) -> Result<Box<dyn Connection>, String> {
+    let addresses = proxy::resolve(proxy).await?;
+    let source_is_ipv6 = request
+        .source_address
+        .and_then(|s| s.parse::<IpAddr>().ok())
+        .map(|s| s.is_ipv6());
+    let proxy_address = addresses
+        .iter()
+        .find(|a| source_is_ipv6.is_none_or(|v6| a.is_ipv6() == v6))
+        .unwrap_or(&addresses[0]);
+    log.push(format!(
+        "* Trying proxy {} ({})...",
+        proxy.display(),
+        proxy_address
+    ));
+    let stream = connect_tcp(*proxy_address, request.source_address).await?;
+    let mut stream: Box<dyn Connection> = if proxy.kind == ProxyKind::Https {
+        let server_name = ServerName::try_from(proxy.host.clone())
+            .map_err(|_| format!("TLSのサーバ名として使用できません: {}", proxy.host))?;
+        let config = tls_config(request.ignore_tls_errors, None)?;
+        let tls_stream = TlsConnector::from(config)
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| format!("プロキシとの{}", describe_tls_error(e)))?;
+        Box::new(tls_stream)
+    } else {
+        Box::new(stream)
+    };
+    log.info.proxy_auth = match route {
+        ProxyRoute::Tunnel(target) => {
+            let auth = proxy::open_tunnel(&mut stream, proxy, target).await?;
+            log.push(format!(
+                "* Tunnel to {} established via {}",
+                target,
+                proxy.display()
+            ));
+            auth
+        }
+        ProxyRoute::Forward(uri) => {
+            let host = format!("{}:{}", request.host, port);
+            let auth = proxy::authenticate_forwarding(&mut stream, proxy, uri, &host).await?;
+            log.push(format!("* Forwarding request via {}", proxy.display()));
+            auth
+        }
+    };
+    if let Some(auth) = log.info.proxy_auth {
+        log.push(format!("* Proxy authentication: {}", auth.as_str()));
+    }
+    Ok(stream)
+}
+
+// HTTP プロキシに転送を依頼する場合の絶対形式の URI（Host ヘッダを上書きした場合も URL の接続先に送る）
+fn absolute_uri(url: &Url, host: &str, port: u16) -> String {
+    format!("{}://{}:{}{}", url.scheme(), host, port, request_path(url))
+}
+
+fn request_path(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+// HTTP の URL をトンネルを開かずに絶対形式の URI で送る HTTP プロキシ
+// （接続先の IP アドレスを固定する場合と HTTP/2 の事前合意の場合は CONNECT でトンネルを開く）
+fn forward_proxy<'a>(
+    request: &'a HttpRequest<'_>,
+    url: &Url,
+    version: HttpVersion,
+) -> Option<&'a ProxyConfig> {
+    request.options.proxy.as_ref().filter(|proxy| {
+        url.scheme() == "http"
+            && matches!(proxy.kind, ProxyKind::Http | ProxyKind::Https)
+            && request.ip_address.is_none()
+            && version == HttpVersion::Http1_1
+    })
+}
+
+// 接続で使うバージョンの送信側
+enum Sender {
+    Http1(http1::SendRequest<Full<Bytes>>),
+    Http2(http2::SendRequest<Full<Bytes>>),
+}
+
+impl Sender {
+    // 前の応答の受信後に次のリクエストを送信できるか（サーバが接続を閉じた場合はエラー）
+    async fn ready(&mut self) -> hyper::Result<()> {
+        match self {
+            Sender::Http1(sender) => sender.ready().await,
+            Sender::Http2(sender) => sender.ready().await,
+        }
+    }
+
+    async fn send(&mut self, request: Request<Full<Bytes>>) -> hyper::Result<Response<Incoming>> {
+        match self {
+            Sender::Http1(sender) => sender.send_request(request).await,
+            Sender::Http2(sender) => sender.send_request(request).await,
+        }
+    }
+}
+
+// サーバ証明書の主体者・発行者・有効期間（解析できない証明書の場合は None）
+fn peer_certificate(certificate: &CertificateDer<'_>) -> Option<PeerCertificate> {
+    let (_, parsed) = x509_parser::parse_x509_certificate(certificate.as_ref()).ok()?;
+    let validity = parsed.validity();
+    let format_time = |time: &x509_parser::time::ASN1Time| {
+        chrono::DateTime::from_timestamp(time.timestamp(), 0)
+            .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+            .unwrap_or_default()
+    };
+    Some(PeerCertificate {
+        subject: parsed.subject().to_string(),
+        issuer: parsed.issuer().to_string(),
+        not_before: format_time(&validity.not_before),
+        not_after: format_time(&validity.not_after),
+    })
+}
+
+// サーバが送信した証明書チェーン
+pub struct ServerCertificateChain {
+    pub tls_version: Option<String>,
+    pub cipher_suite: Option<String>,
+    pub handshake_ms: f64,
+    // サーバが送信した順（先頭がサーバ証明書）
+    pub certificates: Vec<CertificateDer<'static>>,
+    // OS の証明書ストアで検証した結果（通常の測定で発生する TLS エラーと同じ内容）
+    pub verification_error: Option<String>,
+    // サーバがステープリングした OCSP 応答（DER、送信されなかった場合は None）
+    pub ocsp_response: Option<Vec<u8>>,
+}
+
+// 証明書を検証せずに TLS ハンドシェイクを行ってチェーンを取得し、その後で検証する
+// （検証に失敗する場合でも原因を調べられるようにするため、tls_version を指定するとそのバージョンのみ提示する）
+pub async fn fetch_certificate_chain(
+    ip: IpAddr,
+    port: u16,
+    host: &str,
+    tls_version: Option<TlsVersion>,
+    timeout_secs: u64,
+) -> Result<ServerCertificateChain, String> {
+    let timeout = Duration::from_secs(timeout_secs);
+    let deadline = Deadline {
+        at: Instant::now() + timeout,
+        timeout,
+    };
+    let target = SocketAddr::new(ip, port);
+    let server_name = ServerName::try_from(host.trim_matches(['[', ']']).to_string())
+        .map_err(|_| format!("TLSのサーバ名として使用できません: {}", host))?;
+    if dry_run::intercept(
+        ActionKind::Tls,
+        target.to_string(),
+        Some(format!("SNI {}", host)),
+    ) {
+        return Err(DRY_RUN_MESSAGE.to_string());
+    }
+
+    let stream = before_deadline(deadline, "TCP接続", async {
+        TcpStream::connect(target)
+            .await
+            .map_err(|e| format!("{} に接続できません: {}", target, e))
+    })
+    .await?;
+    // rustls は常に OCSP 応答を要求するため、送信された応答を検証器で受け取る
+    let recorder = Arc::new(StapledOcspRecorder {
+        inner: NoCertificateVerification(crypto_provider()),
+        ocsp_response: Mutex::new(None),
+    });
+    let versions = match tls_version {
+        Some(tls_version) => vec![tls_version.protocol_version()],
+        None => rustls::DEFAULT_VERSIONS.to_vec(),
+    };
+    let config = ClientConfig::builder_with_provider(crypto_provider())
+        .with_protocol_versions(&versions)
+        .map_err(|e| format!("TLS設定の作成に失敗: {}", e))?
+        .dangerous()
+        .with_custom_certificate_verifier(recorder.clone())
+        .with_no_client_auth();
+    let tls_started = Instant::now();
+    let tls_stream = before_deadline(deadline, "TLSハンドシェイク", async {
+        TlsConnector::from(Arc::new(config))
+            .connect(server_name.clone(), stream)
+            .await
+            .map_err(describe_tls_error)
+    })
+    .await?;
+    let handshake_ms = elapsed_ms(tls_started);
+
+    let (_, connection) = tls_stream.get_ref();
+    let certificates: Vec<CertificateDer<'static>> = connection
+        .peer_certificates()
+        .map(|certificates| {
+            certificates
+                .iter()
+                .map(|c| c.clone().into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    let verification_error = match certificates.split_first() {
+        Some((end_entity, intermediates)) => {
+            let verifier = WebPkiServerVerifier::builder_with_provider(
+                NATIVE_ROOTS.clone()?,
+                crypto_provider(),
+            )
+            .build()
+            .map_err(|e| format!("TLS設定の作成に失敗: {}", e))?;
+            verifier
+                .verify_server_cert(
+                    end_entity,
+                    intermediates,
+                    &server_name,
+                    &[],
+                    UnixTime::now(),
+                )
+                .err()
+                .map(|e| e.to_string())
+        }
+        None => Some("サーバが証明書を送信しませんでした".to_string()),
+    };
+
+    Ok(ServerCertificateChain {
+        tls_version: connection.protocol_version().map(protocol_version_name),
+        cipher_suite: connection
+            .negotiated_cipher_suite()
+            .map(|s| format!("{:?}", s.suite())),
+        handshake_ms,
+        certificates,
+        verification_error,
+        ocsp_response: recorder
+            .ocsp_response
+            .lock()
+            .ok()
+            .and_then(|mut recorded| recorded.take()),
+    })
+}
+
+// サーバとの TLS のネゴシエーションに失敗した場合のメッセージの先頭（タイムアウトや接続エラーとの区別用）
+pub const TLS_HANDSHAKE_FAILED: &str = "TLSハンドシェイクに失敗";
+
+// サーバ証明書の検証に失敗した場合のメッセージの先頭
+const TLS_CERTIFICATE_INVALID: &str = "TLS証明書の検証に失敗";
+
+// TLS のネゴシエーションまたは証明書の検証に失敗したか（TLS エラーを無視して再試行するかの判定用）
+pub fn is_tls_error(message: &str) -> bool {
+    message.starts_with(TLS_HANDSHAKE_FAILED) || message.starts_with(TLS_CERTIFICATE_INVALID)
+}
+
+// TLS エラーを利用者向けのメッセージに変換
+fn describe_tls_error(e: std::io::Error) -> String {
+    let certificate_error = e
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+        .is_some_and(|inner| matches!(inner, rustls::Error::InvalidCertificate(_)));
+    if certificate_error {
+        format!(
+            "{}: {}（自己署名証明書の場合は TLS エラーを無視する設定で再試行してください）",
+            TLS_CERTIFICATE_INVALID, e
+        )
+    } else {
+        format!("{}: {}", TLS_HANDSHAKE_FAILED, e)
+    }
+}
+
+// HTTP/1.1 または HTTP/2 でリクエストを送信し、応答本文を最後まで読み捨てる
+#[allow(clippy::too_many_arguments)]
+async fn exchange<S>(
+    stream: S,
+    url: &Url,
+    request: &HttpRequest<'_>,
+    port: u16,
+    version: HttpVersion,
+    deadline: Deadline,
+    timings: &mut HttpTimings,
+    log: &mut VerboseLog,
+) -> Result<HttpResponseData, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    let (mut sender, connection) = before_deadline(deadline, "HTTP接続の準備", async {
+        match version {
+            HttpVersion::Http1_1 => http1::handshake(TokioIo::new(stream))
+                .await
+                .map(|(sender, connection)| (Sender::Http1(sender), tokio::spawn(connection))),
+            HttpVersion::Http2 => http2::handshake(TokioExecutor::new(), TokioIo::new(stream))
+                .await
+                .map(|(sender, connection)| (Sender::Http2(sender), tokio::spawn(connection))),
+        }
+        .map_err(|e| format!("HTTP接続の準備に失敗: {}", e))
+    })
+    .await?;
+
+    // 既定ポート以外の場合は Host ヘッダにポートを含める
+    let host_header = match url.port() {
+        Some(_) => format!("{}:{}", request.host, port),
+        None => request.host.to_string(),
+    };
+    let path = request_path(url);
+    let method = request.options.method;
+    let custom_headers = &request.options.headers;
+    let forward_proxy = forward_proxy(request, url, version);
+    let proxy_authorization = forward_proxy.and_then(ProxyConfig::basic_authorization);
+    // 利用者が同名のヘッダを指定した場合は既定値を置き換える
+    let mut headers: Vec<(&str, &str)> = [
+        ("host", host_header.as_str()),
+        ("user-agent", request.options.effective_user_agent()),
+        ("accept", "*/*"),
+    ]
+    .into_iter()
+    .filter(|(name, _)| !custom_headers.iter().any(|(n, _)| n == name))
+    .chain(custom_headers.iter().map(|(n, v)| (n.as_str(), v.as_str())))
+    .chain(
+        proxy_authorization
+            .as_deref()
+            .map(|value| ("proxy-authorization", value)),
+    )
+    .collect();
+    // HTTP/2 では Host の代わりに :authority 疑似ヘッダで送るため、絶対形式の URI にする
+    let authority = match version {
+        HttpVersion::Http1_1 => None,
+        HttpVersion::Http2 => Some(
+            headers
+                .iter()
+                .position(|(name, _)| *name == "host")
+                .map(|index| headers.remove(index).1)
+                .unwrap_or(host_header.as_str()),
+        ),
+    };
+    // HTTP プロキシに転送を依頼する場合も絶対形式にする
+    let uri = match authority {
+        Some(authority) => format!("{}://{}{}", url.scheme(), authority, path),
+        None if forward_proxy.is_some() => absolute_uri(url, request.host, port),
+        None => path.clone(),
+    };
+    // 本文を伴うメソッドは本文がなくても長さを明示する（Content-Length がないと 411 を返すサーバがある）
+    let body = request.options.body.clone().unwrap_or_default();
+    let content_length = body.len().to_string();
+    if matches!(method, HttpMethod::Post | HttpMethod::Put) {
+        headers.push(("content-length", content_length.as_str()));
+    }
+    // 同じ接続で 2 回目を送信する場合も同じ内容のリクエストを作る
+    let build_request = || {
+        let mut builder = Request::builder()
+            .method(method.to_hyper())
+            .uri(uri.as_str());
+        if version == HttpVersion::Http2 {
+            builder = builder.version(hyper::Version::HTTP_2);
+        }
+        for (name, value) in &headers {
+            builder = builder.header(*name, *value);
+        }
+        builder
+            .body(Full::new(body.clone()))
+            .map_err(|e| format!("リクエストの作成に失敗: {}", e))
+    };
+    let request_target = if forward_proxy.is_some() { &uri } else { &path };
+    let request_line = format!(
+        "{} {} {}",
+        method.as_str(),
+        request_target,
+        version.as_str()
+    );
+    log.push(format!("> {}", request_line));
+    log.info.request_line = Some(request_line);
+    if let Some(authority) = authority {
+        log.push(format!("> :authority: {}", authority));
+        log.info
+            .request_headers
+            .push((":authority".to_string(), authority.to_string()));
+    }
+    for (name, value) in &headers {
+        let logged = if SENSITIVE_HEADERS.contains(name) {
+            "[REDACTED]"
+        } else {
+            value
+        };
+        log.push(format!("> {}: {}", name, logged));
+        log.info
+            .request_headers
+            .push((name.to_string(), logged.to_string()));
+    }
+    // 本文には認証情報などが含まれることがあるため、ログには長さのみ残す
+    if !body.is_empty() {
+        log.push(format!("* Sending {} bytes of request body", body.len()));
+    }
+    let http_request = build_request()?;
+
+    let result = async {
+        let request_started = Instant::now();
+        let response = before_deadline(deadline, "応答待ち", async {
+            sender
+                .send(http_request)
+                .await
+                .map_err(|e| format!("応答を受信できません: {}", e))
+        })
+        .await?;
+        timings.first_byte_ms = Some(elapsed_ms(request_started));
+
+        let status = response.status();
+        let alt_svc: Vec<String> = response
+            .headers()
+            .get_all(ALT_SVC)
+            .iter()
+            .map(|v| String::from_utf8_lossy(v.as_bytes()).to_string())
+            .collect();
+        let status_line = format!(
+            "{} {} {}",
+            version.as_str(),
+            status.as_u16(),
+            status.canonical_reason().unwrap_or("")
+        );
+        log.push(format!("< {}", status_line));
+        log.info.status_line = Some(status_line);
+        let headers: Vec<(String, String)> = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).to_string(),
+                )
+            })
+            .collect();
+        for (name, value) in &headers {
+            log.push(format!("< {}: {}", name, value));
+        }
+        log.info.response_headers = headers.clone();
+
+        let mut body = response.into_body();
+        let mut received = 0usize;
+        let mut truncated = false;
+        let mut kept = (request.max_body_bytes > 0).then(Vec::new);
+        let mut prefix = Vec::new();
+        let prefix_limit = SNIFF_BYTES.max(request.options.body_prefix_bytes);
+        let max_download_bytes = request.options.max_download_bytes;
+        let transfer_started = Instant::now();
+        before_deadline(deadline, "応答本文の受信", async {
+            while let Some(frame) = body.frame().await {
+                let frame = frame.map_err(|e| format!("応答本文の受信に失敗: {}", e))?;
+                if let Some(data) = frame.data_ref() {
+                    received += data.len();
+                    if prefix.len() < prefix_limit {
+                        let take = data.len().min(prefix_limit - prefix.len());
+                        prefix.extend_from_slice(&data[..take]);
+                    }
+                    // 上限に達したら残りは受信せず、接続ごと破棄する
+                    if max_download_bytes.is_some_and(|max| received as u64 >= max) {
+                        truncated = true;
+                        break;
+                    }
+                    if let Some(kept) = kept.as_mut() {
+                        if received > request.max_body_bytes {
+                            return Err(format!(
+                                "応答本文が大きすぎます（上限 {} バイト）",
+                                request.max_body_bytes
+                            ));
+                        }
+                        kept.extend_from_slice(data);
+                    }
+                }
+            }
+            Ok(())
+        })
+        .await?;
+        timings.transfer_ms = Some(elapsed_ms(transfer_started));
+        log.push(format!(
+            "* Received {} bytes ({:.1} ms){}",
+            received,
+            timings.transfer_ms.unwrap_or_default(),
+            if truncated {
+                ", stopped at max_download_bytes"
+            } else {
+                ""
+            }
+        ));
+        let second_request = if request.options.reuse_connection {
+            // 受信を打ち切った HTTP/2 のストリームは破棄して、接続の受信枠を空ける
+            drop(body);
+            Some(
+                send_second_request(
+                    &mut sender,
+                    build_request,
+                    truncated,
+                    version,
+                    deadline.timeout,
+                    max_download_bytes,
+                    log,
+                )
+                .await,
+            )
+        } else {
+            None
+        };
+        Ok(HttpResponseData {
+            status_code: status.as_u16(),
+            http_version: version,
+            alt_svc: (!alt_svc.is_empty()).then(|| alt_svc.join(", ")),
+            headers,
+            body: kept,
+            body_prefix: prefix,
+            body_bytes: received as u64,
+            body_truncated: truncated,
+            second_request,
+        })
+    }
+    .await;
+
+    connection.abort();
+    result
+}
+
+// 応答を受信した接続で同じリクエストをもう一度送信し、応答本文の受信完了までを測定
+// （1 回目とは別に、同じ長さのタイムアウトを適用する）
+async fn send_second_request(
+    sender: &mut Sender,
+    build_request: impl Fn() -> Result<Request<Full<Bytes>>, String>,
+    truncated: bool,
+    version: HttpVersion,
+    timeout: Duration,
+    max_download_bytes: Option<u64>,
+    log: &mut VerboseLog,
+) -> SecondRequest {
+    let started = Instant::now();
+    let deadline = Deadline {
+        at: started + timeout,
+        timeout,
+    };
+    let mut second = SecondRequest {
+        status_code: None,
+        first_byte_ms: None,
+        transfer_ms: None,
+        total_ms: 0.0,
+        saved_ms: None,
+        error_message: None,
+    };
+    let result = async {
+        // HTTP/1.1 では受信を打ち切った応答の残りが接続に残るため、同じ接続で次を送信できない
+        if truncated && version == HttpVersion::Http1_1 {
+            return Err(
+                "max_download_bytes で受信を打ち切ったため HTTP/1.1 の接続を再利用できません"
+                    .to_string(),
+            );
+        }
+        let http_request = build_request()?;
+        before_deadline(deadline, "接続の再利用の準備", async {
+            sender
+                .ready()
+                .await
+                .map_err(|e| format!("サーバが接続を閉じたため再利用できません: {}", e))
+        })
+        .await?;
+        log.push("* Re-using existing connection".to_string());
+
+        let request_started = Instant::now();
+        let response = before_deadline(deadline, "2 回目の応答待ち", async {
+            sender
+                .send(http_request)
+                .await
+                .map_err(|e| format!("2 回目の応答を受信できません: {}", e))
+        })
+        .await?;
+        second.first_byte_ms = Some(elapsed_ms(request_started));
+        let status = response.status();
+        second.status_code = Some(status.as_u16());
+        log.push(format!(
+            "< {} {} {}",
+            version.as_str(),
+            status.as_u16(),
+            status.canonical_reason().unwrap_or("")
+        ));
+
+        let mut body = response.into_body();
+        let mut received = 0u64;
+        let transfer_started = Instant::now();
+        before_deadline(deadline, "2 回目の応答本文の受信", async {
+            while let Some(frame) = body.frame().await {
+                let frame = frame.map_err(|e| format!("応答本文の受信に失敗: {}", e))?;
+                if let Some(data) = frame.data_ref() {
+                    received += data.len() as u64;
+                    if max_download_bytes.is_some_and(|max| received >= max) {
+                        break;
+                    }
+                }
+            }
+            Ok(())
+        })
+        .await?;
+        second.transfer_ms = Some(elapsed_ms(transfer_started));
+        log.push(format!(
+            "* Received {} bytes on the reused connection ({:.1} ms)",
+            received,
+            second.transfer_ms.unwrap_or_default()
+        ));
+        Ok(())
+    }
+    .await;
+
+    second.total_ms = elapsed_ms(started);
+    if let Err(e) = result {
+        log.push(format!("* {}", e));
+        second.error_message = Some(e);
+    }
+    second
+}
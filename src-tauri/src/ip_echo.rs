@@ -0,0 +1,202 @@
+use chrono::{FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+
+use crate::GlobalIPInfo;
+
+// アプリデータディレクトリに保存する設定ファイル名
+pub const IP_ECHO_CONFIG_FILE_NAME: &str = "ip_echo_endpoints.json";
+
+// 同一ファミリで試行するエンドポイントの上限
+const MAX_ENDPOINTS_PER_FAMILY: usize = 5;
+
+// 応答の解析方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IpEchoParser {
+    // getipv4/getipv6.0nyx.net 形式の JSON（client_host, datetime_jst）
+    Ghttpping,
+    // api.ipify.org / api64.ipify.org の JSON（?format=json, ip）
+    Ipify,
+    // ifconfig.co の JSON（Accept: application/json または /json, ip）
+    IfconfigCo,
+    // IP アドレスのみを返すテキスト（icanhazip.com, ifconfig.me/ip など）
+    PlainText,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpEchoEndpoint {
+    pub url: String,
+    pub ip_version: u8,
+    pub parser: IpEchoParser,
+}
+
+// getipv4/getipv6.0nyx.net の応答
+#[derive(Deserialize)]
+struct GhttppingResponse {
+    client_host: String,
+    datetime_jst: String,
+}
+
+// ipify / ifconfig.co の応答（ip 以外のフィールドは使用しない）
+#[derive(Deserialize)]
+struct IpFieldResponse {
+    ip: String,
+}
+
+// 時刻を返さないサービス用に、取得時刻を日本時間で記録
+fn now_jst() -> String {
+    let jst = FixedOffset::east_opt(9 * 3600).expect("valid JST offset");
+    Utc::now()
+        .with_timezone(&jst)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+// 応答に含まれるアドレスが IP アドレスであることを確認（キャプティブポータルの HTML などを除外）
+fn validate_ip(ip: &str) -> Result<String, String> {
+    let ip = ip.trim();
+    ip.parse::<IpAddr>()
+        .map(|addr| addr.to_string())
+        .map_err(|_| {
+            let preview: String = ip.chars().take(40).collect();
+            format!(
+                "応答が IP アドレスではありません: {}（キャプティブポータルやプロキシが応答している可能性があります）",
+                preview
+            )
+        })
+}
+
+impl IpEchoParser {
+    // 応答本文からグローバル IP 情報を取り出す
+    pub fn parse(&self, body: &str) -> Result<GlobalIPInfo, String> {
+        match self {
+            IpEchoParser::Ghttpping => {
+                let response: GhttppingResponse =
+                    serde_json::from_str(body).map_err(|e| format!("JSON解析失敗: {}", e))?;
+                Ok(GlobalIPInfo {
+                    client_host: validate_ip(&response.client_host)?,
+                    datetime_jst: response.datetime_jst,
+                })
+            }
+            IpEchoParser::Ipify | IpEchoParser::IfconfigCo => {
+                let response: IpFieldResponse =
+                    serde_json::from_str(body).map_err(|e| format!("JSON解析失敗: {}", e))?;
+                Ok(GlobalIPInfo {
+                    client_host: validate_ip(&response.ip)?,
+                    datetime_jst: now_jst(),
+                })
+            }
+            IpEchoParser::PlainText => {
+                let line = body.lines().map(|l| l.trim()).find(|l| !l.is_empty());
+                Ok(GlobalIPInfo {
+                    client_host: validate_ip(line.unwrap_or(""))?,
+                    datetime_jst: now_jst(),
+                })
+            }
+        }
+    }
+}
+
+// 既定のエンドポイント
+pub fn default_ip_echo_endpoints() -> Vec<IpEchoEndpoint> {
+    vec![
+        IpEchoEndpoint {
+            url: "https://getipv4.0nyx.net/json".to_string(),
+            ip_version: 4,
+            parser: IpEchoParser::Ghttpping,
+        },
+        IpEchoEndpoint {
+            url: "https://getipv6.0nyx.net/json".to_string(),
+            ip_version: 6,
+            parser: IpEchoParser::Ghttpping,
+        },
+    ]
+}
+
+fn config_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("アプリデータディレクトリの取得に失敗: {}", e))?;
+    Ok(dir.join(IP_ECHO_CONFIG_FILE_NAME))
+}
+
+// 設定ファイルがあればそれを、なければ既定のエンドポイントを返す
+pub fn load_ip_echo_endpoints(app: &AppHandle) -> Vec<IpEchoEndpoint> {
+    let Ok(path) = config_file_path(app) else {
+        return default_ip_echo_endpoints();
+    };
+    let Ok(content) = fs::read_to_string(&path) else {
+        return default_ip_echo_endpoints();
+    };
+
+    match serde_json::from_str::<Vec<IpEchoEndpoint>>(&content) {
+        Ok(endpoints) if validate_endpoints(&endpoints).is_ok() => endpoints,
+        Ok(_) => {
+            eprintln!("Invalid IP echo endpoints in {:?}", path);
+            default_ip_echo_endpoints()
+        }
+        Err(e) => {
+            eprintln!("Invalid IP echo config file {:?}: {}", path, e);
+            default_ip_echo_endpoints()
+        }
+    }
+}
+
+fn validate_endpoints(endpoints: &[IpEchoEndpoint]) -> Result<(), String> {
+    for endpoint in endpoints {
+        crate::validate_url(&endpoint.url)?;
+        if endpoint.ip_version != 4 && endpoint.ip_version != 6 {
+            return Err("IPバージョンは 4 または 6 を指定してください".to_string());
+        }
+    }
+    for ip_version in [4, 6] {
+        if endpoints
+            .iter()
+            .filter(|e| e.ip_version == ip_version)
+            .count()
+            > MAX_ENDPOINTS_PER_FAMILY
+        {
+            return Err(format!(
+                "IPv{} のエンドポイントは {} 個以内で指定してください",
+                ip_version, MAX_ENDPOINTS_PER_FAMILY
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_ip_echo_endpoints(app: AppHandle) -> Result<Vec<IpEchoEndpoint>, String> {
+    Ok(load_ip_echo_endpoints(&app))
+}
+
+// エンドポイントを保存（None の場合は既定値に戻す）
+#[tauri::command]
+pub async fn set_ip_echo_endpoints(
+    app: AppHandle,
+    endpoints: Option<Vec<IpEchoEndpoint>>,
+) -> Result<Vec<IpEchoEndpoint>, String> {
+    let path = config_file_path(&app)?;
+
+    let Some(endpoints) = endpoints else {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("設定ファイルの削除に失敗: {}", e))?;
+        }
+        return Ok(default_ip_echo_endpoints());
+    };
+
+    validate_endpoints(&endpoints)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("設定ディレクトリの作成に失敗: {}", e))?;
+    }
+    let body = serde_json::to_string_pretty(&endpoints)
+        .map_err(|e| format!("設定のシリアライズに失敗: {}", e))?;
+    fs::write(&path, body).map_err(|e| format!("設定ファイルの保存に失敗: {}", e))?;
+
+    Ok(endpoints)
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+use crate::GlobalIPInfo;
+
+// グローバルIPの変化履歴ファイル名（アプリデータディレクトリ配下）
+const IP_HISTORY_FILE_NAME: &str = "ip_history.json";
+
+// 保持する期間の最大件数（超過分は古いものから削除）
+const MAX_IP_HISTORY_ENTRIES: usize = 1000;
+
+// 同じアドレスが続いている期間（同じアドレスの観測は1件にまとめる）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GlobalIpPeriod {
+    pub ip_version: u8,
+    pub address: String,
+    pub previous_address: Option<String>,
+    pub first_seen_at: String,
+    pub last_seen_at: String,
+    pub observations: u32,
+}
+
+// グローバルIPの変化履歴（Tauri の State として管理）
+#[derive(Default)]
+pub struct IpHistoryStore {
+    periods: Mutex<Option<Vec<GlobalIpPeriod>>>,
+}
+
+fn ip_history_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("アプリデータディレクトリの取得に失敗: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("履歴ディレクトリの作成に失敗: {}", e))?;
+    Ok(dir.join(IP_HISTORY_FILE_NAME))
+}
+
+// 履歴ファイルを読み込む（壊れている場合は空として扱う）
+fn read_ip_history_file(path: &PathBuf) -> Vec<GlobalIpPeriod> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Invalid IP history file {:?}: {}", path, e);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+// 観測したアドレスを追加（直前と同じアドレスなら最終観測時刻のみ更新）
+fn observe(periods: &mut Vec<GlobalIpPeriod>, ip_version: u8, address: &str, observed_at: &str) {
+    let last = periods
+        .iter_mut()
+        .rev()
+        .find(|p| p.ip_version == ip_version);
+    let previous_address = match last {
+        Some(period) if period.address == address => {
+            period.last_seen_at = observed_at.to_string();
+            period.observations = period.observations.saturating_add(1);
+            return;
+        }
+        Some(period) => Some(period.address.clone()),
+        None => None,
+    };
+    periods.push(GlobalIpPeriod {
+        ip_version,
+        address: address.to_string(),
+        previous_address,
+        first_seen_at: observed_at.to_string(),
+        last_seen_at: observed_at.to_string(),
+        observations: 1,
+    });
+}
+
+// 環境確認で取得したグローバルIPを履歴に記録
+pub fn record_global_ips(
+    app: &AppHandle,
+    ipv4: Option<&GlobalIPInfo>,
+    ipv6: Option<&GlobalIPInfo>,
+) -> Result<(), String> {
+    if ipv4.is_none() && ipv6.is_none() {
+        return Ok(());
+    }
+    let path = ip_history_file_path(app)?;
+    let observed_at = chrono::Local::now().to_rfc3339();
+
+    let store = app.state::<IpHistoryStore>();
+    let mut guard = store
+        .periods
+        .lock()
+        .map_err(|_| "IP履歴のロック取得に失敗".to_string())?;
+    let periods = guard.get_or_insert_with(|| read_ip_history_file(&path));
+
+    if let Some(info) = ipv4 {
+        observe(periods, 4, &info.client_host, &observed_at);
+    }
+    if let Some(info) = ipv6 {
+        observe(periods, 6, &info.client_host, &observed_at);
+    }
+    if periods.len() > MAX_IP_HISTORY_ENTRIES {
+        let excess = periods.len() - MAX_IP_HISTORY_ENTRIES;
+        periods.drain(..excess);
+    }
+
+    let body =
+        serde_json::to_string_pretty(periods).map_err(|e| format!("IP履歴の変換に失敗: {}", e))?;
+    fs::write(&path, body).map_err(|e| format!("IP履歴ファイルの書き込みに失敗: {}", e))
+}
+
+// グローバルIPの変化履歴を取得（古い順、limit 指定時は新しいものから limit 件）
+#[tauri::command]
+pub async fn get_global_ip_history(
+    app: AppHandle,
+    store: State<'_, IpHistoryStore>,
+    ip_version: Option<u8>,
+    limit: Option<usize>,
+) -> Result<Vec<GlobalIpPeriod>, String> {
+    if ip_version.is_some_and(|v| v != 4 && v != 6) {
+        return Err("IPバージョンは 4 または 6 を指定してください".to_string());
+    }
+    let path = ip_history_file_path(&app)?;
+    let mut periods: Vec<GlobalIpPeriod> = store
+        .periods
+        .lock()
+        .map_err(|_| "IP履歴のロック取得に失敗".to_string())?
+        .get_or_insert_with(|| read_ip_history_file(&path))
+        .iter()
+        .filter(|p| ip_version.is_none_or(|v| p.ip_version == v))
+        .cloned()
+        .collect();
+
+    if let Some(limit) = limit {
+        if periods.len() > limit {
+            periods.drain(..periods.len() - limit);
+        }
+    }
+
+    Ok(periods)
+}
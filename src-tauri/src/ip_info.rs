@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use crate::dns::{self, DirectQueryError, DirectQueryResponse, RECORD_TYPE_PTR, RECORD_TYPE_TXT};
+
+// 逆引きと AS の問い合わせのタイムアウト
+const QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+
+// IP アドレスから経路を広告している AS を返す DNS サービス（Team Cymru）
+const ORIGIN_ZONE_V4: &str = "origin.asn.cymru.com";
+const ORIGIN_ZONE_V6: &str = "origin6.asn.cymru.com";
+const ASN_ZONE: &str = "asn.cymru.com";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpAddressInfo {
+    pub ip_address: String,
+    // 逆引き（PTR）の名前
+    pub ptr_name: Option<String>,
+    // 経路を広告している AS と、その経路のプレフィックス・国・割り当て元の RIR
+    pub asn: Option<u32>,
+    pub as_name: Option<String>,
+    pub prefix: Option<String>,
+    pub country: Option<String>,
+    pub registry: Option<String>,
+    // 問い合わせに失敗した場合の理由（結果が見つからなかった場合は含めない）
+    pub error_messages: Vec<String>,
+}
+
+// システムの DNS サーバで逆引きと AS を問い合わせる（プライベートアドレスなどは AS を問い合わせない）
+pub async fn lookup_ip_info(ip: IpAddr) -> IpAddressInfo {
+    let mut info = IpAddressInfo {
+        ip_address: ip.to_string(),
+        ptr_name: None,
+        asn: None,
+        as_name: None,
+        prefix: None,
+        country: None,
+        registry: None,
+        error_messages: Vec::new(),
+    };
+    let Some(server) = crate::dns_failure::system_dns_servers()
+        .await
+        .into_iter()
+        .next()
+    else {
+        info.error_messages
+            .push("システムの DNS サーバを取得できません".to_string());
+        return info;
+    };
+
+    let is_global = match ip {
+        IpAddr::V4(v4) => crate::is_global_ipv4(&v4),
+        IpAddr::V6(v6) => crate::is_global_ipv6(&v6),
+    };
+    let ptr_name = dns::ptr_name(ip);
+    let origin_zone = if ip.is_ipv4() {
+        ORIGIN_ZONE_V4
+    } else {
+        ORIGIN_ZONE_V6
+    };
+    let origin_name = format!("{}.{}", dns::reversed_labels(ip), origin_zone);
+    let origin_query = async {
+        if is_global {
+            Some(query(server, &origin_name, RECORD_TYPE_TXT).await)
+        } else {
+            None
+        }
+    };
+    let (ptr, origin) = tokio::join!(query(server, &ptr_name, RECORD_TYPE_PTR), origin_query);
+
+    match ptr {
+        Ok(response) => info.ptr_name = first_record(&response, RECORD_TYPE_PTR),
+        Err(e) => info.error_messages.push(format!("逆引きに失敗: {}", e)),
+    }
+    match origin {
+        Some(Ok(response)) => {
+            // "13335 | 1.1.1.0/24 | AU | apnic | 2011-08-11"（複数の AS が広告している場合は先頭を使う）
+            if let Some(text) = first_record(&response, RECORD_TYPE_TXT) {
+                let fields = split_fields(&text);
+                info.asn = fields
+                    .first()
+                    .and_then(|f| f.split_whitespace().next())
+                    .and_then(|asn| asn.parse().ok());
+                info.prefix = fields.get(1).cloned();
+                info.country = fields.get(2).cloned();
+                info.registry = fields.get(3).cloned();
+            }
+        }
+        Some(Err(e)) => info
+            .error_messages
+            .push(format!("AS の問い合わせに失敗: {}", e)),
+        None => {}
+    }
+    if let Some(asn) = info.asn {
+        // "13335 | US | arin | 2010-07-14 | CLOUDFLARENET, US"
+        let name = format!("AS{}.{}", asn, ASN_ZONE);
+        match query(server, &name, RECORD_TYPE_TXT).await {
+            Ok(response) => {
+                info.as_name = first_record(&response, RECORD_TYPE_TXT)
+                    .and_then(|text| split_fields(&text).get(4).cloned());
+            }
+            Err(e) => info
+                .error_messages
+                .push(format!("AS 名の問い合わせに失敗: {}", e)),
+        }
+    }
+    info
+}
+
+async fn query(
+    server: SocketAddr,
+    name: &str,
+    record_type: u16,
+) -> Result<DirectQueryResponse, String> {
+    dns::direct_query(server, name, record_type, QUERY_TIMEOUT)
+        .await
+        .map_err(|e| match e {
+            DirectQueryError::Timeout => format!("DNS サーバ {} から応答がありません", server.ip()),
+            DirectQueryError::Failed(message) => message,
+        })
+}
+
+fn first_record(response: &DirectQueryResponse, record_type: u16) -> Option<String> {
+    response
+        .records
+        .iter()
+        .find(|r| r.record_type == record_type)
+        .map(|r| r.data.trim_end_matches('.').to_string())
+        .filter(|data| !data.is_empty())
+}
+
+fn split_fields(text: &str) -> Vec<String> {
+    text.split('|').map(|f| f.trim().to_string()).collect()
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::history::{record_history, HistoryKind};
+use crate::operations::{run_operation, OperationKind};
+use crate::process::run_command;
+
+// iperf3 のデフォルト値と上限
+const IPERF3_DEFAULT_PORT: u16 = 5201;
+const IPERF3_DEFAULT_DURATION_SECS: u64 = 5;
+const IPERF3_MAX_DURATION_SECS: u64 = 60;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Iperf3Result {
+    pub server: String,
+    pub port: u16,
+    pub reverse: bool,
+    pub ip_version: Option<u8>,
+    pub duration_secs: u64,
+    pub remote_host: Option<String>,
+    pub sent_bits_per_second: Option<f64>,
+    pub received_bits_per_second: Option<f64>,
+    pub retransmits: Option<u64>,
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+// iperf3 -J 出力の解析用の内部構造体
+#[derive(Deserialize, Default)]
+struct Iperf3Output {
+    #[serde(default)]
+    start: Option<Iperf3Start>,
+    #[serde(default)]
+    end: Option<Iperf3End>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+struct Iperf3Start {
+    #[serde(default)]
+    connected: Vec<Iperf3Connected>,
+}
+
+#[derive(Deserialize)]
+struct Iperf3Connected {
+    remote_host: String,
+}
+
+#[derive(Deserialize, Default)]
+struct Iperf3End {
+    #[serde(default)]
+    sum_sent: Option<Iperf3Sum>,
+    #[serde(default)]
+    sum_received: Option<Iperf3Sum>,
+}
+
+#[derive(Deserialize)]
+struct Iperf3Sum {
+    bits_per_second: f64,
+    #[serde(default)]
+    retransmits: Option<u64>,
+}
+
+#[tauri::command]
+pub async fn run_iperf3(
+    app: AppHandle,
+    server: String,
+    port: Option<u16>,
+    duration_secs: Option<u64>,
+    reverse: Option<bool>,
+    ip_version: Option<u8>,
+) -> Result<Iperf3Result, String> {
+    let server = crate::normalize_hostname(&server)?;
+
+    let port = port.unwrap_or(IPERF3_DEFAULT_PORT);
+    let duration_secs = duration_secs.unwrap_or(IPERF3_DEFAULT_DURATION_SECS);
+    if duration_secs == 0 || duration_secs > IPERF3_MAX_DURATION_SECS {
+        return Err(format!(
+            "測定時間は 1〜{} 秒の範囲で指定してください",
+            IPERF3_MAX_DURATION_SECS
+        ));
+    }
+    if let Some(v) = ip_version {
+        if v != 4 && v != 6 {
+            return Err("IPバージョンは 4 または 6 を指定してください".to_string());
+        }
+    }
+    let reverse = reverse.unwrap_or(false);
+
+    let mut args = vec![
+        "--client".to_string(),
+        server.clone(),
+        "--port".to_string(),
+        port.to_string(),
+        "--time".to_string(),
+        duration_secs.to_string(),
+        "--json".to_string(),
+    ];
+    if reverse {
+        args.push("--reverse".to_string());
+    }
+    match ip_version {
+        Some(4) => args.push("-4".to_string()),
+        Some(6) => args.push("-6".to_string()),
+        _ => {}
+    }
+
+    let target = format!("{}:{}", server, port);
+    run_operation(
+        &app,
+        OperationKind::Iperf3,
+        &target,
+        execute_iperf3(
+            app.clone(),
+            server,
+            port,
+            duration_secs,
+            reverse,
+            ip_version,
+            args,
+        ),
+    )
+    .await
+}
+
+async fn execute_iperf3(
+    app: AppHandle,
+    server: String,
+    port: u16,
+    duration_secs: u64,
+    reverse: bool,
+    ip_version: Option<u8>,
+    args: Vec<String>,
+) -> Result<Iperf3Result, String> {
+    let output = run_command("iperf3.exe", &args).await.map_err(|e| {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            "iperf3.exe が見つかりません。iperf3 をインストールし、PATH に追加してください".to_string()
+        } else {
+            format!("iperf3 実行失敗: {}", e)
+        }
+    })?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let parsed: Iperf3Output = serde_json::from_str(&stdout).unwrap_or_default();
+
+    let mut result = Iperf3Result {
+        server: server.clone(),
+        port,
+        reverse,
+        ip_version,
+        duration_secs,
+        remote_host: parsed
+            .start
+            .and_then(|s| s.connected.into_iter().next())
+            .map(|c| c.remote_host),
+        sent_bits_per_second: None,
+        received_bits_per_second: None,
+        retransmits: None,
+        success: false,
+        error_message: None,
+    };
+
+    if let Some(end) = parsed.end {
+        if let Some(sent) = end.sum_sent {
+            result.sent_bits_per_second = Some(sent.bits_per_second);
+            result.retransmits = sent.retransmits;
+        }
+        result.received_bits_per_second = end.sum_received.map(|r| r.bits_per_second);
+    }
+
+    if let Some(error) = parsed.error {
+        result.error_message = Some(format!("iperf3 エラー: {}", error));
+    } else if !output.status.success() {
+        let stderr = crate::decode_command_output(&output.stderr).trim().to_string();
+        result.error_message = Some(if stderr.is_empty() {
+            format!("iperf3 終了コード: {}", output.status.code().unwrap_or(-1))
+        } else {
+            format!("iperf3 エラー: {}", stderr)
+        });
+    } else if result.received_bits_per_second.is_none() {
+        result.error_message = Some("iperf3 の出力を解析できませんでした".to_string());
+    } else {
+        result.success = true;
+    }
+
+    if let Err(e) = record_history(&app, HistoryKind::Iperf3, &server, &result) {
+        eprintln!("Failed to record iperf3 history: {}", e);
+    }
+
+    Ok(result)
+}
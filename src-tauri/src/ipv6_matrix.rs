@@ -0,0 +1,211 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tokio::task::JoinSet;
+use url::Url;
+
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::HttpPingResult;
+
+// IPv6 に対応している主要サイト（名前, URL）
+const DUAL_STACK_SITES: [(&str, &str); 11] = [
+    ("Google", "https://www.google.com/"),
+    ("YouTube", "https://www.youtube.com/"),
+    ("Facebook", "https://www.facebook.com/"),
+    ("Instagram", "https://www.instagram.com/"),
+    ("Wikipedia", "https://www.wikipedia.org/"),
+    ("Cloudflare", "https://www.cloudflare.com/"),
+    ("Netflix", "https://www.netflix.com/"),
+    ("Microsoft", "https://www.microsoft.com/"),
+    ("Apple", "https://www.apple.com/"),
+    ("LinkedIn", "https://www.linkedin.com/"),
+    ("JPNIC", "https://www.nic.ad.jp/"),
+];
+
+// 1サイトあたりのタイムアウト（秒）
+const SITE_TIMEOUT_SECS: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Ipv6Diagnosis {
+    Healthy,
+    TargetSpecific,
+    NoAaaaResolution,
+    Ipv6Unreachable,
+    NoConnectivity,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Ipv6MatrixRow {
+    pub name: String,
+    pub url: String,
+    pub aaaa_resolved: bool,
+    pub ipv4_reachable: bool,
+    pub ipv6_reachable: bool,
+    pub ipv4: HttpPingResult,
+    pub ipv6: HttpPingResult,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Ipv6MatrixResult {
+    pub rows: Vec<Ipv6MatrixRow>,
+    pub aaaa_resolved_count: usize,
+    pub ipv4_reachable_count: usize,
+    pub ipv6_reachable_count: usize,
+    pub diagnosis: Ipv6Diagnosis,
+    pub findings: Vec<String>,
+}
+
+// 主要サイトの AAAA 解決と IPv6/IPv4 での HTTPS 到達性を一覧にし、IPv6 の不具合が特定サイトのみか全体かを判定
+#[tauri::command]
+pub async fn check_ipv6_reachability_matrix(
+    app: AppHandle,
+    ignore_tls_errors: Option<bool>,
+) -> Result<Ipv6MatrixResult, String> {
+    let target = format!("{} sites", DUAL_STACK_SITES.len());
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        &target,
+        execute_matrix_check(ignore_tls_errors.unwrap_or(false)),
+    )
+    .await
+}
+
+async fn execute_matrix_check(ignore_tls_errors: bool) -> Result<Ipv6MatrixResult, String> {
+    if ignore_tls_errors {
+        crate::log_security_warning("TLS証明書検証が無効化されています");
+    }
+
+    let mut tasks = JoinSet::new();
+    for (index, &(name, url)) in DUAL_STACK_SITES.iter().enumerate() {
+        tasks.spawn(async move { (index, check_site(name, url, ignore_tls_errors).await) });
+    }
+    let total = tasks.len();
+    let mut rows = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(row) = joined {
+            rows.push(row);
+        }
+        report_progress((total - tasks.len()) as f64 * 100.0 / total as f64);
+    }
+    rows.sort_by_key(|(index, _)| *index);
+    let rows: Vec<Ipv6MatrixRow> = rows.into_iter().map(|(_, row)| row).collect();
+
+    let aaaa_resolved_count = rows.iter().filter(|r| r.aaaa_resolved).count();
+    let ipv4_reachable_count = rows.iter().filter(|r| r.ipv4_reachable).count();
+    let ipv6_reachable_count = rows.iter().filter(|r| r.ipv6_reachable).count();
+    let (diagnosis, findings) = diagnose(&rows);
+
+    Ok(Ipv6MatrixResult {
+        rows,
+        aaaa_resolved_count,
+        ipv4_reachable_count,
+        ipv6_reachable_count,
+        diagnosis,
+        findings,
+    })
+}
+
+async fn check_site(name: &str, url: &str, ignore_tls_errors: bool) -> Ipv6MatrixRow {
+    let host = Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()))
+        .unwrap_or_default();
+    let dns_result = crate::resolve_dns(&host).await;
+
+    let request = crate::http_client::RequestOptions::default();
+
+    let (ipv4, ipv6) = tokio::join!(
+        crate::connect_to_ip_with_host(
+            url.to_string(),
+            &request,
+            &dns_result.ipv4_addresses,
+            &host,
+            ignore_tls_errors,
+            None,
+            false,
+            None,
+            SITE_TIMEOUT_SECS,
+        ),
+        crate::connect_to_ip_with_host(
+            url.to_string(),
+            &request,
+            &dns_result.ipv6_addresses,
+            &host,
+            ignore_tls_errors,
+            None,
+            false,
+            None,
+            SITE_TIMEOUT_SECS,
+        ),
+    );
+
+    // リダイレクトやエラーページでも HTTP 応答があれば到達できたとみなす
+    Ipv6MatrixRow {
+        name: name.to_string(),
+        url: url.to_string(),
+        aaaa_resolved: !dns_result.ipv6_addresses.is_empty(),
+        ipv4_reachable: ipv4.status_code.is_some(),
+        ipv6_reachable: ipv6.status_code.is_some(),
+        ipv4,
+        ipv6,
+    }
+}
+
+// 失敗の広がり方から原因の切り分けを判定
+fn diagnose(rows: &[Ipv6MatrixRow]) -> (Ipv6Diagnosis, Vec<String>) {
+    let mut findings = Vec::new();
+    let names = |filter: &dyn Fn(&Ipv6MatrixRow) -> bool| {
+        rows.iter()
+            .filter(|r| filter(r))
+            .map(|r| r.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let aaaa_resolved = rows.iter().filter(|r| r.aaaa_resolved).count();
+    let ipv4_reachable = rows.iter().filter(|r| r.ipv4_reachable).count();
+    let ipv6_reachable = rows.iter().filter(|r| r.ipv6_reachable).count();
+
+    let diagnosis = if ipv4_reachable == 0 && ipv6_reachable == 0 {
+        findings.push(
+            "IPv4・IPv6 ともにどのサイトにも接続できません。インターネット接続を確認してください"
+                .to_string(),
+        );
+        Ipv6Diagnosis::NoConnectivity
+    } else if aaaa_resolved == 0 {
+        findings.push(
+            "どのサイトの AAAA レコードも取得できません。DNS サーバが AAAA を返していないか、IPv6 アドレスが割り当てられていない可能性があります"
+                .to_string(),
+        );
+        Ipv6Diagnosis::NoAaaaResolution
+    } else if ipv6_reachable == 0 {
+        findings.push(
+            "AAAA レコードは取得できますが、IPv6 ではどのサイトにも接続できません。ルーターや回線の IPv6 設定に問題がある可能性があります"
+                .to_string(),
+        );
+        Ipv6Diagnosis::Ipv6Unreachable
+    } else if ipv6_reachable < aaaa_resolved {
+        findings.push(format!(
+            "一部のサイトのみ IPv6 で接続できません: {}（サイト側または経路上の問題の可能性があります）",
+            names(&|r| r.aaaa_resolved && !r.ipv6_reachable)
+        ));
+        Ipv6Diagnosis::TargetSpecific
+    } else {
+        Ipv6Diagnosis::Healthy
+    };
+
+    if aaaa_resolved > 0 && aaaa_resolved < rows.len() {
+        findings.push(format!(
+            "AAAA レコードを取得できないサイトがあります: {}",
+            names(&|r| !r.aaaa_resolved)
+        ));
+    }
+    if ipv4_reachable < rows.len() && diagnosis != Ipv6Diagnosis::NoConnectivity {
+        findings.push(format!(
+            "IPv4 で接続できないサイトがあります: {}",
+            names(&|r| !r.ipv4_reachable)
+        ));
+    }
+
+    (diagnosis, findings)
+}
@@ -0,0 +1,334 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use url::Url;
+
+use crate::http_client::{self, HttpMethod, RequestOptions};
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::stats::{compute_latency_stats, LatencyStats, PingSample};
+use crate::DnsResolution;
+
+// 測定時間（秒）と送信間隔（ミリ秒）の既定値と範囲
+const DEFAULT_DURATION_SECS: u64 = 60;
+const MIN_DURATION_SECS: u64 = 5;
+const MAX_DURATION_SECS: u64 = 3600;
+const DEFAULT_INTERVAL_MS: u64 = 200;
+const MIN_INTERVAL_MS: u64 = 50;
+const MAX_INTERVAL_MS: u64 = 10_000;
+
+// 1ファミリあたりの最大送信数
+const MAX_PROBES: u64 = 20_000;
+
+// 中央値をこの値（ミリ秒）以上上回った応答をスパイクとみなす
+const DEFAULT_SPIKE_THRESHOLD_MS: f64 = 50.0;
+
+// 送信する測定パケットの種類（HEAD はレート制限の設定に従い、枠が空くまで送信を待つ）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JitterProbe {
+    #[default]
+    Tcp,
+    Head,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JitterSample {
+    pub sent_at: String,
+    pub rtt_ms: Option<f64>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JitterSpike {
+    pub sent_at: String,
+    pub rtt_ms: f64,
+    // 中央値からの増加分
+    pub excess_ms: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JitterFamilyResult {
+    pub ip_address: Option<String>,
+    pub stats: LatencyStats,
+    // 連続する応答時間の差の平均と最大（小数点以下まで）
+    pub jitter_ms: Option<f64>,
+    pub max_jitter_ms: Option<f64>,
+    pub spikes: Vec<JitterSpike>,
+    pub samples: Vec<JitterSample>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JitterTestResult {
+    pub url: String,
+    pub probe: JitterProbe,
+    pub duration_secs: u64,
+    pub interval_ms: u64,
+    pub spike_threshold_ms: f64,
+    pub dns_resolution: DnsResolution,
+    pub ipv4: JitterFamilyResult,
+    pub ipv6: JitterFamilyResult,
+}
+
+// 一定時間、軽い測定（TCP 接続または HEAD）を短い間隔で送り続け、IPv4/IPv6 ごとのジッタと一時的な遅延の発生時刻を返す
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn run_jitter_test(
+    app: AppHandle,
+    url: String,
+    probe: Option<JitterProbe>,
+    duration_secs: Option<u64>,
+    interval_ms: Option<u64>,
+    spike_threshold_ms: Option<f64>,
+    ignore_tls_errors: Option<bool>,
+    timeout_secs: Option<u64>,
+) -> Result<JitterTestResult, String> {
+    let duration_secs = duration_secs.unwrap_or(DEFAULT_DURATION_SECS);
+    if !(MIN_DURATION_SECS..=MAX_DURATION_SECS).contains(&duration_secs) {
+        return Err(format!(
+            "測定時間は {} から {} 秒の範囲で指定してください",
+            MIN_DURATION_SECS, MAX_DURATION_SECS
+        ));
+    }
+    let interval_ms = interval_ms.unwrap_or(DEFAULT_INTERVAL_MS);
+    if !(MIN_INTERVAL_MS..=MAX_INTERVAL_MS).contains(&interval_ms) {
+        return Err(format!(
+            "送信間隔は {} から {} ミリ秒の範囲で指定してください",
+            MIN_INTERVAL_MS, MAX_INTERVAL_MS
+        ));
+    }
+    if duration_secs * 1000 / interval_ms > MAX_PROBES {
+        return Err(format!(
+            "送信回数が {} 回を超えます。測定時間を短くするか送信間隔を長くしてください",
+            MAX_PROBES
+        ));
+    }
+    let spike_threshold_ms = spike_threshold_ms.unwrap_or(DEFAULT_SPIKE_THRESHOLD_MS);
+    if !(spike_threshold_ms.is_finite() && spike_threshold_ms > 0.0) {
+        return Err("スパイクのしきい値は 0 より大きい値を指定してください".to_string());
+    }
+    let timeout_secs = timeout_secs.unwrap_or(http_client::DEFAULT_TIMEOUT_SECS);
+    http_client::validate_timeout_secs(timeout_secs)?;
+    crate::validate_url(&url)?;
+
+    let target = url.clone();
+    run_operation(
+        &app,
+        OperationKind::Ping,
+        &target,
+        execute_jitter_test(
+            url,
+            probe.unwrap_or_default(),
+            duration_secs,
+            interval_ms,
+            spike_threshold_ms,
+            ignore_tls_errors.unwrap_or(false),
+            timeout_secs,
+        ),
+    )
+    .await
+}
+
+async fn execute_jitter_test(
+    url: String,
+    probe: JitterProbe,
+    duration_secs: u64,
+    interval_ms: u64,
+    spike_threshold_ms: f64,
+    ignore_tls_errors: bool,
+    timeout_secs: u64,
+) -> Result<JitterTestResult, String> {
+    if ignore_tls_errors && probe == JitterProbe::Head {
+        crate::log_security_warning("TLS証明書検証が無効化されています");
+    }
+
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+
+    // 名前解決の揺らぎを含めないよう、解決は最初の1回のみ
+    let dns_resolution = crate::resolve_dns(&host).await;
+    let prober = Prober {
+        url: &url,
+        host: &host,
+        port: parsed_url.port(),
+        tcp_port: parsed_url.port_or_known_default().unwrap_or(443),
+        probe,
+        ignore_tls_errors,
+        timeout_secs,
+    };
+    let duration = Duration::from_secs(duration_secs);
+    let interval = Duration::from_millis(interval_ms);
+    let (ipv4_samples, ipv6_samples) = tokio::join!(
+        prober.run(dns_resolution.ipv4_addresses.first(), duration, interval),
+        prober.run(dns_resolution.ipv6_addresses.first(), duration, interval),
+    );
+
+    Ok(JitterTestResult {
+        url,
+        probe,
+        duration_secs,
+        interval_ms,
+        spike_threshold_ms,
+        ipv4: jitter_family(
+            dns_resolution.ipv4_addresses.first(),
+            ipv4_samples,
+            spike_threshold_ms,
+        ),
+        ipv6: jitter_family(
+            dns_resolution.ipv6_addresses.first(),
+            ipv6_samples,
+            spike_threshold_ms,
+        ),
+        dns_resolution,
+    })
+}
+
+struct Prober<'a> {
+    url: &'a str,
+    host: &'a str,
+    port: Option<u16>,
+    tcp_port: u16,
+    probe: JitterProbe,
+    ignore_tls_errors: bool,
+    timeout_secs: u64,
+}
+
+impl Prober<'_> {
+    // 開始時刻を基準にした間隔で送信（応答が間隔より遅れた回は飛ばす）
+    async fn run(
+        &self,
+        ip_address: Option<&String>,
+        duration: Duration,
+        interval: Duration,
+    ) -> Vec<JitterSample> {
+        let Some(ip_address) = ip_address else {
+            return Vec::new();
+        };
+        let started = Instant::now();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut samples = Vec::new();
+
+        loop {
+            ticker.tick().await;
+            let elapsed = started.elapsed();
+            if elapsed >= duration {
+                break;
+            }
+            report_progress(elapsed.as_secs_f64() * 100.0 / duration.as_secs_f64());
+            let sent_at = crate::now_rfc3339();
+            let (rtt_ms, error_message) = match self.probe {
+                JitterProbe::Tcp => self.tcp_connect(ip_address).await,
+                JitterProbe::Head => self.head(ip_address).await,
+            };
+            samples.push(JitterSample {
+                sent_at,
+                rtt_ms,
+                error_message,
+            });
+        }
+        samples
+    }
+
+    async fn tcp_connect(&self, ip_address: &str) -> (Option<f64>, Option<String>) {
+        let ip: IpAddr = match ip_address.parse() {
+            Ok(ip) => ip,
+            Err(_) => return (None, Some(format!("無効なIPアドレス: {}", ip_address))),
+        };
+        let start = Instant::now();
+        match tokio::time::timeout(
+            Duration::from_secs(self.timeout_secs),
+            crate::dry_run::connect_tcp(SocketAddr::new(ip, self.tcp_port)),
+        )
+        .await
+        {
+            Ok(Ok(_)) => (Some(start.elapsed().as_secs_f64() * 1000.0), None),
+            Ok(Err(e)) => (None, Some(format!("接続エラー: {}", e))),
+            Err(_) => (None, Some("接続がタイムアウトしました".to_string())),
+        }
+    }
+
+    async fn head(&self, ip_address: &String) -> (Option<f64>, Option<String>) {
+        let request = RequestOptions {
+            method: HttpMethod::Head,
+            ..RequestOptions::default()
+        };
+        let result = crate::connect_to_ip_with_host(
+            self.url.to_string(),
+            &request,
+            std::slice::from_ref(ip_address),
+            self.host,
+            self.ignore_tls_errors,
+            self.port,
+            false,
+            None,
+            self.timeout_secs,
+        )
+        .await;
+        // ステータスコードに関係なく、応答が返れば経路は生きているとみなす
+        match (result.status_code, result.timings) {
+            (Some(_), Some(timings)) => (Some(timings.total_ms), None),
+            _ => (None, result.error_message),
+        }
+    }
+}
+
+// アドレスがなく測定していないファミリは 0 件の結果とする
+fn jitter_family(
+    ip_address: Option<&String>,
+    samples: Vec<JitterSample>,
+    spike_threshold_ms: f64,
+) -> JitterFamilyResult {
+    let ping_samples: Vec<PingSample> = samples
+        .iter()
+        .map(|s| PingSample {
+            recorded_at: Local::now(),
+            success: s.rtt_ms.is_some(),
+            response_time_ms: s.rtt_ms.map(|ms| ms.round() as u64),
+        })
+        .collect();
+
+    let rtts: Vec<f64> = samples.iter().filter_map(|s| s.rtt_ms).collect();
+    let deltas: Vec<f64> = rtts.windows(2).map(|w| (w[1] - w[0]).abs()).collect();
+    let jitter_ms = (!deltas.is_empty()).then(|| deltas.iter().sum::<f64>() / deltas.len() as f64);
+    let max_jitter_ms = deltas.iter().copied().reduce(f64::max);
+
+    let spikes = match median(&rtts) {
+        Some(baseline) => samples
+            .iter()
+            .filter_map(|s| {
+                let rtt_ms = s.rtt_ms?;
+                (rtt_ms - baseline >= spike_threshold_ms).then(|| JitterSpike {
+                    sent_at: s.sent_at.clone(),
+                    rtt_ms,
+                    excess_ms: rtt_ms - baseline,
+                })
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    JitterFamilyResult {
+        ip_address: ip_address.cloned(),
+        stats: compute_latency_stats(&ping_samples),
+        jitter_ms,
+        max_jitter_ms,
+        spikes,
+        samples,
+    }
+}
+
+fn median(values: &[f64]) -> Option<f64> {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    match sorted.len() {
+        0 => None,
+        n if n % 2 == 1 => Some(sorted[n / 2]),
+        n => Some((sorted[n / 2 - 1] + sorted[n / 2]) / 2.0),
+    }
+}
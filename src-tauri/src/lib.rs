@@ -1,10 +1,95 @@
 ﻿use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
-use std::time::Instant;
 use std::process::{Command, Stdio};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use url::Url;
 use encoding_rs::SHIFT_JIS;
+use tauri::AppHandle;
+
+mod adaptive_timeout;
+mod alt_svc;
+mod anomaly;
+mod app_info;
+mod baseline;
+mod batch;
+mod benchmark;
+mod capture;
+mod clipboard;
+mod content_sniff;
+mod continuous_ping;
+mod curl;
+mod diagnose;
+mod dns;
+mod dns_failure;
+mod dns_hijack;
+mod dns_latency;
+mod dns_round_robin;
+mod download_speed;
+mod dry_run;
+mod env_diff;
+mod env_monitor;
+mod ephemeral_ports;
+mod global_ip_check;
+mod happy_eyeballs;
+mod har;
+mod header_assertion;
+mod health_check;
+mod history;
+mod http_client;
+mod ip_echo;
+mod ip_history;
+mod ip_info;
+mod iperf;
+mod ipv6_matrix;
+mod jitter;
+mod maintenance;
+mod monitor_group;
+mod ncsi;
+mod ntlm;
+mod ocsp;
+mod operations;
+mod per_adapter;
+mod port_check;
+mod powershell;
+mod prefix_policy;
+mod process;
+mod proxy;
+mod proxy_detect;
+mod rate_limit;
+mod receiver;
+mod redact;
+mod redirect;
+mod response_assertion;
+mod result_stream;
+mod series;
+mod sni_filter;
+mod socks_dns;
+mod speedtest;
+mod stats;
+mod support_bundle;
+mod target_import;
+mod targets;
+mod template;
+mod tls;
+mod tls_chain;
+mod tls_extended;
+mod tls_intercept;
+mod tls_versions;
+mod traceroute;
+mod transition;
+mod webhook;
+mod windows;
+
+use continuous_ping::ContinuousPing;
+use env_monitor::EnvironmentMonitor;
+use history::{record_history, HistoryKind, HistoryStore};
+use ip_echo::IpEchoEndpoint;
+use ip_history::IpHistoryStore;
+use maintenance::MaintenanceStore;
+use monitor_group::MonitorGroups;
+use operations::{run_operation, OperationKind, OperationRegistry};
+use webhook::WebhookStore;
+use windows::ResultWindows;
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -42,16 +127,60 @@ pub struct EnvironmentCheckResult {
     pub ipv4_global_ip: Option<GlobalIPInfo>,
     pub ipv6_global_ip: Option<GlobalIPInfo>,
     pub dns_servers: Vec<DnsServerInfo>,
+    #[serde(default)]
+    pub dns_server_latencies: Vec<dns_latency::DnsServerLatency>,
+    #[serde(default)]
+    pub offline: bool,
+    #[serde(default)]
+    pub windows_connectivity: Option<ncsi::WindowsConnectivity>,
+    #[serde(default)]
+    pub transition_interfaces: Option<transition::TransitionInterfaces>,
+    #[serde(default)]
+    pub prefix_policies: Option<prefix_policy::PrefixPolicyTable>,
+    // WinINET / WinHTTP のプロキシ設定（直接接続の確認が失敗する原因の確認用）
+    #[serde(default)]
+    pub proxy_settings: Option<proxy::SystemProxySettings>,
+    // PowerShell がポリシーで制限されているか（制限されている場合は ipconfig で取得する）
+    #[serde(default)]
+    pub powershell: Option<powershell::PowerShellStatus>,
+    // アダプタと DNS サーバの情報をどこから取得したか
+    #[serde(default)]
+    pub data_sources: EnvironmentDataSources,
+    // 段階ごとの状態（失敗した段階のみ rerun_stage で再実行できる）
+    #[serde(default)]
+    pub stages: Vec<EnvironmentStage>,
+    // 各段階のメッセージをまとめた画面表示用の一覧
     pub error_messages: Vec<String>,
 }
 
+// 環境情報の取得元
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DataSource {
+    PowerShell,
+    Ipconfig,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct EnvironmentDataSources {
+    pub adapters: Option<DataSource>,
+    pub dns_servers: Option<DataSource>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DnsResolution {
     pub ipv4_addresses: Vec<String>,
     pub ipv6_addresses: Vec<String>,
+    #[serde(default)]
+    pub failure: Option<dns_failure::DnsFailure>,
+    #[serde(default)]
+    pub lookup_ms: Option<f64>,
+    // DNS サーバへ直接問い合わせて得た CNAME の連鎖と TTL（CDN の別名かどうか、キャッシュされる時間の確認用）
+    #[serde(default)]
+    pub details: Option<dns::DnsAnswerDetails>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HttpPingResult {
     pub url: String,
     pub ip_address: Option<String>,
@@ -60,25 +189,268 @@ pub struct HttpPingResult {
     pub success: bool,
     pub error_message: Option<String>,
     pub verbose_log: Option<String>,
+    // save_verbose_log を指定した場合の詳細ログを構造化したもの（接続先・TLS・証明書・ヘッダ）
+    #[serde(default)]
+    pub verbose_info: Option<http_client::VerboseInfo>,
+    #[serde(default)]
+    pub started_at: Option<String>,
+    #[serde(default)]
+    pub finished_at: Option<String>,
+    #[serde(default)]
+    pub timings: Option<http_client::HttpTimings>,
+    #[serde(default)]
+    pub alt_svc: Option<String>,
+    // 実際に使われた HTTP のバージョン（ALPN で選択されたもの）
+    #[serde(default)]
+    pub http_version: Option<http_client::HttpVersion>,
+    // HTTPS の場合にネゴシエーションされた TLS のバージョンと暗号スイート
+    #[serde(default)]
+    pub tls_version: Option<String>,
+    #[serde(default)]
+    pub cipher_suite: Option<String>,
+    // 受信した応答本文のバイト数と受信速度（バイト/秒、ファミリごとの回線速度の目安）
+    #[serde(default)]
+    pub downloaded_bytes: Option<u64>,
+    #[serde(default)]
+    pub download_bytes_per_sec: Option<f64>,
+    // max_download_bytes で受信を打ち切った場合は true
+    #[serde(default)]
+    pub download_truncated: bool,
+    // Content-Type と本文の先頭から判定した実際の種類（200 でも中継機器のエラーページでないかの確認用）
+    #[serde(default)]
+    pub content: Option<content_sniff::ResponseContent>,
+    // アサーションの評価用（結果や履歴には含めない）
+    #[serde(skip)]
+    pub response_headers: Vec<(String, String)>,
+    // capture_headers を指定した場合の応答ヘッダ（Server, Via, X-Cache など CDN やプロキシの確認用）
+    #[serde(default)]
+    pub captured_headers: Option<BTreeMap<String, String>>,
+    #[serde(default)]
+    pub header_assertions: Vec<header_assertion::HeaderAssertionResult>,
+    // 本文のアサーションの評価用の応答本文の先頭（結果や履歴には含めない）
+    #[serde(skip)]
+    pub response_body_prefix: Vec<u8>,
+    // expected_body・expected_body_regex を指定した場合の結果（失敗すると success は false）
+    #[serde(default)]
+    pub body_assertions: Vec<response_assertion::BodyAssertionResult>,
+    // retries を指定した場合の各試行の結果（最後の試行が status_code などの値）
+    #[serde(default)]
+    pub attempts: Vec<PingAttempt>,
+    // follow_redirects を指定した場合にたどったリダイレクト先（status_code と success は最終的な応答のもの）
+    #[serde(default)]
+    pub redirects: Vec<redirect::RedirectHop>,
+    // 同じリクエストを curl で再現するためのコマンドライン（接続したアドレスを --resolve で固定）
+    #[serde(default)]
+    pub curl_command: Option<curl::CurlCommand>,
+    // reuse_connection を指定した場合の、同じ接続で送信した 2 回目のリクエストの結果
+    #[serde(default)]
+    pub second_request: Option<http_client::SecondRequest>,
+    // 期限の確認用の接続先のサーバ証明書（結果や履歴には含めない）
+    #[serde(skip)]
+    pub certificate: Option<http_client::PeerCertificate>,
+    // 測定は成功したが注意が必要な事項（証明書の期限が近いなど）
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    // プロキシ経由の場合に認証した方式（認証を求められなかった場合は None）
+    #[serde(default)]
+    pub proxy_auth: Option<proxy::ProxyAuthMethod>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PingAttempt {
+    pub attempt: u32,
+    pub started_at: Option<String>,
+    pub status_code: Option<u16>,
+    pub response_time_ms: Option<u64>,
+    pub success: bool,
+    pub error_message: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HttpPingDualResult {
     pub url: String,
+    #[serde(default)]
+    pub url_template: Option<String>,
+    #[serde(default)]
+    pub method: http_client::HttpMethod,
     pub dns_resolution: DnsResolution,
     pub ipv4: HttpPingResult,
     pub ipv6: HttpPingResult,
+    #[serde(default)]
+    pub packet_capture: Option<capture::PacketCapture>,
+    // 全アドレスを確認した場合の IP アドレスごとの結果（ipv4/ipv6 は各ファミリの先頭アドレスの結果）
+    #[serde(default)]
+    pub address_results: Vec<HttpPingResult>,
+    #[serde(default)]
+    pub alt_svc_probes: Vec<alt_svc::AltSvcProbe>,
+    // 経由したプロキシ（認証情報は含めない）
+    #[serde(default)]
+    pub proxy: Option<String>,
+    // プロキシが接続先の名前を解決した場合の結果（ipv4/ipv6 は測定しない）
+    #[serde(default)]
+    pub via_proxy: Option<HttpPingResult>,
+    // URL に IP アドレスを指定した場合の逆引きの名前と AS
+    #[serde(default)]
+    pub ip_info: Option<ip_info::IpAddressInfo>,
+    // host_override を指定した場合に送信した Host（SNI はポートを除いた名前）
+    #[serde(default)]
+    pub host_override: Option<String>,
+    // 国際化ドメイン名の場合の Unicode と A ラベル（xn--）のホスト名（url は A ラベルの形）
+    #[serde(default)]
+    pub idn: Option<IdnHostname>,
 }
 
-// IP取得用の内部構造体
-#[derive(Deserialize)]
-struct IpResponse {
-    client_host: String,
-    datetime_jst: String,
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdnHostname {
+    pub unicode: String,
+    pub ascii: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvironmentStageKind {
+    Adapters,
+    Ipv4Connectivity,
+    Ipv6Connectivity,
+    DnsResolution,
+    DnsServers,
+    WindowsConnectivity,
+    TransitionInterfaces,
+    PrefixPolicies,
+    ProxySettings,
+}
+
+impl EnvironmentStageKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            EnvironmentStageKind::Adapters => "adapters",
+            EnvironmentStageKind::Ipv4Connectivity => "ipv4_connectivity",
+            EnvironmentStageKind::Ipv6Connectivity => "ipv6_connectivity",
+            EnvironmentStageKind::DnsResolution => "dns_resolution",
+            EnvironmentStageKind::DnsServers => "dns_servers",
+            EnvironmentStageKind::WindowsConnectivity => "windows_connectivity",
+            EnvironmentStageKind::TransitionInterfaces => "transition_interfaces",
+            EnvironmentStageKind::PrefixPolicies => "prefix_policies",
+            EnvironmentStageKind::ProxySettings => "proxy_settings",
+        }
+    }
+}
+
+// インターネット接続が必要な段階（アドレスがない場合は省略）
+const INTERNET_STAGES: [EnvironmentStageKind; 3] = [
+    EnvironmentStageKind::Ipv4Connectivity,
+    EnvironmentStageKind::Ipv6Connectivity,
+    EnvironmentStageKind::DnsResolution,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StageStatus {
+    Ok,
+    Failed,
+    Skipped,
+    Timeout,
+}
+
+// 環境確認の各段階の状態と開始・終了時刻（RFC 3339）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EnvironmentStage {
+    pub stage: EnvironmentStageKind,
+    pub status: StageStatus,
+    pub started_at: String,
+    pub finished_at: String,
+    pub message: Option<String>,
+}
+
+impl EnvironmentStage {
+    fn new(
+        stage: EnvironmentStageKind,
+        started_at: String,
+        finished_at: String,
+        outcome: StageOutcome,
+    ) -> Self {
+        EnvironmentStage {
+            stage,
+            status: outcome.status,
+            started_at,
+            finished_at,
+            message: outcome.message,
+        }
+    }
+}
+
+// 1段階分の実行結果
+struct StageOutcome {
+    status: StageStatus,
+    message: Option<String>,
+}
+
+impl StageOutcome {
+    fn ok() -> Self {
+        StageOutcome {
+            status: StageStatus::Ok,
+            message: None,
+        }
+    }
+
+    fn failed(message: String) -> Self {
+        StageOutcome {
+            status: StageStatus::Failed,
+            message: Some(message),
+        }
+    }
+
+    fn skipped(message: &str) -> Self {
+        StageOutcome {
+            status: StageStatus::Skipped,
+            message: Some(message.to_string()),
+        }
+    }
+
+    fn timeout(message: &str) -> Self {
+        StageOutcome {
+            status: StageStatus::Timeout,
+            message: Some(message.to_string()),
+        }
+    }
+}
+
+// 現在時刻（ミリ秒精度の RFC 3339）
+fn now_rfc3339() -> String {
+    chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
+}
+
+// 環境確認での DNS 解決確認のタイムアウト
+const DNS_CHECK_TIMEOUT_SECS: u64 = 3;
+
+// IP エコーサービスの応答本文を保持する上限
+const IP_ECHO_MAX_BODY_BYTES: usize = 64 * 1024;
+
+// 全アドレスを確認する場合の1ファミリあたりの上限
+const MAX_ADDRESSES_PER_FAMILY: usize = 16;
+
+// 失敗した場合の再試行の上限と、再試行までの待ち時間（ミリ秒、毎回 2 倍にする）の既定値と上限
+const MAX_RETRIES: u32 = 10;
+const DEFAULT_RETRY_DELAY_MS: u64 = 500;
+const MAX_RETRY_DELAY_MS: u64 = 60_000;
+
+// 証明書の有効期限の警告を出す残り日数の既定値と上限
+const DEFAULT_CERT_EXPIRY_WARNING_DAYS: u32 = 14;
+const MAX_CERT_EXPIRY_WARNING_DAYS: u32 = 365;
+
+// 中止できるよう操作として実行（中止時は PowerShell などの子プロセスも終了する）
 #[tauri::command]
-async fn environment_check() -> Result<EnvironmentCheckResult, String> {
+async fn environment_check(app: AppHandle) -> Result<EnvironmentCheckResult, String> {
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        "environment",
+        execute_environment_check(app.clone()),
+    )
+    .await
+}
+
+async fn execute_environment_check(app: AppHandle) -> Result<EnvironmentCheckResult, String> {
     let mut result = EnvironmentCheckResult {
         adapters: vec![],
         ipv4_connectivity: false,
@@ -88,100 +460,721 @@ async fn environment_check() -> Result<EnvironmentCheckResult, String> {
         ipv4_global_ip: None,
         ipv6_global_ip: None,
         dns_servers: vec![],
+        dns_server_latencies: vec![],
+        offline: false,
+        windows_connectivity: None,
+        transition_interfaces: None,
+        prefix_policies: None,
+        proxy_settings: None,
+        powershell: None,
+        data_sources: EnvironmentDataSources::default(),
+        stages: vec![],
         error_messages: vec![],
     };
 
     // ネットワークアダプタの取得
-    match get_network_interfaces() {
-        Ok(adapters) => {
-            result.adapters = adapters;
+    let stage = run_environment_stage(&app, EnvironmentStageKind::Adapters, &mut result).await;
+    result.stages.push(stage);
+
+    // アドレスを持つアダプタが1つもなければ外部への確認は省略
+    if is_without_address(&result) {
+        let now = now_rfc3339();
+        for stage in INTERNET_STAGES {
+            result.stages.push(EnvironmentStage::new(
+                stage,
+                now.clone(),
+                now.clone(),
+                StageOutcome::skipped(
+                    "IPアドレスが割り当てられたネットワークアダプタがないため省略しました",
+                ),
+            ));
         }
-        Err(e) => {
-            result
-                .error_messages
-                .push(format!("ネットワークアダプタの取得に失敗: {}", e));
+    } else {
+        let stages = check_internet_stages(&app, &mut result).await;
+        result.stages.extend(stages);
+    }
+
+    // DNSサーバ情報、Windows 自身の接続判定（NCSI）、Teredo などの移行技術、アドレス選択ポリシー、プロキシ設定
+    for stage in [
+        EnvironmentStageKind::DnsServers,
+        EnvironmentStageKind::WindowsConnectivity,
+        EnvironmentStageKind::TransitionInterfaces,
+        EnvironmentStageKind::PrefixPolicies,
+        EnvironmentStageKind::ProxySettings,
+    ] {
+        let stage = run_environment_stage(&app, stage, &mut result).await;
+        result.stages.push(stage);
+    }
+
+    summarize_environment_stages(&mut result);
+
+    // グローバルIPの変化を追跡するため観測結果を記録
+    if let Err(e) = ip_history::record_global_ips(
+        &app,
+        result.ipv4_global_ip.as_ref(),
+        result.ipv6_global_ip.as_ref(),
+    ) {
+        eprintln!("Failed to record global IP history: {}", e);
+    }
+
+    // 前後比較用に履歴へ記録（失敗しても結果は返す）
+    if let Err(e) = record_history(&app, HistoryKind::Environment, "environment", &result) {
+        eprintln!("Failed to record environment history: {}", e);
+    }
+
+    Ok(result)
+}
+
+// 環境確認の結果のうち、指定した段階のみ再実行して更新（他の段階の結果はそのまま返す）
+#[tauri::command]
+async fn rerun_stage(
+    app: AppHandle,
+    stage: EnvironmentStageKind,
+    result: EnvironmentCheckResult,
+) -> Result<EnvironmentCheckResult, String> {
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        stage.as_str(),
+        execute_rerun_stage(app.clone(), stage, result),
+    )
+    .await
+}
+
+async fn execute_rerun_stage(
+    app: AppHandle,
+    stage: EnvironmentStageKind,
+    mut result: EnvironmentCheckResult,
+) -> Result<EnvironmentCheckResult, String> {
+    let rerun = run_environment_stage(&app, stage, &mut result).await;
+    match result.stages.iter_mut().find(|s| s.stage == stage) {
+        Some(existing) => *existing = rerun,
+        None => result.stages.push(rerun),
+    }
+    summarize_environment_stages(&mut result);
+
+    if matches!(
+        stage,
+        EnvironmentStageKind::Ipv4Connectivity | EnvironmentStageKind::Ipv6Connectivity
+    ) {
+        if let Err(e) = ip_history::record_global_ips(
+            &app,
+            result.ipv4_global_ip.as_ref(),
+            result.ipv6_global_ip.as_ref(),
+        ) {
+            eprintln!("Failed to record global IP history: {}", e);
         }
     }
 
-    // IPv4接続確認（グローバルIP取得で兼ねる）
-    match fetch_global_ip_info("https://getipv4.0nyx.net/json", 2).await {
-        Ok(info) => {
-            result.ipv4_connectivity = true;
-            result.ipv4_global_ip = Some(info);
+    Ok(result)
+}
+
+// 1つの段階を実行して結果に反映（失敗した場合はその段階のデータを空にする）
+async fn run_environment_stage(
+    app: &AppHandle,
+    stage: EnvironmentStageKind,
+    result: &mut EnvironmentCheckResult,
+) -> EnvironmentStage {
+    let started_at = now_rfc3339();
+    let outcome = match stage {
+        EnvironmentStageKind::Adapters => match get_network_interfaces().await {
+            Ok((adapters, source)) => {
+                result.adapters = adapters;
+                result.data_sources.adapters = Some(source);
+                result.powershell = Some(powershell::status().await.clone());
+                StageOutcome::ok()
+            }
+            Err(e) => {
+                result.adapters = vec![];
+                StageOutcome::failed(format!("ネットワークアダプタの取得に失敗: {}", e))
+            }
+        },
+        EnvironmentStageKind::Ipv4Connectivity | EnvironmentStageKind::Ipv6Connectivity => {
+            let ip_version = if stage == EnvironmentStageKind::Ipv4Connectivity {
+                4
+            } else {
+                6
+            };
+            let echo_endpoints = ip_echo::load_ip_echo_endpoints(app);
+            let fetched = fetch_global_ip(&echo_endpoints, ip_version, 2).await;
+            apply_global_ip(result, ip_version, fetched)
         }
-        Err(e) => {
-            result.ipv4_connectivity = false;
-            result.error_messages.push(format!("IPv4グローバルIP取得に失敗: {}", e));
+        EnvironmentStageKind::DnsResolution => {
+            let checked = tokio::time::timeout(
+                tokio::time::Duration::from_secs(DNS_CHECK_TIMEOUT_SECS),
+                check_dns_resolution(),
+            )
+            .await;
+            apply_dns_resolution(result, checked)
         }
-    }
+        EnvironmentStageKind::DnsServers => {
+            match tokio::time::timeout(tokio::time::Duration::from_secs(5), get_dns_servers_async())
+                .await
+            {
+                Ok(Ok((dns_info, source))) => {
+                    result.data_sources.dns_servers = Some(source);
+                    // 各 DNS サーバの応答時間を測定して順位付け
+                    result.dns_server_latencies = dns_latency::rank_dns_servers(&dns_info).await;
+                    result.dns_servers = dns_info;
+                    StageOutcome::ok()
+                }
+                Ok(Err(e)) => {
+                    result.dns_servers = vec![];
+                    result.dns_server_latencies = vec![];
+                    StageOutcome::failed(format!("DNSサーバ情報取得に失敗: {}", e))
+                }
+                Err(_) => {
+                    result.dns_servers = vec![];
+                    result.dns_server_latencies = vec![];
+                    StageOutcome::timeout("DNSサーバ情報取得がタイムアウトしました")
+                }
+            }
+        }
+        EnvironmentStageKind::WindowsConnectivity => match ncsi::get_windows_connectivity().await {
+            Ok(mut windows) => {
+                windows.discrepancies = ncsi::find_discrepancies(
+                    &windows,
+                    result.ipv4_connectivity,
+                    result.ipv6_connectivity,
+                );
+                result.windows_connectivity = Some(windows);
+                StageOutcome::ok()
+            }
+            Err(e) => {
+                result.windows_connectivity = None;
+                StageOutcome::failed(e)
+            }
+        },
+        EnvironmentStageKind::TransitionInterfaces => {
+            match transition::get_transition_interfaces().await {
+                Ok(mut state) => {
+                    transition::find_transition_issues(&mut state, &result.adapters);
+                    result.transition_interfaces = Some(state);
+                    StageOutcome::ok()
+                }
+                Err(e) => {
+                    result.transition_interfaces = None;
+                    StageOutcome::failed(e)
+                }
+            }
+        }
+        EnvironmentStageKind::PrefixPolicies => match prefix_policy::get_prefix_policies().await {
+            Ok(table) => {
+                result.prefix_policies = Some(table);
+                StageOutcome::ok()
+            }
+            Err(e) => {
+                result.prefix_policies = None;
+                StageOutcome::failed(e)
+            }
+        },
+        EnvironmentStageKind::ProxySettings => match proxy::detect_system_proxy().await {
+            Ok(settings) => {
+                result.proxy_settings = Some(settings);
+                StageOutcome::ok()
+            }
+            Err(e) => {
+                result.proxy_settings = None;
+                StageOutcome::failed(e)
+            }
+        },
+    };
+    EnvironmentStage::new(stage, started_at, now_rfc3339(), outcome)
+}
+
+// インターネット接続が必要な確認（IPv4/IPv6 と DNS を並列に実行し、待ち時間の連鎖を避ける）
+async fn check_internet_stages(
+    app: &AppHandle,
+    result: &mut EnvironmentCheckResult,
+) -> Vec<EnvironmentStage> {
+    let echo_endpoints = ip_echo::load_ip_echo_endpoints(app);
+    let started_at = now_rfc3339();
+
+    let (
+        (ipv4_result, ipv4_finished_at),
+        (ipv6_result, ipv6_finished_at),
+        (dns_result, dns_finished_at),
+    ) = tokio::join!(
+        async { (fetch_global_ip(&echo_endpoints, 4, 2).await, now_rfc3339()) },
+        async { (fetch_global_ip(&echo_endpoints, 6, 2).await, now_rfc3339()) },
+        async {
+            let checked = tokio::time::timeout(
+                tokio::time::Duration::from_secs(DNS_CHECK_TIMEOUT_SECS),
+                check_dns_resolution(),
+            )
+            .await;
+            (checked, now_rfc3339())
+        },
+    );
+
+    vec![
+        EnvironmentStage::new(
+            EnvironmentStageKind::Ipv4Connectivity,
+            started_at.clone(),
+            ipv4_finished_at,
+            apply_global_ip(result, 4, ipv4_result),
+        ),
+        EnvironmentStage::new(
+            EnvironmentStageKind::Ipv6Connectivity,
+            started_at.clone(),
+            ipv6_finished_at,
+            apply_global_ip(result, 6, ipv6_result),
+        ),
+        EnvironmentStage::new(
+            EnvironmentStageKind::DnsResolution,
+            started_at,
+            dns_finished_at,
+            apply_dns_resolution(result, dns_result),
+        ),
+    ]
+}
 
-    // IPv6接続確認（グローバルIP取得で兼ねる）
-    match fetch_global_ip_info("https://getipv6.0nyx.net/json", 2).await {
+// IPv4/IPv6接続確認（グローバルIP取得で兼ねる）
+fn apply_global_ip(
+    result: &mut EnvironmentCheckResult,
+    ip_version: u8,
+    fetched: Result<GlobalIPInfo, String>,
+) -> StageOutcome {
+    let (connectivity, global_ip) = if ip_version == 4 {
+        (&mut result.ipv4_connectivity, &mut result.ipv4_global_ip)
+    } else {
+        (&mut result.ipv6_connectivity, &mut result.ipv6_global_ip)
+    };
+    match fetched {
         Ok(info) => {
-            result.ipv6_connectivity = true;
-            result.ipv6_global_ip = Some(info);
+            *connectivity = true;
+            *global_ip = Some(info);
+            StageOutcome::ok()
         }
         Err(e) => {
-            result.ipv6_connectivity = false;
-            // IPv4が成功している場合は、IPv6エラーを表示しない
-            if !result.ipv4_connectivity {
-                result.error_messages.push(format!("IPv6グローバルIP取得に失敗: {}", e));
-            }
+            *connectivity = false;
+            *global_ip = None;
+            StageOutcome::failed(format!("IPv{}グローバルIP取得に失敗: {}", ip_version, e))
         }
     }
+}
 
-    // DNS解決確認
-    match check_dns_resolution().await {
-        Ok(resolved) => {
-            result.dns_resolution = resolved;
-        }
-        Err(e) => {
-            result
-                .error_messages
-                .push(format!("DNS解決確認に失敗: {}", e));
+// DNS解決確認
+fn apply_dns_resolution(
+    result: &mut EnvironmentCheckResult,
+    checked: Result<Result<bool, String>, tokio::time::error::Elapsed>,
+) -> StageOutcome {
+    result.dns_resolution = false;
+    match checked {
+        Ok(Ok(true)) => {
+            result.dns_resolution = true;
+            StageOutcome::ok()
         }
+        Ok(Ok(false)) => StageOutcome::failed("example.com の名前解決に失敗しました".to_string()),
+        Ok(Err(e)) => StageOutcome::failed(format!("DNS解決確認に失敗: {}", e)),
+        Err(_) => StageOutcome::timeout("DNS解決確認がタイムアウトしました"),
     }
+}
 
-    // DNSサーバ情報の取得（タイムアウト付き）
-    match tokio::time::timeout(
-        tokio::time::Duration::from_secs(5),
-        get_dns_servers_async(),
-    )
-    .await
+// アダプタを取得でき、どのアダプタにもアドレスがない場合
+fn is_without_address(result: &EnvironmentCheckResult) -> bool {
+    result
+        .stages
+        .iter()
+        .any(|s| s.stage == EnvironmentStageKind::Adapters && s.status == StageStatus::Ok)
+        && result.adapters.iter().all(|a| a.ip_addresses.is_empty())
+}
+
+// 各段階の結果から接続可否の判定と、画面表示用のメッセージ一覧を作り直す
+fn summarize_environment_stages(result: &mut EnvironmentCheckResult) {
+    let without_address = is_without_address(result);
+    let internet_checked = result
+        .stages
+        .iter()
+        .any(|s| INTERNET_STAGES.contains(&s.stage) && s.status != StageStatus::Skipped);
+    // いずれの確認も通らなければオフラインとみなす
+    let unreachable = internet_checked
+        && !result.ipv4_connectivity
+        && !result.ipv6_connectivity
+        && !result.dns_resolution;
+
+    result.offline = without_address || unreachable;
+    result.internet_available =
+        (result.ipv4_connectivity || result.ipv6_connectivity) && result.dns_resolution;
+
+    let mut error_messages: Vec<String> = result
+        .stages
+        .iter()
+        .filter(|s| s.status == StageStatus::Failed || s.status == StageStatus::Timeout)
+        // IPv4が成功している場合は、IPv6エラーを表示しない
+        .filter(|s| {
+            !(s.stage == EnvironmentStageKind::Ipv6Connectivity && result.ipv4_connectivity)
+        })
+        .filter_map(|s| s.message.clone())
+        .collect();
+    if without_address {
+        error_messages.push(
+            "IPアドレスが割り当てられたネットワークアダプタがありません。LANケーブルやWi-Fiの接続を確認してください"
+                .to_string(),
+        );
+    } else if unreachable {
+        error_messages.push(
+            "インターネットに接続できません。ルータやモデムの状態、プロキシ設定を確認してください"
+                .to_string(),
+        );
+    }
+    // 直接接続できない原因がプロキシの経由が必須のネットワークであることに気付けるようにする
+    let proxy_configured = result
+        .proxy_settings
+        .as_ref()
+        .is_some_and(|p| p.proxy_configured);
+    if proxy_configured
+        && internet_checked
+        && !result.ipv4_connectivity
+        && !result.ipv6_connectivity
     {
-        Ok(Ok(dns_info)) => {
-            result.dns_servers = dns_info;
+        error_messages.push(
+            "システムにプロキシが設定されています。直接接続の確認が失敗したのは、プロキシの経由が必須のネットワークのためである可能性があります"
+                .to_string(),
+        );
+    }
+    result.error_messages = error_messages;
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn ping_http_dual(
+    app: AppHandle,
+    url: String,
+    ignore_tls_errors: bool,
+    save_verbose_log: bool,
+    capture_packets: Option<bool>,
+    test_all_addresses: Option<bool>,
+    probe_alt_svc: Option<bool>,
+    timeout_secs: Option<u64>,
+    method: Option<String>,
+    header_assertions: Option<Vec<String>>,
+    headers: Option<HashMap<String, String>>,
+    body: Option<String>,
+    content_type: Option<String>,
+    follow_redirects: Option<bool>,
+    http_version: Option<String>,
+    capture_headers: Option<bool>,
+    max_download_bytes: Option<u64>,
+    cert_expiry_warning_days: Option<u32>,
+    tls_version: Option<String>,
+    sni: Option<String>,
+    user_agent: Option<String>,
+    proxy: Option<String>,
+    proxy_username: Option<String>,
+    proxy_password: Option<String>,
+    proxy_auth: Option<String>,
+    proxy_credential: Option<String>,
+    use_system_proxy: Option<bool>,
+    proxy_pin_address: Option<bool>,
+    expected_status: Option<String>,
+    expected_body: Option<String>,
+    expected_body_regex: Option<String>,
+    retries: Option<u32>,
+    retry_delay_ms: Option<u64>,
+    host_override: Option<String>,
+    reuse_connection: Option<bool>,
+) -> Result<HttpPingDualResult, String> {
+    // 対話的な測定では短く、衛星回線などでは長く指定できるようにする
+    let timeout_secs = timeout_secs.unwrap_or(http_client::DEFAULT_TIMEOUT_SECS);
+    http_client::validate_timeout_secs(timeout_secs)?;
+    // 軽量な HEAD や API への POST でも測定できるようにする（既定は GET）
+    let method = match method {
+        Some(method) => http_client::HttpMethod::parse(&method)?,
+        None => http_client::HttpMethod::Get,
+    };
+    // 認証が必要な API や Host の上書き、ヘルスチェック用のヘッダを送れるようにする
+    let mut headers = http_client::parse_custom_headers(headers.unwrap_or_default())?;
+    // WAF が curl などの User-Agent を拒否する場合に、ブラウザなどの値で測定できるようにする（既定はアプリの値）
+    let user_agent = user_agent
+        .map(|ua| http_client::normalize_user_agent(&ua))
+        .transpose()?;
+    if user_agent.is_some() && headers.iter().any(|(name, _)| name == "user-agent") {
+        return Err("User-Agent はヘッダと user_agent の一方のみで指定してください".to_string());
+    }
+    // DNS を変更せずに特定のバックエンドを確認できるよう、IP アドレスの URL に送る Host と SNI の名前をまとめて指定できるようにする
+    let mut sni = sni.map(|sni| normalize_sni(&sni)).transpose()?;
+    let host_override = host_override
+        .map(|value| parse_host_override(&value))
+        .transpose()?;
+    if let Some((name, host_header)) = &host_override {
+        if sni.is_some() || headers.iter().any(|(name, _)| name == "host") {
+            return Err("host_override は sni・Host ヘッダと同時に指定できません".to_string());
         }
-        Ok(Err(e)) => {
-            result
-                .error_messages
-                .push(format!("DNSサーバ情報取得に失敗: {}", e));
+        sni = Some(name.clone());
+        headers.push(("host".to_string(), host_header.clone()));
+    }
+    // すべての通信がプロキシを経由する必要がある環境でも測定できるようにする
+    let use_system_proxy = use_system_proxy.unwrap_or(false);
+    if proxy.is_some() && use_system_proxy {
+        return Err("proxy と use_system_proxy は同時に指定できません".to_string());
+    }
+    let has_proxy_auth = proxy_username.is_some()
+        || proxy_password.is_some()
+        || proxy_credential.is_some()
+        || proxy_auth.is_some();
+    if has_proxy_auth && proxy.is_none() && !use_system_proxy {
+        return Err(
+            "proxy_username・proxy_password・proxy_credential・proxy_auth は proxy または use_system_proxy とあわせて指定してください"
+                .to_string(),
+        );
+    }
+    // 認証が必要なプロキシでも、NTLM などの方式や資格情報マネージャーに保存した認証情報で測定できるようにする
+    let proxy_auth = proxy_auth
+        .map(|auth| proxy::ProxyAuthMethod::parse(&auth))
+        .transpose()?;
+    let (proxy_username, proxy_password) = match proxy_credential {
+        Some(_) if proxy_username.is_some() || proxy_password.is_some() => {
+            return Err(
+                "proxy_credential は proxy_username・proxy_password と同時に指定できません"
+                    .to_string(),
+            );
         }
-        Err(_) => {
-            result
-                .error_messages
-                .push("DNSサーバ情報取得がタイムアウトしました".to_string());
+        Some(target) => {
+            let (username, password) = proxy::read_stored_credential(&target).await?;
+            (Some(username), Some(password))
         }
+        None => (proxy_username, proxy_password),
+    };
+    let proxy = match proxy {
+        Some(proxy) => Some(
+            proxy::ProxyConfig::parse(&proxy, proxy_username.clone(), proxy_password.clone())?
+                .with_auth(proxy_auth)?,
+        ),
+        None => None,
+    };
+    // プロキシ経由でもファミリごとに比較できるよう、ローカルで解決したアドレスへの接続を依頼できるようにする（既定はプロキシが名前を解決）
+    let proxy_pin_address = proxy_pin_address.unwrap_or(false);
+    if proxy_pin_address && proxy.is_none() && !use_system_proxy {
+        return Err(
+            "proxy_pin_address は proxy または use_system_proxy とあわせて指定してください"
+                .to_string(),
+        );
     }
+    if proxy_pin_address
+        && proxy
+            .as_ref()
+            .is_some_and(|p| p.kind == proxy::ProxyKind::Socks5h)
+    {
+        return Err(
+            "socks5h はプロキシが名前を解決するため proxy_pin_address を指定できません（socks5 を指定してください）"
+                .to_string(),
+        );
+    }
+    let mut request = http_client::RequestOptions {
+        method,
+        headers,
+        body: None,
+        follow_redirects: follow_redirects.unwrap_or(false),
+        // 同じホストで HTTP/1.1 と HTTP/2 の遅延を比較できるようにする（既定は ALPN で選択）
+        http_version: http_version
+            .map(|v| http_client::HttpVersion::parse(&v))
+            .transpose()?,
+        // 大きなファイルの URL でも、先頭の一定量だけで受信速度を確認できるようにする
+        max_download_bytes,
+        // 経路上の機器による TLS 1.2 へのダウングレードなどを確認できるよう、バージョンを固定できるようにする
+        tls_version: tls_version
+            .map(|v| http_client::TlsVersion::parse(&v))
+            .transpose()?,
+        // CDN のエッジの IP アドレスを URL に指定し、特定のサイトの名前で TLS 接続できるようにする（Host は headers で指定）
+        sni,
+        proxy,
+        user_agent,
+        body_prefix_bytes: 0,
+        // 接続と TLS の確立にかかる時間と、サーバの処理時間を切り分けられるよう、同じ接続で 2 回送信できるようにする
+        reuse_connection: reuse_connection.unwrap_or(false),
+    };
+    if max_download_bytes == Some(0) {
+        return Err("max_download_bytes は 1 以上を指定してください".to_string());
+    }
+    // JSON の本文を付けた POST にのみ応答するサービスも測定できるようにする
+    match body {
+        Some(body) => request.set_body(body, content_type)?,
+        None if content_type.is_some() => {
+            return Err("content_type は本文とあわせて指定してください".to_string());
+        }
+        None => {}
+    }
+    // 意図した CDN/WAF を経由しているかを応答ヘッダで確認（測定前に構文エラーを返す）
+    let header_assertions =
+        header_assertion::parse_header_assertions(&header_assertions.unwrap_or_default())?;
+    // 認証が必要で 401 を返すエンドポイントなども正常と判定できるよう、成功の条件を指定できるようにする
+    let response_assertions = response_assertion::ResponseAssertions::parse(
+        expected_status.as_deref(),
+        expected_body,
+        expected_body_regex.as_deref(),
+    )?;
+    if response_assertions.needs_body() {
+        request.body_prefix_bytes = response_assertion::MAX_BODY_ASSERTION_BYTES;
+    }
+    // 一時的な失敗で失敗と報告しないよう、待ち時間を倍にしながら再試行できるようにする
+    let retry = RetryPolicy {
+        retries: retries.unwrap_or(0),
+        delay_ms: retry_delay_ms.unwrap_or(DEFAULT_RETRY_DELAY_MS),
+    };
+    if retry.retries > MAX_RETRIES {
+        return Err(format!(
+            "retries は 0 から {} の範囲で指定してください",
+            MAX_RETRIES
+        ));
+    }
+    if !(1..=MAX_RETRY_DELAY_MS).contains(&retry.delay_ms) {
+        return Err(format!(
+            "retry_delay_ms は 1 から {} の範囲で指定してください",
+            MAX_RETRY_DELAY_MS
+        ));
+    }
+    if retries.is_none() && retry_delay_ms.is_some() {
+        return Err("retry_delay_ms は retries とあわせて指定してください".to_string());
+    }
+    // 証明書の期限切れを事前に気付けるよう、残り日数が少ない場合に警告する（0 で無効）
+    let cert_expiry_warning_days =
+        cert_expiry_warning_days.unwrap_or(DEFAULT_CERT_EXPIRY_WARNING_DAYS);
+    if cert_expiry_warning_days > MAX_CERT_EXPIRY_WARNING_DAYS {
+        return Err(format!(
+            "証明書の期限の警告日数は 0 から {} 日の範囲で指定してください",
+            MAX_CERT_EXPIRY_WARNING_DAYS
+        ));
+    }
+    // {{timestamp}} などの変数を含む URL・ヘッダ・本文はリクエストごとに展開
+    let url_template = template::is_template(&url).then(|| url.clone());
+    let (url, mut request) = template::expand_request(&url, &request)?;
+    // Windows のプロキシ設定（除外する宛先を含む）を展開後の URL に適用
+    if use_system_proxy {
+        let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+        request.proxy = proxy::system_proxy(&parsed_url)
+            .await?
+            .map(|p| {
+                p.with_credentials(proxy_username, proxy_password)?
+                    .with_auth(proxy_auth)
+            })
+            .transpose()?;
+    }
+    let target = url.clone();
+    run_operation(
+        &app,
+        OperationKind::Ping,
+        &target,
+        execute_ping_http_dual(
+            app.clone(),
+            url,
+            url_template,
+            PingDualOptions {
+                ignore_tls_errors,
+                save_verbose_log,
+                capture_packets: capture_packets.unwrap_or(false),
+                test_all_addresses: test_all_addresses.unwrap_or(false),
+                probe_alt_svc: probe_alt_svc.unwrap_or(false),
+                capture_headers: capture_headers.unwrap_or(false),
+                cert_expiry_warning_days,
+                timeout_secs,
+                request,
+                header_assertions,
+                response_assertions,
+                retry,
+                host_override: host_override.map(|(_, host_header)| host_header),
+                proxy_pin_address,
+            },
+        ),
+    )
+    .await
+}
 
-    // インターネット接続判定
-    result.internet_available = (result.ipv4_connectivity || result.ipv6_connectivity)
-        && result.dns_resolution;
-
-    Ok(result)
+// ping_http_dual 以外から測定する場合の既定のオプション（ベースライン・一括測定用）
+fn default_ping_options() -> PingDualOptions {
+    PingDualOptions {
+        ignore_tls_errors: false,
+        save_verbose_log: false,
+        capture_packets: false,
+        test_all_addresses: false,
+        probe_alt_svc: false,
+        capture_headers: false,
+        cert_expiry_warning_days: DEFAULT_CERT_EXPIRY_WARNING_DAYS,
+        timeout_secs: http_client::DEFAULT_TIMEOUT_SECS,
+        request: http_client::RequestOptions::default(),
+        header_assertions: Vec::new(),
+        response_assertions: response_assertion::ResponseAssertions::default(),
+        retry: RetryPolicy::default(),
+        host_override: None,
+        proxy_pin_address: false,
+    }
 }
 
-#[tauri::command]
-async fn ping_http_dual(
+// URL・ヘッダ・本文の変数を展開して測定（手動の測定と同様に履歴にも記録する）
+async fn ping_with_options(
+    app: AppHandle,
     url: String,
+    mut options: PingDualOptions,
+) -> Result<HttpPingDualResult, String> {
+    let url_template = template::is_template(&url).then(|| url.clone());
+    let (url, request) = template::expand_request(&url, &options.request)?;
+    options.request = request;
+    execute_ping_http_dual(app, url, url_template, options).await
+}
+
+// ping_http_dual の測定オプション
+struct PingDualOptions {
     ignore_tls_errors: bool,
     save_verbose_log: bool,
+    capture_packets: bool,
+    test_all_addresses: bool,
+    probe_alt_svc: bool,
+    capture_headers: bool,
+    cert_expiry_warning_days: u32,
+    timeout_secs: u64,
+    request: http_client::RequestOptions,
+    header_assertions: Vec<header_assertion::HeaderAssertion>,
+    response_assertions: response_assertion::ResponseAssertions,
+    retry: RetryPolicy,
+    host_override: Option<String>,
+    // プロキシを経由する場合もローカルで解決したアドレスにファミリごとに接続する
+    proxy_pin_address: bool,
+}
+
+// 失敗した場合の再試行（retries が 0 の場合は再試行せず、attempts も返さない）
+#[derive(Debug, Clone, Copy, Default)]
+struct RetryPolicy {
+    retries: u32,
+    delay_ms: u64,
+}
+
+impl RetryPolicy {
+    // attempt 回目の試行の後の待ち時間
+    fn delay_after(&self, attempt: u32) -> tokio::time::Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(16);
+        tokio::time::Duration::from_millis(
+            self.delay_ms.saturating_mul(factor).min(MAX_RETRY_DELAY_MS),
+        )
+    }
+}
+
+async fn execute_ping_http_dual(
+    app: AppHandle,
+    url: String,
+    url_template: Option<String>,
+    options: PingDualOptions,
 ) -> Result<HttpPingDualResult, String> {
+    let PingDualOptions {
+        ignore_tls_errors,
+        save_verbose_log,
+        capture_packets,
+        test_all_addresses,
+        probe_alt_svc,
+        capture_headers,
+        cert_expiry_warning_days,
+        timeout_secs,
+        mut request,
+        header_assertions,
+        response_assertions,
+        retry,
+        host_override,
+        proxy_pin_address,
+    } = options;
     if ignore_tls_errors {
         log_security_warning("TLS証明書検証が無効化されています");
     }
 
-    validate_url(&url)?;
+    let url = normalize_url(&url)?;
 
     let parsed_url = match Url::parse(&url) {
         Ok(u) => u,
@@ -196,37 +1189,311 @@ async fn ping_http_dual(
     // ホスト名の検証（セキュリティ）
     validate_hostname(host)?;
 
+    // IP アドレスの URL では名前解決を行わず、SNI には Host ヘッダの名前を使う（名前の指定がなければ SNI なし）
+    let target_ip = ip_literal(host);
+    let mut ip_literal_warning = None;
+    if target_ip.is_some() && parsed_url.scheme() == "https" && request.sni.is_none() {
+        let host_header_name = request
+            .headers
+            .iter()
+            .find(|(name, _)| name == "host")
+            .and_then(|(_, value)| Url::parse(&format!("https://{}", value)).ok())
+            .and_then(|u| u.host_str().and_then(|h| normalize_sni(h).ok()));
+        match host_header_name {
+            Some(name) => request.sni = Some(name),
+            None => {
+                ip_literal_warning = Some(
+                    "URL に IP アドレスを指定したため SNI を送信していません。証明書の名前が一致しない場合は sni と Host ヘッダでサイトの名前を指定してください"
+                        .to_string(),
+                );
+            }
+        }
+    }
+
+    // プロキシが接続先の名前を解決する場合は、ローカルの名前解決とファミリごとの接続を行わない
+    let resolve_via_proxy = !proxy_pin_address
+        && request
+            .proxy
+            .as_ref()
+            .is_some_and(proxy::ProxyConfig::resolves_remotely);
+
     // DNS名前解決
-    let dns_result = resolve_dns(host).await;
+    let mut dns_result = if resolve_via_proxy {
+        DnsResolution {
+            ipv4_addresses: Vec::new(),
+            ipv6_addresses: Vec::new(),
+            failure: None,
+            lookup_ms: None,
+            details: None,
+        }
+    } else {
+        resolve_dns(host).await
+    };
     let ipv4_addresses = dns_result.ipv4_addresses.clone();
     let ipv6_addresses = dns_result.ipv6_addresses.clone();
+    let addresses_per_family = if test_all_addresses {
+        MAX_ADDRESSES_PER_FAMILY
+    } else {
+        1
+    };
 
-    // IPv4/IPv6への並列接続試行
-    let (ipv4_result, ipv6_result) = tokio::join!(
-        connect_to_ip_with_host(
-            url.clone(),
-            &ipv4_addresses,
-            host,
-            ignore_tls_errors,
-            parsed_url.port(),
-            save_verbose_log,
-        ),
-        connect_to_ip_with_host(
-            url.clone(),
-            &ipv6_addresses,
+    // 詳細調査用に接続先へのパケットをキャプチャ（開始できなくても測定は行う）
+    let capture_targets: Vec<&str> = ipv4_addresses
+        .iter()
+        .take(addresses_per_family)
+        .chain(ipv6_addresses.iter().take(addresses_per_family))
+        .map(String::as_str)
+        .collect();
+    let capture_port = parsed_url.port_or_known_default().unwrap_or(443);
+    let capture_session = if capture_packets {
+        Some(capture::start_capture(&app, &capture_targets, capture_port).await)
+    } else {
+        None
+    };
+
+    // IPv4/IPv6への並列接続試行（プロキシが名前を解決する場合は 1 回のみ送信）
+    let (mut ipv4_result, mut ipv6_result, mut via_proxy) = if resolve_via_proxy {
+        let via_proxy = connect_with_retries(
+            &url,
+            &request,
+            None,
             host,
             ignore_tls_errors,
             parsed_url.port(),
             save_verbose_log,
-        ),
-    );
+            timeout_secs,
+            &response_assertions,
+            retry,
+        )
+        .await;
+        let unmeasured = |ip_version: u8| {
+            unmeasured_result(
+                &url,
+                format!(
+                    "プロキシが接続先の名前を解決するため、IPv{} では個別に測定していません（結果は via_proxy）",
+                    ip_version
+                ),
+            )
+        };
+        (unmeasured(4), unmeasured(6), Some(via_proxy))
+    } else {
+        let (ipv4_result, ipv6_result) = tokio::join!(
+            connect_with_retries(
+                &url,
+                &request,
+                Some(&ipv4_addresses),
+                host,
+                ignore_tls_errors,
+                parsed_url.port(),
+                save_verbose_log,
+                timeout_secs,
+                &response_assertions,
+                retry,
+            ),
+            connect_with_retries(
+                &url,
+                &request,
+                Some(&ipv6_addresses),
+                host,
+                ignore_tls_errors,
+                parsed_url.port(),
+                save_verbose_log,
+                timeout_secs,
+                &response_assertions,
+                retry,
+            ),
+        );
+        (ipv4_result, ipv6_result, None)
+    };
+
+    // ラウンドロビン DNS の背後の一部のサーバ障害を見つけるため、2件目以降のアドレスも順に測定
+    let mut address_results = Vec::new();
+    if test_all_addresses {
+        let (ipv4_rest, ipv6_rest) = tokio::join!(
+            ping_remaining_addresses(
+                &url,
+                &request,
+                &ipv4_addresses,
+                host,
+                ignore_tls_errors,
+                parsed_url.port(),
+                save_verbose_log,
+                timeout_secs,
+            ),
+            ping_remaining_addresses(
+                &url,
+                &request,
+                &ipv6_addresses,
+                host,
+                ignore_tls_errors,
+                parsed_url.port(),
+                save_verbose_log,
+                timeout_secs,
+            ),
+        );
+        for (first, rest) in [(&ipv4_result, ipv4_rest), (&ipv6_result, ipv6_rest)] {
+            if first.ip_address.is_some() {
+                address_results.push(first.clone());
+            }
+            address_results.extend(rest);
+        }
+    }
+
+    // 名前解決は両ファミリ共通の1回のため、同じ所要時間を内訳に含める
+    for ping_result in [&mut ipv4_result, &mut ipv6_result]
+        .into_iter()
+        .chain(address_results.iter_mut())
+        .chain(via_proxy.iter_mut())
+    {
+        if let Some(timings) = ping_result.timings.as_mut() {
+            timings.dns_lookup_ms = dns_result.lookup_ms;
+        }
+        if ping_result.started_at.is_some() {
+            ping_result.curl_command = Some(curl::ping_command(
+                &parsed_url,
+                &request,
+                ping_result.ip_address.as_deref(),
+                ignore_tls_errors,
+                timeout_secs,
+            ));
+        }
+        response_assertions.apply(ping_result);
+        // 応答を受信できた結果のみヘッダのアサーションを評価
+        if ping_result.status_code.is_some() {
+            ping_result.header_assertions = header_assertion::evaluate_header_assertions(
+                &header_assertions,
+                &ping_result.response_headers,
+            );
+            // 詳細ログを解析しなくても CDN やプロキシの情報を表示できるよう、応答ヘッダを返す
+            if capture_headers {
+                ping_result.captured_headers =
+                    Some(http_client::header_map(&ping_result.response_headers));
+            }
+        }
+        if let Some(warning) =
+            certificate_expiry_warning(ping_result.certificate.as_ref(), cert_expiry_warning_days)
+        {
+            ping_result.warnings.push(warning);
+        }
+        if let Some(mismatch) = ping_result.content.as_ref().and_then(|c| c.mismatch.clone()) {
+            ping_result.warnings.push(mismatch);
+        }
+    }
+    // 片方のファミリだけ TLS の終端装置などを経由している場合に気付けるようにする
+    if let Some(warning) = tls_negotiation_mismatch(&ipv4_result, &ipv6_result) {
+        ipv4_result.warnings.push(warning.clone());
+        ipv6_result.warnings.push(warning);
+    }
+    if let Some(warning) = ip_literal_warning {
+        for ping_result in [&mut ipv4_result, &mut ipv6_result]
+            .into_iter()
+            .chain(address_results.iter_mut())
+            .chain(via_proxy.iter_mut())
+            .filter(|r| r.started_at.is_some())
+        {
+            ping_result.warnings.push(warning.clone());
+        }
+    }
 
-    Ok(HttpPingDualResult {
+    // 広告された代替サービス（HTTP/3 など）に実際に接続できるか確認
+    let mut alt_svc_probes = Vec::new();
+    if probe_alt_svc {
+        let origin_port = parsed_url.port_or_known_default().unwrap_or(443);
+        for (ip_version, ping_result) in [(4, &ipv4_result), (6, &ipv6_result)] {
+            let (Some(alt_svc), Some(ip_address)) = (&ping_result.alt_svc, &ping_result.ip_address)
+            else {
+                continue;
+            };
+            for service in alt_svc::parse_alt_svc(alt_svc) {
+                alt_svc_probes.push(
+                    alt_svc::probe_alt_service(
+                        &url,
+                        host,
+                        origin_port,
+                        ip_address,
+                        ip_version,
+                        service,
+                        ignore_tls_errors,
+                        request.effective_user_agent(),
+                    )
+                    .await,
+                );
+            }
+        }
+    }
+
+    let packet_capture = match capture_session {
+        Some(Ok(session)) => Some(session.stop().await),
+        Some(Err(e)) => Some(capture::failed_capture(&capture_targets, capture_port, e)),
+        None => None,
+    };
+
+    // 測定に影響しないよう、接続を終えてから CNAME の連鎖と TTL（IP アドレスの場合は逆引きと AS）を確認
+    // プロキシが名前を解決する場合は、直接の問い合わせができない環境のため行わない
+    let ip_info = match target_ip.filter(|_| !resolve_via_proxy) {
+        Some(ip) => Some(ip_info::lookup_ip_info(ip).await),
+        None => None,
+    };
+    if !resolve_via_proxy {
+        dns_result.details = lookup_dns_details(host, &dns_result).await;
+    }
+
+    let result = HttpPingDualResult {
         url,
+        url_template,
+        method: request.method,
         dns_resolution: dns_result,
         ipv4: ipv4_result,
         ipv6: ipv6_result,
-    })
+        packet_capture,
+        address_results,
+        alt_svc_probes,
+        proxy: request.proxy.as_ref().map(|p| p.display()),
+        via_proxy,
+        ip_info,
+        host_override,
+        idn: idn_hostname(host),
+    };
+
+    // 測定履歴に記録（テンプレートの場合は展開前の URL で集計できるようにする）
+    let history_target = result.url_template.as_deref().unwrap_or(&result.url);
+    if let Err(e) = record_history(&app, HistoryKind::Ping, history_target, &result) {
+        eprintln!("Failed to record ping history: {}", e);
+    }
+
+    Ok(result)
+}
+
+// 各ファミリの2件目以降のアドレスに順に接続（同時に接続すると互いの遅延に影響するため）
+#[allow(clippy::too_many_arguments)]
+async fn ping_remaining_addresses(
+    url: &str,
+    request: &http_client::RequestOptions,
+    ip_addresses: &[String],
+    host: &str,
+    ignore_tls_errors: bool,
+    port: Option<u16>,
+    save_verbose_log: bool,
+    timeout_secs: u64,
+) -> Vec<HttpPingResult> {
+    let mut results = Vec::new();
+    for ip_address in ip_addresses.iter().take(MAX_ADDRESSES_PER_FAMILY).skip(1) {
+        results.push(
+            perform_http_request(
+                url,
+                request,
+                Some(ip_address),
+                host,
+                ignore_tls_errors,
+                port,
+                save_verbose_log,
+                None,
+                timeout_secs,
+            )
+            .await,
+        );
+    }
+    results
 }
 
 // DNS名前解決を実行（tokio を使用・非ブロッキング）
@@ -236,9 +1503,37 @@ async fn resolve_dns(host: &str) -> DnsResolution {
 
     let mut ipv4_addresses = Vec::new();
     let mut ipv6_addresses = Vec::new();
+    let mut os_error = None;
+
+    // IP アドレスはリゾルバに渡さず、そのファミリのアドレスとして扱う
+    if let Some(ip) = ip_literal(host) {
+        match ip {
+            IpAddr::V4(_) => ipv4_addresses.push(ip.to_string()),
+            IpAddr::V6(_) => ipv6_addresses.push(ip.to_string()),
+        }
+        return DnsResolution {
+            ipv4_addresses,
+            ipv6_addresses,
+            failure: None,
+            lookup_ms: None,
+            details: None,
+        };
+    }
+
+    // ドライランでは名前解決せず、以降の操作の引数を確認できるよう文書用のアドレスを返す
+    if dry_run::intercept(dry_run::ActionKind::Dns, host, Some("OS のリゾルバ".to_string())) {
+        return DnsResolution {
+            ipv4_addresses: vec![dry_run::PLACEHOLDER_IPV4.to_string()],
+            ipv6_addresses: vec![dry_run::PLACEHOLDER_IPV6.to_string()],
+            failure: None,
+            lookup_ms: None,
+            details: None,
+        };
+    }
 
     let socket_addr = format!("{}:80", host);
 
+    let lookup_started = std::time::Instant::now();
     match lookup_host(&socket_addr).await {
         Ok(addrs) => {
             for addr in addrs {
@@ -260,188 +1555,374 @@ async fn resolve_dns(host: &str) -> DnsResolution {
         }
         Err(e) => {
             eprintln!("DNS resolution failed for {}: {:?}", host, e);
+            os_error = Some(e.to_string());
         }
     }
+    let lookup_ms = lookup_started.elapsed().as_secs_f64() * 1000.0;
+
+    // 解決できなかった場合は DNS サーバへ直接問い合わせて原因を分類
+    let failure = if ipv4_addresses.is_empty() && ipv6_addresses.is_empty() {
+        Some(dns_failure::classify_dns_failure(host, os_error).await)
+    } else {
+        None
+    };
 
     DnsResolution {
         ipv4_addresses,
         ipv6_addresses,
+        failure,
+        lookup_ms: Some(lookup_ms),
+        details: None,
+    }
+}
+
+// URL のホストが IP アドレス（[IPv6] を含む）の場合はそのアドレス
+fn ip_literal(host: &str) -> Option<IpAddr> {
+    host.strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host)
+        .parse()
+        .ok()
+}
+
+// 名前解決の詳細を取得（DNS サーバ一覧の取得に時間がかかるため、測定後に1回だけ行う）
+async fn lookup_dns_details(
+    host: &str,
+    resolution: &DnsResolution,
+) -> Option<dns::DnsAnswerDetails> {
+    if resolution.failure.is_some() || ip_literal(host).is_some() {
+        return None;
+    }
+    let server = dns_failure::system_dns_servers().await.into_iter().next()?;
+    match dns::query_answer_details(server, host, tokio::time::Duration::from_secs(2)).await {
+        Ok(details) => Some(details),
+        Err(e) => {
+            eprintln!("Failed to query DNS details for {}: {}", host, e);
+            None
+        }
+    }
+}
+
+// 失敗した場合は待ち時間を倍にしながら最初のアドレスへの接続を再試行し、各試行の結果を attempts に含める
+// （期待するステータスや本文の指定も含めて成否を判定する、アドレスがない場合は再試行しない）
+// 再試行するのは接続・タイムアウト・TLS のエラーと、一時的な問題を示すステータス（408・429・5xx）のみ
+#[allow(clippy::too_many_arguments)]
+async fn connect_with_retries(
+    url: &str,
+    request: &http_client::RequestOptions,
+    ip_addresses: Option<&[String]>,
+    host: &str,
+    ignore_tls_errors: bool,
+    port: Option<u16>,
+    save_verbose_log: bool,
+    timeout_secs: u64,
+    response_assertions: &response_assertion::ResponseAssertions,
+    retry: RetryPolicy,
+) -> HttpPingResult {
+    let mut attempts = Vec::new();
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let mut result = match ip_addresses {
+            Some(ip_addresses) => {
+                connect_to_ip_with_host(
+                    url.to_string(),
+                    request,
+                    ip_addresses,
+                    host,
+                    ignore_tls_errors,
+                    port,
+                    save_verbose_log,
+                    None,
+                    timeout_secs,
+                )
+                .await
+            }
+            None => {
+                perform_http_request(
+                    url,
+                    request,
+                    None,
+                    host,
+                    ignore_tls_errors,
+                    port,
+                    save_verbose_log,
+                    None,
+                    timeout_secs,
+                )
+                .await
+            }
+        };
+        if retry.retries == 0 {
+            return result;
+        }
+        response_assertions.apply(&mut result);
+        attempts.push(PingAttempt {
+            attempt,
+            started_at: result.started_at.clone(),
+            status_code: result.status_code,
+            response_time_ms: result.response_time_ms,
+            success: result.success,
+            error_message: result.error_message.clone(),
+        });
+        if result.success
+            || result.started_at.is_none()
+            || !is_transient_failure(&result)
+            || attempt > retry.retries
+            || dry_run::is_enabled()
+        {
+            result.attempts = attempts;
+            return result;
+        }
+        tokio::time::sleep(retry.delay_after(attempt)).await;
+    }
+}
+
+// 応答を受信できなかった場合と、Webhook の再送と同じく受信側の一時的な問題を示すステータスの場合
+// 応答の内容が期待と異なるだけの場合は、再試行しても結果が変わらないため含めない
+fn is_transient_failure(result: &HttpPingResult) -> bool {
+    match result.status_code {
+        None => true,
+        Some(status) => matches!(status, 408 | 429 | 500..=599),
     }
 }
 
-// 指定されたIPアドレスにHTTP接続（curl コマンドを使用・SNI対応）
+// 指定されたIPアドレスにHTTP接続（SNI対応）
+#[allow(clippy::too_many_arguments)]
 async fn connect_to_ip_with_host(
     original_url: String,
+    request: &http_client::RequestOptions,
     ip_addresses: &[String],
     host: &str,
     ignore_tls_errors: bool,
     port: Option<u16>,
     save_verbose_log: bool,
+    source_address: Option<&str>,
+    timeout_secs: u64,
 ) -> HttpPingResult {
     // IPアドレスが存在しない場合
     if ip_addresses.is_empty() {
-        let is_https = original_url.starts_with("https");
-        return HttpPingResult {
-            url: original_url,
-            ip_address: None,
-            status_code: None,
-            response_time_ms: None,
-            success: false,
-            error_message: Some(
-                if is_https {
-                    "IPv6アドレスが見つかりません".to_string()
-                } else {
-                    "IPv4アドレスが見つかりません".to_string()
-                }
-            ),
-            verbose_log: None,
+        let message = if original_url.starts_with("https") {
+            "IPv6アドレスが見つかりません"
+        } else {
+            "IPv4アドレスが見つかりません"
         };
+        return unmeasured_result(&original_url, message.to_string());
     }
 
     // 最初のIPアドレスを使用して接続を試行
     let ip_address = &ip_addresses[0];
-    perform_curl_request(&original_url, ip_address, host, ignore_tls_errors, port, save_verbose_log).await
+    perform_http_request(
+        &original_url,
+        request,
+        Some(ip_address),
+        host,
+        ignore_tls_errors,
+        port,
+        save_verbose_log,
+        source_address,
+        timeout_secs,
+    )
+    .await
 }
 
-// curlを使用したHTTPリクエスト実行
-async fn perform_curl_request(
+// 接続を試行しなかった場合の結果
+fn unmeasured_result(url: &str, message: String) -> HttpPingResult {
+    HttpPingResult {
+        url: url.to_string(),
+        ip_address: None,
+        status_code: None,
+        response_time_ms: None,
+        success: false,
+        error_message: Some(message),
+        verbose_log: None,
+        verbose_info: None,
+        started_at: None,
+        finished_at: None,
+        timings: None,
+        alt_svc: None,
+        http_version: None,
+        tls_version: None,
+        cipher_suite: None,
+        downloaded_bytes: None,
+        download_bytes_per_sec: None,
+        download_truncated: false,
+        content: None,
+        response_headers: Vec::new(),
+        captured_headers: None,
+        header_assertions: Vec::new(),
+        response_body_prefix: Vec::new(),
+        body_assertions: Vec::new(),
+        attempts: Vec::new(),
+        redirects: Vec::new(),
+        curl_command: None,
+        second_request: None,
+        certificate: None,
+        warnings: Vec::new(),
+        proxy_auth: None,
+    }
+}
+
+// 指定したIPアドレスへのHTTPリクエスト実行（curl.exe を使わず直接接続して各段階の時間を測定）
+// ip_address が None の場合はプロキシに名前の解決を任せる
+#[allow(clippy::too_many_arguments)]
+async fn perform_http_request(
     original_url: &str,
-    ip_address: &str,
+    request: &http_client::RequestOptions,
+    ip_address: Option<&str>,
     host: &str,
     ignore_tls_errors: bool,
     port: Option<u16>,
     save_verbose_log: bool,
+    source_address: Option<&str>,
+    timeout_secs: u64,
 ) -> HttpPingResult {
-    let start = Instant::now();
-
-    let is_https = original_url.starts_with("https");
-    let default_port = if is_https { 443 } else { 80 };
-    let port_num = port.unwrap_or(default_port);
-
-    // --resolveオプションの構築（IPv6は角括弧で囲む）
-    let resolve_arg = if ip_address.contains(':') {
-        format!("{}:{}:[{}]", host, port_num, ip_address)
-    } else {
-        format!("{}:{}:{}", host, port_num, ip_address)
+    let started_at = now_rfc3339();
+    let outcome = http_client::send_request(&http_client::HttpRequest {
+        url: original_url,
+        options: request,
+        ip_address,
+        host,
+        port,
+        ignore_tls_errors,
+        source_address,
+        verbose: save_verbose_log,
+        max_body_bytes: 0,
+        timeout_secs,
+    })
+    .await;
+
+    let download_speed = outcome.download_speed();
+    let (success, error_message) = evaluate_status(outcome.status_code, outcome.error_message);
+
+    // サーバログやパケットキャプチャと突き合わせられるよう、試行の開始・終了時刻を記録
+    let mut result = HttpPingResult {
+        url: original_url.to_string(),
+        ip_address: ip_address.map(str::to_string),
+        status_code: outcome.status_code,
+        response_time_ms: Some(outcome.timings.total_ms.round() as u64),
+        success,
+        error_message,
+        verbose_log: outcome.verbose_log,
+        verbose_info: outcome.verbose_info,
+        started_at: Some(started_at),
+        finished_at: Some(now_rfc3339()),
+        timings: Some(outcome.timings),
+        alt_svc: outcome.alt_svc,
+        http_version: outcome.http_version,
+        tls_version: outcome.tls_version,
+        cipher_suite: outcome.cipher_suite,
+        downloaded_bytes: outcome.body_bytes,
+        download_bytes_per_sec: download_speed,
+        download_truncated: outcome.body_truncated,
+        content: outcome.content,
+        response_headers: outcome.headers,
+        captured_headers: None,
+        header_assertions: Vec::new(),
+        response_body_prefix: outcome.body_prefix,
+        body_assertions: Vec::new(),
+        attempts: Vec::new(),
+        redirects: Vec::new(),
+        curl_command: None,
+        second_request: outcome.second_request,
+        certificate: outcome.certificate,
+        warnings: Vec::new(),
+        proxy_auth: outcome.proxy_auth,
     };
-
-    let mut cmd_args = vec![
-        "--resolve".to_string(),
-        resolve_arg,
-    ];
-
-    // verbose ログを保存する場合は --verbose オプションを追加
-    if save_verbose_log {
-        cmd_args.push("--verbose".to_string());
+    if request.follow_redirects {
+        redirect::follow_redirects(
+            &mut result,
+            request,
+            ignore_tls_errors,
+            source_address,
+            save_verbose_log,
+            timeout_secs,
+        )
+        .await;
     }
+    result
+}
 
-    cmd_args.extend(vec![
-        "--silent".to_string(),
-        "--output".to_string(),
-        "nul".to_string(),
-        "--write-out".to_string(),
-        "%{http_code}".to_string(),
-        "--max-time".to_string(),
-        "10".to_string(),
-    ]);
-
-    if ignore_tls_errors {
-        cmd_args.push("--insecure".to_string());
+// サーバ証明書の有効期限が warning_days 日以内（または期限切れ）の場合の警告
+fn certificate_expiry_warning(
+    certificate: Option<&http_client::PeerCertificate>,
+    warning_days: u32,
+) -> Option<String> {
+    let certificate = certificate.filter(|_| warning_days > 0)?;
+    let expires_in = certificate.expires_in()?;
+    if expires_in <= chrono::TimeDelta::zero() {
+        Some(format!(
+            "証明書の有効期限が切れています（{}）",
+            certificate.not_after
+        ))
+    } else if expires_in.num_days() < warning_days as i64 {
+        Some(format!(
+            "証明書の有効期限まであと {} 日です（{}）",
+            expires_in.num_days(),
+            certificate.not_after
+        ))
+    } else {
+        None
     }
+}
 
-    cmd_args.push(original_url.to_string());
-
-    let output = Command::new("curl.exe")
-        .args(&cmd_args)
-        .creation_flags(0x08000200) // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
-        .stderr(Stdio::piped())
-        .stdout(Stdio::piped())
-        .output();
+// IPv4 と IPv6 でネゴシエーションされた TLS のバージョンや暗号スイートが異なる場合の警告
+fn tls_negotiation_mismatch(ipv4: &HttpPingResult, ipv6: &HttpPingResult) -> Option<String> {
+    let (Some(ipv4_version), Some(ipv6_version)) = (&ipv4.tls_version, &ipv6.tls_version) else {
+        return None;
+    };
+    if ipv4_version != ipv6_version {
+        return Some(format!(
+            "IPv4 と IPv6 で TLS のバージョンが異なります（IPv4: {}, IPv6: {}）",
+            ipv4_version, ipv6_version
+        ));
+    }
+    match (&ipv4.cipher_suite, &ipv6.cipher_suite) {
+        (Some(ipv4_suite), Some(ipv6_suite)) if ipv4_suite != ipv6_suite => Some(format!(
+            "IPv4 と IPv6 で TLS の暗号スイートが異なります（IPv4: {}, IPv6: {}）",
+            ipv4_suite, ipv6_suite
+        )),
+        _ => None,
+    }
+}
 
-    let elapsed = start.elapsed().as_millis() as u64;
+// 2xx のみ成功とし、失敗の理由を返す（期待するステータスの指定は response_assertion で判定し直す）
+fn evaluate_status(status_code: Option<u16>, error: Option<String>) -> (bool, Option<String>) {
+    let success = status_code.is_some_and(|status_code| (200..300).contains(&status_code));
+    let error_message = match (status_code, error) {
+        (_, Some(e)) => Some(format!("接続エラー: {}", e)),
+        (Some(status_code), None) if !success => Some(status_error_message(status_code)),
+        _ => None,
+    };
+    (success, error_message)
+}
 
-    match output {
-        Ok(output) => {
-            let status_code_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
-            let verbose_log_str = String::from_utf8_lossy(&output.stderr).trim().to_string();
-            let verbose_log = if !verbose_log_str.is_empty() {
-                Some(verbose_log_str.clone())
-            } else {
-                None
-            };
+fn status_error_message(status_code: u16) -> String {
+    format!("HTTPステータス: {}", status_code)
+}
 
-            if output.status.success() && !status_code_str.is_empty() {
-                if let Ok(status_code) = status_code_str.parse::<u16>() {
-                    let success = status_code >= 200 && status_code < 300;
-                    HttpPingResult {
-                        url: original_url.to_string(),
-                        ip_address: Some(ip_address.to_string()),
-                        status_code: Some(status_code),
-                        response_time_ms: Some(elapsed),
-                        success,
-                        error_message: if success {
-                            None
-                        } else {
-                            Some(format!("HTTPステータス: {}", status_code))
-                        },
-                        verbose_log,
-                    }
-                } else {
-                    HttpPingResult {
-                        url: original_url.to_string(),
-                        ip_address: Some(ip_address.to_string()),
-                        status_code: None,
-                        response_time_ms: Some(elapsed),
-                        success: false,
-                        error_message: Some(format!("ステータスコード解析失敗: {}", status_code_str)),
-                        verbose_log,
-                    }
-                }
-            } else {
-                let error_msg = if !verbose_log_str.is_empty() {
-                    verbose_log_str.clone()
-                } else {
-                    format!("curl 終了コード: {}", output.status.code().unwrap_or(-1))
-                };
-
-                HttpPingResult {
-                    url: original_url.to_string(),
-                    ip_address: Some(ip_address.to_string()),
-                    status_code: None,
-                    response_time_ms: Some(elapsed),
-                    success: false,
-                    error_message: Some(format!("接続エラー: {}", error_msg)),
-                    verbose_log,
-                }
-            }
+// ネットワークインターフェース情報を取得（PowerShell が制限されている、または失敗した場合は ipconfig から取得）
+async fn get_network_interfaces() -> Result<(Vec<NetworkAdapter>, DataSource), String> {
+    if powershell::status().await.is_available() {
+        match get_network_interfaces_from_powershell().await {
+            Ok(adapters) => return Ok((adapters, DataSource::PowerShell)),
+            Err(e) => eprintln!("Falling back to ipconfig for adapters: {}", e),
         }
-        Err(e) => HttpPingResult {
-            url: original_url.to_string(),
-            ip_address: Some(ip_address.to_string()),
-            status_code: None,
-            response_time_ms: Some(elapsed),
-            success: false,
-            error_message: Some(format!("curl 実行失敗: {}", e)),
-            verbose_log: None,
-        },
     }
+    let adapters = get_network_interfaces_from_ipconfig().await?;
+    Ok((adapters, DataSource::Ipconfig))
 }
 
-// ネットワークインターフェース情報を取得（セキュリティ強化版）
-fn get_network_interfaces() -> Result<Vec<NetworkAdapter>, String> {
-    let output = Command::new("powershell")
-        .args(&[
-            "-NoProfile",
-            "-WindowStyle",
-            "Hidden",
-            "-Command",
+// PowerShell でネットワークインターフェース情報を取得（セキュリティ強化版）
+async fn get_network_interfaces_from_powershell() -> Result<Vec<NetworkAdapter>, String> {
+    let output = process::run_command(
+        "powershell",
+        &powershell_args(
             "Get-NetAdapter | Where-Object {$_.Status -eq 'Up'} | Select-Object -ExpandProperty Name",
-        ])
-        .creation_flags(0x08000200) // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
-        .stderr(Stdio::piped())
-        .stdout(Stdio::piped())
-        .output()
-        .map_err(|e| format!("PowerShellコマンド実行失敗: {}", e))?;
+        ),
+    )
+    .await
+    .map_err(|e| format!("PowerShellコマンド実行失敗: {}", e))?;
 
     if !output.status.success() {
         return Err("ネットワークアダプタの取得に失敗しました".to_string());
@@ -468,12 +1949,7 @@ fn get_network_interfaces() -> Result<Vec<NetworkAdapter>, String> {
             name
         );
 
-        let ip_output = Command::new("powershell")
-            .args(&["-NoProfile", "-WindowStyle", "Hidden", "-Command", &get_ip_cmd])
-            .creation_flags(0x08000200) // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
-            .stderr(Stdio::piped())
-            .stdout(Stdio::piped())
-            .output();
+        let ip_output = process::run_command("powershell", &powershell_args(&get_ip_cmd)).await;
 
         if let Ok(ip_out) = ip_output {
             let ip_addresses: Vec<String> = decode_command_output(&ip_out.stdout)
@@ -499,6 +1975,93 @@ fn get_network_interfaces() -> Result<Vec<NetworkAdapter>, String> {
     Ok(adapters)
 }
 
+// ipconfig /all からネットワークインターフェース情報を取得
+async fn get_network_interfaces_from_ipconfig() -> Result<Vec<NetworkAdapter>, String> {
+    let output = process::run_command("ipconfig", &["/all".to_string()])
+        .await
+        .map_err(|e| format!("ipconfig コマンド実行失敗: {}", e))?;
+
+    if !output.status.success() {
+        return Err("ネットワークアダプタの取得に失敗しました".to_string());
+    }
+
+    Ok(parse_ipconfig_adapters(&decode_command_output(&output.stdout)))
+}
+
+// 項目名に含まれる "IPv4"/"IPv6" で判定し、表示言語に依存しないようにする
+fn parse_ipconfig_adapters(output: &str) -> Vec<NetworkAdapter> {
+    let mut adapters: Vec<(String, Vec<String>)> = Vec::new();
+    let mut in_tunnel_adapter = false;
+
+    for line in output.lines() {
+        if let Some(name) = ipconfig_adapter_name(line) {
+            // Get-NetAdapter と同じく Teredo などのトンネルアダプタは対象外
+            in_tunnel_adapter = line.starts_with("Tunnel") || line.starts_with("トンネル");
+            if !in_tunnel_adapter {
+                adapters.push((name, Vec::new()));
+            }
+            continue;
+        }
+        let Some((_, addresses)) = adapters.last_mut().filter(|_| !in_tunnel_adapter) else {
+            continue;
+        };
+        let Some((label, value)) = line.split_once(':') else {
+            continue;
+        };
+        if !(label.contains("IPv4") || label.contains("IPv6")) || label.contains("DNS") {
+            continue;
+        }
+
+        // "(Preferred)" / "(優先)" などの状態とゾーン ID を除く
+        let value = value.split(['(', '%']).next().unwrap_or("").trim();
+        let Ok(ip) = value.parse::<IpAddr>() else {
+            continue;
+        };
+        // PowerShell での取得（PrefixOrigin が WellKnown のものを除く）と揃えるため、リンクローカルは除く
+        let link_local = match ip {
+            IpAddr::V4(v4) => v4.is_link_local(),
+            IpAddr::V6(v6) => (v6.segments()[0] & 0xffc0) == 0xfe80,
+        };
+        if !link_local && !addresses.contains(&ip.to_string()) {
+            addresses.push(ip.to_string());
+        }
+    }
+
+    adapters
+        .into_iter()
+        .filter(|(_, ip_addresses)| !ip_addresses.is_empty())
+        .map(|(name, ip_addresses)| {
+            let (has_ipv4, has_ipv6, has_ipv4_global, has_ipv6_global) =
+                analyze_ip_addresses(&ip_addresses);
+            NetworkAdapter {
+                name,
+                ip_addresses,
+                has_ipv4,
+                has_ipv6,
+                has_ipv4_global,
+                has_ipv6_global,
+            }
+        })
+        .collect()
+}
+
+// ipconfig /all のアダプタの見出し行（"Ethernet adapter イーサネット:" など）からアダプタ名を取り出す
+fn ipconfig_adapter_name(line: &str) -> Option<String> {
+    let line_lower = line.to_lowercase();
+    if line.starts_with(' ')
+        || line.is_empty()
+        || !(line_lower.contains("アダプター") || line_lower.contains("adapter"))
+    {
+        return None;
+    }
+    let header = line[..line.find(':')?].trim();
+    let name = ["アダプター ", "adapter ", "Adapter "]
+        .iter()
+        .find_map(|marker| header.find(marker).map(|pos| &header[pos + marker.len()..]))
+        .unwrap_or(header);
+    Some(name.to_string())
+}
+
 // IPv4がグローバルアドレスかどうかを判定
 fn is_global_ipv4(ip: &Ipv4Addr) -> bool {
     !ip.is_private()
@@ -545,50 +2108,97 @@ async fn check_connectivity(url: &str, timeout_secs: u64) -> Result<bool, String
     }
 }
 
+// 設定された IP エコーサービスを順に試してグローバルIPを取得
+async fn fetch_global_ip(
+    endpoints: &[IpEchoEndpoint],
+    ip_version: u8,
+    timeout_secs: u64,
+) -> Result<GlobalIPInfo, String> {
+    let mut last_error = format!("IPv{} 用の IP エコーサービスが設定されていません", ip_version);
+    for endpoint in endpoints.iter().filter(|e| e.ip_version == ip_version) {
+        match fetch_global_ip_info(endpoint, timeout_secs).await {
+            Ok(info) => return Ok(info),
+            Err(e) => last_error = format!("{}: {}", endpoint.url, e),
+        }
+    }
+    Err(last_error)
+}
+
 // グローバルIP情報取得（汎用関数）
-async fn fetch_global_ip_info(url: &str, timeout_secs: u64) -> Result<GlobalIPInfo, String> {
+async fn fetch_global_ip_info(
+    endpoint: &IpEchoEndpoint,
+    timeout_secs: u64,
+) -> Result<GlobalIPInfo, String> {
+    let url = endpoint.url.as_str();
+    let parsed_url = Url::parse(url).map_err(|e| format!("無効なURL: {}", e))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?;
+
+    // 指定したファミリのアドレスに接続する
+    let dns_resolution = resolve_dns(host).await;
+    let addresses = if endpoint.ip_version == 6 {
+        &dns_resolution.ipv6_addresses
+    } else {
+        &dns_resolution.ipv4_addresses
+    };
+    let ip_address = addresses
+        .first()
+        .ok_or_else(|| format!("IPv{}アドレスが見つかりません", endpoint.ip_version))?;
+
+    let options = http_client::RequestOptions::default();
+    let request = |ignore_tls_errors| http_client::HttpRequest {
+        url,
+        options: &options,
+        ip_address: Some(ip_address),
+        host,
+        port: parsed_url.port(),
+        ignore_tls_errors,
+        source_address: None,
+        verbose: false,
+        max_body_bytes: IP_ECHO_MAX_BODY_BYTES,
+        timeout_secs,
+    };
+
     // 1回目: 通常のTLS検証で接続を試みる
-    let output = Command::new("curl.exe")
-        .args(&["--silent", "--max-time", &timeout_secs.to_string(), url])
-        .creation_flags(0x08000200) // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
-        .stderr(Stdio::piped())
-        .stdout(Stdio::piped())
-        .output()
-        .map_err(|e| format!("curl実行失敗: {}", e))?;
+    let mut outcome = http_client::send_request(&request(false)).await;
 
-    // 失敗時はTLS証明書検証を無視してフォールバック
-    let json_str = if output.status.success() {
-        String::from_utf8_lossy(&output.stdout).to_string()
-    } else {
+    // TLSエラーの場合のみ証明書検証を無視してフォールバック（接続できない場合は再試行しない）
+    if outcome
+        .error_message
+        .as_deref()
+        .is_some_and(http_client::is_tls_error)
+    {
         // 2回目: TLS証明書検証を無視して接続を試みる
-        let fallback_output = Command::new("curl.exe")
-            .args(&["--silent", "--insecure", "--max-time", &timeout_secs.to_string(), url])
-            .creation_flags(0x08000200) // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
-            .stderr(Stdio::piped())
-            .stdout(Stdio::piped())
-            .output()
-            .map_err(|e| format!("curl実行失敗(フォールバック): {}", e))?;
-
-        if !fallback_output.status.success() {
+        outcome = http_client::send_request(&request(true)).await;
+        if outcome.error_message.is_some() {
             return Err("グローバルIP取得失敗（TLS検証有無両方失敗）".to_string());
         }
+    }
+    if let Some(e) = outcome.error_message {
+        return Err(format!("接続できません: {}", e));
+    }
+    match outcome.status_code {
+        Some(200..=299) => {}
+        status_code => {
+            return Err(format!(
+                "HTTPステータス: {}",
+                status_code.unwrap_or_default()
+            ))
+        }
+    }
 
-        String::from_utf8_lossy(&fallback_output.stdout).to_string()
-    };
-
-    let body: IpResponse = serde_json::from_str(&json_str)
-        .map_err(|e| format!("JSON解析失敗: {}", e))?;
-
-    Ok(GlobalIPInfo {
-        client_host: body.client_host,
-        datetime_jst: body.datetime_jst,
-    })
+    let body = outcome.body.unwrap_or_default();
+    endpoint.parser.parse(&String::from_utf8_lossy(&body))
 }
 
 // DNS解決確認
 async fn check_dns_resolution() -> Result<bool, String> {
     use tokio::net::lookup_host;
 
+    if dry_run::intercept(dry_run::ActionKind::Dns, "example.com", Some("OS のリゾルバ".to_string())) {
+        return Err(dry_run::DRY_RUN_MESSAGE.to_string());
+    }
     match lookup_host("example.com:80").await {
         Ok(mut addrs) => Ok(addrs.next().is_some()),
         Err(_) => Ok(false),
@@ -596,27 +2206,18 @@ async fn check_dns_resolution() -> Result<bool, String> {
 }
 
 // DNS サーバ情報の取得（非同期版）
-async fn get_dns_servers_async() -> Result<Vec<DnsServerInfo>, String> {
+async fn get_dns_servers_async() -> Result<(Vec<DnsServerInfo>, DataSource), String> {
     // ipconfig /all を優先的に使用（最も確実）
-    match tokio::task::spawn_blocking(parse_dns_from_ipconfig_blocking).await {
-        Ok(Ok(result)) if !result.is_empty() => return Ok(result),
-        _ => {}
-    }
-
-    // PowerShell を別スレッドで実行
-    match tokio::task::spawn_blocking(get_dns_servers_from_powershell_blocking).await {
-        Ok(result) => result,
-        Err(_) => Err("DNSサーバ取得スレッドエラー".to_string()),
-    }
-}
-
-// DNS サーバ情報の取得（互換性のための同期版）
-#[allow(dead_code)]
-fn get_dns_servers() -> Result<Vec<DnsServerInfo>, String> {
-    // ipconfig /all を優先的に使用（最も確実）
-    match parse_dns_from_ipconfig() {
-        Ok(result) if !result.is_empty() => Ok(result),
-        _ => get_dns_servers_from_powershell(),
+    let from_ipconfig = parse_dns_from_ipconfig().await;
+    match from_ipconfig {
+        Ok(result) if !result.is_empty() => Ok((result, DataSource::Ipconfig)),
+        // PowerShell が制限されている場合は ipconfig の結果をそのまま返す
+        _ if !powershell::status().await.is_available() => {
+            from_ipconfig.map(|result| (result, DataSource::Ipconfig))
+        }
+        _ => get_dns_servers_from_powershell()
+            .await
+            .map(|result| (result, DataSource::PowerShell)),
     }
 }
 
@@ -627,18 +2228,16 @@ fn decode_command_output(bytes: &[u8]) -> String {
     cow.to_string()
 }
 
-// parse_dns_from_ipconfig のブロッキング版
-fn parse_dns_from_ipconfig_blocking() -> Result<Vec<DnsServerInfo>, String> {
-    parse_dns_from_ipconfig()
-}
-
-// get_dns_servers_from_powershell のブロッキング版
-fn get_dns_servers_from_powershell_blocking() -> Result<Vec<DnsServerInfo>, String> {
-    get_dns_servers_from_powershell()
+// PowerShell にコマンドを渡す引数（プロファイルを読み込まず、ウィンドウを表示しない）
+fn powershell_args(command: &str) -> Vec<String> {
+    ["-NoProfile", "-WindowStyle", "Hidden", "-Command", command]
+        .iter()
+        .map(|a| a.to_string())
+        .collect()
 }
 
 // PowerShell を使用して DNS サーバ情報を取得
-fn get_dns_servers_from_powershell() -> Result<Vec<DnsServerInfo>, String> {
+async fn get_dns_servers_from_powershell() -> Result<Vec<DnsServerInfo>, String> {
     let ps_command = r#"Get-NetAdapter | Where-Object {$_.Status -eq 'Up'} | ForEach-Object {
         $iface = $_.Name
         Get-DnsClientServerAddress -InterfaceAlias $iface -ErrorAction SilentlyContinue |
@@ -646,12 +2245,8 @@ fn get_dns_servers_from_powershell() -> Result<Vec<DnsServerInfo>, String> {
         ForEach-Object { "$iface : $_" }
     }"#;
 
-    let output = Command::new("powershell")
-        .args(&["-NoProfile", "-WindowStyle", "Hidden", "-Command", ps_command])
-        .creation_flags(0x08000200) // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
-        .stderr(Stdio::piped())
-        .stdout(Stdio::piped())
-        .output()
+    let output = process::run_command("powershell", &powershell_args(ps_command))
+        .await
         .map_err(|e| format!("PowerShellコマンド実行失敗: {}", e))?;
 
     if !output.status.success() {
@@ -705,13 +2300,9 @@ fn get_dns_servers_from_powershell() -> Result<Vec<DnsServerInfo>, String> {
 }
 
 // ipconfig /all から DNS サーバ情報を取得
-fn parse_dns_from_ipconfig() -> Result<Vec<DnsServerInfo>, String> {
-    let output = Command::new("ipconfig")
-        .args(&["/all"])
-        .creation_flags(0x08000200) // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
-        .stderr(Stdio::piped())
-        .stdout(Stdio::piped())
-        .output()
+async fn parse_dns_from_ipconfig() -> Result<Vec<DnsServerInfo>, String> {
+    let output = process::run_command("ipconfig", &["/all".to_string()])
+        .await
         .map_err(|e| format!("ipconfig コマンド実行失敗: {}", e))?;
 
     if !output.status.success() {
@@ -729,37 +2320,22 @@ fn parse_dns_from_ipconfig() -> Result<Vec<DnsServerInfo>, String> {
         let trimmed = line.trim();
 
         // アダプタ行の検出
-        if !line.starts_with(' ')
-            && !line.is_empty()
-            && (line_lower.contains("アダプター") || line_lower.contains("adapter"))
-            && line.contains(':')
-        {
+        if let Some(adapter_name) = ipconfig_adapter_name(line) {
             // 前のアダプタ情報を保存
-            if let Some(adapter_name) = current_adapter.take() {
+            if let Some(previous_adapter) = current_adapter.take() {
                 if !current_ipv4_dns.is_empty() || !current_ipv6_dns.is_empty() {
                     result.push(DnsServerInfo {
-                        interface_alias: adapter_name,
+                        interface_alias: previous_adapter,
                         ipv4_dns_servers: current_ipv4_dns.clone(),
                         ipv6_dns_servers: current_ipv6_dns.clone(),
                     });
                 }
             }
 
-            // 新しいアダプタ情報を抽出
-            if let Some(pos) = line.find(':') {
-                let adapter_name = line[..pos].trim().to_string();
-                let extracted_name = if let Some(name_start) = adapter_name.to_lowercase().find("アダプター ") {
-                    adapter_name[name_start + 5..].to_string()
-                } else if let Some(name_start) = adapter_name.to_lowercase().find("adapter ") {
-                    adapter_name[name_start + 8..].to_string()
-                } else {
-                    adapter_name
-                };
-
-                current_adapter = Some(extracted_name);
-                current_ipv4_dns.clear();
-                current_ipv6_dns.clear();
-            }
+            // 新しいアダプタ情報
+            current_adapter = Some(adapter_name);
+            current_ipv4_dns.clear();
+            current_ipv6_dns.clear();
         } else if current_adapter.is_some()
             && (line_lower.contains("dns サーバー") || line_lower.contains("dns servers"))
             && line.contains(':')
@@ -830,19 +2406,123 @@ fn validate_url(url: &str) -> Result<(), String> {
     Ok(())
 }
 
+// 国際化ドメイン名の URL はホスト名を A ラベル（xn--）に変換する
+// （curl に渡す URL と --resolve の名前、名前解決、Host ヘッダの名前を一致させる）
+fn normalize_url(url: &str) -> Result<String, String> {
+    validate_url(url)?;
+    if url.is_ascii() {
+        return Ok(url.to_string());
+    }
+    let parsed = Url::parse(url).map_err(|e| format!("無効なURL: {}", e))?;
+    if let Some(host) = parsed.host_str() {
+        validate_hostname(host)?;
+    }
+    Ok(parsed.to_string())
+}
+
+// A ラベルを含むホスト名の場合は Unicode の形とあわせて返す（表示用）
+fn idn_hostname(host: &str) -> Option<IdnHostname> {
+    if !host.split('.').any(|label| label.starts_with("xn--")) {
+        return None;
+    }
+    let (unicode, result) = idna::domain_to_unicode(host);
+    result.ok()?;
+    Some(IdnHostname {
+        unicode,
+        ascii: host.to_string(),
+    })
+}
+
 // ホスト名の検証（コマンドインジェクション対策）
 fn validate_hostname(host: &str) -> Result<(), String> {
+    normalize_hostname(host).map(|_| ())
+}
+
+// SNI に指定する名前を検証（IP アドレスは SNI として送られないため拒否）
+fn normalize_sni(sni: &str) -> Result<String, String> {
+    let sni = normalize_hostname(sni)?;
+    if sni.parse::<IpAddr>().is_ok() {
+        return Err(format!("SNI にはホスト名を指定してください: {}", sni));
+    }
+    Ok(sni)
+}
+
+// host_override の値（"example.com" または "example.com:8443"）から SNI の名前と Host ヘッダの値を得る
+fn parse_host_override(value: &str) -> Result<(String, String), String> {
+    let invalid = || {
+        format!(
+            "host_override にはホスト名（ポートは省略可）を指定してください: {}",
+            value
+        )
+    };
+    let parsed = Url::parse(&format!("https://{}", value.trim())).map_err(|_| invalid())?;
+    if parsed.path() != "/" || parsed.query().is_some() || !parsed.username().is_empty() {
+        return Err(invalid());
+    }
+    let name = normalize_sni(parsed.host_str().ok_or_else(invalid)?)?;
+    // 対象が http の場合もあるため、https の既定のポート（443）でも明示されたポートはそのまま送る
+    let explicit_port = value
+        .trim()
+        .rsplit_once(':')
+        .is_some_and(|(_, port)| !port.is_empty() && port.bytes().all(|b| b.is_ascii_digit()));
+    let host_header = match parsed.port_or_known_default() {
+        Some(port) if explicit_port => format!("{}:{}", name, port),
+        _ => name.clone(),
+    };
+    Ok((name, host_header))
+}
+
+// ホスト名を検証し、正規化した形（国際化ドメイン名は A ラベル、末尾のドットなし）で返す
+// RFC 1123 のラベル規則に従い、Windows の名前で使われるアンダースコアのみ追加で許可
+fn normalize_hostname(host: &str) -> Result<String, String> {
+    let host = host.trim();
     if host.is_empty() || host.len() > 255 {
         return Err("ホスト名が無効です".to_string());
     }
 
-    // 危険な文字列を検出
-    let dangerous_chars = ['$', '`', '|', '&', ';', '>', '<', '(', ')'];
-    if dangerous_chars.iter().any(|&c| host.contains(c)) {
-        return Err("ホスト名に無効な文字が含まれています".to_string());
+    // IP アドレス（URL 形式の [IPv6] を含む）はそのまま許可
+    let unbracketed = host
+        .strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host);
+    if let Ok(ip) = unbracketed.parse::<IpAddr>() {
+        return Ok(ip.to_string());
     }
 
-    Ok(())
+    // 国際化ドメイン名を A ラベル（xn--）へ変換（大文字は小文字に正規化される）
+    let ascii = match url::Host::parse(host) {
+        Ok(url::Host::Domain(domain)) => domain,
+        // "0x7f.1" のような省略・16進表記の IPv4 は解釈が曖昧なため拒否
+        Ok(_) => return Err(format!("IPアドレスの表記が正しくありません: {}", host)),
+        Err(_) => return Err(format!("ホスト名に無効な文字が含まれています: {}", host)),
+    };
+
+    let name = ascii.strip_suffix('.').unwrap_or(&ascii);
+    if name.is_empty() || name.len() > 253 {
+        return Err("ホスト名は 253 文字以内で指定してください".to_string());
+    }
+    for label in name.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(format!(
+                "ホスト名の各ラベル（ドット区切り）は 1〜63 文字で指定してください: {}",
+                host
+            ));
+        }
+        if !label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!("ホスト名に無効な文字が含まれています: {}", host));
+        }
+        if label.starts_with('-') || label.ends_with('-') {
+            return Err(format!(
+                "ホスト名のラベルはハイフンで始まったり終わったりできません: {}",
+                host
+            ));
+        }
+    }
+
+    Ok(name.to_string())
 }
 
 // アダプタ名のサニタイズ
@@ -921,7 +2601,98 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .invoke_handler(tauri::generate_handler![environment_check, ping_http_dual])
+        .manage(HistoryStore::default())
+        .manage(IpHistoryStore::default())
+        .manage(OperationRegistry::default())
+        .manage(EnvironmentMonitor::default())
+        .manage(ContinuousPing::default())
+        .manage(MaintenanceStore::default())
+        .manage(MonitorGroups::default())
+        .manage(ResultWindows::default())
+        .manage(WebhookStore::default())
+        .setup(|app| {
+            rate_limit::load_rate_limit(app.handle());
+            Ok(())
+        })
+        .on_window_event(windows::handle_window_event)
+        .invoke_handler(tauri::generate_handler![
+            environment_check,
+            rerun_stage,
+            env_diff::diff_environment_results,
+            baseline::capture_baseline,
+            baseline::get_baseline,
+            baseline::clear_baseline,
+            baseline::compare_to_baseline,
+            batch::ping_http_batch,
+            target_import::import_target_urls,
+            har::export_har,
+            download_speed::measure_download_speed,
+            env_monitor::start_environment_monitor,
+            env_monitor::stop_environment_monitor,
+            env_monitor::get_environment_monitor_status,
+            env_monitor::pause_environment_monitor,
+            env_monitor::resume_environment_monitor,
+            redact::redact_result,
+            clipboard::copy_result,
+            global_ip_check::check_global_ip_consistency,
+            ip_echo::get_ip_echo_endpoints,
+            ip_echo::set_ip_echo_endpoints,
+            ip_history::get_global_ip_history,
+            ping_http_dual,
+            series::ping_http_series,
+            continuous_ping::start_continuous_ping,
+            continuous_ping::stop_continuous_ping,
+            continuous_ping::get_continuous_ping_status,
+            continuous_ping::pause_continuous_ping,
+            continuous_ping::resume_continuous_ping,
+            maintenance::get_maintenance_windows,
+            maintenance::set_maintenance_windows,
+            webhook::get_webhooks,
+            webhook::set_webhooks,
+            webhook::get_webhook_failures,
+            support_bundle::create_support_bundle,
+            per_adapter::ping_http_per_adapter,
+            health_check::check_health_endpoint,
+            benchmark::run_benchmark,
+            operations::stop_all,
+            operations::cancel_operation,
+            operations::list_active_operations,
+            operations::get_operation_progress,
+            rate_limit::get_rate_limit,
+            rate_limit::set_rate_limit,
+            app_info::get_app_info,
+            curl::get_curl_capabilities,
+            history::get_history,
+            iperf::run_iperf3,
+            jitter::run_jitter_test,
+            happy_eyeballs::race_happy_eyeballs,
+            speedtest::run_public_speed_test,
+            dns_hijack::check_dns_hijacking,
+            dns_round_robin::check_dns_round_robin,
+            diagnose::diagnose,
+            ipv6_matrix::check_ipv6_reachability_matrix,
+            tls_intercept::check_tls_interception,
+            tls_extended::run_extended_tls_diagnostics,
+            tls_chain::inspect_tls,
+            tls_versions::scan_tls_versions,
+            proxy_detect::detect_transparent_proxy,
+            port_check::check_port_blocking,
+            receiver::wait_for_inbound_request,
+            ephemeral_ports::check_ephemeral_ports,
+            sni_filter::check_sni_filtering,
+            socks_dns::compare_socks_dns,
+            targets::get_builtin_targets,
+            template::preview_template,
+            stats::get_loss_stats,
+            anomaly::get_latency_anomalies,
+            stats::get_voip_quality,
+            windows::open_result_window,
+            windows::list_result_windows,
+            windows::close_result_window,
+            dry_run::set_dry_run,
+            dry_run::get_dry_run_status,
+            dry_run::get_dry_run_actions,
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
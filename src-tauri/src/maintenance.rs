@@ -0,0 +1,176 @@
+use chrono::{Datelike, Duration, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+// メンテナンス時間帯の設定ファイル名（アプリデータディレクトリ配下）
+pub const MAINTENANCE_FILE_NAME: &str = "maintenance_windows.json";
+
+const MAX_MAINTENANCE_WINDOWS: usize = 50;
+
+// 1回の時間帯の長さ（分、最長 1 週間）
+const MAX_DURATION_MINUTES: u32 = 7 * 24 * 60;
+
+// 時間帯を適用する定期実行の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MonitorKind {
+    ContinuousPing,
+    Environment,
+}
+
+// 毎週決まった曜日・時刻に繰り返すメンテナンス時間帯（ローカル時刻）
+// 時間帯中も測定は続けるが、通知は行わず結果に時間帯の名前を付ける
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceWindow {
+    pub name: String,
+    // 0 = 日曜日 〜 6 = 土曜日
+    pub weekdays: Vec<u8>,
+    // "HH:MM" 形式の開始時刻
+    pub start_time: String,
+    pub duration_minutes: u32,
+    // 対象の定期実行（空の場合はすべて）
+    #[serde(default)]
+    pub monitors: Vec<MonitorKind>,
+}
+
+impl MaintenanceWindow {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("メンテナンス時間帯の名前を指定してください".to_string());
+        }
+        if self.weekdays.is_empty() || self.weekdays.iter().any(|&d| d > 6) {
+            return Err(format!(
+                "{}: 曜日は 0（日曜日）から 6（土曜日）の範囲で1つ以上指定してください",
+                self.name
+            ));
+        }
+        parse_start_time(&self.start_time).map_err(|e| format!("{}: {}", self.name, e))?;
+        if !(1..=MAX_DURATION_MINUTES).contains(&self.duration_minutes) {
+            return Err(format!(
+                "{}: 長さは 1 から {} 分の範囲で指定してください",
+                self.name, MAX_DURATION_MINUTES
+            ));
+        }
+        Ok(())
+    }
+
+    // 開始日を遡って確認し、日付や週をまたぐ時間帯も判定する
+    fn is_active(&self, now: chrono::DateTime<Local>, monitor: MonitorKind) -> bool {
+        if !self.monitors.is_empty() && !self.monitors.contains(&monitor) {
+            return false;
+        }
+        let Ok(start_time) = parse_start_time(&self.start_time) else {
+            return false;
+        };
+        let length = Duration::minutes(self.duration_minutes as i64);
+        let days_back = self.duration_minutes as i64 / (24 * 60) + 1;
+        (0..=days_back).any(|days| {
+            let date = now.date_naive() - Duration::days(days);
+            if !self
+                .weekdays
+                .contains(&(date.weekday().num_days_from_sunday() as u8))
+            {
+                return false;
+            }
+            // 夏時間の切り替えで存在しない時刻は対象外
+            let Some(start) = Local
+                .from_local_datetime(&date.and_time(start_time))
+                .earliest()
+            else {
+                return false;
+            };
+            start <= now && now < start + length
+        })
+    }
+}
+
+fn parse_start_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .map_err(|_| format!("開始時刻は HH:MM 形式で指定してください: {}", value))
+}
+
+// メンテナンス時間帯の設定（Tauri の State として管理）
+#[derive(Default)]
+pub struct MaintenanceStore {
+    windows: Mutex<Option<Vec<MaintenanceWindow>>>,
+}
+
+fn maintenance_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("アプリデータディレクトリの取得に失敗: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("設定ディレクトリの作成に失敗: {}", e))?;
+    Ok(dir.join(MAINTENANCE_FILE_NAME))
+}
+
+// 設定ファイルを読み込む（壊れている場合は時間帯なしとして扱う）
+fn read_maintenance_file(path: &PathBuf) -> Vec<MaintenanceWindow> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Invalid maintenance window file {:?}: {}", path, e);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+// 現在メンテナンス時間帯に入っていれば、その時間帯の名前を返す
+pub fn active_window(app: &AppHandle, monitor: MonitorKind) -> Option<String> {
+    let path = maintenance_file_path(app).ok()?;
+    let store = app.state::<MaintenanceStore>();
+    let mut guard = store.windows.lock().ok()?;
+    let now = Local::now();
+    guard
+        .get_or_insert_with(|| read_maintenance_file(&path))
+        .iter()
+        .find(|w| w.is_active(now, monitor))
+        .map(|w| w.name.clone())
+}
+
+#[tauri::command]
+pub async fn get_maintenance_windows(
+    app: AppHandle,
+    store: State<'_, MaintenanceStore>,
+) -> Result<Vec<MaintenanceWindow>, String> {
+    let path = maintenance_file_path(&app)?;
+    Ok(store
+        .windows
+        .lock()
+        .map_err(|_| "メンテナンス時間帯のロック取得に失敗".to_string())?
+        .get_or_insert_with(|| read_maintenance_file(&path))
+        .clone())
+}
+
+// メンテナンス時間帯をまとめて置き換えて保存（空にするとすべて削除）
+#[tauri::command]
+pub async fn set_maintenance_windows(
+    app: AppHandle,
+    store: State<'_, MaintenanceStore>,
+    windows: Vec<MaintenanceWindow>,
+) -> Result<Vec<MaintenanceWindow>, String> {
+    if windows.len() > MAX_MAINTENANCE_WINDOWS {
+        return Err(format!(
+            "メンテナンス時間帯は {} 件まで登録できます",
+            MAX_MAINTENANCE_WINDOWS
+        ));
+    }
+    for window in &windows {
+        window.validate()?;
+    }
+
+    let path = maintenance_file_path(&app)?;
+    let body = serde_json::to_string_pretty(&windows)
+        .map_err(|e| format!("メンテナンス時間帯の変換に失敗: {}", e))?;
+    fs::write(&path, body)
+        .map_err(|e| format!("メンテナンス時間帯ファイルの書き込みに失敗: {}", e))?;
+
+    *store
+        .windows
+        .lock()
+        .map_err(|_| "メンテナンス時間帯のロック取得に失敗".to_string())? = Some(windows.clone());
+    Ok(windows)
+}
@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use crate::maintenance::MonitorKind;
+
+const MAX_GROUP_NAME_LEN: usize = 64;
+const MAX_DEPENDENCIES: usize = 10;
+
+// 定期実行のグループ（"gateway"・"isp"・"site" など）と、依存するグループ
+// 依存先のグループの定期実行が停止と判定している間は、この定期実行の通知を抑止する
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MonitorDependency {
+    pub group: Option<String>,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+}
+
+struct MonitorHealth {
+    dependency: MonitorDependency,
+    down: bool,
+    // 直近の回の判定を有効とみなす期限（次の回の結果がない場合は古い判定として扱う）
+    valid_until: Option<Instant>,
+}
+
+// 実行中の定期実行のグループと直近の回の状態（Tauri の State として管理）
+#[derive(Default)]
+pub struct MonitorGroups {
+    monitors: Mutex<HashMap<MonitorKind, MonitorHealth>>,
+}
+
+fn validate_group_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_GROUP_NAME_LEN {
+        return Err(format!(
+            "グループ名は 1 から {} 文字で指定してください",
+            MAX_GROUP_NAME_LEN
+        ));
+    }
+    Ok(name.to_string())
+}
+
+// グループと依存先を検証し、定期実行の開始時に登録する（実行中の場合は置き換える）
+pub fn register(
+    app: &AppHandle,
+    monitor: MonitorKind,
+    group: Option<String>,
+    depends_on: Vec<String>,
+) -> Result<MonitorDependency, String> {
+    let group = group.as_deref().map(validate_group_name).transpose()?;
+    if depends_on.len() > MAX_DEPENDENCIES {
+        return Err(format!(
+            "依存先のグループは {} 件まで指定できます",
+            MAX_DEPENDENCIES
+        ));
+    }
+    let mut names: Vec<String> = Vec::new();
+    for name in &depends_on {
+        let name = validate_group_name(name)?;
+        if group.as_ref() == Some(&name) {
+            return Err(format!("自身のグループ {} には依存できません", name));
+        }
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    let dependency = MonitorDependency {
+        group,
+        depends_on: names,
+    };
+
+    let groups = app.state::<MonitorGroups>();
+    let mut monitors = groups
+        .monitors
+        .lock()
+        .map_err(|_| "定期実行のグループのロック取得に失敗".to_string())?;
+    let mut edges: Vec<&MonitorDependency> = monitors
+        .iter()
+        .filter(|(kind, _)| **kind != monitor)
+        .map(|(_, health)| &health.dependency)
+        .collect();
+    edges.push(&dependency);
+    if let Some(group) = &dependency.group {
+        if depends_on_group(&edges, &dependency.depends_on, group, &mut Vec::new()) {
+            return Err(format!("グループ {} の依存関係が循環しています", group));
+        }
+    }
+    monitors.insert(
+        monitor,
+        MonitorHealth {
+            dependency: dependency.clone(),
+            down: false,
+            valid_until: None,
+        },
+    );
+    Ok(dependency)
+}
+
+// upstream のいずれかから依存関係をたどって target のグループに到達するか
+fn depends_on_group(
+    edges: &[&MonitorDependency],
+    upstream: &[String],
+    target: &str,
+    visited: &mut Vec<String>,
+) -> bool {
+    for name in upstream {
+        if name == target {
+            return true;
+        }
+        if visited.contains(name) {
+            continue;
+        }
+        visited.push(name.clone());
+        let next: Vec<String> = edges
+            .iter()
+            .filter(|d| d.group.as_ref() == Some(name))
+            .flat_map(|d| d.depends_on.iter().cloned())
+            .collect();
+        if depends_on_group(edges, &next, target, visited) {
+            return true;
+        }
+    }
+    false
+}
+
+// 定期実行の停止時に登録を外す（停止した定期実行は依存先として扱わない）
+pub fn unregister(app: &AppHandle, monitor: MonitorKind) {
+    if let Ok(mut monitors) = app.state::<MonitorGroups>().monitors.lock() {
+        monitors.remove(&monitor);
+    }
+}
+
+// 1回分の結果を記録し、依存先のグループの定期実行が停止と判定している場合はそのグループ名を返す
+// 判定は valid_for（次の回までの間隔）の間有効とし、同じ時期の回どうしのみを比較する
+pub fn evaluate(
+    app: &AppHandle,
+    monitor: MonitorKind,
+    down: bool,
+    valid_for: Duration,
+) -> Option<String> {
+    let groups = app.state::<MonitorGroups>();
+    let mut monitors = groups.monitors.lock().ok()?;
+    let now = Instant::now();
+    let health = monitors.get_mut(&monitor)?;
+    health.down = down;
+    health.valid_until = Some(now + valid_for);
+    let depends_on = health.dependency.depends_on.clone();
+    depends_on.into_iter().find(|name| {
+        monitors.iter().any(|(kind, health)| {
+            *kind != monitor
+                && health.dependency.group.as_ref() == Some(name)
+                && health.down
+                && health.valid_until.is_some_and(|until| until > now)
+        })
+    })
+}
@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+
+use crate::process::run_command;
+
+// NLM (Network List Manager) の接続状態と接続プロファイルを取得するスクリプト
+const NCSI_SCRIPT: &str = r#"$ErrorActionPreference = 'Stop'
+$nlm = [Activator]::CreateInstance([Type]::GetTypeFromCLSID([Guid]'DCB00C01-570F-4A9B-8D69-199FDBA5723B'))
+$flags = [int]$nlm.GetConnectivity()
+$profiles = @(Get-NetConnectionProfile -ErrorAction SilentlyContinue | ForEach-Object {
+    [pscustomobject]@{
+        interface_alias = $_.InterfaceAlias
+        name = $_.Name
+        network_category = [string]$_.NetworkCategory
+        ipv4_connectivity = [string]$_.IPv4Connectivity
+        ipv6_connectivity = [string]$_.IPv6Connectivity
+    }
+})
+[pscustomobject]@{
+    flags = $flags
+    profiles = $profiles
+} | ConvertTo-Json -Depth 3 -Compress"#;
+
+// NLM_CONNECTIVITY のフラグ値
+const NLM_IPV4_NOTRAFFIC: u32 = 0x1;
+const NLM_IPV6_NOTRAFFIC: u32 = 0x2;
+const NLM_IPV4_SUBNET: u32 = 0x10;
+const NLM_IPV4_LOCALNETWORK: u32 = 0x20;
+const NLM_IPV4_INTERNET: u32 = 0x40;
+const NLM_IPV6_SUBNET: u32 = 0x100;
+const NLM_IPV6_LOCALNETWORK: u32 = 0x200;
+const NLM_IPV6_INTERNET: u32 = 0x400;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowsConnectivityLevel {
+    Disconnected,
+    NoTraffic,
+    Subnet,
+    LocalNetwork,
+    Internet,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConnectionProfile {
+    pub interface_alias: String,
+    pub name: String,
+    pub network_category: String,
+    pub ipv4_connectivity: String,
+    pub ipv6_connectivity: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WindowsConnectivity {
+    pub ipv4: WindowsConnectivityLevel,
+    pub ipv6: WindowsConnectivityLevel,
+    pub raw_flags: u32,
+    pub profiles: Vec<ConnectionProfile>,
+    pub discrepancies: Vec<String>,
+}
+
+// PowerShell 出力解析用の内部構造体
+#[derive(Deserialize)]
+struct NcsiOutput {
+    flags: u32,
+    #[serde(default)]
+    profiles: Vec<ConnectionProfile>,
+}
+
+// Windows 自身の接続判定（NCSI の結果）を取得
+pub async fn get_windows_connectivity() -> Result<WindowsConnectivity, String> {
+    let args = [
+        "-NoProfile".to_string(),
+        "-WindowStyle".to_string(),
+        "Hidden".to_string(),
+        "-Command".to_string(),
+        NCSI_SCRIPT.to_string(),
+    ];
+    let output = run_command("powershell", &args)
+        .await
+        .map_err(|e| format!("PowerShellコマンド実行失敗: {}", e))?;
+    if !output.status.success() {
+        let stderr = crate::decode_command_output(&output.stderr);
+        let reason = stderr
+            .lines()
+            .map(|l| l.trim())
+            .find(|l| !l.is_empty())
+            .unwrap_or("不明なエラー");
+        return Err(format!("Windows の接続状態を取得できません: {}", reason));
+    }
+
+    let stdout = crate::decode_command_output(&output.stdout);
+    let parsed: NcsiOutput =
+        serde_json::from_str(stdout.trim()).map_err(|e| format!("接続状態の解析失敗: {}", e))?;
+
+    Ok(WindowsConnectivity {
+        ipv4: level_from_flags(
+            parsed.flags,
+            NLM_IPV4_INTERNET,
+            NLM_IPV4_LOCALNETWORK,
+            NLM_IPV4_SUBNET,
+            NLM_IPV4_NOTRAFFIC,
+        ),
+        ipv6: level_from_flags(
+            parsed.flags,
+            NLM_IPV6_INTERNET,
+            NLM_IPV6_LOCALNETWORK,
+            NLM_IPV6_SUBNET,
+            NLM_IPV6_NOTRAFFIC,
+        ),
+        raw_flags: parsed.flags,
+        profiles: parsed.profiles,
+        discrepancies: vec![],
+    })
+}
+
+fn level_from_flags(
+    flags: u32,
+    internet: u32,
+    local_network: u32,
+    subnet: u32,
+    no_traffic: u32,
+) -> WindowsConnectivityLevel {
+    if flags & internet != 0 {
+        WindowsConnectivityLevel::Internet
+    } else if flags & local_network != 0 {
+        WindowsConnectivityLevel::LocalNetwork
+    } else if flags & subnet != 0 {
+        WindowsConnectivityLevel::Subnet
+    } else if flags & no_traffic != 0 {
+        WindowsConnectivityLevel::NoTraffic
+    } else {
+        WindowsConnectivityLevel::Disconnected
+    }
+}
+
+// アプリの確認結果と Windows の判定の食い違いを説明する
+pub fn find_discrepancies(
+    windows: &WindowsConnectivity,
+    ipv4_connectivity: bool,
+    ipv6_connectivity: bool,
+) -> Vec<String> {
+    let mut discrepancies = Vec::new();
+    for (family, level, app_connected) in [
+        ("IPv4", windows.ipv4, ipv4_connectivity),
+        ("IPv6", windows.ipv6, ipv6_connectivity),
+    ] {
+        let windows_connected = level == WindowsConnectivityLevel::Internet;
+        if windows_connected && !app_connected {
+            discrepancies.push(format!(
+                "Windows は {} でインターネット接続ありと判定していますが、アプリからは外部に接続できません。ファイアウォールやプロキシ設定を確認してください",
+                family
+            ));
+        } else if !windows_connected && app_connected {
+            discrepancies.push(format!(
+                "アプリからは {} で外部に接続できますが、Windows はインターネット接続なしと判定しています。NCSI の確認先 (www.msftconnecttest.com) への通信が遮断されていないか確認してください",
+                family
+            ));
+        }
+    }
+    discrepancies
+}
@@ -0,0 +1,396 @@
+use hmac::{Hmac, Mac};
+use md4::{Digest, Md4};
+use md5::Md5;
+use ring::rand::{SecureRandom, SystemRandom};
+
+// NTLM（MS-NLMP）のメッセージの先頭（応答は NTLMv2 のみ送る）
+const SIGNATURE: &[u8; 8] = b"NTLMSSP\0";
+
+// NEGOTIATE_MESSAGE で提示するフラグ（UNICODE・REQUEST_TARGET・NTLM・ALWAYS_SIGN・拡張セッションセキュリティ・TARGET_INFO）
+const NEGOTIATE_UNICODE: u32 = 0x0000_0001;
+const NEGOTIATE_FLAGS: u32 =
+    NEGOTIATE_UNICODE | 0x0000_0004 | 0x0000_0200 | 0x0000_8000 | 0x0008_0000 | 0x0080_0000;
+
+// CHALLENGE_MESSAGE の TargetInfo に含まれるサーバの時刻（MsvAvTimestamp）と終端
+const AV_EOL: u16 = 0x0000;
+const AV_TIMESTAMP: u16 = 0x0007;
+
+// 1601-01-01 から 1970-01-01 までの FILETIME（100 ナノ秒単位）
+const FILETIME_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+// AUTHENTICATE_MESSAGE の固定長の部分（バージョンと MIC は含めない）
+const AUTHENTICATE_HEADER_BYTES: usize = 64;
+
+// NTLMv2 の応答のうち TargetInfo 以外の部分（NTProofStr と blob の固定長の部分）
+// 応答の長さは 16 ビットで表すため、TargetInfo はこれを引いた長さまでとする
+const NT_RESPONSE_FIXED_BYTES: usize = 48;
+
+// プロキシが送信したチャレンジ（CHALLENGE_MESSAGE）
+pub struct Challenge {
+    server_challenge: [u8; 8],
+    flags: u32,
+    target_info: Vec<u8>,
+}
+
+// 最初に送るメッセージ（ドメインとワークステーションは送らない）
+pub fn negotiate_message() -> Vec<u8> {
+    let mut message = SIGNATURE.to_vec();
+    message.extend_from_slice(&1u32.to_le_bytes());
+    message.extend_from_slice(&NEGOTIATE_FLAGS.to_le_bytes());
+    message.extend_from_slice(&[0; 16]);
+    message
+}
+
+pub fn parse_challenge(message: &[u8]) -> Result<Challenge, String> {
+    let invalid = || "プロキシの NTLM チャレンジを解析できません".to_string();
+    if message.len() < 32 || !message.starts_with(SIGNATURE) || read_u32(message, 8) != Some(2) {
+        return Err(invalid());
+    }
+    let flags = read_u32(message, 20).ok_or_else(invalid)?;
+    let server_challenge = message[24..32].try_into().map_err(|_| invalid())?;
+    // TargetInfo は古い実装では省略される
+    let target_info = match (read_u16(message, 40), read_u32(message, 44)) {
+        (Some(len), Some(offset)) if len > 0 => message
+            .get(offset as usize..)
+            .and_then(|rest| rest.get(..len as usize))
+            .ok_or_else(invalid)?
+            .to_vec(),
+        _ => Vec::new(),
+    };
+    if target_info.len() > u16::MAX as usize - NT_RESPONSE_FIXED_BYTES {
+        return Err(invalid());
+    }
+    Ok(Challenge {
+        server_challenge,
+        flags,
+        target_info,
+    })
+}
+
+// NTLMv2 の応答（ユーザ名は "DOMAIN\user" または "user@domain" の形式も指定できる）
+pub fn authenticate_message(
+    challenge: &Challenge,
+    username: &str,
+    password: &str,
+) -> Result<Vec<u8>, String> {
+    let mut client_challenge = [0u8; 8];
+    SystemRandom::new()
+        .fill(&mut client_challenge)
+        .map_err(|_| "NTLM の乱数の生成に失敗しました".to_string())?;
+    Ok(build_authenticate(
+        challenge,
+        username,
+        password,
+        client_challenge,
+        filetime_now(),
+    ))
+}
+
+// クライアントのチャレンジと時刻（サーバの時刻がない場合に使う）を指定して応答を組み立てる
+fn build_authenticate(
+    challenge: &Challenge,
+    username: &str,
+    password: &str,
+    client_challenge: [u8; 8],
+    now: u64,
+) -> Vec<u8> {
+    let (user, domain) = match username.split_once('\\') {
+        Some((domain, user)) => (user, domain),
+        None => (username, ""),
+    };
+    let response_key = nt_owf_v2(user, domain, password);
+
+    // サーバの時刻がある場合はそれを使い、LMv2 の応答は送らない
+    let timestamp = av_pair(&challenge.target_info, AV_TIMESTAMP)
+        .and_then(|value| value.try_into().ok())
+        .map(u64::from_le_bytes);
+    let mut blob = vec![0x01, 0x01, 0, 0, 0, 0, 0, 0];
+    blob.extend_from_slice(&timestamp.unwrap_or(now).to_le_bytes());
+    blob.extend_from_slice(&client_challenge);
+    blob.extend_from_slice(&[0; 4]);
+    blob.extend_from_slice(&challenge.target_info);
+    blob.extend_from_slice(&[0; 4]);
+    let nt_proof = hmac_md5(&response_key, &[&challenge.server_challenge, &blob]);
+    let nt_response = [&nt_proof[..], &blob].concat();
+    let lm_response = match timestamp {
+        Some(_) => vec![0; 24],
+        None => [
+            &hmac_md5(
+                &response_key,
+                &[&challenge.server_challenge, &client_challenge],
+            )[..],
+            &client_challenge,
+        ]
+        .concat(),
+    };
+
+    let unicode = challenge.flags & NEGOTIATE_UNICODE != 0;
+    let encode = |value: &str| {
+        if unicode {
+            utf16le(value)
+        } else {
+            value.as_bytes().to_vec()
+        }
+    };
+    let fields = [
+        lm_response,
+        nt_response,
+        encode(domain),
+        encode(user),
+        Vec::new(),
+        Vec::new(),
+    ];
+    let mut message = SIGNATURE.to_vec();
+    message.extend_from_slice(&3u32.to_le_bytes());
+    let mut payload = Vec::new();
+    for field in &fields {
+        let offset = AUTHENTICATE_HEADER_BYTES + payload.len();
+        message.extend_from_slice(&(field.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(field.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(offset as u32).to_le_bytes());
+        payload.extend_from_slice(field);
+    }
+    message.extend_from_slice(&(challenge.flags & NEGOTIATE_FLAGS).to_le_bytes());
+    message.extend_from_slice(&payload);
+    message
+}
+
+// 現在時刻の FILETIME
+fn filetime_now() -> u64 {
+    let since_epoch = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default();
+    FILETIME_UNIX_EPOCH + (since_epoch.as_nanos() / 100) as u64
+}
+
+// NTOWFv2（パスワードの MD4 を鍵に、大文字のユーザ名とドメインの HMAC-MD5）
+fn nt_owf_v2(user: &str, domain: &str, password: &str) -> [u8; 16] {
+    let password_hash: [u8; 16] = Md4::digest(utf16le(password)).into();
+    hmac_md5(
+        &password_hash,
+        &[&utf16le(&user.to_uppercase()), &utf16le(domain)],
+    )
+}
+
+fn hmac_md5(key: &[u8], parts: &[&[u8]]) -> [u8; 16] {
+    let mut mac = <Hmac<Md5> as Mac>::new_from_slice(key).expect("HMAC は任意の長さの鍵を使える");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+// TargetInfo（AV_PAIR の並び）から指定した種類の値を取り出す
+fn av_pair(target_info: &[u8], id: u16) -> Option<&[u8]> {
+    let mut pos = 0;
+    loop {
+        let av_id = read_u16(target_info, pos)?;
+        let len = read_u16(target_info, pos + 2)? as usize;
+        if av_id == AV_EOL {
+            return None;
+        }
+        let value = target_info.get(pos + 4..pos + 4 + len)?;
+        if av_id == id {
+            return Some(value);
+        }
+        pos += 4 + len;
+    }
+}
+
+fn utf16le(value: &str) -> Vec<u8> {
+    value.encode_utf16().flat_map(u16::to_le_bytes).collect()
+}
+
+fn read_u16(bytes: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        bytes.get(pos..pos + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(bytes: &[u8], pos: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(pos..pos + 4)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // MS-NLMP 4.2.4 の NTLMv2 の例
+    const USER: &str = "User";
+    const DOMAIN: &str = "Domain";
+    const PASSWORD: &str = "Password";
+    const SERVER_CHALLENGE: [u8; 8] = [0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef];
+    const CLIENT_CHALLENGE: [u8; 8] = [0xaa; 8];
+    const CHALLENGE_FLAGS: u32 = 0xe28a_8233;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    // MsvAvNbDomainName と MsvAvNbComputerName と終端
+    fn example_target_info() -> Vec<u8> {
+        let mut target_info = Vec::new();
+        for (id, value) in [(2u16, DOMAIN), (1, "Server")] {
+            let value = utf16le(value);
+            target_info.extend_from_slice(&id.to_le_bytes());
+            target_info.extend_from_slice(&(value.len() as u16).to_le_bytes());
+            target_info.extend_from_slice(&value);
+        }
+        target_info.extend_from_slice(&[0; 4]);
+        target_info
+    }
+
+    // TargetName は空、TargetInfo はヘッダの直後に置く
+    fn challenge_message(flags: u32, target_info: &[u8]) -> Vec<u8> {
+        let mut message = SIGNATURE.to_vec();
+        message.extend_from_slice(&2u32.to_le_bytes());
+        message.extend_from_slice(&[0, 0, 0, 0, 48, 0, 0, 0]);
+        message.extend_from_slice(&flags.to_le_bytes());
+        message.extend_from_slice(&SERVER_CHALLENGE);
+        message.extend_from_slice(&[0; 8]);
+        message.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(target_info.len() as u16).to_le_bytes());
+        message.extend_from_slice(&48u32.to_le_bytes());
+        message.extend_from_slice(target_info);
+        message
+    }
+
+    // AUTHENTICATE_MESSAGE の index 番目の可変長の項目
+    fn field(message: &[u8], index: usize) -> &[u8] {
+        let len = read_u16(message, 12 + index * 8).unwrap() as usize;
+        let offset = read_u32(message, 16 + index * 8).unwrap() as usize;
+        &message[offset..offset + len]
+    }
+
+    #[test]
+    fn negotiate_message_layout() {
+        assert_eq!(
+            hex(&negotiate_message()),
+            "4e544c4d53535000010000000582880000000000000000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn nt_owf_v2_matches_spec() {
+        assert_eq!(
+            hex(&nt_owf_v2(USER, DOMAIN, PASSWORD)),
+            "0c868a403bfd7a93a3001ef22ef02e3f"
+        );
+    }
+
+    #[test]
+    fn authenticate_message_matches_spec() {
+        let target_info = example_target_info();
+        let challenge = parse_challenge(&challenge_message(CHALLENGE_FLAGS, &target_info)).unwrap();
+        let message = build_authenticate(
+            &challenge,
+            &format!("{}\\{}", DOMAIN, USER),
+            PASSWORD,
+            CLIENT_CHALLENGE,
+            0,
+        );
+
+        assert!(message.starts_with(SIGNATURE));
+        assert_eq!(read_u32(&message, 8), Some(3));
+        assert_eq!(read_u32(&message, 60), Some(0x0088_8201));
+        assert_eq!(
+            hex(field(&message, 0)),
+            "86c35097ac9cec102554764a57cccc19aaaaaaaaaaaaaaaa"
+        );
+        let nt_response = field(&message, 1);
+        assert_eq!(nt_response.len(), 84);
+        assert_eq!(hex(&nt_response[..16]), "68cd0ab851e51c96aabc927bebef6a1c");
+        assert_eq!(&nt_response[16..24], &[1, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&nt_response[32..40], &CLIENT_CHALLENGE);
+        assert_eq!(&nt_response[44..80], &target_info[..]);
+        assert_eq!(field(&message, 2), &utf16le(DOMAIN)[..]);
+        assert_eq!(field(&message, 3), &utf16le(USER)[..]);
+        assert!(field(&message, 4).is_empty());
+        assert!(field(&message, 5).is_empty());
+        // 項目は固定長の部分の直後から隙間なく並ぶ
+        assert_eq!(
+            read_u32(&message, 16),
+            Some(AUTHENTICATE_HEADER_BYTES as u32)
+        );
+        assert_eq!(message.len(), AUTHENTICATE_HEADER_BYTES + 24 + 84 + 12 + 8);
+    }
+
+    #[test]
+    fn server_timestamp_replaces_lm_response() {
+        let timestamp = 0x01d0_0000_1234_5678u64;
+        let mut target_info = vec![0x07, 0x00, 0x08, 0x00];
+        target_info.extend_from_slice(&timestamp.to_le_bytes());
+        target_info.extend_from_slice(&[0; 4]);
+        let challenge = parse_challenge(&challenge_message(CHALLENGE_FLAGS, &target_info)).unwrap();
+        let message = build_authenticate(&challenge, USER, PASSWORD, CLIENT_CHALLENGE, 1);
+
+        assert_eq!(field(&message, 0), &[0; 24]);
+        assert_eq!(&field(&message, 1)[24..32], &timestamp.to_le_bytes());
+        assert!(field(&message, 2).is_empty());
+    }
+
+    #[test]
+    fn oem_challenge_sends_plain_names() {
+        let challenge = parse_challenge(&challenge_message(0x0000_0202, &[])).unwrap();
+        let message = build_authenticate(&challenge, "dom\\user", PASSWORD, CLIENT_CHALLENGE, 0);
+
+        assert_eq!(field(&message, 2), b"dom");
+        assert_eq!(field(&message, 3), b"user");
+        assert_eq!(read_u32(&message, 60), Some(0x0000_0200));
+    }
+
+    #[test]
+    fn rejects_malformed_challenge() {
+        let valid = challenge_message(CHALLENGE_FLAGS, &example_target_info());
+        assert!(parse_challenge(&valid).is_ok());
+        assert!(parse_challenge(&[]).is_err());
+        assert!(parse_challenge(&valid[..31]).is_err());
+
+        let mut signature = valid.clone();
+        signature[0] = b'X';
+        assert!(parse_challenge(&signature).is_err());
+
+        let mut message_type = valid.clone();
+        message_type[8] = 3;
+        assert!(parse_challenge(&message_type).is_err());
+
+        // TargetInfo が途中で切れている
+        assert!(parse_challenge(&valid[..valid.len() - 1]).is_err());
+
+        let mut offset = valid.clone();
+        offset[44..48].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse_challenge(&offset).is_err());
+
+        // 応答の長さが 16 ビットに収まらない TargetInfo
+        let long = challenge_message(CHALLENGE_FLAGS, &vec![0xff; u16::MAX as usize]);
+        assert!(parse_challenge(&long).is_err());
+    }
+
+    #[test]
+    fn challenge_without_target_info() {
+        let challenge = parse_challenge(&challenge_message(CHALLENGE_FLAGS, &[])).unwrap();
+        assert!(challenge.target_info.is_empty());
+
+        // 古い実装の TargetInfo のフィールドがない 32 バイトのメッセージ
+        let short = &challenge_message(CHALLENGE_FLAGS, &[])[..32];
+        let challenge = parse_challenge(short).unwrap();
+        assert_eq!(challenge.server_challenge, SERVER_CHALLENGE);
+        assert!(challenge.target_info.is_empty());
+    }
+
+    #[test]
+    fn malformed_target_info_falls_back_to_client_time() {
+        // AV_PAIR の長さが残りより長く、終端もない
+        let target_info = [0x07, 0x00, 0xff, 0x00, 0x01, 0x02];
+        assert_eq!(av_pair(&target_info, AV_TIMESTAMP), None);
+        assert_eq!(av_pair(&[0x07], AV_TIMESTAMP), None);
+
+        let challenge = parse_challenge(&challenge_message(CHALLENGE_FLAGS, &target_info)).unwrap();
+        let message = build_authenticate(&challenge, USER, PASSWORD, CLIENT_CHALLENGE, 42);
+        assert_eq!(&field(&message, 1)[24..32], &42u64.to_le_bytes());
+        assert_eq!(field(&message, 0).len(), 24);
+        assert_ne!(field(&message, 0), &[0; 24]);
+    }
+}
@@ -0,0 +1,437 @@
+use ring::digest::{digest, SHA1_FOR_LEGACY_USE_ONLY};
+use rustls::pki_types::CertificateDer;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use x509_parser::certificate::X509Certificate;
+use x509_parser::extensions::{GeneralName, ParsedExtension};
+
+use crate::http_client::{self, HttpMethod, RequestOptions};
+
+// DER のタグ
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_ENUMERATED: u8 = 0x0a;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_CONTEXT_0: u8 = 0xa0;
+
+// SHA-1（1.3.14.3.2.26）と id-pkix-ocsp-basic（1.3.6.1.5.5.7.48.1.1）の OID の内容
+const OID_SHA1: &[u8] = &[0x2b, 0x0e, 0x03, 0x02, 0x1a];
+const OID_OCSP_BASIC: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x30, 0x01, 0x01];
+
+// 証明書の機関情報アクセス（AIA）で OCSP レスポンダを示す id-ad-ocsp
+const ID_AD_OCSP: &str = "1.3.6.1.5.5.7.48.1";
+
+// OCSP レスポンダの応答として受け取る上限
+const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevocationSource {
+    // サーバがハンドシェイクで送信した応答（OCSP ステープリング）
+    Stapled,
+    // 証明書に記載された OCSP レスポンダへ問い合わせた応答
+    Responder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateStatus {
+    Good,
+    Revoked,
+    // レスポンダがその証明書を把握していない
+    Unknown,
+}
+
+// サーバ証明書の失効状態（OCSP 応答の署名は検証しない）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevocationStatus {
+    pub source: RevocationSource,
+    pub responder_url: Option<String>,
+    // 応答を取得・解析できなかった場合は None（理由は error_message）
+    pub status: Option<CertificateStatus>,
+    pub revoked_at: Option<String>,
+    pub revocation_reason: Option<String>,
+    pub produced_at: Option<String>,
+    pub this_update: Option<String>,
+    pub next_update: Option<String>,
+    // 応答の有効期限（next_update）を過ぎているか
+    pub expired: bool,
+    pub error_message: Option<String>,
+}
+
+impl RevocationStatus {
+    fn new(source: RevocationSource, responder_url: Option<String>) -> Self {
+        RevocationStatus {
+            source,
+            responder_url,
+            status: None,
+            revoked_at: None,
+            revocation_reason: None,
+            produced_at: None,
+            this_update: None,
+            next_update: None,
+            expired: false,
+            error_message: None,
+        }
+    }
+
+    fn apply(&mut self, parsed: Result<SingleResponse, String>) {
+        match parsed {
+            Ok(single) => {
+                self.expired = single
+                    .next_update
+                    .is_some_and(|next_update| next_update < chrono::Utc::now());
+                self.status = Some(single.status);
+                self.revoked_at = single.revoked_at.map(format_time);
+                self.revocation_reason = single.revocation_reason;
+                self.produced_at = single.produced_at.map(format_time);
+                self.this_update = single.this_update.map(format_time);
+                self.next_update = single.next_update.map(format_time);
+            }
+            Err(e) => self.error_message = Some(e),
+        }
+    }
+}
+
+// OCSP 応答のうちサーバ証明書に対応する部分
+struct SingleResponse {
+    status: CertificateStatus,
+    revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+    revocation_reason: Option<String>,
+    produced_at: Option<chrono::DateTime<chrono::Utc>>,
+    this_update: Option<chrono::DateTime<chrono::Utc>>,
+    next_update: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+// サーバ証明書の失効状態を、ステープリングされた応答があればそれで、なければ OCSP レスポンダへの問い合わせで確認する
+// （query_responder が false でステープリングもない場合は None）
+pub async fn check_revocation(
+    certificates: &[CertificateDer<'static>],
+    stapled: Option<&[u8]>,
+    query_responder: bool,
+    timeout_secs: u64,
+) -> Option<RevocationStatus> {
+    let (_, leaf) = x509_parser::parse_x509_certificate(certificates.first()?.as_ref()).ok()?;
+    let responder_url = responder_url(&leaf);
+
+    if let Some(stapled) = stapled {
+        let mut status = RevocationStatus::new(RevocationSource::Stapled, responder_url);
+        status.apply(parse_response(stapled, leaf.raw_serial()));
+        return Some(status);
+    }
+    if !query_responder {
+        return None;
+    }
+
+    let mut status = RevocationStatus::new(RevocationSource::Responder, responder_url.clone());
+    let Some(responder_url) = responder_url else {
+        status.error_message =
+            Some("サーバ証明書に OCSP レスポンダの URL が記載されていません".to_string());
+        return Some(status);
+    };
+    let issuer = certificates
+        .get(1)
+        .and_then(|issuer| x509_parser::parse_x509_certificate(issuer.as_ref()).ok())
+        .map(|(_, issuer)| issuer);
+    let Some(issuer) = issuer else {
+        status.error_message = Some(
+            "サーバが中間証明書を送信していないため、OCSP の問い合わせを作成できません".to_string(),
+        );
+        return Some(status);
+    };
+
+    let request = build_request(&leaf, &issuer);
+    match query(&responder_url, request, timeout_secs).await {
+        Ok(response) => status.apply(parse_response(&response, leaf.raw_serial())),
+        Err(e) => status.error_message = Some(e),
+    }
+    Some(status)
+}
+
+// 機関情報アクセス（AIA）拡張に記載された OCSP レスポンダの URL
+fn responder_url(certificate: &X509Certificate<'_>) -> Option<String> {
+    certificate
+        .extensions()
+        .iter()
+        .find_map(|extension| match extension.parsed_extension() {
+            ParsedExtension::AuthorityInfoAccess(aia) => aia
+                .accessdescs
+                .iter()
+                .filter(|desc| desc.access_method.to_id_string() == ID_AD_OCSP)
+                .find_map(|desc| match &desc.access_location {
+                    GeneralName::URI(uri) => Some(uri.to_string()),
+                    _ => None,
+                }),
+            _ => None,
+        })
+}
+
+// RFC 6960 の OCSPRequest を作成（証明書は発行者の名前と公開鍵の SHA-1、シリアル番号で指定する）
+fn build_request(leaf: &X509Certificate<'_>, issuer: &X509Certificate<'_>) -> Vec<u8> {
+    let name_hash = digest(&SHA1_FOR_LEGACY_USE_ONLY, issuer.subject().as_raw());
+    let key_hash = digest(
+        &SHA1_FOR_LEGACY_USE_ONLY,
+        &issuer.public_key().subject_public_key.data,
+    );
+    let algorithm = encode(
+        TAG_SEQUENCE,
+        &[encode(TAG_OID, OID_SHA1), encode(TAG_NULL, &[])].concat(),
+    );
+    let cert_id = encode(
+        TAG_SEQUENCE,
+        &[
+            algorithm,
+            encode(TAG_OCTET_STRING, name_hash.as_ref()),
+            encode(TAG_OCTET_STRING, key_hash.as_ref()),
+            encode(TAG_INTEGER, leaf.raw_serial()),
+        ]
+        .concat(),
+    );
+    // OCSPRequest { TBSRequest { requestList { Request { CertID } } } }
+    let request = encode(TAG_SEQUENCE, &cert_id);
+    let request_list = encode(TAG_SEQUENCE, &request);
+    let tbs_request = encode(TAG_SEQUENCE, &request_list);
+    encode(TAG_SEQUENCE, &tbs_request)
+}
+
+// OCSP レスポンダへ POST で問い合わせ、応答の DER を返す
+async fn query(
+    responder_url: &str,
+    request: Vec<u8>,
+    timeout_secs: u64,
+) -> Result<Vec<u8>, String> {
+    let parsed_url =
+        Url::parse(responder_url).map_err(|e| format!("無効な OCSP レスポンダの URL: {}", e))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "OCSP レスポンダの URL からホスト名を抽出できません".to_string())?;
+    let dns_resolution = crate::resolve_dns(host).await;
+    let ip_address = dns_resolution
+        .ipv4_addresses
+        .first()
+        .or(dns_resolution.ipv6_addresses.first())
+        .ok_or_else(|| format!("OCSP レスポンダの名前を解決できません: {}", host))?;
+
+    let options = RequestOptions {
+        method: HttpMethod::Post,
+        headers: vec![(
+            "content-type".to_string(),
+            "application/ocsp-request".to_string(),
+        )],
+        body: Some(request.into()),
+        ..RequestOptions::default()
+    };
+    let outcome = http_client::send_request(&http_client::HttpRequest {
+        url: responder_url,
+        options: &options,
+        ip_address: Some(ip_address),
+        host,
+        port: parsed_url.port(),
+        ignore_tls_errors: false,
+        source_address: None,
+        verbose: false,
+        max_body_bytes: MAX_RESPONSE_BYTES,
+        timeout_secs,
+    })
+    .await;
+    // 企業のプロキシ環境では OCSP レスポンダへの直接の通信が遮断されていることが多い
+    if let Some(e) = outcome.error_message {
+        return Err(format!(
+            "OCSP レスポンダに接続できません: {}（失効確認の通信が遮断されていると、Windows などで TLS エラーになる場合があります）",
+            e
+        ));
+    }
+    match outcome.status_code {
+        Some(200) => {}
+        status_code => {
+            return Err(format!(
+                "OCSP レスポンダがエラーを返しました（HTTPステータス: {}）",
+                status_code.unwrap_or_default()
+            ))
+        }
+    }
+    if outcome.body_truncated {
+        return Err("OCSP レスポンダの応答が大きすぎます".to_string());
+    }
+    outcome
+        .body
+        .filter(|body| !body.is_empty())
+        .ok_or_else(|| "OCSP レスポンダの応答が空です".to_string())
+}
+
+// OCSPResponse を解析し、シリアル番号が一致する証明書の状態を返す
+fn parse_response(response: &[u8], serial: &[u8]) -> Result<SingleResponse, String> {
+    let malformed = || "OCSP 応答の形式が正しくありません".to_string();
+    let (response, _) = expect(response, TAG_SEQUENCE).ok_or_else(malformed)?;
+    let (response_status, rest) = expect(response, TAG_ENUMERATED).ok_or_else(malformed)?;
+    if response_status != [0] {
+        return Err(format!(
+            "OCSP レスポンダがエラーを返しました: {}",
+            response_status_name(response_status)
+        ));
+    }
+
+    // responseBytes [0] { responseType, response }
+    let (response_bytes, _) = expect(rest, TAG_CONTEXT_0).ok_or_else(malformed)?;
+    let (response_bytes, _) = expect(response_bytes, TAG_SEQUENCE).ok_or_else(malformed)?;
+    let (response_type, rest) = expect(response_bytes, TAG_OID).ok_or_else(malformed)?;
+    if response_type != OID_OCSP_BASIC {
+        return Err("未対応の種類の OCSP 応答です".to_string());
+    }
+    let (basic, _) = expect(rest, TAG_OCTET_STRING).ok_or_else(malformed)?;
+    let (basic, _) = expect(basic, TAG_SEQUENCE).ok_or_else(malformed)?;
+    let (response_data, _) = expect(basic, TAG_SEQUENCE).ok_or_else(malformed)?;
+
+    // ResponseData { version [0] OPTIONAL, responderID, producedAt, responses, ... }
+    let mut rest = response_data;
+    if rest.first() == Some(&TAG_CONTEXT_0) {
+        rest = read_tlv(rest).ok_or_else(malformed)?.2;
+    }
+    let (_, _, rest) = read_tlv(rest).ok_or_else(malformed)?;
+    let (produced_at, rest) = expect(rest, TAG_GENERALIZED_TIME).ok_or_else(malformed)?;
+    let (mut responses, _) = expect(rest, TAG_SEQUENCE).ok_or_else(malformed)?;
+
+    while !responses.is_empty() {
+        let (single, next) = expect(responses, TAG_SEQUENCE).ok_or_else(malformed)?;
+        responses = next;
+        let (cert_id, rest) = expect(single, TAG_SEQUENCE).ok_or_else(malformed)?;
+        if certificate_serial(cert_id) != Some(serial) {
+            continue;
+        }
+        let mut parsed = parse_single_response(rest).ok_or_else(malformed)?;
+        parsed.produced_at = parse_time(produced_at);
+        return Ok(parsed);
+    }
+    Err("OCSP 応答にサーバ証明書の状態が含まれていません".to_string())
+}
+
+// CertID { hashAlgorithm, issuerNameHash, issuerKeyHash, serialNumber } のシリアル番号
+fn certificate_serial(cert_id: &[u8]) -> Option<&[u8]> {
+    let (_, _, rest) = read_tlv(cert_id)?;
+    let (_, _, rest) = read_tlv(rest)?;
+    let (_, _, rest) = read_tlv(rest)?;
+    expect(rest, TAG_INTEGER).map(|(serial, _)| serial)
+}
+
+// SingleResponse の certID より後（certStatus, thisUpdate, nextUpdate [0] OPTIONAL）
+fn parse_single_response(input: &[u8]) -> Option<SingleResponse> {
+    let (tag, content, rest) = read_tlv(input)?;
+    let mut single = SingleResponse {
+        status: CertificateStatus::Unknown,
+        revoked_at: None,
+        revocation_reason: None,
+        produced_at: None,
+        this_update: None,
+        next_update: None,
+    };
+    match tag {
+        // good [0] IMPLICIT NULL
+        0x80 => single.status = CertificateStatus::Good,
+        // revoked [1] IMPLICIT RevokedInfo { revocationTime, revocationReason [0] OPTIONAL }
+        0xa1 => {
+            single.status = CertificateStatus::Revoked;
+            let (revoked_at, reason) = expect(content, TAG_GENERALIZED_TIME)?;
+            single.revoked_at = parse_time(revoked_at);
+            single.revocation_reason = expect(reason, TAG_CONTEXT_0)
+                .and_then(|(reason, _)| expect(reason, TAG_ENUMERATED))
+                .map(|(reason, _)| revocation_reason_name(reason));
+        }
+        // unknown [2] IMPLICIT NULL
+        0x82 => single.status = CertificateStatus::Unknown,
+        _ => return None,
+    }
+    let (this_update, rest) = expect(rest, TAG_GENERALIZED_TIME)?;
+    single.this_update = parse_time(this_update);
+    single.next_update = expect(rest, TAG_CONTEXT_0)
+        .and_then(|(next_update, _)| expect(next_update, TAG_GENERALIZED_TIME))
+        .and_then(|(next_update, _)| parse_time(next_update));
+    Some(single)
+}
+
+fn response_status_name(status: &[u8]) -> String {
+    match status {
+        [1] => "malformedRequest".to_string(),
+        [2] => "internalError".to_string(),
+        [3] => "tryLater".to_string(),
+        [5] => "sigRequired".to_string(),
+        [6] => "unauthorized".to_string(),
+        _ => format!("responseStatus {:?}", status),
+    }
+}
+
+// RFC 5280 の CRLReason
+fn revocation_reason_name(reason: &[u8]) -> String {
+    match reason {
+        [0] => "unspecified".to_string(),
+        [1] => "keyCompromise".to_string(),
+        [2] => "cACompromise".to_string(),
+        [3] => "affiliationChanged".to_string(),
+        [4] => "superseded".to_string(),
+        [5] => "cessationOfOperation".to_string(),
+        [6] => "certificateHold".to_string(),
+        [8] => "removeFromCRL".to_string(),
+        [9] => "privilegeWithdrawn".to_string(),
+        [10] => "aACompromise".to_string(),
+        _ => format!("reason {:?}", reason),
+    }
+}
+
+// GeneralizedTime（"20240101120000Z"、秒の小数部は無視する）
+fn parse_time(value: &[u8]) -> Option<chrono::DateTime<chrono::Utc>> {
+    let value = std::str::from_utf8(value).ok()?;
+    chrono::NaiveDateTime::parse_from_str(value.get(..14)?, "%Y%m%d%H%M%S")
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+fn format_time(time: chrono::DateTime<chrono::Utc>) -> String {
+    time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+// DER の要素を先頭から1つ読み、タグと内容、残りを返す
+fn read_tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (length, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let octets = (first & 0x7f) as usize;
+        if octets == 0 || octets > 4 || rest.len() < octets {
+            return None;
+        }
+        let length = rest[..octets]
+            .iter()
+            .fold(0usize, |length, &b| (length << 8) | b as usize);
+        (length, &rest[octets..])
+    };
+    if rest.len() < length {
+        return None;
+    }
+    Some((tag, &rest[..length], &rest[length..]))
+}
+
+// 指定したタグの要素を読み、内容と残りを返す
+fn expect(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (actual, content, rest) = read_tlv(input)?;
+    (actual == tag).then_some((content, rest))
+}
+
+fn encode(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    if content.len() < 0x80 {
+        encoded.push(content.len() as u8);
+    } else {
+        let length = content.len().to_be_bytes();
+        let start = length
+            .iter()
+            .position(|&b| b != 0)
+            .unwrap_or(length.len() - 1);
+        encoded.push(0x80 | (length.len() - start) as u8);
+        encoded.extend_from_slice(&length[start..]);
+    }
+    encoded.extend_from_slice(content);
+    encoded
+}
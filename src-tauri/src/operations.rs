@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::task::AbortHandle;
+
+// stop_all 実行時にフロントエンドへ通知するイベント名
+pub const OPERATIONS_STOPPED_EVENT: &str = "operations-stopped";
+
+// 操作の開始時に ID を通知するイベント名（コマンドの完了前に cancel_operation で中止できるようにする）
+pub const OPERATION_STARTED_EVENT: &str = "operation-started";
+
+// 中止された操作の run_operation のエラー（定期的な測定で失敗と区別するため）
+pub const OPERATION_CANCELLED_MESSAGE: &str = "操作は中止されました";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Ping,
+    SpeedTest,
+    Iperf3,
+    Diagnostic,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoppedOperation {
+    pub id: u64,
+    pub kind: OperationKind,
+    pub target: String,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveOperation {
+    pub id: u64,
+    pub kind: OperationKind,
+    pub target: String,
+    pub elapsed_ms: u64,
+    pub progress_percent: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StopAllResult {
+    pub stopped_count: usize,
+    pub operations: Vec<StoppedOperation>,
+}
+
+struct RunningOperation {
+    kind: OperationKind,
+    target: String,
+    started_at: Instant,
+    abort_handle: AbortHandle,
+    progress: Arc<Mutex<Option<f64>>>,
+}
+
+impl RunningOperation {
+    fn to_active(&self, id: u64) -> ActiveOperation {
+        ActiveOperation {
+            id,
+            kind: self.kind,
+            target: self.target.clone(),
+            elapsed_ms: self.started_at.elapsed().as_millis() as u64,
+            progress_percent: self.progress.lock().ok().and_then(|p| *p),
+        }
+    }
+}
+
+tokio::task_local! {
+    // 実行中の操作の進捗（run_operation 内のタスクからのみ参照できる）
+    static CURRENT_PROGRESS: Arc<Mutex<Option<f64>>>;
+}
+
+// 実行中の操作の進捗（0〜100%）を更新する（run_operation の外では何もしない）
+pub fn report_progress(percent: f64) {
+    let _ = CURRENT_PROGRESS.try_with(|progress| {
+        if let Ok(mut progress) = progress.lock() {
+            *progress = Some(percent.clamp(0.0, 100.0));
+        }
+    });
+}
+
+// 実行中の操作の一覧（Tauri の State として管理）
+#[derive(Default)]
+pub struct OperationRegistry {
+    next_id: AtomicU64,
+    running: Mutex<HashMap<u64, RunningOperation>>,
+}
+
+// 操作を中止可能なタスクとして実行し、完了まで待つ
+// 中止された場合は子プロセスも kill_on_drop により終了する
+pub async fn run_operation<T, F>(
+    app: &AppHandle,
+    kind: OperationKind,
+    target: &str,
+    future: F,
+) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: Future<Output = Result<T, String>> + Send + 'static,
+{
+    let registry = app.state::<OperationRegistry>();
+    let id = registry.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+    let progress = Arc::new(Mutex::new(None));
+    let handle = tokio::spawn(CURRENT_PROGRESS.scope(progress.clone(), future));
+
+    let operation = RunningOperation {
+        kind,
+        target: target.to_string(),
+        started_at: Instant::now(),
+        abort_handle: handle.abort_handle(),
+        progress,
+    };
+    let started = operation.to_active(id);
+    if let Ok(mut running) = registry.running.lock() {
+        running.insert(id, operation);
+    }
+    if let Err(e) = app.emit(OPERATION_STARTED_EVENT, &started) {
+        eprintln!("Failed to emit {}: {}", OPERATION_STARTED_EVENT, e);
+    }
+
+    let guard = OperationGuard {
+        registry: &registry,
+        id,
+        abort_handle: handle.abort_handle(),
+    };
+    let joined = handle.await;
+    drop(guard);
+
+    match joined {
+        Ok(result) => result,
+        Err(e) if e.is_cancelled() => Err(OPERATION_CANCELLED_MESSAGE.to_string()),
+        Err(e) => Err(format!("操作の実行中にエラーが発生しました: {}", e)),
+    }
+}
+
+// 完了時に一覧から除く（連続測定の停止などで待機中の run_operation が破棄された場合は操作も中止する）
+struct OperationGuard<'a> {
+    registry: &'a OperationRegistry,
+    id: u64,
+    abort_handle: AbortHandle,
+}
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        self.abort_handle.abort();
+        if let Ok(mut running) = self.registry.running.lock() {
+            running.remove(&self.id);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn stop_all(
+    app: AppHandle,
+    registry: State<'_, OperationRegistry>,
+) -> Result<StopAllResult, String> {
+    let stopped: Vec<(u64, RunningOperation)> = registry
+        .running
+        .lock()
+        .map_err(|_| "操作一覧のロック取得に失敗".to_string())?
+        .drain()
+        .collect();
+
+    let mut operations: Vec<StoppedOperation> = stopped
+        .into_iter()
+        .map(|(id, op)| {
+            op.abort_handle.abort();
+            StoppedOperation {
+                id,
+                kind: op.kind,
+                target: op.target,
+                elapsed_ms: op.started_at.elapsed().as_millis() as u64,
+            }
+        })
+        .collect();
+    operations.sort_by_key(|op| op.id);
+
+    let result = StopAllResult {
+        stopped_count: operations.len(),
+        operations,
+    };
+
+    if let Err(e) = app.emit(OPERATIONS_STOPPED_EVENT, &result) {
+        eprintln!("Failed to emit {}: {}", OPERATIONS_STOPPED_EVENT, e);
+    }
+
+    Ok(result)
+}
+
+// 実行中の操作の一覧（開始順）
+#[tauri::command]
+pub async fn list_active_operations(
+    registry: State<'_, OperationRegistry>,
+) -> Result<Vec<ActiveOperation>, String> {
+    let mut operations: Vec<ActiveOperation> = registry
+        .running
+        .lock()
+        .map_err(|_| "操作一覧のロック取得に失敗".to_string())?
+        .iter()
+        .map(|(id, op)| op.to_active(*id))
+        .collect();
+    operations.sort_by_key(|op| op.id);
+    Ok(operations)
+}
+
+// 指定した操作の状態（終了済みの場合は None）
+#[tauri::command]
+pub async fn get_operation_progress(
+    registry: State<'_, OperationRegistry>,
+    id: u64,
+) -> Result<Option<ActiveOperation>, String> {
+    Ok(registry
+        .running
+        .lock()
+        .map_err(|_| "操作一覧のロック取得に失敗".to_string())?
+        .get(&id)
+        .map(|op| op.to_active(id)))
+}
+
+// 指定した操作のみを中止
+#[tauri::command]
+pub async fn cancel_operation(
+    app: AppHandle,
+    registry: State<'_, OperationRegistry>,
+    id: u64,
+) -> Result<StopAllResult, String> {
+    let op = registry
+        .running
+        .lock()
+        .map_err(|_| "操作一覧のロック取得に失敗".to_string())?
+        .remove(&id)
+        .ok_or_else(|| format!("操作 {} は実行中ではありません", id))?;
+
+    op.abort_handle.abort();
+    let result = StopAllResult {
+        stopped_count: 1,
+        operations: vec![StoppedOperation {
+            id,
+            kind: op.kind,
+            target: op.target,
+            elapsed_ms: op.started_at.elapsed().as_millis() as u64,
+        }],
+    };
+
+    if let Err(e) = app.emit(OPERATIONS_STOPPED_EVENT, &result) {
+        eprintln!("Failed to emit {}: {}", OPERATIONS_STOPPED_EVENT, e);
+    }
+
+    Ok(result)
+}
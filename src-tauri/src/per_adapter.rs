@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use tauri::AppHandle;
+use url::Url;
+
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::{DnsResolution, HttpPingResult, NetworkAdapter};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdapterPingResult {
+    pub adapter_name: String,
+    pub ipv4_source: Option<String>,
+    pub ipv6_source: Option<String>,
+    pub ipv4: HttpPingResult,
+    pub ipv6: HttpPingResult,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PerAdapterPingResult {
+    pub url: String,
+    pub dns_resolution: DnsResolution,
+    pub adapters: Vec<AdapterPingResult>,
+}
+
+#[tauri::command]
+pub async fn ping_http_per_adapter(
+    app: AppHandle,
+    url: String,
+    ignore_tls_errors: Option<bool>,
+) -> Result<PerAdapterPingResult, String> {
+    let target = url.clone();
+    run_operation(
+        &app,
+        OperationKind::Ping,
+        &target,
+        execute_ping_per_adapter(url, ignore_tls_errors.unwrap_or(false)),
+    )
+    .await
+}
+
+async fn execute_ping_per_adapter(
+    url: String,
+    ignore_tls_errors: bool,
+) -> Result<PerAdapterPingResult, String> {
+    if ignore_tls_errors {
+        crate::log_security_warning("TLS証明書検証が無効化されています");
+    }
+
+    crate::validate_url(&url)?;
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+
+    let (adapters, _) = crate::get_network_interfaces().await?;
+    if adapters.iter().all(|a| a.ip_addresses.is_empty()) {
+        return Err(
+            "IPアドレスが割り当てられたネットワークアダプタがありません。LANケーブルやWi-Fiの接続を確認してください"
+                .to_string(),
+        );
+    }
+
+    let dns_result = crate::resolve_dns(&host).await;
+
+    // アダプタごとに順番に測定（同時に行うと帯域を奪い合うため）
+    let mut results = Vec::new();
+    for (i, adapter) in adapters.iter().enumerate() {
+        report_progress(i as f64 * 100.0 / adapters.len() as f64);
+        let ipv4_source = ipv4_source_address(adapter);
+        let ipv6_source = ipv6_source_address(adapter);
+        if ipv4_source.is_none() && ipv6_source.is_none() {
+            continue;
+        }
+
+        let (ipv4, ipv6) = tokio::join!(
+            ping_from(
+                &url,
+                &dns_result.ipv4_addresses,
+                &host,
+                ignore_tls_errors,
+                parsed_url.port(),
+                ipv4_source.as_deref(),
+                4,
+            ),
+            ping_from(
+                &url,
+                &dns_result.ipv6_addresses,
+                &host,
+                ignore_tls_errors,
+                parsed_url.port(),
+                ipv6_source.as_deref(),
+                6,
+            ),
+        );
+
+        results.push(AdapterPingResult {
+            adapter_name: adapter.name.clone(),
+            ipv4_source,
+            ipv6_source,
+            ipv4,
+            ipv6,
+        });
+    }
+
+    Ok(PerAdapterPingResult {
+        url,
+        dns_resolution: dns_result,
+        adapters: results,
+    })
+}
+
+// 送信元アドレスを固定して接続（アドレスがなければ未実施として返す）
+async fn ping_from(
+    url: &str,
+    ip_addresses: &[String],
+    host: &str,
+    ignore_tls_errors: bool,
+    port: Option<u16>,
+    source_address: Option<&str>,
+    ip_version: u8,
+) -> HttpPingResult {
+    let Some(source_address) = source_address else {
+        return HttpPingResult {
+            url: url.to_string(),
+            ip_address: None,
+            status_code: None,
+            response_time_ms: None,
+            success: false,
+            error_message: Some(format!(
+                "このアダプタには IPv{} アドレスがありません",
+                ip_version
+            )),
+            verbose_log: None,
+            verbose_info: None,
+            started_at: None,
+            finished_at: None,
+            timings: None,
+            alt_svc: None,
+            http_version: None,
+            tls_version: None,
+            cipher_suite: None,
+            downloaded_bytes: None,
+            download_bytes_per_sec: None,
+            download_truncated: false,
+            content: None,
+            response_headers: Vec::new(),
+            response_body_prefix: Vec::new(),
+            captured_headers: None,
+            header_assertions: Vec::new(),
+            body_assertions: Vec::new(),
+            attempts: Vec::new(),
+            redirects: Vec::new(),
+            curl_command: None,
+            second_request: None,
+            certificate: None,
+            warnings: Vec::new(),
+            proxy_auth: None,
+        };
+    };
+
+    crate::connect_to_ip_with_host(
+        url.to_string(),
+        &crate::http_client::RequestOptions::default(),
+        ip_addresses,
+        host,
+        ignore_tls_errors,
+        port,
+        false,
+        Some(source_address),
+        crate::http_client::DEFAULT_TIMEOUT_SECS,
+    )
+    .await
+}
+
+// 送信元に使う IPv4 アドレス（リンクローカルを除く）
+fn ipv4_source_address(adapter: &NetworkAdapter) -> Option<String> {
+    adapter
+        .ip_addresses
+        .iter()
+        .filter_map(|a| a.parse::<Ipv4Addr>().ok())
+        .find(|ip| !ip.is_link_local() && !ip.is_loopback())
+        .map(|ip| ip.to_string())
+}
+
+// 送信元に使う IPv6 アドレス（グローバルまたはユニークローカル）
+fn ipv6_source_address(adapter: &NetworkAdapter) -> Option<String> {
+    adapter
+        .ip_addresses
+        .iter()
+        .filter_map(|a| a.parse::<Ipv6Addr>().ok())
+        .find(|ip| {
+            let first = ip.segments()[0];
+            (first & 0xe000) == 0x2000 || (first & 0xfe00) == 0xfc00
+        })
+        .map(|ip| ip.to_string())
+}
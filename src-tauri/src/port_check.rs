@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tokio::task::JoinSet;
+
+use crate::dns::system_lookup;
+use crate::operations::{report_progress, run_operation, OperationKind};
+
+// すべての TCP ポートで待ち受けているエコーサーバ
+const PORT_ECHO_HOST: &str = "portquiz.net";
+
+// 疎通の基準とする（通常ブロックされない）ポート
+const CONTROL_PORT: u16 = 80;
+
+// ISP やファイアウォールでブロックされやすいポート
+const DEFAULT_PORTS: [u16; 5] = [25, 139, 445, 465, 587];
+
+const CONNECT_TIMEOUT_SECS: u64 = 3;
+const MAX_PORTS: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PortStatus {
+    Open,
+    Refused,
+    Filtered,
+    Error,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortCheckEntry {
+    pub port: u16,
+    pub service: String,
+    pub status: PortStatus,
+    pub connect_ms: Option<u64>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PortBlockingResult {
+    pub endpoint: String,
+    pub ip_address: String,
+    pub control_reachable: bool,
+    pub ports: Vec<PortCheckEntry>,
+    pub blocked_ports: Vec<u16>,
+}
+
+#[tauri::command]
+pub async fn check_port_blocking(
+    app: AppHandle,
+    ports: Option<Vec<u16>>,
+) -> Result<PortBlockingResult, String> {
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        PORT_ECHO_HOST,
+        execute_port_blocking_check(ports),
+    )
+    .await
+}
+
+async fn execute_port_blocking_check(
+    ports: Option<Vec<u16>>,
+) -> Result<PortBlockingResult, String> {
+    let mut ports = ports.unwrap_or_else(|| DEFAULT_PORTS.to_vec());
+    ports.sort_unstable();
+    ports.dedup();
+    if ports.is_empty() || ports.len() > MAX_PORTS || ports.contains(&0) {
+        return Err(format!(
+            "確認するポートは 1〜65535 の範囲で {} 個以内で指定してください",
+            MAX_PORTS
+        ));
+    }
+
+    let ip_address = system_lookup(PORT_ECHO_HOST)
+        .await?
+        .into_iter()
+        .find(|a| !a.contains(':'))
+        .ok_or_else(|| format!("{} の IPv4 アドレスが見つかりません", PORT_ECHO_HOST))?;
+    let ip: std::net::IpAddr = ip_address
+        .parse()
+        .map_err(|_| format!("無効なIPアドレス: {}", ip_address))?;
+
+    let control = check_port(SocketAddr::new(ip, CONTROL_PORT)).await;
+    let control_reachable = control.status == PortStatus::Open;
+    if !control_reachable {
+        return Err(format!(
+            "{} の {} 番ポートに接続できません。インターネット接続を確認してください",
+            PORT_ECHO_HOST, CONTROL_PORT
+        ));
+    }
+
+    let mut tasks = JoinSet::new();
+    for port in ports {
+        tasks.spawn(check_port(SocketAddr::new(ip, port)));
+    }
+    let total = tasks.len();
+    let mut entries = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(entry) = joined {
+            entries.push(entry);
+        }
+        report_progress((total - tasks.len()) as f64 * 100.0 / total as f64);
+    }
+    entries.sort_by_key(|e| e.port);
+
+    let blocked_ports = entries
+        .iter()
+        .filter(|e| matches!(e.status, PortStatus::Filtered | PortStatus::Refused))
+        .map(|e| e.port)
+        .collect();
+
+    Ok(PortBlockingResult {
+        endpoint: PORT_ECHO_HOST.to_string(),
+        ip_address,
+        control_reachable,
+        ports: entries,
+        blocked_ports,
+    })
+}
+
+// 指定アドレスへ TCP 接続を試み、結果を分類
+async fn check_port(addr: SocketAddr) -> PortCheckEntry {
+    let start = Instant::now();
+    let result = tokio::time::timeout(
+        Duration::from_secs(CONNECT_TIMEOUT_SECS),
+        crate::dry_run::connect_tcp(addr),
+    )
+    .await;
+    let elapsed = start.elapsed().as_millis() as u64;
+
+    let (status, connect_ms, error_message) = match result {
+        Ok(Ok(_)) => (PortStatus::Open, Some(elapsed), None),
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => (
+            PortStatus::Refused,
+            Some(elapsed),
+            Some("接続が拒否されました".to_string()),
+        ),
+        Ok(Err(e)) => (PortStatus::Error, None, Some(format!("接続エラー: {}", e))),
+        Err(_) => (
+            PortStatus::Filtered,
+            None,
+            Some("応答がありません（通信が破棄されている可能性があります）".to_string()),
+        ),
+    };
+
+    PortCheckEntry {
+        port: addr.port(),
+        service: well_known_service(addr.port()).to_string(),
+        status,
+        connect_ms,
+        error_message,
+    }
+}
+
+// ポート番号に対応する代表的なサービス名
+fn well_known_service(port: u16) -> &'static str {
+    match port {
+        25 => "SMTP",
+        80 => "HTTP",
+        139 => "NetBIOS",
+        443 => "HTTPS",
+        445 => "SMB",
+        465 => "SMTPS",
+        587 => "SMTP Submission",
+        _ => "",
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+use crate::process::run_command;
+
+// 判定用の PowerShell の起動を待つ時間
+const DETECT_TIMEOUT_SECS: u64 = 10;
+
+// AppLocker や WDAC のポリシーによる PowerShell の制限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerShellMode {
+    // 制限なし（FullLanguage）
+    Full,
+    // ConstrainedLanguage などの制限モード（.NET の呼び出しなどが失敗する）
+    Constrained,
+    // 起動できない、またはスクリプトを実行できない
+    Blocked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerShellStatus {
+    pub mode: PowerShellMode,
+    pub language_mode: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl PowerShellStatus {
+    // PowerShell のスクリプトで情報を取得してよいか
+    pub fn is_available(&self) -> bool {
+        self.mode == PowerShellMode::Full
+    }
+
+    fn blocked(reason: String) -> Self {
+        PowerShellStatus {
+            mode: PowerShellMode::Blocked,
+            language_mode: None,
+            reason: Some(reason),
+        }
+    }
+}
+
+// ポリシーはアプリ実行中にはまず変わらないため、初回のみ確認
+static STATUS: OnceCell<PowerShellStatus> = OnceCell::const_new();
+
+// ドライラン中で未確認の場合は制限なしと仮定する
+static ASSUMED_STATUS: PowerShellStatus = PowerShellStatus {
+    mode: PowerShellMode::Full,
+    language_mode: None,
+    reason: None,
+};
+
+// 言語モードを出力させ、起動できるかとあわせて判定
+async fn detect_status() -> PowerShellStatus {
+    let args = crate::powershell_args("$ExecutionContext.SessionState.LanguageMode");
+    let output = match tokio::time::timeout(
+        Duration::from_secs(DETECT_TIMEOUT_SECS),
+        run_command("powershell", &args),
+    )
+    .await
+    {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            return PowerShellStatus::blocked(format!("PowerShell を起動できません: {}", e))
+        }
+        Err(_) => {
+            return PowerShellStatus::blocked("PowerShell の起動がタイムアウトしました".to_string())
+        }
+    };
+
+    let language_mode = crate::decode_command_output(&output.stdout)
+        .trim()
+        .to_string();
+    if !output.status.success() || language_mode.is_empty() {
+        let stderr = crate::decode_command_output(&output.stderr);
+        let reason = stderr
+            .lines()
+            .map(|l| l.trim())
+            .find(|l| !l.is_empty())
+            .unwrap_or("PowerShell のスクリプトを実行できません");
+        return PowerShellStatus::blocked(reason.to_string());
+    }
+
+    let mode = if language_mode == "FullLanguage" {
+        PowerShellMode::Full
+    } else {
+        PowerShellMode::Constrained
+    };
+    PowerShellStatus {
+        mode,
+        reason: (mode == PowerShellMode::Constrained).then(|| {
+            format!(
+                "PowerShell が {} で実行されています（ポリシーによる制限）",
+                language_mode
+            )
+        }),
+        language_mode: Some(language_mode),
+    }
+}
+
+// PowerShell を制限なく使えるか
+pub async fn status() -> &'static PowerShellStatus {
+    // ドライランでは確認のために PowerShell を起動しない（確認済みならその結果を使う）
+    if crate::dry_run::is_enabled() && !STATUS.initialized() {
+        return &ASSUMED_STATUS;
+    }
+    STATUS.get_or_init(detect_status).await
+}
+
+// PowerShell の単一引用符の文字列リテラルにする
+// PowerShell は全角の引用符（U+2018〜U+201B）も単一引用符として扱うため、これらも重ねてエスケープする
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('\'');
+    for c in value.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            quoted.push(c);
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Serialize};
+
+use crate::process::run_command;
+
+// アドレス選択ポリシー（RFC 6724）を取得するスクリプト
+// netsh の出力は OS の表示言語で変わるため、同じ情報を返す Get-NetPrefixPolicy を使用
+const PREFIX_POLICY_SCRIPT: &str = r#"@(Get-NetPrefixPolicy -ErrorAction Stop | ForEach-Object {
+    [pscustomobject]@{
+        prefix = [string]$_.Prefix
+        precedence = [int]$_.Precedence
+        label = [int]$_.Label
+    }
+}) | ConvertTo-Json -Compress"#;
+
+// Windows 既定のポリシーテーブル（プレフィックス, 優先順位, ラベル）
+const DEFAULT_POLICIES: [(&str, u32, u32); 9] = [
+    ("::1/128", 50, 0),
+    ("::/0", 40, 1),
+    ("::ffff:0:0/96", 35, 4),
+    ("2002::/16", 30, 2),
+    ("2001::/32", 5, 5),
+    ("fc00::/7", 3, 13),
+    ("fec0::/10", 1, 11),
+    ("3ffe::/16", 1, 12),
+    ("::/96", 1, 3),
+];
+
+// IPv4 射影アドレス（IPv4 宛ての通信を表す）と IPv6 全体のプレフィックス
+const IPV4_MAPPED_PREFIX: &str = "::ffff:0:0/96";
+const IPV6_DEFAULT_PREFIX: &str = "::/0";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PrefixPolicy {
+    pub prefix: String,
+    pub precedence: u32,
+    pub label: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PrefixPolicyTable {
+    pub policies: Vec<PrefixPolicy>,
+    pub is_default: bool,
+    pub prefers_ipv4: bool,
+    #[serde(default)]
+    pub findings: Vec<String>,
+}
+
+// Windows のアドレス選択ポリシーテーブルを取得
+pub async fn get_prefix_policies() -> Result<PrefixPolicyTable, String> {
+    let args = [
+        "-NoProfile".to_string(),
+        "-WindowStyle".to_string(),
+        "Hidden".to_string(),
+        "-Command".to_string(),
+        PREFIX_POLICY_SCRIPT.to_string(),
+    ];
+    let output = run_command("powershell", &args)
+        .await
+        .map_err(|e| format!("PowerShellコマンド実行失敗: {}", e))?;
+    if !output.status.success() {
+        return Err("アドレス選択ポリシーを取得できません".to_string());
+    }
+
+    let stdout = crate::decode_command_output(&output.stdout);
+    let policies: Vec<PrefixPolicy> = serde_json::from_str(stdout.trim())
+        .map_err(|e| format!("アドレス選択ポリシーの解析失敗: {}", e))?;
+
+    let is_default = policies.len() == DEFAULT_POLICIES.len()
+        && DEFAULT_POLICIES.iter().all(|(prefix, precedence, label)| {
+            policies
+                .iter()
+                .any(|p| p.prefix == *prefix && p.precedence == *precedence && p.label == *label)
+        });
+    let precedence_of = |prefix: &str| {
+        policies
+            .iter()
+            .find(|p| p.prefix == prefix)
+            .map(|p| p.precedence)
+    };
+    let prefers_ipv4 = match (
+        precedence_of(IPV4_MAPPED_PREFIX),
+        precedence_of(IPV6_DEFAULT_PREFIX),
+    ) {
+        (Some(ipv4), Some(ipv6)) => ipv4 > ipv6,
+        (Some(_), None) => true,
+        _ => false,
+    };
+
+    let mut findings = Vec::new();
+    if prefers_ipv4 {
+        findings.push(
+            "アドレス選択ポリシーで IPv4 が IPv6 より優先されています。IPv6 で接続できる場合でも IPv4 が使われます。意図した設定でなければ、管理者権限で `netsh interface ipv6 reset` を実行するか、レジストリの DisabledComponents の値を確認してください"
+                .to_string(),
+        );
+    } else if !is_default {
+        findings.push(
+            "アドレス選択ポリシーが Windows の既定値から変更されています。VPN ソフトやグループポリシーによる変更の可能性があります"
+                .to_string(),
+        );
+    }
+
+    Ok(PrefixPolicyTable {
+        policies,
+        is_default,
+        prefers_ipv4,
+        findings,
+    })
+}
@@ -0,0 +1,83 @@
+use std::process::{Output, Stdio};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::dry_run::{self, DRY_RUN_MESSAGE};
+
+// 外部コマンドを非同期で実行
+// 呼び出し元の処理が中止（drop）された場合は子プロセスも終了させる
+pub async fn run_command(program: &str, args: &[String]) -> std::io::Result<Output> {
+    if dry_run::intercept_process(program, args, None) {
+        return Err(std::io::Error::other(DRY_RUN_MESSAGE));
+    }
+    Command::new(program)
+        .args(args)
+        .creation_flags(0x08000200) // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
+        .kill_on_drop(true)
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .stdout(Stdio::piped())
+        .output()
+        .await
+}
+
+// 標準入力にデータを書き込みながら外部コマンドを実行（アップロード測定用）
+pub async fn run_command_with_input(
+    program: &str,
+    args: &[String],
+    input_len: u64,
+) -> std::io::Result<Output> {
+    let detail = format!("標準入力に {} バイトのゼロ埋めデータを書き込む", input_len);
+    if dry_run::intercept_process(program, args, Some(detail)) {
+        return Err(std::io::Error::other(DRY_RUN_MESSAGE));
+    }
+    let mut child = Command::new(program)
+        .args(args)
+        .creation_flags(0x08000200) // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
+        .kill_on_drop(true)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // ゼロ埋めデータを分割して書き込む
+        let chunk = vec![0u8; 64 * 1024];
+        let mut remaining = input_len;
+        while remaining > 0 {
+            let size = remaining.min(chunk.len() as u64) as usize;
+            if stdin.write_all(&chunk[..size]).await.is_err() {
+                break;
+            }
+            remaining -= size as u64;
+        }
+    }
+
+    child.wait_with_output().await
+}
+
+// 標準入力に指定データを渡して外部コマンドを実行
+pub async fn run_command_with_stdin(
+    program: &str,
+    args: &[String],
+    input: &[u8],
+) -> std::io::Result<Output> {
+    let detail = format!("標準入力に {} バイトを書き込む", input.len());
+    if dry_run::intercept_process(program, args, Some(detail)) {
+        return Err(std::io::Error::other(DRY_RUN_MESSAGE));
+    }
+    let mut child = Command::new(program)
+        .args(args)
+        .creation_flags(0x08000200) // CREATE_NO_WINDOW | CREATE_NEW_PROCESS_GROUP
+        .kill_on_drop(true)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input).await?;
+    }
+
+    child.wait_with_output().await
+}
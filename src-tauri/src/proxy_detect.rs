@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tauri::AppHandle;
+
+use crate::curl::{describe_exit_code, run_curl, CurlErrorKind};
+use crate::dns::system_lookup;
+use crate::operations::{run_operation, OperationKind};
+
+// 受信したリクエストヘッダをそのまま返すエコーサービス
+const ECHO_HOST: &str = "httpbin.org";
+
+// Host ヘッダ不一致テストで指定する別サイトとその本文に含まれる文字列
+const MISMATCH_HOST: &str = "example.com";
+const MISMATCH_MARKER: &str = "Example Domain";
+
+// 接続時間の比較に使うサンプル数
+const LATENCY_SAMPLES: usize = 3;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProxyHeaderCheck {
+    pub http_headers: HashMap<String, String>,
+    pub https_headers: HashMap<String, String>,
+    pub injected_headers: Vec<String>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HostMismatchCheck {
+    pub ip_address: Option<String>,
+    pub requested_host: String,
+    pub status_code: Option<u16>,
+    pub served_requested_host: bool,
+    pub error_message: Option<String>,
+    #[serde(default)]
+    pub curl_error: Option<CurlErrorKind>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LatencySignatureCheck {
+    pub http_connect_ms: Option<f64>,
+    pub https_connect_ms: Option<f64>,
+    pub suspicious: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransparentProxyResult {
+    pub header_check: ProxyHeaderCheck,
+    pub host_mismatch_check: HostMismatchCheck,
+    pub latency_check: LatencySignatureCheck,
+    pub proxy_suspected: bool,
+    pub findings: Vec<String>,
+}
+
+// httpbin の /headers レスポンス解析用の内部構造体
+#[derive(Deserialize)]
+struct EchoHeadersResponse {
+    headers: HashMap<String, String>,
+}
+
+#[tauri::command]
+pub async fn detect_transparent_proxy(app: AppHandle) -> Result<TransparentProxyResult, String> {
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        "transparent_proxy",
+        execute_transparent_proxy_check(),
+    )
+    .await
+}
+
+async fn execute_transparent_proxy_check() -> Result<TransparentProxyResult, String> {
+    let (header_check, host_mismatch_check, latency_check) = tokio::join!(
+        check_injected_headers(),
+        check_host_mismatch(),
+        check_latency_signature(),
+    );
+
+    let mut findings = Vec::new();
+    if !header_check.injected_headers.is_empty() {
+        findings.push(format!(
+            "HTTP 通信にのみ経路上で追加されたヘッダがあります: {}",
+            header_check.injected_headers.join(", ")
+        ));
+    }
+    if host_mismatch_check.served_requested_host {
+        findings.push(format!(
+            "別サーバの IP アドレス宛ての通信に {} の内容が返されました。Host ヘッダで転送先を決める透過プロキシの可能性があります",
+            host_mismatch_check.requested_host
+        ));
+    }
+    if latency_check.suspicious {
+        findings.push(
+            "HTTP (80番) の接続時間が HTTPS (443番) より極端に短く、手前で応答している機器がある可能性があります"
+                .to_string(),
+        );
+    }
+
+    let proxy_suspected = !header_check.injected_headers.is_empty()
+        || host_mismatch_check.served_requested_host
+        || latency_check.suspicious;
+
+    Ok(TransparentProxyResult {
+        header_check,
+        host_mismatch_check,
+        latency_check,
+        proxy_suspected,
+        findings,
+    })
+}
+
+// エコーサービスで受信ヘッダを取得
+async fn fetch_echo_headers(scheme: &str) -> Result<HashMap<String, String>, String> {
+    let output = run_curl(vec![
+        "--silent".to_string(),
+        "--max-time".to_string(),
+        "10".to_string(),
+        format!("{}://{}/headers", scheme, ECHO_HOST),
+    ])
+    .await?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "{}://{} への接続に失敗: {}",
+            scheme,
+            ECHO_HOST,
+            describe_exit_code(output.status.code())
+        ));
+    }
+
+    let body: EchoHeadersResponse = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("エコー応答の解析失敗: {}", e))?;
+    Ok(body.headers)
+}
+
+// HTTP と HTTPS で受信ヘッダを比較し、HTTP にだけ追加されたヘッダを検出
+async fn check_injected_headers() -> ProxyHeaderCheck {
+    let (http_result, https_result) =
+        tokio::join!(fetch_echo_headers("http"), fetch_echo_headers("https"));
+
+    match (http_result, https_result) {
+        (Ok(http_headers), Ok(https_headers)) => {
+            let mut injected_headers: Vec<String> = http_headers
+                .keys()
+                .filter(|k| {
+                    !https_headers
+                        .keys()
+                        .any(|h| h.eq_ignore_ascii_case(k))
+                })
+                .cloned()
+                .collect();
+            injected_headers.sort();
+            ProxyHeaderCheck {
+                http_headers,
+                https_headers,
+                injected_headers,
+                error_message: None,
+            }
+        }
+        (http_result, https_result) => ProxyHeaderCheck {
+            http_headers: HashMap::new(),
+            https_headers: HashMap::new(),
+            injected_headers: vec![],
+            error_message: http_result.err().or(https_result.err()),
+        },
+    }
+}
+
+// エコーサービスの IP アドレスに別ホスト名の Host ヘッダを付けて接続
+async fn check_host_mismatch() -> HostMismatchCheck {
+    let mut check = HostMismatchCheck {
+        ip_address: None,
+        requested_host: MISMATCH_HOST.to_string(),
+        status_code: None,
+        served_requested_host: false,
+        error_message: None,
+        curl_error: None,
+    };
+
+    let ip_address = match system_lookup(ECHO_HOST).await {
+        Ok(addrs) => addrs.into_iter().find(|a| !a.contains(':')),
+        Err(e) => {
+            check.error_message = Some(e);
+            return check;
+        }
+    };
+    let Some(ip_address) = ip_address else {
+        check.error_message = Some(format!("{} の IPv4 アドレスが見つかりません", ECHO_HOST));
+        return check;
+    };
+    check.ip_address = Some(ip_address.clone());
+
+    let output = run_curl(vec![
+        "--silent".to_string(),
+        "--max-time".to_string(),
+        "10".to_string(),
+        "--header".to_string(),
+        format!("Host: {}", MISMATCH_HOST),
+        "--write-out".to_string(),
+        "\n%{http_code}".to_string(),
+        format!("http://{}/", ip_address),
+    ])
+    .await;
+
+    match output {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+            let (body, status) = stdout.rsplit_once('\n').unwrap_or(("", stdout.as_str()));
+            check.status_code = status.trim().parse().ok();
+            check.served_requested_host = body.contains(MISMATCH_MARKER);
+        }
+        Ok(output) => {
+            check.curl_error = Some(CurlErrorKind::from_exit_code(output.status.code()));
+            check.error_message = Some(describe_exit_code(output.status.code()));
+        }
+        Err(e) => check.error_message = Some(e),
+    }
+
+    check
+}
+
+// 指定 URL への TCP 接続時間（ミリ秒）の最小値を測定
+async fn measure_connect_ms(url: String) -> Option<f64> {
+    let mut best: Option<f64> = None;
+    for _ in 0..LATENCY_SAMPLES {
+        let output = run_curl(vec![
+            "--silent".to_string(),
+            "--output".to_string(),
+            "nul".to_string(),
+            "--write-out".to_string(),
+            "%{time_connect}".to_string(),
+            "--max-time".to_string(),
+            "5".to_string(),
+            url.clone(),
+        ])
+        .await;
+
+        if let Ok(output) = output {
+            if let Ok(secs) = String::from_utf8_lossy(&output.stdout).trim().parse::<f64>() {
+                if secs > 0.0 {
+                    let ms = secs * 1000.0;
+                    best = Some(best.map_or(ms, |b| b.min(ms)));
+                }
+            }
+        }
+    }
+    best
+}
+
+// HTTP と HTTPS の接続時間を比較（80番だけ手前で応答していないか）
+async fn check_latency_signature() -> LatencySignatureCheck {
+    let (http_connect_ms, https_connect_ms) = tokio::join!(
+        measure_connect_ms(format!("http://{}/get", ECHO_HOST)),
+        measure_connect_ms(format!("https://{}/get", ECHO_HOST)),
+    );
+
+    let suspicious = match (http_connect_ms, https_connect_ms) {
+        (Some(http), Some(https)) => http * 2.0 < https && https - http > 10.0,
+        _ => false,
+    };
+
+    LatencySignatureCheck {
+        http_connect_ms,
+        https_connect_ms,
+        suspicious,
+    }
+}
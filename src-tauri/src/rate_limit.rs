@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+// アプリデータディレクトリに保存する設定ファイル名
+pub const RATE_LIMIT_CONFIG_FILE_NAME: &str = "rate_limit.json";
+
+// 同一ホストへの1分あたりの最大リクエスト数
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 60;
+const MIN_REQUESTS_PER_MINUTE: u32 = 1;
+const MAX_REQUESTS_PER_MINUTE: u32 = 600;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub requests_per_minute: u32,
+}
+
+// 全機能で共有する上限値とホストごとの送信時刻（AppHandle を持たない curl 呼び出しからも参照するため static）
+static REQUESTS_PER_MINUTE: AtomicU32 = AtomicU32::new(DEFAULT_REQUESTS_PER_MINUTE);
+static SENT_AT: LazyLock<Mutex<HashMap<String, VecDeque<Instant>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// 同一ホストへの送信数が上限に達している場合は、枠が空くまで待ってから送信枠を確保する
+pub async fn acquire(host: &str) {
+    // ドライランでは送信しないため枠を確保しない
+    if crate::dry_run::is_enabled() {
+        return;
+    }
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    loop {
+        let wait = {
+            let Ok(mut sent_at) = SENT_AT.lock() else {
+                return;
+            };
+            let now = Instant::now();
+            let history = sent_at.entry(host.clone()).or_default();
+            while history
+                .front()
+                .is_some_and(|t| now.duration_since(*t) >= WINDOW)
+            {
+                history.pop_front();
+            }
+
+            let limit = REQUESTS_PER_MINUTE.load(Ordering::Relaxed) as usize;
+            if history.len() < limit {
+                history.push_back(now);
+                return;
+            }
+            // 最も古い送信から1分経過するまで待つ
+            history
+                .front()
+                .map(|t| WINDOW.saturating_sub(now.duration_since(*t)))
+                .unwrap_or(WINDOW)
+        };
+        eprintln!("Rate limit reached for {}; waiting {:?}", host, wait);
+        tokio::time::sleep(wait).await;
+    }
+}
+
+// curl の引数に含まれる URL の宛先ホストごとに送信枠を確保
+pub async fn acquire_for_curl_args(args: &[String]) {
+    let mut hosts: Vec<String> = Vec::new();
+    for arg in args {
+        let Ok(url) = Url::parse(arg) else {
+            continue;
+        };
+        if url.scheme() != "http" && url.scheme() != "https" {
+            continue;
+        }
+        if let Some(host) = url.host_str() {
+            hosts.push(host.to_string());
+        }
+    }
+    // --next で同じ URL を複数回指定した場合はその回数分を数える
+    for host in hosts {
+        acquire(&host).await;
+    }
+}
+
+fn config_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("アプリデータディレクトリの取得に失敗: {}", e))?;
+    Ok(dir.join(RATE_LIMIT_CONFIG_FILE_NAME))
+}
+
+fn validate_config(config: &RateLimitConfig) -> Result<(), String> {
+    if !(MIN_REQUESTS_PER_MINUTE..=MAX_REQUESTS_PER_MINUTE).contains(&config.requests_per_minute) {
+        return Err(format!(
+            "1分あたりのリクエスト数は {} から {} の範囲で指定してください",
+            MIN_REQUESTS_PER_MINUTE, MAX_REQUESTS_PER_MINUTE
+        ));
+    }
+    Ok(())
+}
+
+// 起動時に設定ファイルから上限値を読み込む
+pub fn load_rate_limit(app: &AppHandle) {
+    let Ok(path) = config_file_path(app) else {
+        return;
+    };
+    let Ok(content) = fs::read_to_string(&path) else {
+        return;
+    };
+    match serde_json::from_str::<RateLimitConfig>(&content) {
+        Ok(config) if validate_config(&config).is_ok() => {
+            REQUESTS_PER_MINUTE.store(config.requests_per_minute, Ordering::Relaxed);
+        }
+        Ok(_) => eprintln!("Invalid rate limit in {:?}", path),
+        Err(e) => eprintln!("Invalid rate limit config file {:?}: {}", path, e),
+    }
+}
+
+#[tauri::command]
+pub async fn get_rate_limit() -> Result<RateLimitConfig, String> {
+    Ok(RateLimitConfig {
+        requests_per_minute: REQUESTS_PER_MINUTE.load(Ordering::Relaxed),
+    })
+}
+
+// 上限値を保存（None の場合は既定値に戻す）
+#[tauri::command]
+pub async fn set_rate_limit(
+    app: AppHandle,
+    config: Option<RateLimitConfig>,
+) -> Result<RateLimitConfig, String> {
+    let path = config_file_path(&app)?;
+
+    let Some(config) = config else {
+        if path.exists() {
+            fs::remove_file(&path).map_err(|e| format!("設定ファイルの削除に失敗: {}", e))?;
+        }
+        REQUESTS_PER_MINUTE.store(DEFAULT_REQUESTS_PER_MINUTE, Ordering::Relaxed);
+        return get_rate_limit().await;
+    };
+
+    validate_config(&config)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("設定ディレクトリの作成に失敗: {}", e))?;
+    }
+    let body = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("設定のシリアライズに失敗: {}", e))?;
+    fs::write(&path, body).map_err(|e| format!("設定ファイルの保存に失敗: {}", e))?;
+    REQUESTS_PER_MINUTE.store(config.requests_per_minute, Ordering::Relaxed);
+
+    Ok(config)
+}
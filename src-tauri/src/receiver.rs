@@ -0,0 +1,379 @@
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response};
+use hyper_util::rt::TokioIo;
+use rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+use rustls::ServerConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::AppHandle;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tokio_rustls::TlsAcceptor;
+
+use crate::http_client;
+use crate::operations::{report_progress, run_operation, OperationKind};
+
+// 待ち受けるポートと待ち時間（秒）の既定値と範囲
+const DEFAULT_PORT: u16 = 8443;
+const DEFAULT_WAIT_SECS: u64 = 120;
+const MIN_WAIT_SECS: u64 = 5;
+const MAX_WAIT_SECS: u64 = 600;
+
+// TLS ハンドシェイクと1リクエストの受信のタイムアウト（秒）
+const CONNECTION_TIMEOUT_SECS: u64 = 10;
+
+// 受信する本文の上限（超えた分は読まない）
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+// 記録する失敗した接続の上限（スキャナなどからの接続で増え続けないようにする）
+const MAX_REJECTED_CONNECTIONS: usize = 20;
+
+const RESPONSE_BODY: &str = "ghttpping: inbound request received\n";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InboundRequest {
+    pub received_at: String,
+    pub remote_address: String,
+    pub local_address: String,
+    pub tls: bool,
+    // クライアントが送った SNI（IP アドレスで接続した場合は None）
+    pub server_name: Option<String>,
+    pub method: String,
+    pub target: String,
+    pub http_version: String,
+    // Authorization や Cookie の値は残さない
+    pub headers: BTreeMap<String, String>,
+    pub body_bytes: usize,
+    pub body_truncated: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejectedConnection {
+    pub remote_address: String,
+    pub error_message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InboundTestResult {
+    pub port: u16,
+    pub https: bool,
+    pub listening_addresses: Vec<String>,
+    // 自己署名証明書の SHA-256 フィンガープリント（ブラウザの警告画面で照合できる）
+    pub certificate_sha256: Option<String>,
+    pub received: Option<InboundRequest>,
+    pub rejected_connections: Vec<RejectedConnection>,
+    pub timed_out: bool,
+}
+
+enum ConnectionEvent {
+    Received(InboundRequest),
+    Rejected(RejectedConnection),
+}
+
+// 一時的に HTTPS（自己署名証明書）または HTTP で待ち受け、外部から届いた最初のリクエストの内容を返す
+// ポート転送や受信側のファイアウォールの設定が正しいかを確認するためのもの
+#[tauri::command]
+pub async fn wait_for_inbound_request(
+    app: AppHandle,
+    port: Option<u16>,
+    https: Option<bool>,
+    wait_secs: Option<u64>,
+) -> Result<InboundTestResult, String> {
+    let port = port.unwrap_or(DEFAULT_PORT);
+    if port == 0 {
+        return Err("待ち受けるポートは 1〜65535 の範囲で指定してください".to_string());
+    }
+    let wait_secs = wait_secs.unwrap_or(DEFAULT_WAIT_SECS);
+    if !(MIN_WAIT_SECS..=MAX_WAIT_SECS).contains(&wait_secs) {
+        return Err(format!(
+            "待ち時間は {} から {} 秒の範囲で指定してください",
+            MIN_WAIT_SECS, MAX_WAIT_SECS
+        ));
+    }
+
+    let target = format!("inbound :{}", port);
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        &target,
+        execute_inbound_test(port, https.unwrap_or(true), wait_secs),
+    )
+    .await
+}
+
+async fn execute_inbound_test(
+    port: u16,
+    https: bool,
+    wait_secs: u64,
+) -> Result<InboundTestResult, String> {
+    let (acceptor, certificate_sha256) = if https {
+        let (acceptor, fingerprint) = self_signed_acceptor()?;
+        (Some(acceptor), Some(fingerprint))
+    } else {
+        (None, None)
+    };
+
+    if crate::dry_run::intercept(
+        crate::dry_run::ActionKind::Listen,
+        format!("0.0.0.0:{}, [::]:{}", port, port),
+        None,
+    ) {
+        return Err(crate::dry_run::DRY_RUN_MESSAGE.to_string());
+    }
+
+    // IPv4 と IPv6 の両方で待ち受ける（片方のみ失敗した場合は残りで続ける）
+    let mut listeners = Vec::new();
+    let mut bind_errors = Vec::new();
+    for address in [
+        SocketAddr::from((Ipv4Addr::UNSPECIFIED, port)),
+        SocketAddr::from((Ipv6Addr::UNSPECIFIED, port)),
+    ] {
+        match TcpListener::bind(address).await {
+            Ok(listener) => listeners.push(listener),
+            Err(e) => bind_errors.push(format!("{}: {}", address, e)),
+        }
+    }
+    if listeners.is_empty() {
+        return Err(format!(
+            "ポート {} で待ち受けできません（他のアプリが使用中の可能性があります）: {}",
+            port,
+            bind_errors.join(", ")
+        ));
+    }
+    let listening_addresses = listeners
+        .iter()
+        .filter_map(|l| l.local_addr().ok())
+        .map(|a| a.to_string())
+        .collect();
+
+    // 戻るときに JoinSet を破棄して、待ち受けと処理中の接続を終了する
+    let (sender, mut receiver) = mpsc::channel(16);
+    let mut accept_tasks = JoinSet::new();
+    for listener in listeners {
+        accept_tasks.spawn(accept_loop(listener, acceptor.clone(), sender.clone()));
+    }
+    drop(sender);
+
+    let deadline = tokio::time::sleep(Duration::from_secs(wait_secs));
+    tokio::pin!(deadline);
+    let started = tokio::time::Instant::now();
+    let mut progress = tokio::time::interval(Duration::from_secs(1));
+    let mut received = None;
+    let mut rejected_connections = Vec::new();
+    loop {
+        tokio::select! {
+            event = receiver.recv() => match event {
+                Some(ConnectionEvent::Received(request)) => {
+                    received = Some(request);
+                    break;
+                }
+                Some(ConnectionEvent::Rejected(rejected)) => {
+                    if rejected_connections.len() < MAX_REJECTED_CONNECTIONS {
+                        rejected_connections.push(rejected);
+                    }
+                }
+                None => break,
+            },
+            _ = progress.tick() => {
+                report_progress(started.elapsed().as_secs_f64() * 100.0 / wait_secs as f64);
+            }
+            _ = &mut deadline => break,
+        }
+    }
+    accept_tasks.shutdown().await;
+
+    Ok(InboundTestResult {
+        port,
+        https,
+        listening_addresses,
+        certificate_sha256,
+        timed_out: received.is_none(),
+        received,
+        rejected_connections,
+    })
+}
+
+// localhost と 127.0.0.1 / ::1 を名前に持つ自己署名証明書を作成
+fn self_signed_acceptor() -> Result<(TlsAcceptor, String), String> {
+    let certified = rcgen::generate_simple_self_signed(vec![
+        "localhost".to_string(),
+        "127.0.0.1".to_string(),
+        "::1".to_string(),
+    ])
+    .map_err(|e| format!("自己署名証明書の作成に失敗: {}", e))?;
+    let certificate = certified.cert.der().clone();
+    let fingerprint = ring::digest::digest(&ring::digest::SHA256, &certificate)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect();
+    let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der()));
+
+    let mut config = ServerConfig::builder_with_provider(http_client::crypto_provider())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| format!("TLS設定の作成に失敗: {}", e))?
+        .with_no_client_auth()
+        .with_single_cert(vec![certificate], key)
+        .map_err(|e| format!("TLS設定の作成に失敗: {}", e))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok((TlsAcceptor::from(Arc::new(config)), fingerprint))
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    acceptor: Option<TlsAcceptor>,
+    sender: mpsc::Sender<ConnectionEvent>,
+) {
+    let mut connections = JoinSet::new();
+    loop {
+        let Ok((stream, remote)) = listener.accept().await else {
+            // ファイルハンドルの枯渇などで失敗し続ける場合に備えて少し待つ
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            continue;
+        };
+        connections.spawn(handle_connection(
+            stream,
+            remote,
+            acceptor.clone(),
+            sender.clone(),
+        ));
+        // 終了した接続のタスクを回収
+        while connections.try_join_next().is_some() {}
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    remote: SocketAddr,
+    acceptor: Option<TlsAcceptor>,
+    sender: mpsc::Sender<ConnectionEvent>,
+) {
+    let local = stream
+        .local_addr()
+        .map(|a| a.to_string())
+        .unwrap_or_default();
+    let timeout = Duration::from_secs(CONNECTION_TIMEOUT_SECS);
+    let rejected = |error_message: String| {
+        ConnectionEvent::Rejected(RejectedConnection {
+            remote_address: remote.to_string(),
+            error_message,
+        })
+    };
+
+    let result = match acceptor {
+        Some(acceptor) => match tokio::time::timeout(timeout, acceptor.accept(stream)).await {
+            Ok(Ok(tls_stream)) => {
+                let server_name = tls_stream.get_ref().1.server_name().map(str::to_string);
+                tokio::time::timeout(
+                    timeout,
+                    serve_one(TokioIo::new(tls_stream), remote, local, true, server_name),
+                )
+                .await
+            }
+            Ok(Err(e)) => {
+                let _ = sender
+                    .send(rejected(format!("TLS ハンドシェイクに失敗: {}", e)))
+                    .await;
+                return;
+            }
+            Err(_) => {
+                let _ = sender
+                    .send(rejected(
+                        "TLS ハンドシェイクがタイムアウトしました".to_string(),
+                    ))
+                    .await;
+                return;
+            }
+        },
+        None => {
+            tokio::time::timeout(
+                timeout,
+                serve_one(TokioIo::new(stream), remote, local, false, None),
+            )
+            .await
+        }
+    };
+
+    let event = match result {
+        Ok(Ok(request)) => ConnectionEvent::Received(request),
+        Ok(Err(e)) => rejected(e),
+        Err(_) => rejected("リクエストの受信がタイムアウトしました".to_string()),
+    };
+    let _ = sender.send(event).await;
+}
+
+// 1件のリクエストに応答して接続を閉じ、受信した内容を返す（応答を送り終えてから結果を通知する）
+async fn serve_one<I>(
+    io: I,
+    remote: SocketAddr,
+    local: String,
+    tls: bool,
+    server_name: Option<String>,
+) -> Result<InboundRequest, String>
+where
+    I: hyper::rt::Read + hyper::rt::Write + Unpin + Send + 'static,
+{
+    let captured = Arc::new(Mutex::new(None));
+    let service_captured = captured.clone();
+    let service = service_fn(move |request: Request<Incoming>| {
+        let captured = service_captured.clone();
+        let local = local.clone();
+        let server_name = server_name.clone();
+        async move {
+            let received_at = crate::now_rfc3339();
+            let (parts, body) = request.into_parts();
+            let headers: Vec<(String, String)> = parts
+                .headers
+                .iter()
+                .map(|(name, value)| {
+                    (
+                        name.as_str().to_string(),
+                        String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                    )
+                })
+                .collect();
+            let (body_bytes, body_truncated) =
+                match Limited::new(body, MAX_BODY_BYTES).collect().await {
+                    Ok(collected) => (collected.to_bytes().len(), false),
+                    Err(_) => (MAX_BODY_BYTES, true),
+                };
+            if let Ok(mut captured) = captured.lock() {
+                *captured = Some(InboundRequest {
+                    received_at,
+                    remote_address: remote.to_string(),
+                    local_address: local,
+                    tls,
+                    server_name,
+                    method: parts.method.to_string(),
+                    target: parts.uri.to_string(),
+                    http_version: format!("{:?}", parts.version),
+                    headers: http_client::header_map(&headers),
+                    body_bytes,
+                    body_truncated,
+                });
+            }
+            Ok::<_, Infallible>(Response::new(Full::new(Bytes::from_static(
+                RESPONSE_BODY.as_bytes(),
+            ))))
+        }
+    });
+
+    let served = http1::Builder::new()
+        .keep_alive(false)
+        .serve_connection(io, service)
+        .await;
+    let request = captured.lock().ok().and_then(|mut c| c.take());
+    match (request, served) {
+        (Some(request), _) => Ok(request),
+        (None, Err(e)) => Err(format!("HTTP リクエストとして解析できません: {}", e)),
+        (None, Ok(())) => Err("リクエストを受信する前に切断されました".to_string()),
+    }
+}
@@ -0,0 +1,144 @@
+use serde_json::Value;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+// グローバル IP を公開する際に残すプレフィックス長（ISP/ASN 程度の粒度）
+const IPV4_KEEP_PREFIX: u8 = 16;
+const IPV6_KEEP_PREFIX: u8 = 32;
+
+const LOCAL_IPV4_MASK: &str = "<local-ipv4>";
+const LOCAL_IPV6_MASK: &str = "<local-ipv6>";
+const SSID_MASK: &str = "<ssid>";
+
+#[tauri::command]
+pub async fn redact_result(data: Value) -> Result<Value, String> {
+    let mut data = data;
+    redact_value(&mut data);
+    Ok(data)
+}
+
+// JSON 値に含まれる IP アドレス・MAC アドレス・SSID を共有用にマスク
+pub fn redact_value(value: &mut Value) {
+    match value {
+        Value::String(s) => *s = redact_text(s),
+        Value::Array(items) => items.iter_mut().for_each(redact_value),
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                if is_ssid_key(key) && item.is_string() {
+                    *item = Value::String(SSID_MASK.to_string());
+                } else {
+                    redact_value(item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_ssid_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    key == "ssid" || key.ends_with("_ssid")
+}
+
+// 文字列中のアドレスらしき部分を置き換える（ログなど自由形式の文字列にも対応）
+pub fn redact_text(text: &str) -> String {
+    let is_token_char = |c: char| c.is_ascii_hexdigit() || matches!(c, ':' | '.' | '-');
+
+    let mut result = String::with_capacity(text.len());
+    let mut token = String::new();
+    for c in text.chars() {
+        if is_token_char(c) {
+            token.push(c);
+        } else {
+            if !token.is_empty() {
+                result.push_str(&redact_token(&token));
+                token.clear();
+            }
+            result.push(c);
+        }
+    }
+    if !token.is_empty() {
+        result.push_str(&redact_token(&token));
+    }
+    result
+}
+
+fn redact_token(token: &str) -> String {
+    if let Some(masked) = mask_mac(token) {
+        return masked;
+    }
+    if let Ok(ip) = token.parse::<Ipv6Addr>() {
+        return mask_ipv6(&ip);
+    }
+    if let Ok(ip) = token.parse::<Ipv4Addr>() {
+        return mask_ipv4(&ip);
+    }
+    // 文末のピリオドや "アドレス:ポート"、範囲表記などを分割して再判定
+    if let Some(trimmed) = token.strip_suffix('.') {
+        return format!("{}.", redact_token(trimmed));
+    }
+    if token.contains('-') {
+        return token
+            .split('-')
+            .map(redact_token)
+            .collect::<Vec<_>>()
+            .join("-");
+    }
+    if token.contains(':') && token.contains('.') {
+        return token
+            .split(':')
+            .map(redact_token)
+            .collect::<Vec<_>>()
+            .join(":");
+    }
+    token.to_string()
+}
+
+// MAC アドレスはベンダー部分（OUI）のみ残す
+fn mask_mac(token: &str) -> Option<String> {
+    let separator = if token.contains(':') { ':' } else { '-' };
+    let parts: Vec<&str> = token.split(separator).collect();
+    let is_mac = parts.len() == 6
+        && parts
+            .iter()
+            .all(|p| p.len() == 2 && p.chars().all(|c| c.is_ascii_hexdigit()));
+    if !is_mac {
+        return None;
+    }
+    let masked = [parts[0], parts[1], parts[2], "xx", "xx", "xx"];
+    Some(masked.join(&separator.to_string()))
+}
+
+fn mask_ipv4(ip: &Ipv4Addr) -> String {
+    if ip.is_loopback() || ip.is_unspecified() || ip.is_broadcast() || ip.is_multicast() {
+        return ip.to_string();
+    }
+    if !crate::is_global_ipv4(ip) || is_shared_ipv4(ip) {
+        return LOCAL_IPV4_MASK.to_string();
+    }
+    let mask = u32::MAX << (32 - IPV4_KEEP_PREFIX);
+    let prefix = Ipv4Addr::from(u32::from(*ip) & mask);
+    format!("{}/{}", prefix, IPV4_KEEP_PREFIX)
+}
+
+// キャリアグレード NAT の共有アドレス（100.64.0.0/10）
+fn is_shared_ipv4(ip: &Ipv4Addr) -> bool {
+    let octets = ip.octets();
+    octets[0] == 100 && (octets[1] & 0xc0) == 64
+}
+
+fn mask_ipv6(ip: &Ipv6Addr) -> String {
+    if ip.is_loopback() || ip.is_unspecified() || ip.is_multicast() {
+        return ip.to_string();
+    }
+    if let Some(v4) = ip.to_ipv4_mapped() {
+        return mask_ipv4(&v4);
+    }
+    let first = ip.segments()[0];
+    // リンクローカル（fe80::/10）とユニークローカル（fc00::/7）
+    if (first & 0xffc0) == 0xfe80 || (first & 0xfe00) == 0xfc00 {
+        return LOCAL_IPV6_MASK.to_string();
+    }
+    let mask = u128::MAX << (128 - IPV6_KEEP_PREFIX);
+    let prefix = Ipv6Addr::from(u128::from(*ip) & mask);
+    format!("{}/{}", prefix, IPV6_KEEP_PREFIX)
+}
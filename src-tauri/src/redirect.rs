@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::http_client::{self, HttpMethod, RequestOptions};
+use crate::HttpPingResult;
+
+// たどるリダイレクトの上限
+pub const MAX_REDIRECTS: usize = 10;
+
+// リダイレクト先への1回分のリクエスト
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedirectHop {
+    pub url: String,
+    pub status_code: Option<u16>,
+    pub ip_address: Option<String>,
+    pub response_time_ms: Option<u64>,
+    pub error_message: Option<String>,
+}
+
+// リダイレクトの応答の場合は Location から次の URL を求める（Location がなければ最終的な応答とみなす）
+fn next_url(
+    current: &Url,
+    status_code: u16,
+    headers: &[(String, String)],
+) -> Option<Result<Url, String>> {
+    if !matches!(status_code, 301 | 302 | 303 | 307 | 308) {
+        return None;
+    }
+    let location = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("location"))
+        .map(|(_, value)| value.trim())?;
+    Some(
+        current
+            .join(location)
+            .map_err(|e| format!("Location ヘッダの URL が不正です: {}", e)),
+    )
+}
+
+// 次のリクエストの内容（303 と、301/302 の POST はブラウザと同様に本文を送らない GET に変える）
+fn next_request(current: &RequestOptions, status_code: u16, same_host: bool) -> RequestOptions {
+    let mut next = current.clone();
+    // 同じ接続での 2 回目のリクエストは最初の URL でのみ行う
+    next.reuse_connection = false;
+    let to_get = (status_code == 303 && current.method != HttpMethod::Head)
+        || (matches!(status_code, 301 | 302) && current.method == HttpMethod::Post);
+    if to_get {
+        next.method = HttpMethod::Get;
+        next.body = None;
+        next.headers.retain(|(name, _)| name != "content-type");
+    }
+    // 別のホストへは認証情報や Host・SNI の上書きを送らない
+    if !same_host {
+        next.sni = None;
+        next.headers.retain(|(name, _)| {
+            !matches!(
+                name.as_str(),
+                "authorization" | "proxy-authorization" | "cookie" | "host"
+            )
+        });
+    }
+    next
+}
+
+// 最初の応答がリダイレクトの場合は同じアドレスファミリで Location をたどり、最終的な応答で成否を判定
+// （接続時間などは測定対象のアドレスへの最初のリクエストのものを残す、プロキシが名前を解決した場合は以降もプロキシに任せる）
+pub async fn follow_redirects(
+    result: &mut HttpPingResult,
+    request: &RequestOptions,
+    ignore_tls_errors: bool,
+    source_address: Option<&str>,
+    save_verbose_log: bool,
+    timeout_secs: u64,
+) {
+    let Ok(mut current_url) = Url::parse(&result.url) else {
+        return;
+    };
+    let mut current_ip = result.ip_address.clone();
+    let ipv6 = current_ip.as_deref().is_some_and(|ip| ip.contains(':'));
+    let mut options = request.clone();
+    let mut status_code = result.status_code;
+    let mut connection_error = None;
+    let mut redirect_error = None;
+    let mut verbose_log = result.verbose_log.take();
+
+    while let Some(status) = status_code {
+        let next = match next_url(&current_url, status, &result.response_headers) {
+            None => break,
+            Some(Ok(next)) => next,
+            Some(Err(e)) => {
+                redirect_error = Some(e);
+                break;
+            }
+        };
+        if result.redirects.len() >= MAX_REDIRECTS {
+            redirect_error = Some(format!(
+                "リダイレクトが {} 回を超えたため中断しました",
+                MAX_REDIRECTS
+            ));
+            break;
+        }
+        let host = match next_host(&next) {
+            Ok(host) => host,
+            Err(e) => {
+                redirect_error = Some(e);
+                break;
+            }
+        };
+
+        // 同じホストへのリダイレクトは同じアドレスに接続し、別のホストは同じファミリのアドレスを解決
+        let same_host = current_url
+            .host_str()
+            .is_some_and(|h| h.eq_ignore_ascii_case(&host));
+        if !same_host && current_ip.is_some() {
+            let dns_result = crate::resolve_dns(&host).await;
+            let addresses = if ipv6 {
+                dns_result.ipv6_addresses
+            } else {
+                dns_result.ipv4_addresses
+            };
+            let Some(address) = addresses.into_iter().next() else {
+                let message = format!(
+                    "IPv{}アドレスが見つかりません: {}",
+                    if ipv6 { 6 } else { 4 },
+                    host
+                );
+                result.redirects.push(RedirectHop {
+                    url: next.to_string(),
+                    status_code: None,
+                    ip_address: None,
+                    response_time_ms: None,
+                    error_message: Some(message.clone()),
+                });
+                status_code = None;
+                redirect_error = Some(message);
+                break;
+            };
+            current_ip = Some(address);
+        }
+        options = next_request(&options, status, same_host);
+
+        let outcome = http_client::send_request(&http_client::HttpRequest {
+            url: next.as_str(),
+            options: &options,
+            ip_address: current_ip.as_deref(),
+            host: &host,
+            port: next.port(),
+            ignore_tls_errors,
+            source_address,
+            verbose: save_verbose_log,
+            max_body_bytes: 0,
+            timeout_secs,
+        })
+        .await;
+        let download_speed = outcome.download_speed();
+
+        if let (Some(log), Some(hop_log)) = (verbose_log.as_mut(), outcome.verbose_log) {
+            log.push_str(&format!("\n* Redirected to {}\n{}", next, hop_log));
+        }
+        result.redirects.push(RedirectHop {
+            url: next.to_string(),
+            status_code: outcome.status_code,
+            ip_address: current_ip.clone(),
+            response_time_ms: Some(outcome.timings.total_ms.round() as u64),
+            error_message: outcome.error_message.clone(),
+        });
+        status_code = outcome.status_code;
+        connection_error = outcome.error_message;
+        result.downloaded_bytes = outcome.body_bytes;
+        result.download_bytes_per_sec = download_speed;
+        result.download_truncated = outcome.body_truncated;
+        result.response_headers = outcome.headers;
+        result.content = outcome.content;
+        result.response_body_prefix = outcome.body_prefix;
+        current_url = next;
+    }
+
+    if result.redirects.is_empty() && redirect_error.is_none() {
+        result.verbose_log = verbose_log;
+        return;
+    }
+    let (success, message) = crate::evaluate_status(status_code, connection_error);
+    result.status_code = status_code;
+    result.success = success && redirect_error.is_none();
+    result.error_message = redirect_error.or(message);
+    result.verbose_log = verbose_log;
+    result.finished_at = Some(crate::now_rfc3339());
+}
+
+fn next_host(url: &Url) -> Result<String, String> {
+    crate::validate_url(url.as_str())?;
+    let host = url
+        .host_str()
+        .ok_or_else(|| "リダイレクト先の URL にホスト名がありません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+    Ok(host)
+}
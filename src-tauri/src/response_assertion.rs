@@ -0,0 +1,187 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::HttpPingResult;
+
+// 本文のアサーションで照合する応答本文の先頭のバイト数（これより後ろは照合しない）
+pub const MAX_BODY_ASSERTION_BYTES: usize = 256 * 1024;
+
+const MAX_STATUS_RANGES: usize = 20;
+const MAX_PATTERN_LEN: usize = 1024;
+// 正規表現をコンパイルした際のサイズの上限（複雑すぎるパターンを拒否する）
+const REGEX_SIZE_LIMIT: usize = 1024 * 1024;
+
+// 成功とみなすステータスコード（None の場合は 2xx）と、応答本文に含まれるべき文字列・正規表現
+#[derive(Debug, Clone, Default)]
+pub struct ResponseAssertions {
+    expected_status: Option<(String, Vec<(u16, u16)>)>,
+    body_contains: Option<String>,
+    body_regex: Option<Regex>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BodyAssertionResult {
+    pub assertion: String,
+    pub passed: bool,
+    pub message: Option<String>,
+}
+
+impl ResponseAssertions {
+    // 測定前に構文エラーを返す
+    pub fn parse(
+        expected_status: Option<&str>,
+        body_contains: Option<String>,
+        body_regex: Option<&str>,
+    ) -> Result<Self, String> {
+        let expected_status = expected_status
+            .map(|s| parse_expected_status(s).map(|ranges| (s.trim().to_string(), ranges)))
+            .transpose()?;
+        if body_contains
+            .as_ref()
+            .is_some_and(|s| s.is_empty() || s.len() > MAX_PATTERN_LEN)
+        {
+            return Err(format!(
+                "応答本文に含まれるべき文字列は 1 から {} 文字で指定してください",
+                MAX_PATTERN_LEN
+            ));
+        }
+        let body_regex = body_regex
+            .map(|pattern| {
+                if pattern.is_empty() || pattern.len() > MAX_PATTERN_LEN {
+                    return Err(format!(
+                        "応答本文の正規表現は 1 から {} 文字で指定してください",
+                        MAX_PATTERN_LEN
+                    ));
+                }
+                regex::RegexBuilder::new(pattern)
+                    .size_limit(REGEX_SIZE_LIMIT)
+                    .build()
+                    .map_err(|e| format!("応答本文の正規表現が不正です: {}", e))
+            })
+            .transpose()?;
+        Ok(ResponseAssertions {
+            expected_status,
+            body_contains,
+            body_regex,
+        })
+    }
+
+    // 応答本文を照合する場合は、受信時に先頭を保持する必要がある
+    pub fn needs_body(&self) -> bool {
+        self.body_contains.is_some() || self.body_regex.is_some()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.expected_status.is_none() && !self.needs_body()
+    }
+
+    // 応答を受信できた結果の success と error_message を、期待するステータスと本文で判定し直す
+    // （接続エラーやリダイレクトの失敗など、ステータス以外の理由で失敗した結果はそのまま）
+    pub fn apply(&self, result: &mut HttpPingResult) {
+        let Some(status_code) = result.status_code else {
+            return;
+        };
+        if self.is_empty() {
+            return;
+        }
+        let status_failure = crate::status_error_message(status_code);
+        if !result.success && result.error_message.as_ref() != Some(&status_failure) {
+            return;
+        }
+
+        let status_ok = match &self.expected_status {
+            Some((_, ranges)) => ranges
+                .iter()
+                .any(|(low, high)| (*low..=*high).contains(&status_code)),
+            None => (200..300).contains(&status_code),
+        };
+        // 再試行の判定で評価済みの場合は評価し直す
+        result.body_assertions.clear();
+        let body = String::from_utf8_lossy(&result.response_body_prefix).into_owned();
+        if let Some(expected) = &self.body_contains {
+            let passed = body.contains(expected.as_str());
+            result.body_assertions.push(BodyAssertionResult {
+                assertion: format!("contains {}", expected),
+                passed,
+                message: (!passed)
+                    .then(|| body_failure_message("文字列", &result.response_body_prefix)),
+            });
+        }
+        if let Some(regex) = &self.body_regex {
+            let passed = regex.is_match(&body);
+            result.body_assertions.push(BodyAssertionResult {
+                assertion: format!("matches {}", regex.as_str()),
+                passed,
+                message: (!passed)
+                    .then(|| body_failure_message("正規表現", &result.response_body_prefix)),
+            });
+        }
+        let body_ok = result.body_assertions.iter().all(|a| a.passed);
+
+        result.success = status_ok && body_ok;
+        result.error_message = if !status_ok {
+            Some(match &self.expected_status {
+                Some((source, _)) => format!("{}（期待値: {}）", status_failure, source),
+                None => status_failure,
+            })
+        } else if !body_ok {
+            Some("応答本文のアサーションが失敗しました".to_string())
+        } else {
+            None
+        };
+    }
+}
+
+fn body_failure_message(kind: &str, body: &[u8]) -> String {
+    if body.len() >= MAX_BODY_ASSERTION_BYTES {
+        format!(
+            "応答本文の先頭 {} バイトに一致する{}がありません",
+            MAX_BODY_ASSERTION_BYTES, kind
+        )
+    } else {
+        format!("応答本文に一致する{}がありません", kind)
+    }
+}
+
+// "200" / "2xx" / "200-399" をカンマ区切りで指定（"401,2xx" なら 401 と 2xx を成功とする）
+fn parse_expected_status(source: &str) -> Result<Vec<(u16, u16)>, String> {
+    let invalid = || {
+        format!(
+            "期待するステータスコードは 200、2xx、200-399 の形式をカンマ区切りで指定してください: {}",
+            source
+        )
+    };
+    let items: Vec<&str> = source.split(',').map(str::trim).collect();
+    if items.len() > MAX_STATUS_RANGES {
+        return Err(format!(
+            "期待するステータスコードは {} 個まで指定できます",
+            MAX_STATUS_RANGES
+        ));
+    }
+    items
+        .into_iter()
+        .map(|item| {
+            let lower = item.to_ascii_lowercase();
+            let (low, high) = if let Some(class) = lower.strip_suffix("xx") {
+                let class: u16 = class
+                    .parse()
+                    .ok()
+                    .filter(|c| (1..=5).contains(c))
+                    .ok_or_else(invalid)?;
+                (class * 100, class * 100 + 99)
+            } else if let Some((low, high)) = item.split_once('-') {
+                (
+                    low.trim().parse().map_err(|_| invalid())?,
+                    high.trim().parse().map_err(|_| invalid())?,
+                )
+            } else {
+                let code = item.parse().map_err(|_| invalid())?;
+                (code, code)
+            };
+            if !(100..=599).contains(&low) || !(100..=599).contains(&high) || low > high {
+                return Err(invalid());
+            }
+            Ok((low, high))
+        })
+        .collect()
+}
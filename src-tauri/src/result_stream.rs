@@ -0,0 +1,72 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+// 1 件の測定が終わるたびに送るイベント
+#[derive(Debug, Serialize)]
+pub struct StreamedResult<'a, T> {
+    // 指定した順の位置（0 始まり）
+    pub index: usize,
+    pub total: usize,
+    pub result: &'a T,
+}
+
+// 複数の対象を測定する機能で共通の、結果の逐次通知と失敗が続いた場合の打ち切り
+pub struct ResultStream {
+    app: AppHandle,
+    event: &'static str,
+    total: usize,
+    stream_results: bool,
+    abort_after_failures: Option<u32>,
+    failures: u32,
+}
+
+impl ResultStream {
+    pub fn new(
+        app: AppHandle,
+        event: &'static str,
+        total: usize,
+        stream_results: bool,
+        abort_after_failures: Option<u32>,
+    ) -> Self {
+        ResultStream {
+            app,
+            event,
+            total,
+            stream_results,
+            abort_after_failures,
+            failures: 0,
+        }
+    }
+
+    // 回線断などで全対象が失敗する場合に、残りのタイムアウトを待たずに打ち切れるようにする
+    pub fn validate_abort_after_failures(abort_after_failures: Option<u32>) -> Result<(), String> {
+        if abort_after_failures == Some(0) {
+            return Err("abort_after_failures は 1 以上を指定してください".to_string());
+        }
+        Ok(())
+    }
+
+    // 失敗した対象の数が abort_after_failures に達したか
+    pub fn should_abort(&self) -> bool {
+        self.abort_after_failures
+            .is_some_and(|max| self.failures >= max)
+    }
+
+    // 1 件の結果を記録し、stream_results を指定した場合は通知する
+    pub fn push<T: Serialize>(&mut self, index: usize, result: &T, failed: bool) {
+        if failed {
+            self.failures += 1;
+        }
+        if !self.stream_results {
+            return;
+        }
+        let event = StreamedResult {
+            index,
+            total: self.total,
+            result,
+        };
+        if let Err(e) = self.app.emit(self.event, &event) {
+            eprintln!("Failed to emit {}: {}", self.event, e);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::AppHandle;
+use url::Url;
+
+use crate::http_client;
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::stats::{
+    compute_latency_histogram, compute_latency_stats, validate_histogram_bounds, LatencyHistogram,
+    LatencyStats, PingSample, DEFAULT_HISTOGRAM_BOUNDS_MS,
+};
+use crate::{template, DnsResolution, HttpPingResult};
+
+// 測定回数と送信間隔（ミリ秒）の既定値と範囲
+const DEFAULT_COUNT: u32 = 10;
+const MAX_COUNT: u32 = 1000;
+const DEFAULT_INTERVAL_MS: u64 = 1000;
+const MIN_INTERVAL_MS: u64 = 200;
+const MAX_INTERVAL_MS: u64 = 60_000;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HttpPingSeriesFamily {
+    pub ip_address: Option<String>,
+    pub results: Vec<HttpPingResult>,
+    pub stats: LatencyStats,
+    pub histogram: LatencyHistogram,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HttpPingSeriesResult {
+    pub url: String,
+    pub url_template: Option<String>,
+    pub count: u32,
+    pub interval_ms: u64,
+    pub dns_resolution: DnsResolution,
+    pub ipv4: HttpPingSeriesFamily,
+    pub ipv6: HttpPingSeriesFamily,
+}
+
+// ping コマンドのように同じ URL へ繰り返し接続し、IPv4/IPv6 ごとの統計を返す
+#[tauri::command]
+pub async fn ping_http_series(
+    app: AppHandle,
+    url: String,
+    count: Option<u32>,
+    interval_ms: Option<u64>,
+    ignore_tls_errors: Option<bool>,
+    timeout_secs: Option<u64>,
+    histogram_bounds_ms: Option<Vec<u64>>,
+) -> Result<HttpPingSeriesResult, String> {
+    let count = count.unwrap_or(DEFAULT_COUNT);
+    if !(1..=MAX_COUNT).contains(&count) {
+        return Err(format!(
+            "測定回数は 1 から {} の範囲で指定してください",
+            MAX_COUNT
+        ));
+    }
+    let interval_ms = interval_ms.unwrap_or(DEFAULT_INTERVAL_MS);
+    if !(MIN_INTERVAL_MS..=MAX_INTERVAL_MS).contains(&interval_ms) {
+        return Err(format!(
+            "送信間隔は {} から {} ミリ秒の範囲で指定してください",
+            MIN_INTERVAL_MS, MAX_INTERVAL_MS
+        ));
+    }
+    let timeout_secs = timeout_secs.unwrap_or(http_client::DEFAULT_TIMEOUT_SECS);
+    http_client::validate_timeout_secs(timeout_secs)?;
+    // 生のサンプルを送らなくても分布の形を表示・出力できるよう、区間ごとの件数も返す
+    let histogram_bounds_ms =
+        histogram_bounds_ms.unwrap_or_else(|| DEFAULT_HISTOGRAM_BOUNDS_MS.to_vec());
+    validate_histogram_bounds(&histogram_bounds_ms)?;
+
+    // {{timestamp}} などの変数を含む URL は試行ごとに展開（キャッシュ回避用）
+    let url_template = template::is_template(&url).then(|| url.clone());
+    let url = template::expand_template(&url)?;
+    crate::validate_url(&url)?;
+
+    let target = url.clone();
+    run_operation(
+        &app,
+        OperationKind::Ping,
+        &target,
+        execute_ping_series(
+            url,
+            url_template,
+            count,
+            interval_ms,
+            ignore_tls_errors.unwrap_or(false),
+            timeout_secs,
+            histogram_bounds_ms,
+        ),
+    )
+    .await
+}
+
+async fn execute_ping_series(
+    url: String,
+    url_template: Option<String>,
+    count: u32,
+    interval_ms: u64,
+    ignore_tls_errors: bool,
+    timeout_secs: u64,
+    histogram_bounds_ms: Vec<u64>,
+) -> Result<HttpPingSeriesResult, String> {
+    if ignore_tls_errors {
+        crate::log_security_warning("TLS証明書検証が無効化されています");
+    }
+
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+
+    // 全試行で同じアドレスに接続するよう、名前解決は最初の1回のみ
+    let mut dns_resolution = crate::resolve_dns(&host).await;
+
+    let mut ipv4_results = Vec::new();
+    let mut ipv6_results = Vec::new();
+    for attempt in 0..count {
+        if attempt > 0 {
+            tokio::time::sleep(Duration::from_millis(interval_ms)).await;
+        }
+        report_progress(attempt as f64 * 100.0 / count as f64);
+
+        let attempt_url = match &url_template {
+            Some(template) => template::expand_template(template)?,
+            None => url.clone(),
+        };
+        let request = http_client::RequestOptions::default();
+        let (ipv4, ipv6) = tokio::join!(
+            crate::connect_to_ip_with_host(
+                attempt_url.clone(),
+                &request,
+                &dns_resolution.ipv4_addresses,
+                &host,
+                ignore_tls_errors,
+                parsed_url.port(),
+                false,
+                None,
+                timeout_secs,
+            ),
+            crate::connect_to_ip_with_host(
+                attempt_url,
+                &request,
+                &dns_resolution.ipv6_addresses,
+                &host,
+                ignore_tls_errors,
+                parsed_url.port(),
+                false,
+                None,
+                timeout_secs,
+            ),
+        );
+        ipv4_results.push(ipv4);
+        ipv6_results.push(ipv6);
+    }
+
+    // 測定に影響しないよう、全試行を終えてから CNAME の連鎖と TTL を確認
+    dns_resolution.details = crate::lookup_dns_details(&host, &dns_resolution).await;
+
+    Ok(HttpPingSeriesResult {
+        url,
+        url_template,
+        count,
+        interval_ms,
+        ipv4: series_family(
+            dns_resolution.ipv4_addresses.first(),
+            ipv4_results,
+            &histogram_bounds_ms,
+        ),
+        ipv6: series_family(
+            dns_resolution.ipv6_addresses.first(),
+            ipv6_results,
+            &histogram_bounds_ms,
+        ),
+        dns_resolution,
+    })
+}
+
+// アドレスがなく接続を試行していない場合は理由を示す結果を1件だけ残し、統計は 0 件とする
+fn series_family(
+    ip_address: Option<&String>,
+    results: Vec<HttpPingResult>,
+    histogram_bounds_ms: &[u64],
+) -> HttpPingSeriesFamily {
+    let results: Vec<HttpPingResult> = match ip_address {
+        Some(_) => results,
+        None => results.into_iter().take(1).collect(),
+    };
+    let samples: Vec<PingSample> = results
+        .iter()
+        .filter(|r| r.ip_address.is_some())
+        .map(|r| PingSample {
+            recorded_at: Local::now(),
+            success: r.success,
+            response_time_ms: r.response_time_ms,
+        })
+        .collect();
+
+    HttpPingSeriesFamily {
+        ip_address: ip_address.cloned(),
+        stats: compute_latency_stats(&samples),
+        histogram: compute_latency_histogram(&samples, histogram_bounds_ms),
+        results,
+    }
+}
@@ -0,0 +1,434 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::AppHandle;
+use url::Url;
+
+use crate::operations::{run_operation, OperationKind};
+use crate::tls::probe_tls;
+use crate::{dns, dns_failure};
+
+// ECH での試行に使うもの（ech フィーチャでビルドした場合のみ）
+#[cfg(feature = "ech")]
+use crate::dry_run::{self, ActionKind, DRY_RUN_MESSAGE};
+#[cfg(feature = "ech")]
+use crate::http_client;
+#[cfg(feature = "ech")]
+use rustls::{pki_types::ServerName, PeerIncompatible};
+#[cfg(feature = "ech")]
+use std::{
+    net::{IpAddr, SocketAddr},
+    time::Instant,
+};
+#[cfg(feature = "ech")]
+use tokio::net::TcpStream;
+#[cfg(feature = "ech")]
+use tokio_rustls::TlsConnector;
+
+// 比較用の無害な SNI
+const INNOCUOUS_SNI: &str = "www.example.com";
+
+// HTTPS レコードの問い合わせと、ECH での TLS ハンドシェイクのタイムアウト
+const ECH_QUERY_TIMEOUT: Duration = Duration::from_secs(2);
+#[cfg(feature = "ech")]
+const ECH_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// ECHConfig のバージョン（draft-ietf-tls-esni の 0xfe0d）
+const ECH_VERSION: u16 = 0xfe0d;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SniProbeOutcome {
+    pub sni: Option<String>,
+    pub success: bool,
+    pub handshake_ms: Option<u64>,
+    pub policy_errors: Option<String>,
+    pub error_message: Option<String>,
+}
+
+// 本来の SNI を ECH で暗号化した試行
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EchProbeOutcome {
+    // 経路上から見える外側の SNI（ECH の設定の公開名）
+    pub public_name: Option<String>,
+    pub success: bool,
+    // サーバが ECH を受け付けなかった（設定が古い場合など、経路上の遮断ではない）
+    pub rejected: bool,
+    // ech フィーチャなしでビルドしたため試行していない
+    #[serde(default)]
+    pub not_built: bool,
+    pub handshake_ms: Option<u64>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SniFilteringEntry {
+    pub ip_address: String,
+    pub real_sni: SniProbeOutcome,
+    pub innocuous_sni: SniProbeOutcome,
+    pub no_sni: SniProbeOutcome,
+    // 接続先が HTTPS レコードで ECH の設定を公開している場合のみ
+    #[serde(default)]
+    pub ech: Option<EchProbeOutcome>,
+    pub filtering_suspected: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SniFilteringResult {
+    pub host: String,
+    pub port: u16,
+    pub entries: Vec<SniFilteringEntry>,
+    pub filtering_suspected: bool,
+    pub findings: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn check_sni_filtering(
+    app: AppHandle,
+    url: String,
+) -> Result<SniFilteringResult, String> {
+    let target = url.clone();
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        &target,
+        execute_sni_filtering_check(url),
+    )
+    .await
+}
+
+async fn execute_sni_filtering_check(url: String) -> Result<SniFilteringResult, String> {
+    crate::validate_url(&url)?;
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    if parsed_url.scheme() != "https" {
+        return Err("SNI の確認には https:// の URL を指定してください".to_string());
+    }
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+    let port = parsed_url.port().unwrap_or(443);
+
+    let dns_result = crate::resolve_dns(&host).await;
+
+    // IPv4/IPv6 それぞれ最初のアドレスで比較
+    let ip_addresses: Vec<String> = dns_result
+        .ipv4_addresses
+        .first()
+        .into_iter()
+        .chain(dns_result.ipv6_addresses.first())
+        .cloned()
+        .collect();
+    if ip_addresses.is_empty() {
+        return Err(format!("{} の名前解決に失敗しました", host));
+    }
+
+    let mut entries = Vec::new();
+    let mut findings = Vec::new();
+    let ech_config = query_ech_config(&host, &mut findings).await;
+    for ip_address in ip_addresses {
+        let ech = async {
+            match &ech_config {
+                Some(config) => {
+                    Some(probe_with_ech(&ip_address, &host, port, config.clone()).await)
+                }
+                None => None,
+            }
+        };
+        let (real_sni, innocuous_sni, no_sni, ech) = tokio::join!(
+            probe_with_sni(&ip_address, Some(&host), port),
+            probe_with_sni(&ip_address, Some(INNOCUOUS_SNI), port),
+            probe_with_sni(&ip_address, None, port),
+            ech,
+        );
+
+        // 本来の SNI だけが失敗し、他の SNI や ECH で暗号化した SNI ではハンドシェイクできる場合は SNI フィルタを疑う
+        let ech_success = ech.as_ref().is_some_and(|e| e.success);
+        let sni_filtered =
+            !real_sni.success && (innocuous_sni.success || no_sni.success || ech_success);
+        if sni_filtered {
+            findings.push(format!(
+                "{} ({}) へは別の SNI では接続できるのに {} では失敗します。SNI による通信遮断（DPI）の可能性があります",
+                ip_address, port, host
+            ));
+        }
+        if !real_sni.success && ech_success {
+            findings.push(format!(
+                "{} ({}) へは ECH で SNI を暗号化すると接続できます",
+                ip_address, port
+            ));
+        }
+        // 平文の SNI では接続できるのに ECH だけが失敗する場合は、経路上で ECH が遮断されている可能性がある
+        let ech_blocked = real_sni.success
+            && ech
+                .as_ref()
+                .is_some_and(|e| !e.success && !e.rejected && !e.not_built);
+        if ech_blocked {
+            findings.push(format!(
+                "{} ({}) へは平文の SNI では接続できるのに ECH では失敗します。ECH による通信が遮断されている可能性があります",
+                ip_address, port
+            ));
+        }
+        if ech.as_ref().is_some_and(|e| e.rejected) {
+            findings.push(format!(
+                "{} ({}) のサーバが ECH を受け付けませんでした。HTTPS レコードの ECH の設定が古い可能性があります",
+                ip_address, port
+            ));
+        }
+
+        entries.push(SniFilteringEntry {
+            ip_address,
+            real_sni,
+            innocuous_sni,
+            no_sni,
+            ech,
+            filtering_suspected: sni_filtered || ech_blocked,
+        });
+    }
+
+    let filtering_suspected = entries.iter().any(|e| e.filtering_suspected);
+
+    Ok(SniFilteringResult {
+        host,
+        port,
+        entries,
+        filtering_suspected,
+        findings,
+    })
+}
+
+// 指定 SNI（None の場合は SNI なし）で TLS ハンドシェイクを試行
+async fn probe_with_sni(ip_address: &str, sni: Option<&str>, port: u16) -> SniProbeOutcome {
+    // SslStream は IP アドレスをホスト名に渡すと SNI を送信しない
+    let server_name = sni.unwrap_or(ip_address);
+
+    match probe_tls(server_name, Some(ip_address), port).await {
+        Ok(tls) => SniProbeOutcome {
+            sni: sni.map(|s| s.to_string()),
+            success: true,
+            handshake_ms: Some(tls.handshake_ms),
+            policy_errors: Some(tls.policy_errors),
+            error_message: None,
+        },
+        Err(e) => SniProbeOutcome {
+            sni: sni.map(|s| s.to_string()),
+            success: false,
+            handshake_ms: None,
+            policy_errors: None,
+            error_message: Some(e),
+        },
+    }
+}
+
+// ECH の設定（接続先の HTTPS レコードの ech）を OS の DNS サーバに問い合わせる（公開していない場合は None）
+async fn query_ech_config(host: &str, findings: &mut Vec<String>) -> Option<Vec<u8>> {
+    let Some(server) = dns_failure::system_dns_servers().await.into_iter().next() else {
+        findings.push("DNS サーバがわからないため、ECH では試行していません".to_string());
+        return None;
+    };
+    match dns::query_ech_config(server, host, ECH_QUERY_TIMEOUT).await {
+        Ok(Some(config)) => Some(config),
+        Ok(None) => {
+            findings.push(format!(
+                "{} は HTTPS レコードで ECH の設定を公開していないため、ECH では試行していません",
+                host
+            ));
+            None
+        }
+        Err(e) => {
+            findings.push(format!(
+                "HTTPS レコードを取得できないため、ECH では試行していません: {}",
+                e
+            ));
+            None
+        }
+    }
+}
+
+// ech フィーチャなしのビルドには ECH に必要な HPKE の実装がないため試行しない
+#[cfg(not(feature = "ech"))]
+async fn probe_with_ech(
+    _ip_address: &str,
+    _host: &str,
+    _port: u16,
+    ech_config_list: Vec<u8>,
+) -> EchProbeOutcome {
+    EchProbeOutcome {
+        public_name: ech_public_name(&ech_config_list),
+        success: false,
+        rejected: false,
+        not_built: true,
+        handshake_ms: None,
+        error_message: Some(
+            "ECH での試行は無効です（ech フィーチャなしでビルドされています）".to_string(),
+        ),
+    }
+}
+
+// 本来の SNI を ECH で暗号化して TLS 1.3 のハンドシェイクを試行
+#[cfg(feature = "ech")]
+async fn probe_with_ech(
+    ip_address: &str,
+    host: &str,
+    port: u16,
+    ech_config_list: Vec<u8>,
+) -> EchProbeOutcome {
+    let public_name = ech_public_name(&ech_config_list);
+    let failed = |message: String, rejected: bool| EchProbeOutcome {
+        public_name: public_name.clone(),
+        success: false,
+        rejected,
+        not_built: false,
+        handshake_ms: None,
+        error_message: Some(message),
+    };
+    let config = match http_client::ech_tls_config(ech_config_list) {
+        Ok(config) => config,
+        Err(e) => return failed(e, false),
+    };
+    let Ok(ip) = ip_address.parse::<IpAddr>() else {
+        return failed(format!("無効なIPアドレス: {}", ip_address), false);
+    };
+    let Ok(server_name) = ServerName::try_from(host.to_string()) else {
+        return failed(
+            format!("TLSのサーバ名として使用できません: {}", host),
+            false,
+        );
+    };
+    let target = SocketAddr::new(ip, port);
+    if dry_run::intercept(
+        ActionKind::Tls,
+        target.to_string(),
+        Some(format!("ECH で暗号化した SNI {}", host)),
+    ) {
+        return failed(DRY_RUN_MESSAGE.to_string(), false);
+    }
+
+    let handshake = async {
+        let stream = TcpStream::connect(target)
+            .await
+            .map_err(|e| (format!("{} に接続できません: {}", target, e), false))?;
+        let started = Instant::now();
+        TlsConnector::from(config)
+            .connect(server_name, stream)
+            .await
+            .map_err(|e| {
+                // サーバが ECH を受け付けない場合は、公開名で応答してからハンドシェイクを中止する
+                let rejected = e
+                    .get_ref()
+                    .and_then(|inner| inner.downcast_ref::<rustls::Error>())
+                    .is_some_and(|inner| {
+                        matches!(
+                            inner,
+                            rustls::Error::PeerIncompatible(
+                                PeerIncompatible::ServerRejectedEncryptedClientHello(_)
+                            )
+                        )
+                    });
+                (format!("TLSハンドシェイク失敗: {}", e), rejected)
+            })?;
+        Ok(started.elapsed().as_millis() as u64)
+    };
+    match tokio::time::timeout(ECH_PROBE_TIMEOUT, handshake).await {
+        Ok(Ok(handshake_ms)) => EchProbeOutcome {
+            public_name,
+            success: true,
+            rejected: false,
+            not_built: false,
+            handshake_ms: Some(handshake_ms),
+            error_message: None,
+        },
+        Ok(Err((message, rejected))) => failed(message, rejected),
+        Err(_) => failed(
+            "ECH での TLS ハンドシェイクがタイムアウトしました".to_string(),
+            false,
+        ),
+    }
+}
+
+// ECHConfigList のうち対応するバージョンの最初の設定の公開名
+fn ech_public_name(ech_config_list: &[u8]) -> Option<String> {
+    let read_u16 = |bytes: &[u8], pos: usize| {
+        bytes
+            .get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+    };
+    let mut pos = 2;
+    while let (Some(version), Some(len)) = (
+        read_u16(ech_config_list, pos),
+        read_u16(ech_config_list, pos + 2),
+    ) {
+        let contents = ech_config_list.get(pos + 4..pos + 4 + len)?;
+        if version == usize::from(ECH_VERSION) {
+            // config_id・kem_id・公開鍵・暗号スイート・最大名長の後に公開名が続く
+            let key_end = 5 + read_u16(contents, 3)?;
+            let suites_end = key_end + 2 + read_u16(contents, key_end)?;
+            let name_len = *contents.get(suites_end + 1)? as usize;
+            let name = contents.get(suites_end + 2..suites_end + 2 + name_len)?;
+            return Some(String::from_utf8_lossy(name).to_string());
+        }
+        pos += 4 + len;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // X25519・HKDF-SHA256・AES-128-GCM の ECHConfig（公開鍵は仮の値）
+    fn ech_config(version: u16, public_name: &str) -> Vec<u8> {
+        let mut contents = vec![0x01, 0x00, 0x20, 0x00, 0x20];
+        contents.extend_from_slice(&[0x07; 32]);
+        contents.extend_from_slice(&[0x00, 0x04, 0x00, 0x01, 0x00, 0x01, 0x00]);
+        contents.push(public_name.len() as u8);
+        contents.extend_from_slice(public_name.as_bytes());
+        contents.extend_from_slice(&[0x00, 0x00]);
+        let mut config = version.to_be_bytes().to_vec();
+        config.extend_from_slice(&(contents.len() as u16).to_be_bytes());
+        config.extend_from_slice(&contents);
+        config
+    }
+
+    fn ech_config_list(configs: &[Vec<u8>]) -> Vec<u8> {
+        let configs = configs.concat();
+        let mut list = (configs.len() as u16).to_be_bytes().to_vec();
+        list.extend_from_slice(&configs);
+        list
+    }
+
+    #[test]
+    fn reads_public_name() {
+        let list = ech_config_list(&[ech_config(ECH_VERSION, "cloudflare-ech.com")]);
+        assert_eq!(
+            ech_public_name(&list).as_deref(),
+            Some("cloudflare-ech.com")
+        );
+    }
+
+    #[test]
+    fn skips_unknown_versions() {
+        let list = ech_config_list(&[
+            ech_config(0xfe0a, "old.example"),
+            ech_config(ECH_VERSION, "pub.example"),
+        ]);
+        assert_eq!(ech_public_name(&list).as_deref(), Some("pub.example"));
+
+        let list = ech_config_list(&[ech_config(0xfe0a, "old.example")]);
+        assert_eq!(ech_public_name(&list), None);
+    }
+
+    #[test]
+    fn truncated_config_list() {
+        let list = ech_config_list(&[ech_config(ECH_VERSION, "cloudflare-ech.com")]);
+        // 公開名の途中、ECHConfig の途中、長さのみ
+        for len in [list.len() - 5, 30, 2] {
+            assert_eq!(ech_public_name(&list[..len]), None, "{}", len);
+        }
+        assert_eq!(ech_public_name(&[]), None);
+
+        // ECHConfig の長さは足りているが、中の公開鍵の長さが長すぎる
+        let mut config = ech_config(ECH_VERSION, "pub.example");
+        config[7..9].copy_from_slice(&0x00ffu16.to_be_bytes());
+        assert_eq!(ech_public_name(&ech_config_list(&[config])), None);
+    }
+}
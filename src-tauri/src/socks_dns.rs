@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use url::Url;
+
+use crate::http_client::{self, RequestOptions};
+use crate::operations::{run_operation, OperationKind};
+use crate::proxy::{ProxyConfig, ProxyKind};
+use crate::{DnsResolution, HttpPingResult};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SocksDnsComparison {
+    pub url: String,
+    // 比較に使った SOCKS5 プロキシ（認証情報は含めない）
+    pub proxy: String,
+    // ローカルの名前解決の結果（socks5 ではこのアドレスで接続を依頼する）
+    pub local_resolution: DnsResolution,
+    // socks5: ローカルで解決した各ファミリの最初のアドレスへの接続
+    pub socks5_ipv4: HttpPingResult,
+    pub socks5_ipv6: HttpPingResult,
+    // socks5h: 名前のまま接続を依頼し、プロキシが解決したアドレスへの接続
+    pub socks5h: HttpPingResult,
+    // 名前の解決方法によって到達性や応答が異なるか
+    pub difference_detected: bool,
+    pub findings: Vec<String>,
+}
+
+// 同じ SOCKS5 プロキシでローカルの名前解決（socks5）とプロキシでの名前解決（socks5h）を比較する
+// VPN や Tor のように、プロキシの内側でのみ解決できる名前や DNS の応答が異なる環境の確認用
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn compare_socks_dns(
+    app: AppHandle,
+    url: String,
+    proxy: String,
+    proxy_username: Option<String>,
+    proxy_password: Option<String>,
+    ignore_tls_errors: Option<bool>,
+    save_verbose_log: Option<bool>,
+    timeout_secs: Option<u64>,
+) -> Result<SocksDnsComparison, String> {
+    let timeout_secs = timeout_secs.unwrap_or(http_client::DEFAULT_TIMEOUT_SECS);
+    http_client::validate_timeout_secs(timeout_secs)?;
+    let ignore_tls_errors = ignore_tls_errors.unwrap_or(false);
+    if ignore_tls_errors {
+        crate::log_security_warning("TLS証明書検証が無効化されています");
+    }
+    let proxy = ProxyConfig::parse(&proxy, proxy_username, proxy_password)?;
+    if !matches!(proxy.kind, ProxyKind::Socks5 | ProxyKind::Socks5h) {
+        return Err(
+            "socks5 と socks5h の比較には socks5:// または socks5h:// のプロキシを指定してください"
+                .to_string(),
+        );
+    }
+    let url = crate::normalize_url(&url)?;
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+    if crate::ip_literal(&host).is_some() {
+        return Err(
+            "IP アドレスの URL は名前解決を行わないため、ホスト名の URL を指定してください"
+                .to_string(),
+        );
+    }
+
+    let target = url.clone();
+    run_operation(&app, OperationKind::Diagnostic, &target, async move {
+        let port = parsed_url.port();
+        let save_verbose_log = save_verbose_log.unwrap_or(false);
+        let with_kind = |kind: ProxyKind| RequestOptions {
+            proxy: Some(ProxyConfig {
+                kind,
+                ..proxy.clone()
+            }),
+            ..RequestOptions::default()
+        };
+        let socks5 = with_kind(ProxyKind::Socks5);
+        let socks5h = with_kind(ProxyKind::Socks5h);
+
+        let local_resolution = crate::resolve_dns(&host).await;
+        // プロキシの名前解決を先に行い、その後でローカルで解決した各ファミリのアドレスに接続する
+        let socks5h_result = crate::perform_http_request(
+            &url,
+            &socks5h,
+            None,
+            &host,
+            ignore_tls_errors,
+            port,
+            save_verbose_log,
+            None,
+            timeout_secs,
+        )
+        .await;
+        let (socks5_ipv4, socks5_ipv6) = tokio::join!(
+            crate::connect_to_ip_with_host(
+                url.clone(),
+                &socks5,
+                &local_resolution.ipv4_addresses,
+                &host,
+                ignore_tls_errors,
+                port,
+                save_verbose_log,
+                None,
+                timeout_secs,
+            ),
+            crate::connect_to_ip_with_host(
+                url.clone(),
+                &socks5,
+                &local_resolution.ipv6_addresses,
+                &host,
+                ignore_tls_errors,
+                port,
+                save_verbose_log,
+                None,
+                timeout_secs,
+            ),
+        );
+
+        let (difference_detected, findings) = compare(
+            &host,
+            &local_resolution,
+            [&socks5_ipv4, &socks5_ipv6],
+            &socks5h_result,
+        );
+        Ok(SocksDnsComparison {
+            url,
+            proxy: proxy.display(),
+            local_resolution,
+            socks5_ipv4,
+            socks5_ipv6,
+            socks5h: socks5h_result,
+            difference_detected,
+            findings,
+        })
+    })
+    .await
+}
+
+// 名前の解決方法による到達性と応答の違い（違いがあるかと、利用者向けの説明）
+fn compare(
+    host: &str,
+    local_resolution: &DnsResolution,
+    socks5: [&HttpPingResult; 2],
+    socks5h: &HttpPingResult,
+) -> (bool, Vec<String>) {
+    let mut findings = Vec::new();
+    let resolved_locally =
+        !local_resolution.ipv4_addresses.is_empty() || !local_resolution.ipv6_addresses.is_empty();
+    let socks5_success = socks5.iter().find(|r| r.success);
+    // どちらでも到達できない場合は名前解決の違いではないため、違いとしては扱わない
+    let mut difference_detected = socks5_success.is_some() != socks5h.success;
+
+    match (resolved_locally, socks5_success, socks5h.success) {
+        (false, _, true) => findings.push(format!(
+            "ローカルでは {} の名前を解決できませんが、プロキシに解決させると到達できます。VPN や Tor の内側でのみ解決できる名前か、ローカルの DNS が遮断されている可能性があります（socks5h を使用してください）",
+            host
+        )),
+        (true, None, true) => findings.push(
+            "ローカルで解決したアドレスにはプロキシ経由で到達できませんが、プロキシに名前を解決させると到達できます。ローカルとプロキシ側で DNS の応答が異なる（分割 DNS や地域別の CDN）可能性があります"
+                .to_string(),
+        ),
+        (true, Some(_), false) => findings.push(
+            "ローカルで解決したアドレスには到達できますが、プロキシに名前を解決させると失敗します。プロキシ側の名前解決を確認してください"
+                .to_string(),
+        ),
+        (_, None, false) => findings.push(
+            "socks5 と socks5h のどちらでも到達できません。プロキシへの接続と認証を確認してください"
+                .to_string(),
+        ),
+        _ => {}
+    }
+
+    // 両方で応答を受信できた場合は、ステータスとサーバ証明書で同じサーバかを確かめる
+    if let Some(local) = socks5.iter().find(|r| r.status_code.is_some()) {
+        if socks5h.status_code.is_some() && local.status_code != socks5h.status_code {
+            difference_detected = true;
+            findings.push(format!(
+                "socks5 と socks5h で応答のステータスが異なります（{} と {}）",
+                local.status_code.unwrap_or_default(),
+                socks5h.status_code.unwrap_or_default()
+            ));
+        }
+        let subject = |r: &HttpPingResult| r.certificate.as_ref().map(|c| c.subject.clone());
+        if let (Some(local_subject), Some(remote_subject)) = (subject(local), subject(socks5h)) {
+            if local_subject != remote_subject {
+                difference_detected = true;
+                findings.push(format!(
+                    "socks5 と socks5h で接続先のサーバ証明書が異なります（{} と {}）",
+                    local_subject, remote_subject
+                ));
+            }
+        }
+    }
+    (difference_detected, findings)
+}
@@ -0,0 +1,378 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use url::Url;
+
+use crate::curl::{describe_exit_code, ensure_user_agent, run_curl};
+use crate::history::{record_history, HistoryKind};
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::process::{run_command, run_command_with_input};
+use crate::rate_limit::acquire_for_curl_args;
+
+// 測定のデフォルト値と上限
+const DEFAULT_DOWNLOAD_BYTES: u64 = 10 * 1024 * 1024;
+const DEFAULT_UPLOAD_BYTES: u64 = 5 * 1024 * 1024;
+const MAX_DOWNLOAD_BYTES: u64 = 100 * 1024 * 1024;
+const MAX_UPLOAD_BYTES: u64 = 50 * 1024 * 1024;
+const LATENCY_SAMPLES: usize = 5;
+const TRANSFER_TIMEOUT_SECS: u64 = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpeedTestProvider {
+    Cloudflare,
+    FastCom,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpeedMeasurement {
+    pub ip_version: u8,
+    pub server: Option<String>,
+    pub latency_ms: Option<f64>,
+    pub download_bits_per_second: Option<f64>,
+    pub upload_bits_per_second: Option<f64>,
+    pub success: bool,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublicSpeedTestResult {
+    pub provider: SpeedTestProvider,
+    pub download_bytes: u64,
+    pub upload_bytes: u64,
+    pub ipv4: SpeedMeasurement,
+    pub ipv6: SpeedMeasurement,
+}
+
+// curl -w の出力から取り出す転送統計
+struct CurlTransferStats {
+    http_code: u16,
+    time_pretransfer: f64,
+    time_starttransfer: f64,
+    speed_download: f64,
+    speed_upload: f64,
+}
+
+// fast.com API レスポンス解析用の内部構造体
+#[derive(Deserialize)]
+struct FastComResponse {
+    targets: Vec<FastComTarget>,
+}
+
+#[derive(Deserialize)]
+struct FastComTarget {
+    url: String,
+}
+
+#[tauri::command]
+pub async fn run_public_speed_test(
+    app: AppHandle,
+    provider: SpeedTestProvider,
+    download_bytes: Option<u64>,
+    upload_bytes: Option<u64>,
+) -> Result<PublicSpeedTestResult, String> {
+    run_operation(
+        &app,
+        OperationKind::SpeedTest,
+        provider_host(provider),
+        execute_public_speed_test(app.clone(), provider, download_bytes, upload_bytes),
+    )
+    .await
+}
+
+async fn execute_public_speed_test(
+    app: AppHandle,
+    provider: SpeedTestProvider,
+    download_bytes: Option<u64>,
+    upload_bytes: Option<u64>,
+) -> Result<PublicSpeedTestResult, String> {
+    let download_bytes = download_bytes.unwrap_or(DEFAULT_DOWNLOAD_BYTES);
+    let upload_bytes = upload_bytes.unwrap_or(DEFAULT_UPLOAD_BYTES);
+    if download_bytes == 0 || download_bytes > MAX_DOWNLOAD_BYTES {
+        return Err(format!(
+            "ダウンロードサイズは 1〜{} バイトの範囲で指定してください",
+            MAX_DOWNLOAD_BYTES
+        ));
+    }
+    if upload_bytes > MAX_UPLOAD_BYTES {
+        return Err(format!(
+            "アップロードサイズは {} バイト以下で指定してください",
+            MAX_UPLOAD_BYTES
+        ));
+    }
+
+    // 帯域を奪い合わないよう IPv4 → IPv6 の順に測定
+    let ipv4 = measure_family(provider, 4, download_bytes, upload_bytes).await;
+    report_progress(50.0);
+    let ipv6 = measure_family(provider, 6, download_bytes, upload_bytes).await;
+    let result = PublicSpeedTestResult {
+        provider,
+        download_bytes,
+        upload_bytes,
+        ipv4,
+        ipv6,
+    };
+
+    if let Err(e) = record_history(
+        &app,
+        HistoryKind::SpeedTest,
+        provider_host(provider),
+        &result,
+    ) {
+        eprintln!("Failed to record speed test history: {}", e);
+    }
+
+    Ok(result)
+}
+
+// 測定サービスのホスト名
+fn provider_host(provider: SpeedTestProvider) -> &'static str {
+    match provider {
+        SpeedTestProvider::Cloudflare => "speed.cloudflare.com",
+        SpeedTestProvider::FastCom => "fast.com",
+    }
+}
+
+// 指定した IP バージョンで測定を実行
+async fn measure_family(
+    provider: SpeedTestProvider,
+    ip_version: u8,
+    download_bytes: u64,
+    upload_bytes: u64,
+) -> SpeedMeasurement {
+    let mut measurement = SpeedMeasurement {
+        ip_version,
+        server: None,
+        latency_ms: None,
+        download_bits_per_second: None,
+        upload_bits_per_second: None,
+        success: false,
+        error_message: None,
+    };
+
+    let outcome = match provider {
+        SpeedTestProvider::Cloudflare => {
+            measure_cloudflare(&mut measurement, download_bytes, upload_bytes).await
+        }
+        SpeedTestProvider::FastCom => measure_fast_com(&mut measurement, download_bytes).await,
+    };
+
+    match outcome {
+        Ok(()) => measurement.success = true,
+        Err(e) => measurement.error_message = Some(e),
+    }
+    measurement
+}
+
+// speed.cloudflare.com による測定
+async fn measure_cloudflare(
+    measurement: &mut SpeedMeasurement,
+    download_bytes: u64,
+    upload_bytes: u64,
+) -> Result<(), String> {
+    let ip_version = measurement.ip_version;
+    measurement.server = Some("speed.cloudflare.com".to_string());
+
+    measurement.latency_ms =
+        Some(measure_latency("https://speed.cloudflare.com/__down?bytes=0", ip_version).await?);
+
+    let download = run_curl_transfer(
+        &format!("https://speed.cloudflare.com/__down?bytes={}", download_bytes),
+        ip_version,
+        None,
+    )
+    .await?;
+    measurement.download_bits_per_second = Some(download.speed_download * 8.0);
+
+    if upload_bytes > 0 {
+        let upload = run_curl_transfer(
+            "https://speed.cloudflare.com/__up",
+            ip_version,
+            Some(upload_bytes),
+        )
+        .await?;
+        measurement.upload_bits_per_second = Some(upload.speed_upload * 8.0);
+    }
+
+    Ok(())
+}
+
+// fast.com（Netflix OCA）による測定（アップロードは公開 API がないため未対応）
+async fn measure_fast_com(measurement: &mut SpeedMeasurement, download_bytes: u64) -> Result<(), String> {
+    let ip_version = measurement.ip_version;
+    let token = fetch_fast_com_token().await?;
+
+    let api_url = format!(
+        "https://api.fast.com/netflix/speedtest/v2?https=true&token={}&urlCount=1",
+        token
+    );
+    let body = run_curl_get(&api_url, ip_version).await?;
+    let response: FastComResponse =
+        serde_json::from_str(&body).map_err(|e| format!("fast.com API 応答の解析失敗: {}", e))?;
+    let target = response
+        .targets
+        .into_iter()
+        .next()
+        .ok_or_else(|| "fast.com から測定サーバを取得できませんでした".to_string())?;
+
+    measurement.server = Url::parse(&target.url)
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.to_string()));
+
+    measurement.latency_ms = Some(measure_latency(
+        &fast_com_range_url(&target.url, 0),
+        ip_version,
+    )
+    .await?);
+
+    let download = run_curl_transfer(
+        &fast_com_range_url(&target.url, download_bytes),
+        ip_version,
+        None,
+    )
+    .await?;
+    measurement.download_bits_per_second = Some(download.speed_download * 8.0);
+
+    Ok(())
+}
+
+// fast.com の測定 URL にレンジ指定を付与
+fn fast_com_range_url(url: &str, bytes: u64) -> String {
+    let end = bytes.saturating_sub(1);
+    match url.split_once('?') {
+        Some((path, query)) => format!("{}/range/0-{}?{}", path, end, query),
+        None => format!("{}/range/0-{}", url, end),
+    }
+}
+
+// fast.com のトップページから API トークンを取得
+async fn fetch_fast_com_token() -> Result<String, String> {
+    let html = run_curl_get("https://fast.com/", 0).await?;
+    let script_path = extract_between(&html, "src=\"/app-", ".js\"")
+        .ok_or_else(|| "fast.com のスクリプトが見つかりません".to_string())?;
+    let script = run_curl_get(&format!("https://fast.com/app-{}.js", script_path), 0).await?;
+    extract_between(&script, "token:\"", "\"")
+        .ok_or_else(|| "fast.com のトークンを取得できませんでした".to_string())
+}
+
+// 文字列から prefix と suffix に挟まれた部分を取り出す
+fn extract_between(text: &str, prefix: &str, suffix: &str) -> Option<String> {
+    let start = text.find(prefix)? + prefix.len();
+    let end = text[start..].find(suffix)? + start;
+    Some(text[start..end].to_string())
+}
+
+// 0 バイト転送を複数回行い、TTFB の中央値をレイテンシとする
+async fn measure_latency(url: &str, ip_version: u8) -> Result<f64, String> {
+    let mut samples = Vec::new();
+    for _ in 0..LATENCY_SAMPLES {
+        if let Ok(stats) = run_curl_transfer(url, ip_version, None).await {
+            samples.push((stats.time_starttransfer - stats.time_pretransfer) * 1000.0);
+        }
+    }
+    if samples.is_empty() {
+        return Err(format!("IPv{} でのレイテンシ測定に失敗しました", ip_version));
+    }
+    samples.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(samples[samples.len() / 2])
+}
+
+// IP バージョン指定用の curl 引数（0 は指定なし）
+fn ip_version_arg(ip_version: u8) -> Option<&'static str> {
+    match ip_version {
+        4 => Some("-4"),
+        6 => Some("-6"),
+        _ => None,
+    }
+}
+
+// curl で本文を取得
+async fn run_curl_get(url: &str, ip_version: u8) -> Result<String, String> {
+    let mut cmd_args = vec!["--silent".to_string(), "--fail".to_string()];
+    if let Some(arg) = ip_version_arg(ip_version) {
+        cmd_args.push(arg.to_string());
+    }
+    cmd_args.extend(vec![
+        "--max-time".to_string(),
+        "10".to_string(),
+        url.to_string(),
+    ]);
+
+    let output = run_curl(cmd_args).await?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "{} の取得に失敗: {}",
+            url,
+            describe_exit_code(output.status.code())
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// curl で転送を行い統計を取得（upload_bytes 指定時はゼロ埋めデータを POST）
+async fn run_curl_transfer(
+    url: &str,
+    ip_version: u8,
+    upload_bytes: Option<u64>,
+) -> Result<CurlTransferStats, String> {
+    let mut cmd_args = vec!["--silent".to_string()];
+    if let Some(arg) = ip_version_arg(ip_version) {
+        cmd_args.push(arg.to_string());
+    }
+    cmd_args.extend(vec![
+        "--output".to_string(),
+        "nul".to_string(),
+        "--write-out".to_string(),
+        "%{http_code} %{time_pretransfer} %{time_starttransfer} %{speed_download} %{speed_upload}"
+            .to_string(),
+        "--max-time".to_string(),
+        TRANSFER_TIMEOUT_SECS.to_string(),
+    ]);
+    if upload_bytes.is_some() {
+        cmd_args.extend(vec![
+            "--header".to_string(),
+            "Content-Type: application/octet-stream".to_string(),
+            "--data-binary".to_string(),
+            "@-".to_string(),
+        ]);
+    }
+    cmd_args.push(url.to_string());
+    ensure_user_agent(&mut cmd_args);
+
+    acquire_for_curl_args(&cmd_args).await;
+    let output = match upload_bytes {
+        Some(bytes) => run_command_with_input("curl.exe", &cmd_args, bytes).await,
+        None => run_command("curl.exe", &cmd_args).await,
+    }
+    .map_err(|e| format!("curl 実行失敗: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "IPv{} での転送に失敗: {}",
+            ip_version,
+            describe_exit_code(output.status.code())
+        ));
+    }
+
+    let write_out = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let fields: Vec<&str> = write_out.split_whitespace().collect();
+    if fields.len() != 5 {
+        return Err(format!("curl 出力の解析失敗: {}", write_out));
+    }
+
+    let parse_f64 = |s: &str| s.parse::<f64>().unwrap_or(0.0);
+    let stats = CurlTransferStats {
+        http_code: fields[0].parse().unwrap_or(0),
+        time_pretransfer: parse_f64(fields[1]),
+        time_starttransfer: parse_f64(fields[2]),
+        speed_download: parse_f64(fields[3]),
+        speed_upload: parse_f64(fields[4]),
+    };
+
+    if !(200..300).contains(&stats.http_code) {
+        return Err(format!("HTTPステータス: {}", stats.http_code));
+    }
+
+    Ok(stats)
+}
@@ -0,0 +1,394 @@
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::history::{load_history, HistoryEntry, HistoryKind};
+use crate::{HttpPingDualResult, HttpPingResult};
+
+// 集計期間のデフォルト（5分・1時間・24時間）
+const DEFAULT_WINDOWS_MINUTES: [u64; 3] = [5, 60, 1440];
+
+// 応答時間のヒストグラムの既定の区切り（ミリ秒）と区切りの上限数
+pub const DEFAULT_HISTOGRAM_BOUNDS_MS: [u64; 9] = [10, 20, 50, 100, 200, 500, 1000, 2000, 5000];
+const MAX_HISTOGRAM_BOUNDS: usize = 32;
+
+// 1回分の測定サンプル
+#[derive(Debug, Clone)]
+pub struct PingSample {
+    pub recorded_at: DateTime<Local>,
+    pub success: bool,
+    pub response_time_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LossStats {
+    pub total: usize,
+    pub failures: usize,
+    pub loss_percent: f64,
+    pub current_failure_streak: usize,
+    pub max_failure_streak: usize,
+}
+
+// ping コマンドの統計行に相当する応答時間の集計
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub transmitted: usize,
+    pub received: usize,
+    pub loss_percent: f64,
+    pub min_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+    pub avg_ms: Option<f64>,
+    pub median_ms: Option<f64>,
+    pub p95_ms: Option<u64>,
+    pub stddev_ms: Option<f64>,
+    pub jitter_ms: Option<f64>,
+}
+
+// upper_ms が None の区間は上限なし（最後の区切り以上）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    pub lower_ms: u64,
+    pub upper_ms: Option<u64>,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    pub buckets: Vec<HistogramBucket>,
+    pub failures: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WindowLossStats {
+    pub window_minutes: u64,
+    pub ipv4: LossStats,
+    pub ipv6: LossStats,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TargetLossStats {
+    pub target: String,
+    pub windows: Vec<WindowLossStats>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoipVerdict {
+    Good,
+    Ok,
+    Bad,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoipQuality {
+    pub r_factor: f64,
+    pub mos: f64,
+    pub verdict: VoipVerdict,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FamilyVoipQuality {
+    pub latency_ms: f64,
+    pub jitter_ms: f64,
+    pub loss_percent: f64,
+    pub quality: VoipQuality,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VoipQualityReport {
+    pub target: String,
+    pub window_minutes: u64,
+    pub ipv4: Option<FamilyVoipQuality>,
+    pub ipv6: Option<FamilyVoipQuality>,
+}
+
+// 履歴から対象 URL ごとの IPv4/IPv6 サンプルを取り出す（古い順）
+pub fn ping_samples_by_target(
+    entries: &[HistoryEntry],
+) -> Vec<(String, Vec<PingSample>, Vec<PingSample>)> {
+    let mut result: Vec<(String, Vec<PingSample>, Vec<PingSample>)> = Vec::new();
+
+    for entry in entries.iter().filter(|e| e.kind == HistoryKind::Ping) {
+        let Ok(recorded_at) = DateTime::parse_from_rfc3339(&entry.recorded_at) else {
+            continue;
+        };
+        let Ok(dual) = serde_json::from_value::<HttpPingDualResult>(entry.data.clone()) else {
+            continue;
+        };
+        let recorded_at = recorded_at.with_timezone(&Local);
+
+        let index = match result.iter().position(|(t, _, _)| t == &entry.target) {
+            Some(i) => i,
+            None => {
+                result.push((entry.target.clone(), Vec::new(), Vec::new()));
+                result.len() - 1
+            }
+        };
+        if let Some(sample) = to_sample(&dual.ipv4, recorded_at) {
+            result[index].1.push(sample);
+        }
+        if let Some(sample) = to_sample(&dual.ipv6, recorded_at) {
+            result[index].2.push(sample);
+        }
+    }
+
+    result
+}
+
+// 接続を試行した結果のみをサンプルとする（アドレスがなく未実施のものは除外）
+fn to_sample(result: &HttpPingResult, recorded_at: DateTime<Local>) -> Option<PingSample> {
+    result.ip_address.as_ref()?;
+    Some(PingSample {
+        recorded_at,
+        success: result.success,
+        response_time_ms: result.response_time_ms,
+    })
+}
+
+// 指定期間内のサンプルに絞り込む
+pub fn samples_within(samples: &[PingSample], window_minutes: u64) -> Vec<PingSample> {
+    let since = Local::now() - Duration::minutes(window_minutes as i64);
+    samples
+        .iter()
+        .filter(|s| s.recorded_at >= since)
+        .cloned()
+        .collect()
+}
+
+// 失敗率と連続失敗回数を計算
+pub fn compute_loss_stats(samples: &[PingSample]) -> LossStats {
+    let total = samples.len();
+    let failures = samples.iter().filter(|s| !s.success).count();
+
+    let mut streak = 0;
+    let mut max_failure_streak = 0;
+    for sample in samples {
+        if sample.success {
+            streak = 0;
+        } else {
+            streak += 1;
+            max_failure_streak = max_failure_streak.max(streak);
+        }
+    }
+
+    LossStats {
+        total,
+        failures,
+        loss_percent: if total > 0 {
+            failures as f64 * 100.0 / total as f64
+        } else {
+            0.0
+        },
+        current_failure_streak: streak,
+        max_failure_streak,
+    }
+}
+
+#[tauri::command]
+pub async fn get_loss_stats(
+    app: AppHandle,
+    target: Option<String>,
+    windows_minutes: Option<Vec<u64>>,
+) -> Result<Vec<TargetLossStats>, String> {
+    let windows = windows_minutes.unwrap_or_else(|| DEFAULT_WINDOWS_MINUTES.to_vec());
+    if windows.is_empty() || windows.iter().any(|w| *w == 0 || *w > 60 * 24 * 365) {
+        return Err("集計期間は 1 分〜1 年の範囲で指定してください".to_string());
+    }
+
+    let entries = load_history(&app)?;
+    Ok(ping_samples_by_target(&entries)
+        .into_iter()
+        .filter(|(t, _, _)| target.as_ref().is_none_or(|target| t == target))
+        .map(|(target, ipv4, ipv6)| TargetLossStats {
+            target,
+            windows: windows
+                .iter()
+                .map(|&window_minutes| WindowLossStats {
+                    window_minutes,
+                    ipv4: compute_loss_stats(&samples_within(&ipv4, window_minutes)),
+                    ipv6: compute_loss_stats(&samples_within(&ipv6, window_minutes)),
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+// 遅延・ジッタ・損失率から R 値と MOS を推定（ITU-T G.107 E-model の簡易式）
+pub fn estimate_voip_quality(latency_ms: f64, jitter_ms: f64, loss_percent: f64) -> VoipQuality {
+    // ジッタバッファとコーデック遅延を加味した実効遅延
+    let effective_latency = latency_ms + jitter_ms * 2.0 + 10.0;
+    let mut r_factor = if effective_latency < 160.0 {
+        93.2 - effective_latency / 40.0
+    } else {
+        93.2 - (effective_latency - 120.0) / 10.0
+    };
+    r_factor -= loss_percent * 2.5;
+    let r_factor = r_factor.clamp(0.0, 100.0);
+
+    let mos = if r_factor <= 0.0 {
+        1.0
+    } else {
+        1.0 + 0.035 * r_factor + 0.000007 * r_factor * (r_factor - 60.0) * (100.0 - r_factor)
+    };
+    let mos = mos.clamp(1.0, 4.5);
+
+    let verdict = if mos >= 4.0 {
+        VoipVerdict::Good
+    } else if mos >= 3.6 {
+        VoipVerdict::Ok
+    } else {
+        VoipVerdict::Bad
+    };
+
+    VoipQuality {
+        r_factor,
+        mos,
+        verdict,
+    }
+}
+
+// 成功したサンプルの平均遅延と平均ジッタ（連続するサンプル間の差の平均）
+pub fn latency_and_jitter(samples: &[PingSample]) -> Option<(f64, f64)> {
+    let latencies: Vec<f64> = samples
+        .iter()
+        .filter(|s| s.success)
+        .filter_map(|s| s.response_time_ms)
+        .map(|ms| ms as f64)
+        .collect();
+    if latencies.is_empty() {
+        return None;
+    }
+
+    let average = latencies.iter().sum::<f64>() / latencies.len() as f64;
+    let jitter = if latencies.len() > 1 {
+        latencies
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .sum::<f64>()
+            / (latencies.len() - 1) as f64
+    } else {
+        0.0
+    };
+    Some((average, jitter))
+}
+
+// 成功したサンプルの応答時間の分布（p95 は最近接順位法、標準偏差は母標準偏差）
+pub fn compute_latency_stats(samples: &[PingSample]) -> LatencyStats {
+    let loss = compute_loss_stats(samples);
+    let mut latencies: Vec<u64> = samples
+        .iter()
+        .filter(|s| s.success)
+        .filter_map(|s| s.response_time_ms)
+        .collect();
+    latencies.sort_unstable();
+    let latency_and_jitter = latency_and_jitter(samples);
+
+    let count = latencies.len();
+    let median_ms = match count {
+        0 => None,
+        n if n % 2 == 1 => Some(latencies[n / 2] as f64),
+        n => Some((latencies[n / 2 - 1] + latencies[n / 2]) as f64 / 2.0),
+    };
+    let p95_ms = (count > 0).then(|| latencies[((count as f64 * 0.95).ceil() as usize).max(1) - 1]);
+    let stddev_ms = latency_and_jitter.map(|(avg, _)| {
+        (latencies
+            .iter()
+            .map(|&ms| (ms as f64 - avg).powi(2))
+            .sum::<f64>()
+            / count as f64)
+            .sqrt()
+    });
+
+    LatencyStats {
+        transmitted: loss.total,
+        received: loss.total - loss.failures,
+        loss_percent: loss.loss_percent,
+        min_ms: latencies.first().copied(),
+        max_ms: latencies.last().copied(),
+        avg_ms: latency_and_jitter.map(|(avg, _)| avg),
+        median_ms,
+        p95_ms,
+        stddev_ms,
+        jitter_ms: latency_and_jitter.map(|(_, jitter)| jitter),
+    }
+}
+
+// ヒストグラムの区切りを検証（1 以上で昇順に並んでいること）
+pub fn validate_histogram_bounds(bounds: &[u64]) -> Result<(), String> {
+    if bounds.is_empty() || bounds.len() > MAX_HISTOGRAM_BOUNDS {
+        return Err(format!(
+            "ヒストグラムの区切りは 1 から {} 個の範囲で指定してください",
+            MAX_HISTOGRAM_BOUNDS
+        ));
+    }
+    if bounds[0] == 0 || bounds.windows(2).any(|w| w[0] >= w[1]) {
+        return Err("ヒストグラムの区切りは 1 ミリ秒以上の昇順で指定してください".to_string());
+    }
+    Ok(())
+}
+
+// 成功したサンプルを [lower_ms, upper_ms) の区間に振り分け、失敗は別に数える
+pub fn compute_latency_histogram(samples: &[PingSample], bounds: &[u64]) -> LatencyHistogram {
+    let mut buckets: Vec<HistogramBucket> = std::iter::once(0)
+        .chain(bounds.iter().copied())
+        .zip(
+            bounds
+                .iter()
+                .copied()
+                .map(Some)
+                .chain(std::iter::once(None)),
+        )
+        .map(|(lower_ms, upper_ms)| HistogramBucket {
+            lower_ms,
+            upper_ms,
+            count: 0,
+        })
+        .collect();
+    let mut failures = 0;
+    for sample in samples {
+        match sample.response_time_ms.filter(|_| sample.success) {
+            Some(ms) => {
+                let index = bounds.partition_point(|&bound| bound <= ms);
+                buckets[index].count += 1;
+            }
+            None => failures += 1,
+        }
+    }
+    LatencyHistogram { buckets, failures }
+}
+
+// サンプル列から通話品質を推定（HTTP 応答時間はハンドシェイクを含むため控えめな評価になる）
+fn family_voip_quality(samples: &[PingSample]) -> Option<FamilyVoipQuality> {
+    let (latency_ms, jitter_ms) = latency_and_jitter(samples)?;
+    let loss_percent = compute_loss_stats(samples).loss_percent;
+    Some(FamilyVoipQuality {
+        latency_ms,
+        jitter_ms,
+        loss_percent,
+        quality: estimate_voip_quality(latency_ms, jitter_ms, loss_percent),
+    })
+}
+
+#[tauri::command]
+pub async fn get_voip_quality(
+    app: AppHandle,
+    target: Option<String>,
+    window_minutes: Option<u64>,
+) -> Result<Vec<VoipQualityReport>, String> {
+    let window_minutes = window_minutes.unwrap_or(60);
+    if window_minutes == 0 || window_minutes > 60 * 24 * 365 {
+        return Err("集計期間は 1 分〜1 年の範囲で指定してください".to_string());
+    }
+
+    let entries = load_history(&app)?;
+    Ok(ping_samples_by_target(&entries)
+        .into_iter()
+        .filter(|(t, _, _)| target.as_ref().is_none_or(|target| t == target))
+        .map(|(target, ipv4, ipv6)| VoipQualityReport {
+            target,
+            window_minutes,
+            ipv4: family_voip_quality(&samples_within(&ipv4, window_minutes)),
+            ipv6: family_voip_quality(&samples_within(&ipv6, window_minutes)),
+        })
+        .collect())
+}
@@ -0,0 +1,302 @@
+use chrono::{Datelike, Local, Timelike};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+use url::Url;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::history::{load_history, HistoryKind};
+use crate::redact::{redact_text, redact_value};
+
+// サポート用の情報に含める直近の測定結果の件数
+const RECENT_RESULT_COUNT: usize = 100;
+
+// ログファイルとして含める上限（大きいファイルは末尾のみ含める）
+const MAX_LOG_BYTES: usize = 1024 * 1024;
+
+// 機密情報として値を伏せる設定項目のキー（小文字で部分一致）
+const SECRET_KEYS: &[&str] = &["secret", "password", "token", "authorization", "api_key"];
+const SECRET_MASK: &str = "<redacted>";
+
+// 同梱する設定ファイル（アプリデータディレクトリ配下）と、URL のパスも伏せるかどうか
+// Webhook の URL はパスに認証用のトークンを含むことが多いため、ホスト名までにする
+const SETTINGS_FILES: &[(&str, bool)] = &[
+    (crate::ip_echo::IP_ECHO_CONFIG_FILE_NAME, false),
+    (crate::maintenance::MAINTENANCE_FILE_NAME, false),
+    (crate::rate_limit::RATE_LIMIT_CONFIG_FILE_NAME, false),
+    (crate::targets::TARGETS_OVERRIDE_FILE_NAME, false),
+    (crate::webhook::WEBHOOK_FILE_NAME, true),
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SupportBundle {
+    pub path: String,
+    pub size_bytes: u64,
+    // ZIP に含めたファイル名
+    pub files: Vec<String>,
+    // 一部の情報を取得できなかった場合の理由（それ以外の情報は含めて作成する）
+    pub error_messages: Vec<String>,
+}
+
+// 直近の環境チェック・測定結果・ログ・設定・アプリ情報を 1 つの ZIP にまとめる
+// 保存先はダイアログで選択する（IP アドレスなどは共有用にマスクする）
+#[tauri::command]
+pub async fn create_support_bundle(app: AppHandle) -> Result<SupportBundle, String> {
+    // 任意のパスに書き込めないよう、保存先は利用者がダイアログで選択したものに限る
+    let path = choose_save_path(&app).await?;
+
+    let mut error_messages = Vec::new();
+    let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+
+    match crate::app_info::get_app_info(app.clone()).await {
+        Ok(info) => push_json(&mut entries, "app_info.json", &info, &mut error_messages),
+        Err(e) => error_messages.push(format!("アプリ情報の取得に失敗: {}", e)),
+    }
+    match crate::dry_run::get_dry_run_status().await {
+        Ok(status) => push_json(&mut entries, "dry_run.json", &status, &mut error_messages),
+        Err(e) => error_messages.push(format!("ドライランの状態の取得に失敗: {}", e)),
+    }
+
+    match load_history(&app) {
+        Ok(history) => {
+            match history
+                .iter()
+                .rev()
+                .find(|e| e.kind == HistoryKind::Environment)
+            {
+                Some(entry) => push_json(
+                    &mut entries,
+                    "environment_check.json",
+                    entry,
+                    &mut error_messages,
+                ),
+                None => error_messages.push("環境チェックの履歴がありません".to_string()),
+            }
+            let recent: Vec<_> = history
+                .iter()
+                .filter(|e| e.kind != HistoryKind::Environment)
+                .rev()
+                .take(RECENT_RESULT_COUNT)
+                .collect();
+            push_json(
+                &mut entries,
+                "recent_results.json",
+                &recent,
+                &mut error_messages,
+            );
+        }
+        Err(e) => error_messages.push(format!("履歴の読み込みに失敗: {}", e)),
+    }
+
+    collect_settings(&app, &mut entries, &mut error_messages);
+    collect_logs(&app, &mut entries, &mut error_messages).await;
+
+    let manifest = json!({
+        "created_at": crate::now_rfc3339(),
+        "app_version": app.package_info().version.to_string(),
+        "files": entries.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>(),
+        "error_messages": error_messages,
+    });
+    push_json(
+        &mut entries,
+        "manifest.json",
+        &manifest,
+        &mut error_messages,
+    );
+
+    let body = write_zip(&entries)?;
+    fs::write(&path, &body).map_err(|e| format!("ZIP ファイルの書き込みに失敗: {}", e))?;
+
+    Ok(SupportBundle {
+        path: path.to_string_lossy().to_string(),
+        size_bytes: body.len() as u64,
+        files: entries.into_iter().map(|(name, _)| name).collect(),
+        error_messages,
+    })
+}
+
+async fn choose_save_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let file_name = format!(
+        "ghttpping-support-{}.zip",
+        Local::now().format("%Y%m%d-%H%M%S")
+    );
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("サポート用の情報の保存先")
+        .add_filter("ZIP", &["zip"])
+        .set_file_name(file_name)
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    rx.await
+        .ok()
+        .flatten()
+        .ok_or_else(|| "保存先が選択されませんでした".to_string())?
+        .into_path()
+        .map_err(|e| format!("保存先のパスを取得できません: {}", e))
+}
+
+// 共有用にマスクした JSON として追加する
+fn push_json(
+    entries: &mut Vec<(String, Vec<u8>)>,
+    name: &str,
+    data: &impl Serialize,
+    error_messages: &mut Vec<String>,
+) {
+    let mut value = match serde_json::to_value(data) {
+        Ok(value) => value,
+        Err(e) => {
+            error_messages.push(format!("{} の変換に失敗: {}", name, e));
+            return;
+        }
+    };
+    redact_value(&mut value);
+    match serde_json::to_vec_pretty(&value) {
+        Ok(body) => entries.push((name.to_string(), body)),
+        Err(e) => error_messages.push(format!("{} の変換に失敗: {}", name, e)),
+    }
+}
+
+fn collect_settings(
+    app: &AppHandle,
+    entries: &mut Vec<(String, Vec<u8>)>,
+    error_messages: &mut Vec<String>,
+) {
+    let dir = match app.path().app_data_dir() {
+        Ok(dir) => dir,
+        Err(e) => {
+            error_messages.push(format!("アプリデータディレクトリの取得に失敗: {}", e));
+            return;
+        }
+    };
+    for (file_name, mask_url_path) in SETTINGS_FILES {
+        // 未設定（ファイルがない）場合は既定値のため含めない
+        let Ok(content) = fs::read_to_string(dir.join(file_name)) else {
+            continue;
+        };
+        match serde_json::from_str::<Value>(&content) {
+            Ok(mut value) => {
+                redact_settings(&mut value, *mask_url_path);
+                push_json(
+                    entries,
+                    &format!("settings/{}", file_name),
+                    &value,
+                    error_messages,
+                );
+            }
+            Err(e) => error_messages.push(format!("{} を解析できません: {}", file_name, e)),
+        }
+    }
+}
+
+// 鍵やパスワードを伏せ、URL からユーザー情報とクエリを取り除く
+fn redact_settings(value: &mut Value, mask_url_path: bool) {
+    match value {
+        Value::String(s) => {
+            if let Ok(mut url) = Url::parse(s) {
+                if matches!(url.scheme(), "http" | "https") {
+                    let _ = url.set_username("");
+                    let _ = url.set_password(None);
+                    url.set_query(None);
+                    url.set_fragment(None);
+                    if mask_url_path && url.path() != "/" {
+                        url.set_path("redacted");
+                    }
+                    *s = url.to_string();
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| redact_settings(item, mask_url_path)),
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SECRET_KEYS.iter().any(|k| key.contains(k)) {
+                    if !item.is_null() {
+                        *item = Value::String(SECRET_MASK.to_string());
+                    }
+                } else {
+                    redact_settings(item, mask_url_path);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+// Webhook の送信失敗の記録と、ログディレクトリ配下のファイルを含める
+async fn collect_logs(
+    app: &AppHandle,
+    entries: &mut Vec<(String, Vec<u8>)>,
+    error_messages: &mut Vec<String>,
+) {
+    match crate::webhook::get_webhook_failures(app.state(), None).await {
+        Ok(failures) => {
+            let mut value = serde_json::to_value(&failures).unwrap_or(Value::Null);
+            redact_settings(&mut value, true);
+            push_json(
+                entries,
+                "logs/webhook_failures.json",
+                &value,
+                error_messages,
+            );
+        }
+        Err(e) => error_messages.push(e),
+    }
+
+    let Ok(dir) = app.path().app_log_dir() else {
+        return;
+    };
+    let Ok(read_dir) = fs::read_dir(&dir) else {
+        return;
+    };
+    for item in read_dir.flatten() {
+        let path = item.path();
+        if !path.is_file() {
+            continue;
+        }
+        let name = item.file_name().to_string_lossy().to_string();
+        match fs::read(&path) {
+            Ok(bytes) => {
+                let start = bytes.len().saturating_sub(MAX_LOG_BYTES);
+                let text = redact_text(&String::from_utf8_lossy(&bytes[start..]));
+                entries.push((format!("logs/{}", name), text.into_bytes()));
+            }
+            Err(e) => error_messages.push(format!("ログ {} の読み込みに失敗: {}", name, e)),
+        }
+    }
+}
+
+// Deflate で圧縮した ZIP を作成（更新日時はローカル時刻）
+fn write_zip(entries: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let now = Local::now();
+    let modified = zip::DateTime::from_date_and_time(
+        now.year() as u16,
+        now.month() as u8,
+        now.day() as u8,
+        now.hour() as u8,
+        now.minute() as u8,
+        now.second() as u8,
+    )
+    .unwrap_or_default();
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .last_modified_time(modified);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in entries {
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| format!("{} の圧縮に失敗: {}", name, e))?;
+        zip.write_all(content)
+            .map_err(|e| format!("{} の圧縮に失敗: {}", name, e))?;
+    }
+    zip.finish()
+        .map(Cursor::into_inner)
+        .map_err(|e| format!("ZIP ファイルの作成に失敗: {}", e))
+}
@@ -0,0 +1,169 @@
+use encoding_rs::SHIFT_JIS;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tauri::AppHandle;
+use tauri_plugin_dialog::{DialogExt, FilePath};
+use tauri_plugin_fs::FsExt;
+use url::Url;
+
+use crate::{batch, template};
+
+// 読み込むファイルサイズの上限
+const MAX_FILE_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvalidTargetLine {
+    pub line: usize,
+    pub value: String,
+    pub error_message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TargetImport {
+    pub path: String,
+    // ping_http_batch にそのまま渡せる URL（ファイル内の順、重複は除く）
+    pub urls: Vec<String>,
+    pub duplicate_count: usize,
+    pub invalid_lines: Vec<InvalidTargetLine>,
+}
+
+// 改行区切りまたは CSV の URL の一覧を読み込み、検証して重複を除く
+// path を省略した場合はダイアログで選択する（CSV は URL を含む最初の列を使い、見出し行は読み飛ばす）
+#[tauri::command]
+pub async fn import_target_urls(
+    app: AppHandle,
+    path: Option<String>,
+) -> Result<TargetImport, String> {
+    let path = match path {
+        Some(path) => FilePath::from(std::path::PathBuf::from(path)),
+        None => choose_file(&app).await?,
+    };
+    let bytes = app
+        .fs()
+        .read(path.clone())
+        .map_err(|e| format!("ファイルの読み込みに失敗: {}", e))?;
+    if bytes.len() > MAX_FILE_BYTES {
+        return Err(format!(
+            "ファイルが大きすぎます（{} バイトまで）",
+            MAX_FILE_BYTES
+        ));
+    }
+    let content = decode(&bytes);
+
+    let mut urls = Vec::new();
+    let mut seen = HashSet::new();
+    let mut duplicate_count = 0;
+    let mut invalid_lines = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields = split_csv_line(line);
+        let Some(url) = fields
+            .iter()
+            .find(|f| f.starts_with("http://") || f.starts_with("https://"))
+        else {
+            // 1 行目に URL がない場合は CSV の見出し行とみなす
+            if index > 0 {
+                invalid_lines.push(InvalidTargetLine {
+                    line: index + 1,
+                    value: line.to_string(),
+                    error_message: "URL が見つかりません".to_string(),
+                });
+            }
+            continue;
+        };
+        if let Err(error_message) = validate(url) {
+            invalid_lines.push(InvalidTargetLine {
+                line: index + 1,
+                value: url.clone(),
+                error_message,
+            });
+            continue;
+        }
+        if seen.insert(dedupe_key(url)) {
+            urls.push(url.clone());
+        } else {
+            duplicate_count += 1;
+        }
+    }
+
+    if urls.is_empty() {
+        return Err("ファイルに有効な URL がありません".to_string());
+    }
+    if urls.len() > batch::MAX_URLS {
+        return Err(format!(
+            "URL が多すぎます（{} 件、{} 件まで）",
+            urls.len(),
+            batch::MAX_URLS
+        ));
+    }
+
+    Ok(TargetImport {
+        path: path.to_string(),
+        urls,
+        duplicate_count,
+        invalid_lines,
+    })
+}
+
+async fn choose_file(app: &AppHandle) -> Result<FilePath, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("読み込む URL の一覧")
+        .add_filter("URL の一覧", &["txt", "csv"])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+    rx.await
+        .ok()
+        .flatten()
+        .ok_or_else(|| "ファイルが選択されませんでした".to_string())
+}
+
+// BOM 付き UTF-8 と、Excel などで保存された Shift_JIS の CSV に対応する
+fn decode(bytes: &[u8]) -> String {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    match std::str::from_utf8(bytes) {
+        Ok(content) => content.to_string(),
+        Err(_) => SHIFT_JIS.decode(bytes).0.into_owned(),
+    }
+}
+
+// ダブルクォートで囲んだ列（"" はクォート自体）に対応した簡易的な CSV の分割
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+// 変数を含む URL は展開した結果で検証する（返す URL は変数を含んだまま）
+fn validate(url: &str) -> Result<(), String> {
+    crate::validate_url(&template::expand_template(url)?)
+}
+
+// ホスト名の大文字・小文字や既定のポートの違いは同じ URL とみなす
+fn dedupe_key(url: &str) -> String {
+    if template::is_template(url) {
+        return url.to_string();
+    }
+    Url::parse(url)
+        .map(|u| u.to_string())
+        .unwrap_or_else(|_| url.to_string())
+}
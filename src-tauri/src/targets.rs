@@ -0,0 +1,60 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::{AppHandle, Manager};
+
+// アプリに同梱するプリセット
+const BUNDLED_TARGETS_JSON: &str = include_str!("builtin_targets.json");
+
+// アプリデータディレクトリに置くと同梱プリセットより優先される更新用ファイル
+pub const TARGETS_OVERRIDE_FILE_NAME: &str = "builtin_targets.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuiltinTarget {
+    pub name: String,
+    pub url: String,
+    pub region: String,
+    pub provider: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TargetPresetFile {
+    version: u32,
+    targets: Vec<BuiltinTarget>,
+}
+
+// 同梱プリセットと更新用ファイルのうち、新しい方を読み込む
+fn load_target_presets(app: &AppHandle) -> Result<TargetPresetFile, String> {
+    let bundled: TargetPresetFile = serde_json::from_str(BUNDLED_TARGETS_JSON)
+        .map_err(|e| format!("同梱プリセットの解析失敗: {}", e))?;
+
+    let override_path = match app.path().app_data_dir() {
+        Ok(dir) => dir.join(TARGETS_OVERRIDE_FILE_NAME),
+        Err(_) => return Ok(bundled),
+    };
+    let Ok(content) = fs::read_to_string(&override_path) else {
+        return Ok(bundled);
+    };
+
+    match serde_json::from_str::<TargetPresetFile>(&content) {
+        Ok(updated) if updated.version >= bundled.version => Ok(updated),
+        Ok(_) => Ok(bundled),
+        Err(e) => {
+            eprintln!("Invalid target preset file {:?}: {}", override_path, e);
+            Ok(bundled)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn get_builtin_targets(
+    app: AppHandle,
+    region: Option<String>,
+) -> Result<Vec<BuiltinTarget>, String> {
+    let presets = load_target_presets(&app)?;
+    Ok(presets
+        .targets
+        .into_iter()
+        .filter(|t| region.as_ref().is_none_or(|r| &t.region == r))
+        .filter(|t| crate::validate_url(&t.url).is_ok())
+        .collect())
+}
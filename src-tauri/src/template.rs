@@ -0,0 +1,122 @@
+use hyper::body::Bytes;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::http_client::RequestOptions;
+
+// テンプレート中の変数の区切り
+const VARIABLE_OPEN: &str = "{{";
+const VARIABLE_CLOSE: &str = "}}";
+
+// 展開したリクエストの通し番号（アプリ起動中は全機能で共有）
+static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// 変数を含むかどうか
+pub fn is_template(template: &str) -> bool {
+    template.contains(VARIABLE_OPEN)
+}
+
+// 推測されにくい 64 ビットの乱数（RandomState は起動ごとに OS の乱数で初期化される）
+fn random_token() -> String {
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.load(Ordering::Relaxed));
+    format!("{:016x}", hasher.finish())
+}
+
+// リクエストごとに変数を展開する
+// {{timestamp}}: UNIX 時刻（秒）, {{timestamp_ms}}: UNIX 時刻（ミリ秒）,
+// {{counter}}: 通し番号, {{random}}: 16 桁の16進乱数（いずれも URL でそのまま使える文字のみ）
+pub fn expand_template(template: &str) -> Result<String, String> {
+    if !is_template(template) {
+        return Ok(template.to_string());
+    }
+    Expansion::new().expand(template)
+}
+
+// URL とヘッダの値・本文（UTF-8 の場合）の変数を展開する
+// 1 回のリクエストの中ではどこに書いても同じ値になる（通し番号も 1 つだけ進む）
+pub fn expand_request(
+    url: &str,
+    request: &RequestOptions,
+) -> Result<(String, RequestOptions), String> {
+    let body = request
+        .body
+        .as_ref()
+        .and_then(|body| std::str::from_utf8(body).ok())
+        .filter(|body| is_template(body));
+    let has_template = is_template(url)
+        || body.is_some()
+        || request.headers.iter().any(|(_, value)| is_template(value));
+    if !has_template {
+        return Ok((url.to_string(), request.clone()));
+    }
+
+    let expansion = Expansion::new();
+    let mut expanded = request.clone();
+    for (_, value) in expanded.headers.iter_mut() {
+        if is_template(value) {
+            *value = expansion.expand(value)?;
+        }
+    }
+    if let Some(body) = body {
+        expanded.body = Some(Bytes::from(expansion.expand(body)?));
+    }
+    Ok((expansion.expand(url)?, expanded))
+}
+
+// 1 回の展開で使う変数の値
+struct Expansion {
+    counter: u64,
+    now: chrono::DateTime<chrono::Local>,
+    random: String,
+}
+
+impl Expansion {
+    fn new() -> Self {
+        let counter = COUNTER.fetch_add(1, Ordering::Relaxed) + 1;
+        Expansion {
+            counter,
+            now: chrono::Local::now(),
+            random: random_token(),
+        }
+    }
+
+    fn expand(&self, template: &str) -> Result<String, String> {
+        let mut expanded = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(start) = rest.find(VARIABLE_OPEN) {
+            expanded.push_str(&rest[..start]);
+            let after_open = &rest[start + VARIABLE_OPEN.len()..];
+            let end = after_open
+                .find(VARIABLE_CLOSE)
+                .ok_or_else(|| format!("テンプレートの変数が閉じられていません: {}", template))?;
+            let name = after_open[..end].trim();
+
+            let value = match name {
+                "timestamp" => self.now.timestamp().to_string(),
+                "timestamp_ms" => self.now.timestamp_millis().to_string(),
+                "counter" => self.counter.to_string(),
+                "random" => self.random.clone(),
+                _ => {
+                    return Err(format!(
+                        "不明なテンプレート変数です: {{{{{}}}}}（timestamp, timestamp_ms, counter, random が使用できます）",
+                        name
+                    ))
+                }
+            };
+            expanded.push_str(&value);
+            rest = &after_open[end + VARIABLE_CLOSE.len()..];
+        }
+        expanded.push_str(rest);
+
+        Ok(expanded)
+    }
+}
+
+// テンプレートの展開結果を確認（通し番号は進む）
+#[tauri::command]
+pub async fn preview_template(template: String) -> Result<String, String> {
+    expand_template(&template)
+}
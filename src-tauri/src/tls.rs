@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+
+use crate::powershell;
+use crate::process::run_command;
+
+// TLS ハンドシェイクのタイムアウト
+const TLS_PROBE_TIMEOUT_MS: u64 = 5000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: String,
+    pub not_after: String,
+    pub sha1_fingerprint: String,
+    pub sha256_fingerprint: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsProbeResult {
+    pub host: String,
+    pub ip_address: Option<String>,
+    pub port: u16,
+    pub handshake_ms: u64,
+    pub policy_errors: String,
+    pub certificates: Vec<CertificateInfo>,
+}
+
+impl TlsProbeResult {
+    // 証明書チェーンの最上位（ルート）証明書
+    pub fn root_certificate(&self) -> Option<&CertificateInfo> {
+        self.certificates.last()
+    }
+}
+
+// 識別名（DN）から指定した属性（CN, O など）の値を取り出す
+pub fn dn_attribute(dn: &str, key: &str) -> Option<String> {
+    dn.split(',').find_map(|part| {
+        let (k, v) = part.trim().split_once('=')?;
+        if k.trim().eq_ignore_ascii_case(key) {
+            Some(v.trim().trim_matches('"').to_string())
+        } else {
+            None
+        }
+    })
+}
+
+// .NET の SslStream で TLS ハンドシェイクを行い、証明書チェーンを取得
+// ip_address を指定した場合はそのアドレスへ接続し、SNI には host を使う
+pub async fn probe_tls(
+    host: &str,
+    ip_address: Option<&str>,
+    port: u16,
+) -> Result<TlsProbeResult, String> {
+    crate::validate_hostname(host)?;
+    if let Some(ip) = ip_address {
+        if ip.parse::<std::net::IpAddr>().is_err() {
+            return Err(format!("無効なIPアドレス: {}", ip));
+        }
+    }
+
+    let connect_to = ip_address.unwrap_or(host);
+    let script = format!(
+        r#"$ErrorActionPreference = 'Stop'
+$client = New-Object System.Net.Sockets.TcpClient
+$iar = $client.BeginConnect({connect}, {port}, $null, $null)
+if (-not $iar.AsyncWaitHandle.WaitOne({timeout})) {{ throw 'TCP接続がタイムアウトしました' }}
+$client.EndConnect($iar)
+$script:policyErrors = 'None'
+$callback = [System.Net.Security.RemoteCertificateValidationCallback]{{ param($s, $c, $ch, $e) $script:policyErrors = "$e"; $true }}
+$stream = New-Object System.Net.Security.SslStream($client.GetStream(), $false, $callback)
+$stream.ReadTimeout = {timeout}
+$stream.WriteTimeout = {timeout}
+$sw = [System.Diagnostics.Stopwatch]::StartNew()
+$stream.AuthenticateAsClient({sni})
+$sw.Stop()
+$leaf = New-Object System.Security.Cryptography.X509Certificates.X509Certificate2($stream.RemoteCertificate)
+$chain = New-Object System.Security.Cryptography.X509Certificates.X509Chain
+$chain.ChainPolicy.RevocationMode = 'NoCheck'
+[void]$chain.Build($leaf)
+$sha256 = [System.Security.Cryptography.SHA256]::Create()
+$certs = @($chain.ChainElements | ForEach-Object {{
+    $c = $_.Certificate
+    [pscustomobject]@{{
+        subject = $c.Subject
+        issuer = $c.Issuer
+        not_before = $c.NotBefore.ToUniversalTime().ToString('o')
+        not_after = $c.NotAfter.ToUniversalTime().ToString('o')
+        sha1_fingerprint = $c.Thumbprint
+        sha256_fingerprint = [BitConverter]::ToString($sha256.ComputeHash($c.RawData)).Replace('-', '')
+    }}
+}})
+$stream.Dispose()
+$client.Close()
+[pscustomobject]@{{
+    handshake_ms = $sw.ElapsedMilliseconds
+    policy_errors = $script:policyErrors
+    certificates = $certs
+}} | ConvertTo-Json -Depth 4 -Compress"#,
+        connect = powershell::quote(connect_to),
+        port = port,
+        timeout = TLS_PROBE_TIMEOUT_MS,
+        sni = powershell::quote(host),
+    );
+
+    let args = [
+        "-NoProfile".to_string(),
+        "-WindowStyle".to_string(),
+        "Hidden".to_string(),
+        "-Command".to_string(),
+        script,
+    ];
+    let output = run_command("powershell", &args)
+        .await
+        .map_err(|e| format!("PowerShellコマンド実行失敗: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = crate::decode_command_output(&output.stderr);
+        let reason = stderr
+            .lines()
+            .map(|l| l.trim())
+            .find(|l| !l.is_empty())
+            .unwrap_or("不明なエラー");
+        return Err(format!("TLSハンドシェイクに失敗: {}", reason));
+    }
+
+    // PowerShell 出力解析用の内部構造体
+    #[derive(Deserialize)]
+    struct ProbeOutput {
+        handshake_ms: u64,
+        policy_errors: String,
+        certificates: Vec<CertificateInfo>,
+    }
+
+    let stdout = crate::decode_command_output(&output.stdout);
+    let parsed: ProbeOutput = serde_json::from_str(stdout.trim())
+        .map_err(|e| format!("TLS確認結果の解析失敗: {}", e))?;
+
+    Ok(TlsProbeResult {
+        host: host.to_string(),
+        ip_address: ip_address.map(|s| s.to_string()),
+        port,
+        handshake_ms: parsed.handshake_ms,
+        policy_errors: parsed.policy_errors,
+        certificates: parsed.certificates,
+    })
+}
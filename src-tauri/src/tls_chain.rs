@@ -0,0 +1,292 @@
+use ring::digest::{digest, SHA1_FOR_LEGACY_USE_ONLY, SHA256};
+use rustls::pki_types::CertificateDer;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use tauri::AppHandle;
+use url::Url;
+use x509_parser::extensions::GeneralName;
+use x509_parser::public_key::PublicKey;
+
+use crate::http_client;
+use crate::ocsp::{self, RevocationStatus};
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::DnsResolution;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub serial_number: String,
+    pub subject_alternative_names: Vec<String>,
+    pub not_before: String,
+    pub not_after: String,
+    // 確認した時点で有効期間外か
+    pub expired: bool,
+    // "RSA" / "ECDSA P-256" など
+    pub key_algorithm: String,
+    pub key_size_bits: Option<usize>,
+    pub signature_algorithm: String,
+    pub is_ca: bool,
+    pub sha1_fingerprint: String,
+    pub sha256_fingerprint: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TlsChainResult {
+    pub ip_address: String,
+    pub tls_version: Option<String>,
+    pub cipher_suite: Option<String>,
+    pub handshake_ms: Option<f64>,
+    // OS の証明書ストアで検証した結果（None なら測定時に TLS エラーにならない）
+    pub verification_error: Option<String>,
+    // サーバが送信した順（先頭がサーバ証明書、ルート証明書は通常含まれない）
+    pub certificates: Vec<ChainCertificate>,
+    // サーバが OCSP 応答をステープリングしたか
+    #[serde(default)]
+    pub ocsp_stapled: bool,
+    // サーバ証明書の失効状態（ステープリングがなく、レスポンダへの問い合わせもしない場合は None）
+    #[serde(default)]
+    pub revocation: Option<RevocationStatus>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TlsInspectionResult {
+    pub url: String,
+    pub host: String,
+    pub port: u16,
+    pub dns_resolution: DnsResolution,
+    pub ipv4: Option<TlsChainResult>,
+    pub ipv6: Option<TlsChainResult>,
+}
+
+// 接続先が送信した証明書チェーンを IPv4/IPv6 ごとに取得し、各証明書の内容と検証結果を返す
+// TLS エラーで測定に失敗する原因（中間証明書の不足、期限切れ、名前の不一致、失効など）の確認用
+// check_revocation（既定は true）の場合、OCSP ステープリングがなければ OCSP レスポンダへ問い合わせる
+#[tauri::command]
+pub async fn inspect_tls(
+    app: AppHandle,
+    url: String,
+    timeout_secs: Option<u64>,
+    check_revocation: Option<bool>,
+) -> Result<TlsInspectionResult, String> {
+    let check_revocation = check_revocation.unwrap_or(true);
+    let timeout_secs = timeout_secs.unwrap_or(http_client::DEFAULT_TIMEOUT_SECS);
+    http_client::validate_timeout_secs(timeout_secs)?;
+    crate::validate_url(&url)?;
+
+    let target = url.clone();
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        &target,
+        execute_inspection(url, check_revocation, timeout_secs),
+    )
+    .await
+}
+
+async fn execute_inspection(
+    url: String,
+    check_revocation: bool,
+    timeout_secs: u64,
+) -> Result<TlsInspectionResult, String> {
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    if parsed_url.scheme() != "https" {
+        return Err("証明書を確認するには https の URL を指定してください".to_string());
+    }
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+    let port = parsed_url.port_or_known_default().unwrap_or(443);
+
+    let dns_resolution = crate::resolve_dns(&host).await;
+    report_progress(20.0);
+    let (ipv4, ipv6) = tokio::join!(
+        inspect_address(
+            dns_resolution.ipv4_addresses.first(),
+            port,
+            &host,
+            check_revocation,
+            timeout_secs
+        ),
+        inspect_address(
+            dns_resolution.ipv6_addresses.first(),
+            port,
+            &host,
+            check_revocation,
+            timeout_secs
+        ),
+    );
+
+    Ok(TlsInspectionResult {
+        url,
+        host,
+        port,
+        dns_resolution,
+        ipv4,
+        ipv6,
+    })
+}
+
+async fn inspect_address(
+    ip_address: Option<&String>,
+    port: u16,
+    host: &str,
+    check_revocation: bool,
+    timeout_secs: u64,
+) -> Option<TlsChainResult> {
+    let ip_address = ip_address?;
+    let mut result = TlsChainResult {
+        ip_address: ip_address.clone(),
+        tls_version: None,
+        cipher_suite: None,
+        handshake_ms: None,
+        verification_error: None,
+        certificates: Vec::new(),
+        ocsp_stapled: false,
+        revocation: None,
+        error_message: None,
+    };
+    let ip: IpAddr = match ip_address.parse() {
+        Ok(ip) => ip,
+        Err(_) => {
+            result.error_message = Some(format!("無効なIPアドレス: {}", ip_address));
+            return Some(result);
+        }
+    };
+
+    match http_client::fetch_certificate_chain(ip, port, host, None, timeout_secs).await {
+        Ok(chain) => {
+            result.tls_version = chain.tls_version;
+            result.cipher_suite = chain.cipher_suite;
+            result.handshake_ms = Some(chain.handshake_ms);
+            result.verification_error = chain.verification_error;
+            result.certificates = chain
+                .certificates
+                .iter()
+                .filter_map(chain_certificate)
+                .collect();
+            result.ocsp_stapled = chain.ocsp_response.is_some();
+            result.revocation = ocsp::check_revocation(
+                &chain.certificates,
+                chain.ocsp_response.as_deref(),
+                check_revocation,
+                timeout_secs,
+            )
+            .await;
+        }
+        Err(e) => result.error_message = Some(e),
+    }
+    Some(result)
+}
+
+fn chain_certificate(certificate: &CertificateDer<'_>) -> Option<ChainCertificate> {
+    let (_, parsed) = x509_parser::parse_x509_certificate(certificate.as_ref()).ok()?;
+    let validity = parsed.validity();
+    let format_time = |time: &x509_parser::time::ASN1Time| {
+        chrono::DateTime::from_timestamp(time.timestamp(), 0)
+            .map(|t| t.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+            .unwrap_or_default()
+    };
+
+    let subject_alternative_names = parsed
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|san| {
+            san.value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(dns) => Some(dns.to_string()),
+                    GeneralName::IPAddress(bytes) => ip_from_bytes(bytes),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let (key_algorithm, key_size_bits) = match parsed.public_key().parsed() {
+        Ok(PublicKey::RSA(key)) => ("RSA".to_string(), Some(key.key_size())),
+        Ok(PublicKey::EC(point)) => {
+            let curve = parsed
+                .public_key()
+                .algorithm
+                .parameters
+                .as_ref()
+                .and_then(|p| p.as_oid().ok())
+                .map(|oid| curve_name(&oid.to_id_string()));
+            (
+                format!("ECDSA {}", curve.unwrap_or("unknown")),
+                Some(point.key_size()),
+            )
+        }
+        Ok(PublicKey::DSA(_)) => ("DSA".to_string(), None),
+        // Ed25519 などは署名アルゴリズムと同じ OID
+        _ => (
+            signature_algorithm_name(&parsed.public_key().algorithm.algorithm.to_id_string()),
+            None,
+        ),
+    };
+
+    Some(ChainCertificate {
+        subject: parsed.subject().to_string(),
+        issuer: parsed.issuer().to_string(),
+        serial_number: parsed.raw_serial_as_string().to_uppercase(),
+        subject_alternative_names,
+        not_before: format_time(&validity.not_before),
+        not_after: format_time(&validity.not_after),
+        expired: !validity.is_valid(),
+        key_algorithm,
+        key_size_bits,
+        signature_algorithm: signature_algorithm_name(
+            &parsed.signature_algorithm.algorithm.to_id_string(),
+        ),
+        is_ca: parsed.is_ca(),
+        sha1_fingerprint: hex_upper(
+            digest(&SHA1_FOR_LEGACY_USE_ONLY, certificate.as_ref()).as_ref(),
+        ),
+        sha256_fingerprint: hex_upper(digest(&SHA256, certificate.as_ref()).as_ref()),
+    })
+}
+
+fn ip_from_bytes(bytes: &[u8]) -> Option<String> {
+    match bytes.len() {
+        4 => Some(IpAddr::from(<[u8; 4]>::try_from(bytes).ok()?).to_string()),
+        16 => Some(IpAddr::from(<[u8; 16]>::try_from(bytes).ok()?).to_string()),
+        _ => None,
+    }
+}
+
+fn curve_name(oid: &str) -> &'static str {
+    match oid {
+        "1.2.840.10045.3.1.7" => "P-256",
+        "1.3.132.0.34" => "P-384",
+        "1.3.132.0.35" => "P-521",
+        _ => "unknown",
+    }
+}
+
+// よく使われる署名アルゴリズムは名前で、それ以外は OID のまま返す
+fn signature_algorithm_name(oid: &str) -> String {
+    match oid {
+        "1.2.840.113549.1.1.5" => "sha1WithRSAEncryption",
+        "1.2.840.113549.1.1.11" => "sha256WithRSAEncryption",
+        "1.2.840.113549.1.1.12" => "sha384WithRSAEncryption",
+        "1.2.840.113549.1.1.13" => "sha512WithRSAEncryption",
+        "1.2.840.113549.1.1.10" => "rsassaPss",
+        "1.2.840.10045.4.3.2" => "ecdsa-with-SHA256",
+        "1.2.840.10045.4.3.3" => "ecdsa-with-SHA384",
+        "1.2.840.10045.4.3.4" => "ecdsa-with-SHA512",
+        "1.3.101.112" => "Ed25519",
+        _ => return oid.to_string(),
+    }
+    .to_string()
+}
+
+// tls.rs の証明書情報と同じく区切りなしの大文字16進数
+fn hex_upper(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect()
+}
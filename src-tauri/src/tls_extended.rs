@@ -0,0 +1,480 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use url::Url;
+
+use crate::curl::{describe_exit_code, run_curl, CurlCapabilities, CurlErrorKind, CurlFeature};
+use crate::operations::{run_operation, OperationKind};
+use crate::DnsResolution;
+
+// 2回目のハンドシェイクがこの割合より速ければ再開による短縮とみなす
+const RESUMPTION_SPEEDUP_RATIO: f64 = 0.8;
+
+// curl の verbose ログに出るセッション再利用のメッセージ（小文字で比較）
+const SESSION_REUSE_MARKERS: [&str; 4] = [
+    "reusing session",
+    "re-using session",
+    "reusing existing credential",
+    "re-using existing credential",
+];
+
+// 0-RTT を受け入れたサーバでは、早期データの再送（リプレイ）に注意が必要
+const EARLY_DATA_REPLAY_WARNING: &str = "早期データ (0-RTT) は経路上で再送（リプレイ）される可能性があります。サーバ側で冪等でない要求（POST など）を早期データとして処理しないよう設定されているか確認してください";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TlsResumptionResult {
+    pub ip_address: Option<String>,
+    pub first_handshake_ms: Option<f64>,
+    pub second_handshake_ms: Option<f64>,
+    pub saving_ms: Option<f64>,
+    pub resumption_attempted: bool,
+    pub resumed: bool,
+    pub error_message: Option<String>,
+    #[serde(default)]
+    pub curl_error: Option<CurlErrorKind>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EarlyDataStatus {
+    Accepted,
+    Rejected,
+    NotAttempted,
+    ClientUnsupported,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TlsEarlyDataResult {
+    pub ip_address: Option<String>,
+    pub status: EarlyDataStatus,
+    pub accepted_bytes: Option<u64>,
+    pub error_message: Option<String>,
+    #[serde(default)]
+    pub curl_error: Option<CurlErrorKind>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TlsFamilyDiagnostics {
+    pub resumption: TlsResumptionResult,
+    #[serde(default)]
+    pub early_data: Option<TlsEarlyDataResult>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtendedTlsDiagnostics {
+    pub url: String,
+    pub host: String,
+    pub port: u16,
+    pub dns_resolution: DnsResolution,
+    pub ipv4: TlsFamilyDiagnostics,
+    pub ipv6: TlsFamilyDiagnostics,
+    pub findings: Vec<String>,
+    #[serde(default)]
+    pub unsupported_features: Vec<CurlFeature>,
+}
+
+#[tauri::command]
+pub async fn run_extended_tls_diagnostics(
+    app: AppHandle,
+    url: String,
+    ignore_tls_errors: Option<bool>,
+) -> Result<ExtendedTlsDiagnostics, String> {
+    let target = url.clone();
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        &target,
+        execute_extended_tls_diagnostics(url, ignore_tls_errors.unwrap_or(false)),
+    )
+    .await
+}
+
+async fn execute_extended_tls_diagnostics(
+    url: String,
+    ignore_tls_errors: bool,
+) -> Result<ExtendedTlsDiagnostics, String> {
+    if ignore_tls_errors {
+        crate::log_security_warning("TLS証明書検証が無効化されています");
+    }
+
+    let url = crate::normalize_url(&url)?;
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    if parsed_url.scheme() != "https" {
+        return Err("TLS の詳細診断には https:// の URL を指定してください".to_string());
+    }
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+    let port = parsed_url.port().unwrap_or(443);
+
+    let dns_resolution = crate::resolve_dns(&host).await;
+
+    // 古い curl では使えない機能を避けて測定し、結果に明示する
+    let capabilities = crate::curl::capabilities().await;
+    let unsupported_features: Vec<CurlFeature> =
+        [CurlFeature::ResolveIpv6Brackets, CurlFeature::TlsEarlyData]
+            .into_iter()
+            .filter(|f| !capabilities.supports(*f))
+            .collect();
+
+    // IPv4 と IPv6 で並行して測定（それぞれ最初のアドレスを使用）
+    let ipv4_address = dns_resolution.ipv4_addresses.first();
+    let ipv6_address = dns_resolution.ipv6_addresses.first();
+    let (ipv4_resumption, ipv6_resumption) = tokio::join!(
+        test_resumption(
+            &url,
+            &host,
+            port,
+            ipv4_address,
+            ignore_tls_errors,
+            4,
+            capabilities
+        ),
+        test_resumption(
+            &url,
+            &host,
+            port,
+            ipv6_address,
+            ignore_tls_errors,
+            6,
+            capabilities
+        ),
+    );
+
+    // 早期データはセッションを再開できた場合のみ送信できる
+    let (ipv4_early_data, ipv6_early_data) = tokio::join!(
+        test_early_data(
+            &url,
+            &host,
+            port,
+            &ipv4_resumption,
+            ignore_tls_errors,
+            capabilities
+        ),
+        test_early_data(
+            &url,
+            &host,
+            port,
+            &ipv6_resumption,
+            ignore_tls_errors,
+            capabilities
+        ),
+    );
+
+    let mut findings = Vec::new();
+    for (family, result) in [("IPv4", &ipv4_resumption), ("IPv6", &ipv6_resumption)] {
+        if result.ip_address.is_none() || result.error_message.is_some() {
+            continue;
+        }
+        if result.resumed {
+            findings.push(format!(
+                "{} では TLS セッションが再開され、ハンドシェイクが {:.1} ミリ秒短縮されました",
+                family,
+                result.saving_ms.unwrap_or(0.0)
+            ));
+        } else {
+            findings.push(format!(
+                "{} では TLS セッションが再開されませんでした。再接続のたびに完全なハンドシェイクが必要になり、接続が遅くなります。負荷分散装置でセッションチケットが無効になっていないか確認してください",
+                family
+            ));
+        }
+    }
+    if ipv4_resumption.resumed != ipv6_resumption.resumed
+        && ipv4_resumption.error_message.is_none()
+        && ipv6_resumption.error_message.is_none()
+        && ipv4_resumption.ip_address.is_some()
+        && ipv6_resumption.ip_address.is_some()
+    {
+        findings.push(
+            "IPv4 と IPv6 でセッション再開の可否が異なります。IPv4 と IPv6 で別の TLS 終端装置が応答している可能性があります"
+                .to_string(),
+        );
+    }
+
+    for (family, early_data) in [("IPv4", &ipv4_early_data), ("IPv6", &ipv6_early_data)] {
+        match early_data.as_ref().map(|e| e.status) {
+            Some(EarlyDataStatus::Accepted) => {
+                findings.push(format!(
+                    "{} ではサーバが TLS 1.3 の早期データ (0-RTT) を受け入れました",
+                    family
+                ));
+                if !findings.iter().any(|f| f == EARLY_DATA_REPLAY_WARNING) {
+                    findings.push(EARLY_DATA_REPLAY_WARNING.to_string());
+                }
+            }
+            Some(EarlyDataStatus::Rejected) => findings.push(format!(
+                "{} ではサーバが早期データ (0-RTT) を受け入れませんでした（セッション再開は可能です）",
+                family
+            )),
+            _ => {}
+        }
+    }
+    if [&ipv4_early_data, &ipv6_early_data]
+        .iter()
+        .any(|e| e.as_ref().map(|e| e.status) == Some(EarlyDataStatus::ClientUnsupported))
+    {
+        findings.push(
+            "インストールされている curl は早期データの送信に対応していないため、0-RTT の対応状況は判定できませんでした"
+                .to_string(),
+        );
+    }
+
+    Ok(ExtendedTlsDiagnostics {
+        url,
+        host,
+        port,
+        dns_resolution,
+        ipv4: TlsFamilyDiagnostics {
+            resumption: ipv4_resumption,
+            early_data: ipv4_early_data,
+        },
+        ipv6: TlsFamilyDiagnostics {
+            resumption: ipv6_resumption,
+            early_data: ipv6_early_data,
+        },
+        findings,
+        unsupported_features,
+    })
+}
+
+// 同じアドレスに2回続けて接続し、2回目で TLS セッションが再開されるかを確認
+async fn test_resumption(
+    url: &str,
+    host: &str,
+    port: u16,
+    ip_address: Option<&String>,
+    ignore_tls_errors: bool,
+    ip_version: u8,
+    capabilities: &CurlCapabilities,
+) -> TlsResumptionResult {
+    let mut result = TlsResumptionResult {
+        ip_address: ip_address.cloned(),
+        first_handshake_ms: None,
+        second_handshake_ms: None,
+        saving_ms: None,
+        resumption_attempted: false,
+        resumed: false,
+        error_message: None,
+        curl_error: None,
+    };
+    let Some(ip_address) = ip_address else {
+        result.error_message = Some(format!("IPv{}アドレスが見つかりません", ip_version));
+        return result;
+    };
+
+    // --next で区切った2つの転送は同一プロセス内でセッションキャッシュを共有する
+    // Connection: close により2回目は必ず新しい接続になる
+    let brackets = capabilities.supports(CurlFeature::ResolveIpv6Brackets);
+    let mut args = transfer_args(
+        url,
+        host,
+        port,
+        ip_address,
+        ignore_tls_errors,
+        false,
+        brackets,
+    );
+    args.push("--next".to_string());
+    args.extend(transfer_args(
+        url,
+        host,
+        port,
+        ip_address,
+        ignore_tls_errors,
+        false,
+        brackets,
+    ));
+    args.insert(0, "--verbose".to_string());
+
+    let output = match run_curl(args).await {
+        Ok(output) => output,
+        Err(e) => {
+            result.error_message = Some(e);
+            return result;
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let handshakes: Vec<Option<f64>> = stdout.lines().map(parse_handshake_ms).collect();
+    result.first_handshake_ms = handshakes.first().copied().flatten();
+    result.second_handshake_ms = handshakes.get(1).copied().flatten();
+
+    if !output.status.success() {
+        result.curl_error = Some(CurlErrorKind::from_exit_code(output.status.code()));
+        result.error_message = Some(format!(
+            "TLS 接続に失敗: {}",
+            describe_exit_code(output.status.code())
+        ));
+        return result;
+    }
+    if result.second_handshake_ms.is_none() {
+        result.error_message =
+            Some("TLS 接続に失敗: 2回目のハンドシェイクの時間を取得できません".to_string());
+        return result;
+    }
+
+    let verbose_log = String::from_utf8_lossy(&output.stderr).to_lowercase();
+    result.resumption_attempted = SESSION_REUSE_MARKERS
+        .iter()
+        .any(|marker| verbose_log.contains(marker));
+
+    if let (Some(first), Some(second)) = (result.first_handshake_ms, result.second_handshake_ms) {
+        result.saving_ms = Some(first - second);
+        result.resumed = result.resumption_attempted && second < first * RESUMPTION_SPEEDUP_RATIO;
+    }
+    result
+}
+
+// セッション再開時に TLS 1.3 の早期データ (0-RTT) が受け入れられるかを確認
+async fn test_early_data(
+    url: &str,
+    host: &str,
+    port: u16,
+    resumption: &TlsResumptionResult,
+    ignore_tls_errors: bool,
+    capabilities: &CurlCapabilities,
+) -> Option<TlsEarlyDataResult> {
+    let ip_address = resumption.ip_address.as_ref()?;
+    if resumption.error_message.is_some() {
+        return None;
+    }
+    let mut result = TlsEarlyDataResult {
+        ip_address: Some(ip_address.clone()),
+        status: EarlyDataStatus::NotAttempted,
+        accepted_bytes: None,
+        error_message: None,
+        curl_error: None,
+    };
+    if !resumption.resumed {
+        result.error_message =
+            Some("セッションが再開されないため、早期データは送信されません".to_string());
+        return Some(result);
+    }
+
+    // --tls-earlydata のない curl では実行しても終了コード 2 になるだけのため送信しない
+    if !capabilities.supports(CurlFeature::TlsEarlyData) {
+        result.status = EarlyDataStatus::ClientUnsupported;
+        return Some(result);
+    }
+
+    // 1回目でセッションチケットを受け取り、2回目で早期データを送る
+    let brackets = capabilities.supports(CurlFeature::ResolveIpv6Brackets);
+    let mut args = transfer_args(
+        url,
+        host,
+        port,
+        ip_address,
+        ignore_tls_errors,
+        true,
+        brackets,
+    );
+    args.push("--next".to_string());
+    args.extend(transfer_args(
+        url,
+        host,
+        port,
+        ip_address,
+        ignore_tls_errors,
+        true,
+        brackets,
+    ));
+    args.insert(0, "--verbose".to_string());
+
+    let output = match run_curl(args).await {
+        Ok(output) => output,
+        Err(e) => {
+            result.status = EarlyDataStatus::Failed;
+            result.error_message = Some(e);
+            return Some(result);
+        }
+    };
+    let verbose_log = String::from_utf8_lossy(&output.stderr).to_lowercase();
+
+    // curl の終了コード 2 はオプション未対応（古い curl）
+    if output.status.code() == Some(2) || verbose_log.contains("is unknown") {
+        result.status = EarlyDataStatus::ClientUnsupported;
+        return Some(result);
+    }
+    if !output.status.success() {
+        result.status = EarlyDataStatus::Failed;
+        result.curl_error = Some(CurlErrorKind::from_exit_code(output.status.code()));
+        result.error_message = Some(format!(
+            "TLS 接続に失敗: {}",
+            describe_exit_code(output.status.code())
+        ));
+        return Some(result);
+    }
+
+    let early_data_lines: Vec<&str> = verbose_log
+        .lines()
+        .filter(|l| l.contains("early data"))
+        .collect();
+    if let Some(line) = early_data_lines.iter().find(|l| l.contains("accepted")) {
+        result.status = EarlyDataStatus::Accepted;
+        result.accepted_bytes = line
+            .split_whitespace()
+            .find_map(|word| word.parse::<u64>().ok());
+    } else if early_data_lines
+        .iter()
+        .any(|l| l.contains("rejected") || l.contains("declined"))
+    {
+        result.status = EarlyDataStatus::Rejected;
+    } else if early_data_lines.is_empty() {
+        // TLS ライブラリ（Schannel など）が対応していない場合、オプションは無視される
+        result.status = EarlyDataStatus::ClientUnsupported;
+    }
+    Some(result)
+}
+
+// 1回分の転送の引数（接続先を固定し、TLS ハンドシェイクの時間を出力）
+pub fn transfer_args(
+    url: &str,
+    host: &str,
+    port: u16,
+    ip_address: &str,
+    ignore_tls_errors: bool,
+    early_data: bool,
+    ipv6_brackets: bool,
+) -> Vec<String> {
+    // 7.57.0 より前の curl は --resolve の IPv6 アドレスを角括弧なしで指定する
+    let resolve_arg = if ip_address.contains(':') && ipv6_brackets {
+        format!("{}:{}:[{}]", host, port, ip_address)
+    } else {
+        format!("{}:{}:{}", host, port, ip_address)
+    };
+    let mut args = vec![
+        "--resolve".to_string(),
+        resolve_arg,
+        "--header".to_string(),
+        "Connection: close".to_string(),
+        "--silent".to_string(),
+        "--output".to_string(),
+        "nul".to_string(),
+        "--write-out".to_string(),
+        "%{time_connect} %{time_appconnect}\\n".to_string(),
+        "--max-time".to_string(),
+        "10".to_string(),
+    ];
+    if ignore_tls_errors {
+        args.push("--insecure".to_string());
+    }
+    if early_data {
+        args.push("--tlsv1.3".to_string());
+        args.push("--tls-earlydata".to_string());
+    }
+    args.push(url.to_string());
+    args
+}
+
+// "time_connect time_appconnect" の行から TLS ハンドシェイクの時間（ミリ秒）を求める
+pub fn parse_handshake_ms(line: &str) -> Option<f64> {
+    let mut parts = line.split_whitespace();
+    let connect: f64 = parts.next()?.parse().ok()?;
+    let appconnect: f64 = parts.next()?.parse().ok()?;
+    if appconnect <= 0.0 {
+        return None;
+    }
+    Some((appconnect - connect) * 1000.0)
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+use crate::operations::{run_operation, OperationKind};
+use crate::tls::{dn_attribute, probe_tls};
+
+// 確認に使う有名ドメイン（いずれも公的 CA の証明書を使用）
+const WELL_KNOWN_DOMAINS: [&str; 4] = [
+    "www.google.com",
+    "www.microsoft.com",
+    "www.amazon.com",
+    "www.cloudflare.com",
+];
+
+// 公的 CA のルート証明書に含まれる名称
+const PUBLIC_CA_NAMES: [&str; 32] = [
+    "DigiCert",
+    "GlobalSign",
+    "ISRG",
+    "Let's Encrypt",
+    "Google Trust Services",
+    "GTS Root",
+    "Sectigo",
+    "COMODO",
+    "USERTrust",
+    "AAA Certificate Services",
+    "Microsoft",
+    "Amazon",
+    "Starfield",
+    "Go Daddy",
+    "GoDaddy",
+    "Entrust",
+    "Baltimore",
+    "IdenTrust",
+    "Certum",
+    "SSL.com",
+    "QuoVadis",
+    "Actalis",
+    "Buypass",
+    "Security Communication",
+    "SECOM",
+    "Cybertrust",
+    "HARICA",
+    "T-TeleSec",
+    "D-TRUST",
+    "SwissSign",
+    "VeriSign",
+    "Certainly",
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InterceptionProbe {
+    pub domain: String,
+    pub issuer: Option<String>,
+    pub root_ca: Option<String>,
+    pub sha256_fingerprint: Option<String>,
+    pub public_ca: bool,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TlsInterceptionResult {
+    pub probes: Vec<InterceptionProbe>,
+    pub interception_suspected: bool,
+    pub interceptor_ca: Option<String>,
+    pub findings: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn check_tls_interception(app: AppHandle) -> Result<TlsInterceptionResult, String> {
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        "tls_interception",
+        execute_tls_interception_check(),
+    )
+    .await
+}
+
+async fn execute_tls_interception_check() -> Result<TlsInterceptionResult, String> {
+    let mut probes = Vec::new();
+    let mut findings = Vec::new();
+    let mut interceptor_ca: Option<String> = None;
+
+    for domain in WELL_KNOWN_DOMAINS {
+        let probe = match probe_tls(domain, None, 443).await {
+            Ok(tls) => {
+                let leaf = tls.certificates.first();
+                let root = tls.root_certificate();
+                let root_dn = root.map(|c| c.subject.clone());
+                let root_ca = root_dn
+                    .as_deref()
+                    .and_then(|dn| dn_attribute(dn, "CN").or_else(|| dn_attribute(dn, "O")));
+                let public_ca = root_dn.as_deref().is_some_and(is_public_ca);
+
+                if !public_ca {
+                    let name = root_ca.clone().unwrap_or_else(|| "不明な CA".to_string());
+                    findings.push(format!(
+                        "{} の証明書が公的 CA ではない「{}」により発行されています",
+                        domain, name
+                    ));
+                    interceptor_ca.get_or_insert(name);
+                }
+
+                InterceptionProbe {
+                    domain: domain.to_string(),
+                    issuer: leaf.map(|c| c.issuer.clone()),
+                    root_ca,
+                    sha256_fingerprint: leaf.map(|c| c.sha256_fingerprint.clone()),
+                    public_ca,
+                    error_message: None,
+                }
+            }
+            Err(e) => InterceptionProbe {
+                domain: domain.to_string(),
+                issuer: None,
+                root_ca: None,
+                sha256_fingerprint: None,
+                public_ca: false,
+                error_message: Some(e),
+            },
+        };
+        probes.push(probe);
+    }
+
+    let interception_suspected = interceptor_ca.is_some();
+    if let Some(name) = &interceptor_ca {
+        findings.insert(
+            0,
+            format!(
+                "TLS 通信の傍受（インターセプト）が疑われます: {}。社内プロキシやセキュリティソフトによる検査の可能性があります",
+                name
+            ),
+        );
+    }
+
+    Ok(TlsInterceptionResult {
+        probes,
+        interception_suspected,
+        interceptor_ca,
+        findings,
+    })
+}
+
+// ルート証明書の DN が公的 CA のものかどうか
+fn is_public_ca(dn: &str) -> bool {
+    let dn_lower = dn.to_lowercase();
+    PUBLIC_CA_NAMES
+        .iter()
+        .any(|name| dn_lower.contains(&name.to_lowercase()))
+}
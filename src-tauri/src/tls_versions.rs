@@ -0,0 +1,346 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use tauri::AppHandle;
+use url::Url;
+
+use crate::curl::{describe_exit_code, run_curl, CurlErrorKind, CurlFeature};
+use crate::http_client::{self, TlsVersion};
+use crate::operations::{report_progress, run_operation, OperationKind};
+use crate::tls_extended::{parse_handshake_ms, transfer_args};
+use crate::DnsResolution;
+
+// 確認するバージョン（表示名、curl の指定）の古い順
+// TLS 1.0/1.1 は rustls が実装していないため curl.exe で、1.2/1.3 は内蔵のクライアントで確認する
+const LEGACY_VERSIONS: [(&str, &str); 2] = [("TLSv1.0", "1.0"), ("TLSv1.1", "1.1")];
+const MODERN_VERSIONS: [TlsVersion; 2] = [TlsVersion::Tls1_2, TlsVersion::Tls1_3];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsVersionStatus {
+    Supported,
+    // ハンドシェイクが TLS のエラーで失敗した（サーバがそのバージョンを受け付けない）
+    Rejected,
+    // 接続できないなど、バージョン以外の理由で判定できなかった
+    Failed,
+    // curl.exe がバージョンの上限の指定（--tls-max）に対応していない
+    ClientUnsupported,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TlsProbeClient {
+    Builtin,
+    Curl,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TlsVersionAttempt {
+    pub version: String,
+    pub status: TlsVersionStatus,
+    pub client: TlsProbeClient,
+    pub handshake_ms: Option<f64>,
+    pub cipher_suite: Option<String>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FamilyTlsVersions {
+    pub ip_address: Option<String>,
+    pub attempts: Vec<TlsVersionAttempt>,
+    pub supported_versions: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TlsVersionScanResult {
+    pub url: String,
+    pub host: String,
+    pub port: u16,
+    pub dns_resolution: DnsResolution,
+    pub ipv4: FamilyTlsVersions,
+    pub ipv6: FamilyTlsVersions,
+    pub findings: Vec<String>,
+}
+
+// TLS 1.0〜1.3 のそれぞれに固定してハンドシェイクし、IPv4/IPv6 ごとに対応しているバージョンを返す
+// バージョンの対応状況のみを確認するため、証明書は検証しない
+#[tauri::command]
+pub async fn scan_tls_versions(
+    app: AppHandle,
+    url: String,
+    timeout_secs: Option<u64>,
+) -> Result<TlsVersionScanResult, String> {
+    let timeout_secs = timeout_secs.unwrap_or(http_client::DEFAULT_TIMEOUT_SECS);
+    http_client::validate_timeout_secs(timeout_secs)?;
+    let url = crate::normalize_url(&url)?;
+
+    let target = url.clone();
+    run_operation(
+        &app,
+        OperationKind::Diagnostic,
+        &target,
+        execute_scan(url, timeout_secs),
+    )
+    .await
+}
+
+async fn execute_scan(url: String, timeout_secs: u64) -> Result<TlsVersionScanResult, String> {
+    let parsed_url = Url::parse(&url).map_err(|e| format!("無効なURL: {}", e))?;
+    if parsed_url.scheme() != "https" {
+        return Err("TLS のバージョンを確認するには https の URL を指定してください".to_string());
+    }
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| "URLからホスト名を抽出できません".to_string())?
+        .to_string();
+    crate::validate_hostname(&host)?;
+    let port = parsed_url.port_or_known_default().unwrap_or(443);
+
+    let dns_resolution = crate::resolve_dns(&host).await;
+    report_progress(10.0);
+    let (ipv4, ipv6) = tokio::join!(
+        scan_family(
+            &url,
+            &host,
+            port,
+            dns_resolution.ipv4_addresses.first(),
+            timeout_secs
+        ),
+        scan_family(
+            &url,
+            &host,
+            port,
+            dns_resolution.ipv6_addresses.first(),
+            timeout_secs
+        ),
+    );
+    let findings = find_issues(&ipv4, &ipv6);
+
+    Ok(TlsVersionScanResult {
+        url,
+        host,
+        port,
+        dns_resolution,
+        ipv4,
+        ipv6,
+        findings,
+    })
+}
+
+async fn scan_family(
+    url: &str,
+    host: &str,
+    port: u16,
+    ip_address: Option<&String>,
+    timeout_secs: u64,
+) -> FamilyTlsVersions {
+    let mut family = FamilyTlsVersions {
+        ip_address: ip_address.cloned(),
+        attempts: Vec::new(),
+        supported_versions: Vec::new(),
+    };
+    let Some(ip_address) = ip_address else {
+        return family;
+    };
+
+    // 同時に接続すると接続数の制限で失敗するサーバがあるため、順に確認する
+    for (version, curl_version) in LEGACY_VERSIONS {
+        family.attempts.push(
+            probe_with_curl(
+                url,
+                host,
+                port,
+                ip_address,
+                version,
+                curl_version,
+                timeout_secs,
+            )
+            .await,
+        );
+    }
+    for version in MODERN_VERSIONS {
+        family
+            .attempts
+            .push(probe_builtin(host, port, ip_address, version, timeout_secs).await);
+    }
+    family.supported_versions = family
+        .attempts
+        .iter()
+        .filter(|a| a.status == TlsVersionStatus::Supported)
+        .map(|a| a.version.clone())
+        .collect();
+    family
+}
+
+async fn probe_builtin(
+    host: &str,
+    port: u16,
+    ip_address: &str,
+    version: TlsVersion,
+    timeout_secs: u64,
+) -> TlsVersionAttempt {
+    let mut attempt = TlsVersionAttempt {
+        version: version.as_str().to_string(),
+        status: TlsVersionStatus::Failed,
+        client: TlsProbeClient::Builtin,
+        handshake_ms: None,
+        cipher_suite: None,
+        error_message: None,
+    };
+    let ip: IpAddr = match ip_address.parse() {
+        Ok(ip) => ip,
+        Err(_) => {
+            attempt.error_message = Some(format!("無効なIPアドレス: {}", ip_address));
+            return attempt;
+        }
+    };
+    match http_client::fetch_certificate_chain(ip, port, host, Some(version), timeout_secs).await {
+        Ok(chain) => {
+            attempt.status = TlsVersionStatus::Supported;
+            attempt.handshake_ms = Some(chain.handshake_ms);
+            attempt.cipher_suite = chain.cipher_suite;
+        }
+        Err(e) => {
+            // TCP 接続やタイムアウトのエラーはバージョンの拒否とはみなさない
+            if e.starts_with(http_client::TLS_HANDSHAKE_FAILED) {
+                attempt.status = TlsVersionStatus::Rejected;
+            }
+            attempt.error_message = Some(e);
+        }
+    }
+    attempt
+}
+
+async fn probe_with_curl(
+    url: &str,
+    host: &str,
+    port: u16,
+    ip_address: &str,
+    version: &str,
+    curl_version: &str,
+    timeout_secs: u64,
+) -> TlsVersionAttempt {
+    let mut attempt = TlsVersionAttempt {
+        version: version.to_string(),
+        status: TlsVersionStatus::Failed,
+        client: TlsProbeClient::Curl,
+        handshake_ms: None,
+        cipher_suite: None,
+        error_message: None,
+    };
+
+    let capabilities = crate::curl::capabilities().await;
+    let mut args = transfer_args(
+        url,
+        host,
+        port,
+        ip_address,
+        true,
+        false,
+        capabilities.supports(CurlFeature::ResolveIpv6Brackets),
+    );
+    // 本文は不要なため HEAD で送り、下限と上限を同じバージョンにする
+    args.splice(
+        0..0,
+        [
+            "--head".to_string(),
+            format!("--tlsv{}", curl_version),
+            "--tls-max".to_string(),
+            curl_version.to_string(),
+            "--connect-timeout".to_string(),
+            timeout_secs.to_string(),
+        ],
+    );
+
+    let output = match run_curl(args).await {
+        Ok(output) => output,
+        Err(e) => {
+            attempt.error_message = Some(e);
+            return attempt;
+        }
+    };
+    // ハンドシェイクの後で HTTP の段階で失敗した場合も、そのバージョンには対応している
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    attempt.handshake_ms = stdout.lines().find_map(parse_handshake_ms);
+    if attempt.handshake_ms.is_some() {
+        attempt.status = TlsVersionStatus::Supported;
+        return attempt;
+    }
+
+    let error_kind = CurlErrorKind::from_exit_code(output.status.code());
+    attempt.status = if error_kind == CurlErrorKind::UnsupportedOption {
+        TlsVersionStatus::ClientUnsupported
+    } else if error_kind.is_tls() {
+        TlsVersionStatus::Rejected
+    } else {
+        TlsVersionStatus::Failed
+    };
+    attempt.error_message = Some(format!(
+        "TLS 接続に失敗: {}",
+        describe_exit_code(output.status.code())
+    ));
+    attempt
+}
+
+fn find_issues(ipv4: &FamilyTlsVersions, ipv6: &FamilyTlsVersions) -> Vec<String> {
+    let mut findings = Vec::new();
+    let families = [("IPv4", ipv4), ("IPv6", ipv6)];
+    for (family, versions) in families {
+        if versions.ip_address.is_none() {
+            continue;
+        }
+        let supported = |version: &str| versions.supported_versions.iter().any(|v| v == version);
+        let legacy: Vec<&str> = LEGACY_VERSIONS
+            .iter()
+            .map(|(version, _)| *version)
+            .filter(|version| supported(version))
+            .collect();
+        if !legacy.is_empty() {
+            findings.push(format!(
+                "{} では非推奨の {} を受け付けます",
+                family,
+                legacy.join(" / ")
+            ));
+        }
+        if versions.supported_versions.is_empty() {
+            continue;
+        }
+        if !supported(TlsVersion::Tls1_2.as_str()) && !supported(TlsVersion::Tls1_3.as_str()) {
+            findings.push(format!(
+                "{} では TLS 1.2 以降に対応していません（古いバージョンのみ）",
+                family
+            ));
+        } else if !supported(TlsVersion::Tls1_3.as_str()) {
+            findings.push(format!("{} では TLS 1.3 に対応していません", family));
+        } else if versions.supported_versions.len() == 1 {
+            findings.push(format!(
+                "{} では TLS 1.3 のみに対応しています（TLS 1.3 に対応していないクライアントは接続できません）",
+                family
+            ));
+        }
+    }
+    if ipv4.ip_address.is_some()
+        && ipv6.ip_address.is_some()
+        && !ipv4.supported_versions.is_empty()
+        && !ipv6.supported_versions.is_empty()
+        && ipv4.supported_versions != ipv6.supported_versions
+    {
+        findings.push(
+            "IPv4 と IPv6 で対応している TLS のバージョンが異なります。IPv4 と IPv6 で別の TLS 終端装置が応答している可能性があります"
+                .to_string(),
+        );
+    }
+    // Windows の Schannel などでは TLS 1.0/1.1 が OS 側で無効になっている場合がある
+    let legacy_rejected = families.iter().any(|(_, versions)| {
+        versions
+            .attempts
+            .iter()
+            .any(|a| a.client == TlsProbeClient::Curl && a.status == TlsVersionStatus::Rejected)
+    });
+    if legacy_rejected {
+        findings.push(
+            "TLS 1.0/1.1 の失敗には、サーバの拒否のほか、この PC 側で古いバージョンが無効になっている場合も含まれます"
+                .to_string(),
+        );
+    }
+    findings
+}
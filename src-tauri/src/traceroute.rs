@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::process::run_command;
+
+// 最大ホップ数と各ホップの待ち時間（tracert の既定の 30 ホップ・4 秒では時間がかかりすぎるため短くする）
+const MAX_HOPS: u32 = 20;
+const HOP_TIMEOUT_MS: u32 = 750;
+
+// tracert 全体のタイムアウト（全ホップが応答しない場合でも終わる長さ）
+const TRACEROUTE_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracerouteHop {
+    pub hop: u32,
+    pub address: Option<String>,
+    // 3 回分の応答時間（応答がなければ None、<1 ms は 1 とする）
+    pub rtt_ms: Vec<Option<u32>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TracerouteResult {
+    pub target: String,
+    pub hops: Vec<TracerouteHop>,
+    pub reached: bool,
+}
+
+impl TracerouteResult {
+    // 応答のあった最後のホップ
+    pub fn last_responding_hop(&self) -> Option<&TracerouteHop> {
+        self.hops.iter().rev().find(|h| h.address.is_some())
+    }
+}
+
+// tracert.exe で接続先までの経路を調べる（名前の逆引きは行わない）
+pub async fn traceroute(ip_address: &str) -> Result<TracerouteResult, String> {
+    let target: IpAddr = ip_address
+        .parse()
+        .map_err(|_| format!("無効なIPアドレス: {}", ip_address))?;
+    let args = [
+        "-d".to_string(),
+        "-h".to_string(),
+        MAX_HOPS.to_string(),
+        "-w".to_string(),
+        HOP_TIMEOUT_MS.to_string(),
+        if target.is_ipv6() { "-6" } else { "-4" }.to_string(),
+        target.to_string(),
+    ];
+    let output = tokio::time::timeout(
+        Duration::from_secs(TRACEROUTE_TIMEOUT_SECS),
+        run_command("tracert", &args),
+    )
+    .await
+    .map_err(|_| "経路の確認がタイムアウトしました".to_string())?
+    .map_err(|e| format!("tracert の実行に失敗: {}", e))?;
+
+    let stdout = crate::decode_command_output(&output.stdout);
+    let hops = parse_tracert_output(&stdout);
+    if hops.is_empty() {
+        return Err("tracert の出力を解析できません".to_string());
+    }
+    let reached = hops
+        .last()
+        .and_then(|h| h.address.as_deref())
+        .and_then(|a| a.parse::<IpAddr>().ok())
+        == Some(target);
+
+    Ok(TracerouteResult {
+        target: target.to_string(),
+        hops,
+        reached,
+    })
+}
+
+// "  3    12 ms    11 ms    <1 ms  192.0.2.1" 形式の行を解析（表示言語で変わる文言には依存しない）
+fn parse_tracert_output(output: &str) -> Vec<TracerouteHop> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut tokens = line.split_whitespace();
+            let hop: u32 = tokens.next()?.parse().ok()?;
+            let mut rtt_ms = Vec::new();
+            let mut address = None;
+            for token in tokens {
+                if token == "*" {
+                    rtt_ms.push(None);
+                } else if let Ok(ms) = token.trim_start_matches('<').parse::<u32>() {
+                    rtt_ms.push(Some(ms));
+                } else if let Ok(ip) = token.trim_matches(['[', ']']).parse::<IpAddr>() {
+                    address = Some(ip.to_string());
+                }
+            }
+            (!rtt_ms.is_empty()).then_some(TracerouteHop {
+                hop,
+                address,
+                rtt_ms,
+            })
+        })
+        .collect()
+}
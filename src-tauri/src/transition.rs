@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::net::Ipv6Addr;
+
+use crate::process::run_command;
+use crate::NetworkAdapter;
+
+// IPv6 移行技術（Teredo/ISATAP/6to4）の状態と移行用アドレスを取得するスクリプト
+// netsh の出力は OS の表示言語で変わるため、同じ情報を返す NetworkTransition モジュールを使用
+const TRANSITION_SCRIPT: &str = r#"$teredoConfig = Get-NetTeredoConfiguration -ErrorAction SilentlyContinue
+$teredoState = Get-NetTeredoState -ErrorAction SilentlyContinue
+$isatap = Get-NetIsatapConfiguration -ErrorAction SilentlyContinue
+$sixToFour = Get-Net6to4Configuration -ErrorAction SilentlyContinue
+$addresses = @(Get-NetIPAddress -AddressFamily IPv6 -ErrorAction SilentlyContinue |
+    Where-Object { $_.IPAddress -like '2001:0:*' -or $_.IPAddress -like '2002:*' } |
+    ForEach-Object { $_.IPAddress })
+[pscustomobject]@{
+    teredo_type = if ($teredoConfig) { [string]$teredoConfig.Type } else { $null }
+    teredo_server = if ($teredoConfig) { [string]$teredoConfig.ServerName } else { $null }
+    teredo_state = if ($teredoState) { [string]$teredoState.State } else { $null }
+    isatap_state = if ($isatap) { [string]$isatap.State } else { $null }
+    six_to_four_state = if ($sixToFour) { [string]$sixToFour.State } else { $null }
+    transition_addresses = $addresses
+} | ConvertTo-Json -Compress"#;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransitionInterfaces {
+    pub teredo_type: Option<String>,
+    pub teredo_server: Option<String>,
+    pub teredo_state: Option<String>,
+    pub isatap_state: Option<String>,
+    pub six_to_four_state: Option<String>,
+    #[serde(default)]
+    pub transition_addresses: Vec<String>,
+    #[serde(default)]
+    pub findings: Vec<String>,
+}
+
+// Teredo/ISATAP/6to4 の状態を取得
+pub async fn get_transition_interfaces() -> Result<TransitionInterfaces, String> {
+    let args = [
+        "-NoProfile".to_string(),
+        "-WindowStyle".to_string(),
+        "Hidden".to_string(),
+        "-Command".to_string(),
+        TRANSITION_SCRIPT.to_string(),
+    ];
+    let output = run_command("powershell", &args)
+        .await
+        .map_err(|e| format!("PowerShellコマンド実行失敗: {}", e))?;
+    if !output.status.success() {
+        return Err("IPv6 移行技術の状態を取得できません".to_string());
+    }
+
+    let stdout = crate::decode_command_output(&output.stdout);
+    serde_json::from_str(stdout.trim()).map_err(|e| format!("移行技術の状態の解析失敗: {}", e))
+}
+
+// 有効なら IPv6 通信を横取りしうる状態かどうか（"Default" は OS 既定で無効扱い）
+fn is_active(state: &Option<String>) -> bool {
+    state
+        .as_deref()
+        .is_some_and(|s| s.eq_ignore_ascii_case("enabled"))
+}
+
+// Teredo (2001:0::/32) や 6to4 (2002::/16) ではないグローバル IPv6 アドレス
+fn is_native_global_ipv6(address: &str) -> bool {
+    let Ok(ip) = address.parse::<Ipv6Addr>() else {
+        return false;
+    };
+    let segments = ip.segments();
+    let is_teredo = segments[0] == 0x2001 && segments[1] == 0;
+    let is_6to4 = segments[0] == 0x2002;
+    (segments[0] & 0xe000) == 0x2000 && !is_teredo && !is_6to4
+}
+
+// 移行技術が IPv6 通信に影響している可能性を説明する
+pub fn find_transition_issues(state: &mut TransitionInterfaces, adapters: &[NetworkAdapter]) {
+    let has_native_ipv6 = adapters
+        .iter()
+        .flat_map(|a| a.ip_addresses.iter())
+        .any(|a| is_native_global_ipv6(a));
+
+    let teredo_active = state
+        .teredo_state
+        .as_deref()
+        .is_some_and(|s| s.eq_ignore_ascii_case("qualified") || s.eq_ignore_ascii_case("probe"));
+    if teredo_active {
+        state.findings.push(if has_native_ipv6 {
+            "Teredo が動作しています。ネイティブ IPv6 があるため通常は使われませんが、不要であれば管理者権限で `netsh interface teredo set state disabled` を実行して無効化してください".to_string()
+        } else {
+            "ネイティブ IPv6 がなく Teredo が動作しています。IPv6 (AAAA) 宛ての通信が Teredo 経由になり、不安定・低速になる原因となります。管理者権限で `netsh interface teredo set state disabled` を実行して無効化してください".to_string()
+        });
+    }
+    if is_active(&state.isatap_state) {
+        state.findings.push(
+            "ISATAP が有効です。不要であれば管理者権限で `netsh interface isatap set state disabled` を実行して無効化してください"
+                .to_string(),
+        );
+    }
+    if is_active(&state.six_to_four_state) {
+        state.findings.push(
+            "6to4 が有効です。不要であれば管理者権限で `netsh interface 6to4 set state disabled` を実行して無効化してください"
+                .to_string(),
+        );
+    }
+    if !state.transition_addresses.is_empty() && !has_native_ipv6 {
+        state.findings.push(format!(
+            "移行技術による IPv6 アドレスが割り当てられています: {}",
+            state.transition_addresses.join(", ")
+        ));
+    }
+}
@@ -0,0 +1,391 @@
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use url::Url;
+
+use crate::http_client::{self, HttpMethod, RequestOptions};
+use crate::maintenance::MonitorKind;
+
+// Webhook の設定ファイル名（アプリデータディレクトリ配下）
+pub const WEBHOOK_FILE_NAME: &str = "webhooks.json";
+
+const MAX_WEBHOOKS: usize = 20;
+
+// 送信に失敗した場合の再送回数（初回を含まない）と、再送までの待ち時間の初期値（毎回 2 倍にする）
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const MAX_RETRIES: u32 = 10;
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(2);
+
+const DELIVERY_TIMEOUT_SECS: u64 = 10;
+
+// 保持する送信失敗の記録の上限（古いものから削除）
+const MAX_FAILURE_LOG: usize = 100;
+
+// 受信側で照合するためのヘッダ（署名は本文の HMAC-SHA256 を "sha256=" に続けて16進数で表す）
+const SIGNATURE_HEADER: &str = "x-ghttpping-signature-256";
+const MONITOR_HEADER: &str = "x-ghttpping-monitor";
+
+// テンプレート中の変数の区切り
+const VARIABLE_OPEN: &str = "{{";
+const VARIABLE_CLOSE: &str = "}}";
+
+// 再送しても送信できなかったときにフロントエンドへ通知するイベント名
+pub const WEBHOOK_FAILED_EVENT: &str = "webhook-delivery-failed";
+
+// 定期実行の結果を POST する宛先
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub name: String,
+    pub monitor: MonitorKind,
+    pub url: String,
+    // HMAC-SHA256 の署名に使う鍵（None の場合は署名しない）
+    #[serde(default)]
+    pub secret: Option<String>,
+    // 本文のテンプレート（None の場合は monitor・webhook・sent_at・result を含む JSON）
+    // {{result.ipv4.response_time_ms}} のように、既定の JSON 内の値をドット区切りで参照する
+    #[serde(default)]
+    pub payload_template: Option<String>,
+    // None の場合は application/json
+    #[serde(default)]
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub max_retries: Option<u32>,
+    // 失敗や変化を含む結果のみ送信する
+    #[serde(default)]
+    pub only_problems: bool,
+}
+
+impl Webhook {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Webhook の名前を指定してください".to_string());
+        }
+        crate::validate_url(&self.url).map_err(|e| format!("{}: {}", self.name, e))?;
+        if self.secret.as_ref().is_some_and(|s| s.is_empty()) {
+            return Err(format!(
+                "{}: 署名の鍵を空にすることはできません（署名しない場合は指定しないでください）",
+                self.name
+            ));
+        }
+        if let Some(template) = &self.payload_template {
+            render(template, &Value::Null).map_err(|e| format!("{}: {}", self.name, e))?;
+        }
+        if let Some(content_type) = &self.content_type {
+            if content_type.trim().is_empty() || content_type.contains(['\r', '\n']) {
+                return Err(format!("{}: Content-Type が正しくありません", self.name));
+            }
+        }
+        if self.max_retries.is_some_and(|r| r > MAX_RETRIES) {
+            return Err(format!(
+                "{}: 再送回数は {} 回以内で指定してください",
+                self.name, MAX_RETRIES
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookFailure {
+    pub webhook: String,
+    pub monitor: MonitorKind,
+    pub url: String,
+    pub attempts: u32,
+    pub failed_at: String,
+    pub error_message: String,
+}
+
+// Webhook の設定と送信失敗の記録（Tauri の State として管理）
+#[derive(Default)]
+pub struct WebhookStore {
+    webhooks: Mutex<Option<Vec<Webhook>>>,
+    failures: Mutex<VecDeque<WebhookFailure>>,
+}
+
+fn webhook_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("アプリデータディレクトリの取得に失敗: {}", e))?;
+    fs::create_dir_all(&dir).map_err(|e| format!("設定ディレクトリの作成に失敗: {}", e))?;
+    Ok(dir.join(WEBHOOK_FILE_NAME))
+}
+
+// 設定ファイルを読み込む（壊れている場合は Webhook なしとして扱う）
+fn read_webhook_file(path: &PathBuf) -> Vec<Webhook> {
+    match fs::read_to_string(path) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            eprintln!("Invalid webhook file {:?}: {}", path, e);
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn webhooks_for(app: &AppHandle, monitor: MonitorKind) -> Vec<Webhook> {
+    let Ok(path) = webhook_file_path(app) else {
+        return Vec::new();
+    };
+    let store = app.state::<WebhookStore>();
+    let Ok(mut guard) = store.webhooks.lock() else {
+        return Vec::new();
+    };
+    guard
+        .get_or_insert_with(|| read_webhook_file(&path))
+        .iter()
+        .filter(|w| w.monitor == monitor)
+        .cloned()
+        .collect()
+}
+
+// 定期実行の1回分の結果を、その定期実行の Webhook へバックグラウンドで送信する
+// problem は失敗や変化を含むかどうか（only_problems の Webhook はこれが true の場合のみ送信）
+// suppressed_by は依存先の停止により抑止した通知のグループ名（only_problems の Webhook には送信しない）
+pub fn notify(
+    app: &AppHandle,
+    monitor: MonitorKind,
+    result: &impl Serialize,
+    problem: bool,
+    suppressed_by: Option<&str>,
+) {
+    let alert = problem && suppressed_by.is_none();
+    let webhooks: Vec<Webhook> = webhooks_for(app, monitor)
+        .into_iter()
+        .filter(|w| alert || !w.only_problems)
+        .collect();
+    if webhooks.is_empty() {
+        return;
+    }
+    let result = match serde_json::to_value(result) {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Failed to serialize webhook payload: {}", e);
+            return;
+        }
+    };
+    let sent_at = crate::now_rfc3339();
+    for webhook in webhooks {
+        let payload = json!({
+            "monitor": monitor,
+            "webhook": webhook.name,
+            "sent_at": sent_at,
+            "result": result,
+        });
+        tokio::spawn(deliver(app.clone(), webhook, payload));
+    }
+}
+
+// 送信できるまで間隔を空けて再送し、最後まで失敗した場合は記録して通知する
+async fn deliver(app: AppHandle, webhook: Webhook, payload: Value) {
+    let body = match &webhook.payload_template {
+        Some(template) => render(template, &payload),
+        None => serde_json::to_string(&payload).map_err(|e| e.to_string()),
+    };
+    let max_retries = webhook.max_retries.unwrap_or(DEFAULT_MAX_RETRIES);
+    let mut attempts = 0;
+    let result = match body {
+        Ok(body) => loop {
+            attempts += 1;
+            match post(&webhook, &body).await {
+                Ok(()) => return,
+                Err((e, retryable)) if retryable && attempts <= max_retries => {
+                    eprintln!(
+                        "Webhook {} delivery failed (attempt {}): {}",
+                        webhook.name, attempts, e
+                    );
+                    tokio::time::sleep(RETRY_BASE_DELAY * 2u32.pow(attempts - 1)).await;
+                }
+                Err((e, _)) => break e,
+            }
+        },
+        Err(e) => format!("本文のテンプレートを展開できません: {}", e),
+    };
+
+    let failure = WebhookFailure {
+        webhook: webhook.name.clone(),
+        monitor: webhook.monitor,
+        url: webhook.url.clone(),
+        attempts,
+        failed_at: crate::now_rfc3339(),
+        error_message: result,
+    };
+    eprintln!(
+        "Webhook {} delivery failed: {}",
+        failure.webhook, failure.error_message
+    );
+    if let Ok(mut failures) = app.state::<WebhookStore>().failures.lock() {
+        if failures.len() >= MAX_FAILURE_LOG {
+            failures.pop_front();
+        }
+        failures.push_back(failure.clone());
+    }
+    if let Err(e) = app.emit(WEBHOOK_FAILED_EVENT, &failure) {
+        eprintln!("Failed to emit {}: {}", WEBHOOK_FAILED_EVENT, e);
+    }
+}
+
+// 1回送信する（失敗した場合は再送してよいかを添えて返す）
+async fn post(webhook: &Webhook, body: &str) -> Result<(), (String, bool)> {
+    let parsed_url = Url::parse(&webhook.url).map_err(|e| (format!("無効なURL: {}", e), false))?;
+    let host = parsed_url
+        .host_str()
+        .ok_or_else(|| ("URLからホスト名を抽出できません".to_string(), false))?;
+    let dns_resolution = crate::resolve_dns(host).await;
+    let ip_address = dns_resolution
+        .ipv4_addresses
+        .first()
+        .or(dns_resolution.ipv6_addresses.first())
+        .ok_or_else(|| (format!("名前を解決できません: {}", host), true))?;
+
+    let mut headers = vec![
+        (
+            "content-type".to_string(),
+            webhook
+                .content_type
+                .clone()
+                .unwrap_or_else(|| "application/json".to_string()),
+        ),
+        (
+            MONITOR_HEADER.to_string(),
+            serde_json::to_value(webhook.monitor)
+                .ok()
+                .and_then(|v| v.as_str().map(str::to_string))
+                .unwrap_or_default(),
+        ),
+    ];
+    if let Some(secret) = &webhook.secret {
+        headers.push((SIGNATURE_HEADER.to_string(), sign(secret, body.as_bytes())));
+    }
+    let options = RequestOptions {
+        method: HttpMethod::Post,
+        headers,
+        body: Some(body.to_string().into()),
+        ..RequestOptions::default()
+    };
+    let outcome = http_client::send_request(&http_client::HttpRequest {
+        url: &webhook.url,
+        options: &options,
+        ip_address: Some(ip_address),
+        host,
+        port: parsed_url.port(),
+        ignore_tls_errors: false,
+        source_address: None,
+        verbose: false,
+        max_body_bytes: 0,
+        timeout_secs: DELIVERY_TIMEOUT_SECS,
+    })
+    .await;
+    if let Some(e) = outcome.error_message {
+        return Err((format!("送信できません: {}", e), true));
+    }
+    match outcome.status_code.unwrap_or_default() {
+        200..=299 => Ok(()),
+        // 受信側の一時的な問題の場合のみ再送する
+        status @ (408 | 429 | 500..=599) => Err((format!("HTTPステータス: {}", status), true)),
+        status => Err((format!("HTTPステータス: {}", status), false)),
+    }
+}
+
+// 本文の HMAC-SHA256（GitHub の Webhook と同じ形式）
+fn sign(secret: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    let tag = hmac::sign(&key, body);
+    let hex: String = tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+// {{path}} を既定の JSON 内の値に置き換える
+// 文字列は JSON の文字列としてエスケープした中身（引用符なし）、それ以外は JSON 表現、値がない場合は null
+fn render(template: &str, payload: &Value) -> Result<String, String> {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find(VARIABLE_OPEN) {
+        rendered.push_str(&rest[..start]);
+        let after_open = &rest[start + VARIABLE_OPEN.len()..];
+        let end = after_open
+            .find(VARIABLE_CLOSE)
+            .ok_or_else(|| format!("テンプレートの変数が閉じられていません: {}", template))?;
+        let path = after_open[..end].trim();
+        if path.is_empty() {
+            return Err("テンプレートに空の変数があります".to_string());
+        }
+        let value = path.split('.').try_fold(payload, |value, key| match value {
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => value.get(key),
+        });
+        rendered.push_str(&match value {
+            Some(Value::String(s)) => {
+                let quoted = serde_json::to_string(s).map_err(|e| e.to_string())?;
+                quoted[1..quoted.len() - 1].to_string()
+            }
+            Some(value) => value.to_string(),
+            None => "null".to_string(),
+        });
+        rest = &after_open[end + VARIABLE_CLOSE.len()..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+#[tauri::command]
+pub async fn get_webhooks(
+    app: AppHandle,
+    store: State<'_, WebhookStore>,
+) -> Result<Vec<Webhook>, String> {
+    let path = webhook_file_path(&app)?;
+    Ok(store
+        .webhooks
+        .lock()
+        .map_err(|_| "Webhook の設定のロック取得に失敗".to_string())?
+        .get_or_insert_with(|| read_webhook_file(&path))
+        .clone())
+}
+
+// Webhook をまとめて置き換えて保存（空にするとすべて削除、署名の鍵も設定ファイルに保存される）
+#[tauri::command]
+pub async fn set_webhooks(
+    app: AppHandle,
+    store: State<'_, WebhookStore>,
+    webhooks: Vec<Webhook>,
+) -> Result<Vec<Webhook>, String> {
+    if webhooks.len() > MAX_WEBHOOKS {
+        return Err(format!("Webhook は {} 件まで登録できます", MAX_WEBHOOKS));
+    }
+    for webhook in &webhooks {
+        webhook.validate()?;
+    }
+
+    let path = webhook_file_path(&app)?;
+    let body = serde_json::to_string_pretty(&webhooks)
+        .map_err(|e| format!("Webhook の設定の変換に失敗: {}", e))?;
+    fs::write(&path, body).map_err(|e| format!("Webhook の設定ファイルの書き込みに失敗: {}", e))?;
+
+    *store
+        .webhooks
+        .lock()
+        .map_err(|_| "Webhook の設定のロック取得に失敗".to_string())? = Some(webhooks.clone());
+    Ok(webhooks)
+}
+
+// 再送しても送信できなかった記録を古い順に返す（clear を指定すると返した分を消去する）
+#[tauri::command]
+pub async fn get_webhook_failures(
+    store: State<'_, WebhookStore>,
+    clear: Option<bool>,
+) -> Result<Vec<WebhookFailure>, String> {
+    let mut failures = store
+        .failures
+        .lock()
+        .map_err(|_| "Webhook の送信失敗の記録のロック取得に失敗".to_string())?;
+    Ok(if clear.unwrap_or(false) {
+        failures.drain(..).collect()
+    } else {
+        failures.iter().cloned().collect()
+    })
+}
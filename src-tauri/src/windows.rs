@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State, WebviewUrl, WebviewWindowBuilder, Window, WindowEvent};
+
+// 結果表示用ウィンドウのラベルの接頭辞（capabilities の対象指定にも使用）
+const RESULT_WINDOW_PREFIX: &str = "result-";
+
+// メインウィンドウのラベル
+const MAIN_WINDOW_LABEL: &str = "main";
+
+const MAX_RESULT_ID_LEN: usize = 64;
+const RESULT_WINDOW_WIDTH: f64 = 700.0;
+const RESULT_WINDOW_HEIGHT: f64 = 500.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultWindowInfo {
+    pub label: String,
+    pub result_id: String,
+    pub title: String,
+    pub opened_at: String,
+}
+
+// 開いている結果表示用ウィンドウの一覧（Tauri の State として管理）
+#[derive(Default)]
+pub struct ResultWindows {
+    windows: Mutex<HashMap<String, ResultWindowInfo>>,
+}
+
+// 結果 ID はウィンドウラベルと URL に使うため英数字・ハイフン・アンダースコアのみ許可
+fn validate_result_id(result_id: &str) -> Result<(), String> {
+    if result_id.is_empty() || result_id.len() > MAX_RESULT_ID_LEN {
+        return Err(format!(
+            "結果 ID は 1 から {} 文字で指定してください",
+            MAX_RESULT_ID_LEN
+        ));
+    }
+    if !result_id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err("結果 ID には英数字・ハイフン・アンダースコアのみ使用できます".to_string());
+    }
+    Ok(())
+}
+
+// 指定した結果・モニタを表示する別ウィンドウを開く（既に開いている場合は前面に表示）
+#[tauri::command]
+pub async fn open_result_window(
+    app: AppHandle,
+    windows: State<'_, ResultWindows>,
+    result_id: String,
+    title: Option<String>,
+) -> Result<ResultWindowInfo, String> {
+    validate_result_id(&result_id)?;
+    let label = format!("{}{}", RESULT_WINDOW_PREFIX, result_id);
+
+    if let Some(window) = app.get_webview_window(&label) {
+        window
+            .set_focus()
+            .map_err(|e| format!("ウィンドウの表示に失敗: {}", e))?;
+        let existing = windows
+            .windows
+            .lock()
+            .map_err(|_| "ウィンドウ一覧のロック取得に失敗".to_string())?
+            .get(&label)
+            .cloned();
+        if let Some(info) = existing {
+            return Ok(info);
+        }
+    }
+
+    let title = title.unwrap_or_else(|| format!("ghttpping-tauri - {}", result_id));
+    let url = WebviewUrl::App(format!("index.html?result={}", result_id).into());
+    WebviewWindowBuilder::new(&app, &label, url)
+        .title(&title)
+        .inner_size(RESULT_WINDOW_WIDTH, RESULT_WINDOW_HEIGHT)
+        .resizable(true)
+        .build()
+        .map_err(|e| format!("ウィンドウの作成に失敗: {}", e))?;
+
+    let info = ResultWindowInfo {
+        label: label.clone(),
+        result_id,
+        title,
+        opened_at: chrono::Local::now().to_rfc3339(),
+    };
+    windows
+        .windows
+        .lock()
+        .map_err(|_| "ウィンドウ一覧のロック取得に失敗".to_string())?
+        .insert(label, info.clone());
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn list_result_windows(
+    windows: State<'_, ResultWindows>,
+) -> Result<Vec<ResultWindowInfo>, String> {
+    let mut list: Vec<ResultWindowInfo> = windows
+        .windows
+        .lock()
+        .map_err(|_| "ウィンドウ一覧のロック取得に失敗".to_string())?
+        .values()
+        .cloned()
+        .collect();
+    list.sort_by(|a, b| a.opened_at.cmp(&b.opened_at));
+    Ok(list)
+}
+
+#[tauri::command]
+pub async fn close_result_window(app: AppHandle, result_id: String) -> Result<(), String> {
+    validate_result_id(&result_id)?;
+    let label = format!("{}{}", RESULT_WINDOW_PREFIX, result_id);
+    match app.get_webview_window(&label) {
+        Some(window) => window
+            .close()
+            .map_err(|e| format!("ウィンドウを閉じられません: {}", e)),
+        None => Err(format!("結果 {} のウィンドウは開いていません", result_id)),
+    }
+}
+
+// ウィンドウのライフサイクル管理（run() の on_window_event から呼び出す）
+pub fn handle_window_event(window: &Window, event: &WindowEvent) {
+    match event {
+        // メインウィンドウを閉じたら結果表示用ウィンドウもすべて閉じる
+        WindowEvent::CloseRequested { .. } if window.label() == MAIN_WINDOW_LABEL => {
+            for (label, webview_window) in window.app_handle().webview_windows() {
+                if label.starts_with(RESULT_WINDOW_PREFIX) {
+                    if let Err(e) = webview_window.close() {
+                        eprintln!("Failed to close window {}: {}", label, e);
+                    }
+                }
+            }
+        }
+        WindowEvent::Destroyed if window.label().starts_with(RESULT_WINDOW_PREFIX) => {
+            let state = window.app_handle().state::<ResultWindows>();
+            if let Ok(mut windows) = state.windows.lock() {
+                windows.remove(window.label());
+            };
+        }
+        _ => {}
+    }
+}